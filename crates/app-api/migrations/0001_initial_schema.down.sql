@@ -0,0 +1,21 @@
+-- Drops every table created by 0001 (dependents first). Extensions are left
+-- in place since other databases on the same cluster may rely on them.
+DROP TABLE IF EXISTS user_preferences;
+DROP TABLE IF EXISTS chat_messages;
+DROP TABLE IF EXISTS chat_sessions;
+DROP TABLE IF EXISTS meal_plan_slots;
+DROP TABLE IF EXISTS meal_plans;
+DROP TABLE IF EXISTS inventory_items;
+DROP TABLE IF EXISTS cooking_history;
+DROP TABLE IF EXISTS recipe_ratings;
+DROP TABLE IF EXISTS user_favorites;
+DROP TABLE IF EXISTS recipe_nutrition;
+DROP TABLE IF EXISTS recipe_images;
+DROP TABLE IF EXISTS recipe_steps;
+DROP TABLE IF EXISTS recipe_ingredients;
+DROP TABLE IF EXISTS recipes;
+DROP TABLE IF EXISTS ingredient_allergens;
+DROP TABLE IF EXISTS portion_sizes;
+DROP TABLE IF EXISTS ingredient_nutrients;
+DROP TABLE IF EXISTS ingredients;
+DROP TABLE IF EXISTS users;
@@ -0,0 +1,20 @@
+DROP INDEX IF EXISTS idx_recipes_author;
+ALTER TABLE recipes DROP COLUMN IF EXISTS is_public;
+ALTER TABLE recipes DROP COLUMN IF EXISTS author_id;
+
+-- recipe_id stays nullable: flex slots may already hold NULLs
+ALTER TABLE meal_plan_slots DROP COLUMN IF EXISTS energy_level;
+ALTER TABLE meal_plan_slots DROP COLUMN IF EXISTS flex_type;
+ALTER TABLE meal_plan_slots DROP COLUMN IF EXISTS is_flex;
+
+DROP INDEX IF EXISTS idx_users_stripe;
+ALTER TABLE users DROP COLUMN IF EXISTS is_admin;
+ALTER TABLE users DROP COLUMN IF EXISTS onboarding_completed;
+ALTER TABLE users DROP COLUMN IF EXISTS preferred_time_per_meal_min;
+ALTER TABLE users DROP COLUMN IF EXISTS weekly_budget;
+ALTER TABLE users DROP COLUMN IF EXISTS health_goals;
+ALTER TABLE users DROP COLUMN IF EXISTS preferred_cuisines;
+ALTER TABLE users DROP COLUMN IF EXISTS cooking_skill_level;
+ALTER TABLE users DROP COLUMN IF EXISTS stripe_customer_id;
+ALTER TABLE users DROP COLUMN IF EXISTS subscription_valid_until;
+ALTER TABLE users DROP COLUMN IF EXISTS subscription_tier;
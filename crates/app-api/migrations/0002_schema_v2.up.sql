@@ -0,0 +1,23 @@
+-- Schema v2: Subscription + onboarding fields on users
+ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_tier TEXT NOT NULL DEFAULT 'free';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_valid_until TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS stripe_customer_id TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS cooking_skill_level TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS preferred_cuisines TEXT[] DEFAULT '{}';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS health_goals TEXT[] DEFAULT '{}';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS weekly_budget NUMERIC(10,2);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS preferred_time_per_meal_min INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS onboarding_completed BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+CREATE INDEX IF NOT EXISTS idx_users_stripe ON users(stripe_customer_id) WHERE stripe_customer_id IS NOT NULL;
+
+-- Schema v2: Flex / energy fields on meal_plan_slots
+ALTER TABLE meal_plan_slots ALTER COLUMN recipe_id DROP NOT NULL;
+ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS is_flex BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS flex_type TEXT;
+ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS energy_level TEXT;
+
+-- Schema v2: author_id on recipes
+ALTER TABLE recipes ADD COLUMN IF NOT EXISTS author_id UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE recipes ADD COLUMN IF NOT EXISTS is_public BOOLEAN NOT NULL DEFAULT TRUE;
+CREATE INDEX IF NOT EXISTS idx_recipes_author ON recipes(author_id) WHERE author_id IS NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE ingredients DROP COLUMN IF EXISTS density_g_per_ml;
@@ -0,0 +1,2 @@
+-- Schema v3: density for volume → mass unit conversion
+ALTER TABLE ingredients ADD COLUMN IF NOT EXISTS density_g_per_ml NUMERIC(8,4);
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS store_promotion_candidates;
+DROP TABLE IF EXISTS store_promotion_ingredients;
+DROP TABLE IF EXISTS store_promotions;
+DROP TABLE IF EXISTS pdf_processing_jobs;
+DROP TABLE IF EXISTS stores;
@@ -0,0 +1,86 @@
+-- Stores
+CREATE TABLE IF NOT EXISTS stores (
+    id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name        TEXT NOT NULL,
+    slug        TEXT UNIQUE NOT NULL,
+    website     TEXT,
+    logo_url    TEXT,
+    country     TEXT,
+    city        TEXT,
+    lat         DOUBLE PRECISION,
+    lng         DOUBLE PRECISION,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_stores_slug ON stores(slug);
+
+-- PDF Processing Jobs
+CREATE TABLE IF NOT EXISTS pdf_processing_jobs (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    store_id        UUID NOT NULL REFERENCES stores(id) ON DELETE CASCADE,
+    file_path       TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'pending',
+    error           TEXT,
+    retry_count     INTEGER NOT NULL DEFAULT 0,
+    started_at      TIMESTAMPTZ,
+    heartbeat_at    TIMESTAMPTZ,
+    processed_at    TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_pdf_jobs_store  ON pdf_processing_jobs(store_id);
+CREATE INDEX IF NOT EXISTS idx_pdf_jobs_status ON pdf_processing_jobs(status);
+
+-- Store Promotions (published, admin-approved)
+CREATE TABLE IF NOT EXISTS store_promotions (
+    id                  UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    store_id            UUID NOT NULL REFERENCES stores(id) ON DELETE CASCADE,
+    product_name        TEXT NOT NULL,
+    brand               TEXT,
+    original_price      NUMERIC(10,2),
+    discounted_price    NUMERIC(10,2) NOT NULL,
+    discount_pct        NUMERIC(5,2),
+    unit                TEXT,
+    valid_from          TIMESTAMPTZ,
+    valid_until         TIMESTAMPTZ,
+    is_active           BOOLEAN NOT NULL DEFAULT TRUE,
+    source_pdf_url      TEXT,
+    confidence          NUMERIC(4,3),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_store_promotions_store  ON store_promotions(store_id);
+CREATE INDEX IF NOT EXISTS idx_store_promotions_active ON store_promotions(store_id, is_active, valid_until);
+CREATE INDEX IF NOT EXISTS idx_store_promotions_name_trgm
+    ON store_promotions USING GIN (product_name gin_trgm_ops);
+
+-- Store Promotion Ingredients (cross-reference)
+CREATE TABLE IF NOT EXISTS store_promotion_ingredients (
+    promotion_id    UUID NOT NULL REFERENCES store_promotions(id) ON DELETE CASCADE,
+    ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
+    similarity_score NUMERIC(4,3),
+    PRIMARY KEY (promotion_id, ingredient_id)
+);
+CREATE INDEX IF NOT EXISTS idx_promo_ingredients_ingredient
+    ON store_promotion_ingredients(ingredient_id);
+
+-- Store Promotion Candidates (AI staging, awaiting admin review)
+CREATE TABLE IF NOT EXISTS store_promotion_candidates (
+    id                  UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    store_id            UUID NOT NULL REFERENCES stores(id) ON DELETE CASCADE,
+    job_id              UUID NOT NULL REFERENCES pdf_processing_jobs(id) ON DELETE CASCADE,
+    product_name        TEXT NOT NULL,
+    brand               TEXT,
+    original_price      NUMERIC(10,2),
+    discounted_price    NUMERIC(10,2) NOT NULL,
+    discount_pct        NUMERIC(5,2),
+    unit                TEXT,
+    valid_from          TIMESTAMPTZ,
+    valid_until         TIMESTAMPTZ,
+    confidence          NUMERIC(4,3),
+    review_status       TEXT NOT NULL DEFAULT 'pending',
+    reviewed_by         UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at         TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_candidates_job    ON store_promotion_candidates(job_id);
+CREATE INDEX IF NOT EXISTS idx_candidates_review ON store_promotion_candidates(review_status);
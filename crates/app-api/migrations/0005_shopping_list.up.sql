@@ -0,0 +1,16 @@
+-- Shopping List
+CREATE TABLE IF NOT EXISTS shopping_list_items (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id         UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ingredient_id   BIGINT REFERENCES ingredients(id) ON DELETE SET NULL,
+    name            TEXT NOT NULL,
+    quantity        NUMERIC(10,3),
+    unit            TEXT,
+    is_checked      BOOLEAN NOT NULL DEFAULT FALSE,
+    is_manual       BOOLEAN NOT NULL DEFAULT FALSE,
+    meal_plan_id    BIGINT REFERENCES meal_plans(id) ON DELETE SET NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_shopping_list_user       ON shopping_list_items(user_id);
+CREATE INDEX IF NOT EXISTS idx_shopping_list_ingredient ON shopping_list_items(ingredient_id) WHERE ingredient_id IS NOT NULL;
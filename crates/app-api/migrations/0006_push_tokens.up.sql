@@ -0,0 +1,9 @@
+-- User Push Tokens
+CREATE TABLE IF NOT EXISTS user_push_tokens (
+    id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token       TEXT UNIQUE NOT NULL,
+    platform    TEXT NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_push_tokens_user ON user_push_tokens(user_id);
@@ -0,0 +1,5 @@
+-- Stripe Processed Events (idempotency)
+CREATE TABLE IF NOT EXISTS stripe_processed_events (
+    event_id        TEXT PRIMARY KEY,
+    processed_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS refresh_token_hash TEXT;
+DROP TABLE IF EXISTS user_sessions;
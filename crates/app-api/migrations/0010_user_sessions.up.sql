@@ -0,0 +1,20 @@
+-- One row per signed-in device. Replaces the single users.refresh_token_hash,
+-- so logging in on one device no longer signs out the others.
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id                  UUID PRIMARY KEY,
+    user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- SHA-256 of the session's current refresh token (rotated on every refresh)
+    refresh_token_hash  TEXT NOT NULL,
+    device_name         VARCHAR(100),
+    user_agent          TEXT,
+    ip_address          VARCHAR(45),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at          TIMESTAMPTZ NOT NULL,
+    revoked_at          TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user ON user_sessions(user_id);
+
+-- Existing single-device refresh tokens are dropped; those users sign in again
+ALTER TABLE users DROP COLUMN IF EXISTS refresh_token_hash;
@@ -0,0 +1,17 @@
+DROP INDEX IF EXISTS idx_meal_plans_household_week;
+DROP INDEX IF EXISTS idx_meal_plans_user_week;
+DROP INDEX IF EXISTS idx_shopping_list_items_household;
+DROP INDEX IF EXISTS idx_inventory_items_household;
+
+ALTER TABLE shopping_list_items DROP COLUMN IF EXISTS household_id;
+ALTER TABLE meal_plans DROP COLUMN IF EXISTS household_id;
+ALTER TABLE inventory_items DROP COLUMN IF EXISTS household_id;
+
+-- Shared plans created by the same user for the same week can no longer coexist
+DELETE FROM meal_plans a USING meal_plans b
+    WHERE a.user_id = b.user_id AND a.week_start = b.week_start AND a.id < b.id;
+ALTER TABLE meal_plans ADD CONSTRAINT meal_plans_user_id_week_start_key UNIQUE (user_id, week_start);
+
+DROP TABLE IF EXISTS household_invites;
+DROP TABLE IF EXISTS household_members;
+DROP TABLE IF EXISTS households;
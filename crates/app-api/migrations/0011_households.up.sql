@@ -0,0 +1,51 @@
+-- Households (Family tier): members share one pantry, meal plan and shopping list.
+CREATE TABLE IF NOT EXISTS households (
+    id          UUID PRIMARY KEY,
+    name        VARCHAR(100) NOT NULL,
+    owner_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A user belongs to at most one household
+CREATE TABLE IF NOT EXISTS household_members (
+    household_id  UUID NOT NULL REFERENCES households(id) ON DELETE CASCADE,
+    user_id       UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    role          TEXT NOT NULL CHECK (role IN ('owner', 'admin', 'member')),
+    joined_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (household_id, user_id)
+);
+
+-- Email invitations; only the SHA-256 of the token is stored
+CREATE TABLE IF NOT EXISTS household_invites (
+    id            UUID PRIMARY KEY,
+    household_id  UUID NOT NULL REFERENCES households(id) ON DELETE CASCADE,
+    email         VARCHAR(255) NOT NULL,
+    role          TEXT NOT NULL CHECK (role IN ('admin', 'member')),
+    token_hash    TEXT NOT NULL UNIQUE,
+    invited_by    UUID REFERENCES users(id) ON DELETE SET NULL,
+    expires_at    TIMESTAMPTZ NOT NULL,
+    accepted_at   TIMESTAMPTZ,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_household_invites_household ON household_invites(household_id);
+
+-- Shared rows carry the household; user_id stays as "added by".
+-- Deleting a household hands its rows back to whoever created them.
+ALTER TABLE inventory_items
+    ADD COLUMN IF NOT EXISTS household_id UUID REFERENCES households(id) ON DELETE SET NULL;
+ALTER TABLE meal_plans
+    ADD COLUMN IF NOT EXISTS household_id UUID REFERENCES households(id) ON DELETE SET NULL;
+ALTER TABLE shopping_list_items
+    ADD COLUMN IF NOT EXISTS household_id UUID REFERENCES households(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_inventory_items_household ON inventory_items(household_id);
+CREATE INDEX IF NOT EXISTS idx_shopping_list_items_household ON shopping_list_items(household_id);
+
+-- One plan per week per owner: the user for personal plans, the household for shared ones
+ALTER TABLE meal_plans DROP CONSTRAINT IF EXISTS meal_plans_user_id_week_start_key;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_meal_plans_user_week
+    ON meal_plans(user_id, week_start) WHERE household_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_meal_plans_household_week
+    ON meal_plans(household_id, week_start) WHERE household_id IS NOT NULL;
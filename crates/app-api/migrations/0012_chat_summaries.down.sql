@@ -0,0 +1,2 @@
+ALTER TABLE chat_sessions DROP COLUMN IF EXISTS summary_through_id;
+ALTER TABLE chat_sessions DROP COLUMN IF EXISTS summary;
@@ -0,0 +1,5 @@
+-- Rolling summary of a chat session's older turns. Messages up to and
+-- including summary_through_id are represented by `summary` in the prompt
+-- instead of verbatim; they stay in chat_messages for the history view.
+ALTER TABLE chat_sessions ADD COLUMN IF NOT EXISTS summary TEXT;
+ALTER TABLE chat_sessions ADD COLUMN IF NOT EXISTS summary_through_id BIGINT;
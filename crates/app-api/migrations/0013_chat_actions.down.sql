@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS chat_tool_audit;
+DROP TABLE IF EXISTS chat_pending_actions;
@@ -0,0 +1,34 @@
+-- Destructive tool calls proposed by the assistant. Nothing is changed until
+-- the user confirms; unconfirmed proposals lapse at expires_at.
+CREATE TABLE IF NOT EXISTS chat_pending_actions (
+    id           UUID PRIMARY KEY,
+    session_id   BIGINT NOT NULL REFERENCES chat_sessions(id) ON DELETE CASCADE,
+    user_id      UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tool_name    TEXT NOT NULL,
+    arguments    JSONB NOT NULL,
+    preview      TEXT NOT NULL,
+    status       TEXT NOT NULL DEFAULT 'pending'
+                 CHECK (status IN ('pending', 'confirmed', 'rejected', 'failed')),
+    expires_at   TIMESTAMPTZ NOT NULL,
+    resolved_at  TIMESTAMPTZ,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_pending_actions_user ON chat_pending_actions(user_id, status);
+
+-- Every tool the assistant invoked, with its arguments. Kept when the chat
+-- session is deleted.
+CREATE TABLE IF NOT EXISTS chat_tool_audit (
+    id          BIGSERIAL PRIMARY KEY,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    session_id  BIGINT REFERENCES chat_sessions(id) ON DELETE SET NULL,
+    tool_name   TEXT NOT NULL,
+    arguments   JSONB NOT NULL,
+    outcome     TEXT NOT NULL
+                CHECK (outcome IN ('executed', 'failed', 'proposed', 'confirmed', 'rejected')),
+    action_id   UUID REFERENCES chat_pending_actions(id) ON DELETE SET NULL,
+    result      TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_tool_audit_user ON chat_tool_audit(user_id, created_at DESC);
@@ -0,0 +1 @@
+ALTER TABLE ingredients DROP COLUMN IF EXISTS reference_price_per_kg;
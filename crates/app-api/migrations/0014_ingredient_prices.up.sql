@@ -0,0 +1,3 @@
+-- Reference shelf price per kilogram, used to estimate recipe and meal plan
+-- costs when no current store promotion is matched to the ingredient.
+ALTER TABLE ingredients ADD COLUMN IF NOT EXISTS reference_price_per_kg NUMERIC(10,2);
@@ -0,0 +1,11 @@
+ALTER TABLE users DROP COLUMN IF EXISTS nutrition_targets_manual;
+ALTER TABLE users DROP COLUMN IF EXISTS target_fiber_g;
+ALTER TABLE users DROP COLUMN IF EXISTS target_fat_g;
+ALTER TABLE users DROP COLUMN IF EXISTS target_carbs_g;
+ALTER TABLE users DROP COLUMN IF EXISTS target_protein_g;
+ALTER TABLE users DROP COLUMN IF EXISTS target_calories;
+ALTER TABLE users DROP COLUMN IF EXISTS activity_level;
+ALTER TABLE users DROP COLUMN IF EXISTS height_cm;
+ALTER TABLE users DROP COLUMN IF EXISTS weight_kg;
+ALTER TABLE users DROP COLUMN IF EXISTS sex;
+ALTER TABLE users DROP COLUMN IF EXISTS birth_year;
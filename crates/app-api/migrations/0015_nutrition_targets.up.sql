@@ -0,0 +1,17 @@
+-- Body metrics and daily nutrition targets per user. Targets are computed
+-- from the metrics, activity level and health goals whenever the profile
+-- changes, unless the user has set them by hand (nutrition_targets_manual).
+ALTER TABLE users ADD COLUMN IF NOT EXISTS birth_year INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS sex TEXT
+    CHECK (sex IN ('female', 'male'));
+ALTER TABLE users ADD COLUMN IF NOT EXISTS weight_kg NUMERIC(5,1);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS height_cm NUMERIC(5,1);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS activity_level TEXT
+    CHECK (activity_level IN ('sedentary', 'light', 'moderate', 'active', 'very_active'));
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS target_calories INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS target_protein_g INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS target_carbs_g INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS target_fat_g INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS target_fiber_g INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS nutrition_targets_manual BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE meal_plans DROP COLUMN IF EXISTS generation_seed;
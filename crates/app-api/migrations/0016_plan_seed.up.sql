@@ -0,0 +1,3 @@
+-- Seed the optimiser used to generate a plan, so the same inputs can
+-- reproduce the same week. NULL for plans built by hand.
+ALTER TABLE meal_plans ADD COLUMN IF NOT EXISTS generation_seed BIGINT;
@@ -0,0 +1 @@
+ALTER TABLE meal_plan_slots DROP COLUMN IF EXISTS is_locked;
@@ -0,0 +1,3 @@
+-- Locked slots are kept when a plan is generated or regenerated.
+-- Existing hand-picked slots cannot be told apart, so everything starts unlocked.
+ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS is_locked BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,7 @@
+ALTER TABLE meal_plan_slots
+    DROP COLUMN IF EXISTS leftover_of_slot_id,
+    DROP COLUMN IF EXISTS is_leftover;
+
+DROP INDEX IF EXISTS idx_leftovers_household;
+DROP INDEX IF EXISTS idx_leftovers_user;
+DROP TABLE IF EXISTS leftovers;
@@ -0,0 +1,22 @@
+-- Cooked servings kept for later. Stored alongside the pantry and shared
+-- with the household like inventory_items.
+CREATE TABLE IF NOT EXISTS leftovers (
+    id                BIGSERIAL PRIMARY KEY,
+    user_id           UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    household_id      UUID REFERENCES households(id) ON DELETE SET NULL,
+    recipe_id         BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    servings          INT NOT NULL CHECK (servings > 0),
+    storage_location  TEXT NOT NULL DEFAULT 'fridge',
+    expiry_date       DATE NOT NULL,
+    cooked_at         TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_leftovers_user ON leftovers(user_id, expiry_date);
+CREATE INDEX IF NOT EXISTS idx_leftovers_household ON leftovers(household_id);
+
+-- Slots that eat leftovers instead of cooking. A planned batch cook links its
+-- leftover slots to the slot that cooks them; leftovers already stored have
+-- no cooking slot.
+ALTER TABLE meal_plan_slots
+    ADD COLUMN IF NOT EXISTS is_leftover BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS leftover_of_slot_id BIGINT REFERENCES meal_plan_slots(id) ON DELETE SET NULL;
@@ -0,0 +1,38 @@
+-- Which push notifications a user wants, and when not to deliver them.
+-- Quiet hours are local wall-clock times; the window may wrap past midnight.
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id             UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    enabled             BOOLEAN NOT NULL DEFAULT TRUE,
+    expiry_alerts       BOOLEAN NOT NULL DEFAULT TRUE,
+    meal_plan_updates   BOOLEAN NOT NULL DEFAULT TRUE,
+    household_activity  BOOLEAN NOT NULL DEFAULT TRUE,
+    quiet_hours_start   TIME,
+    quiet_hours_end     TIME,
+    utc_offset_minutes  SMALLINT NOT NULL DEFAULT 0 CHECK (utc_offset_minutes BETWEEN -840 AND 840),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Notifications waiting to be pushed. The dispatcher claims due rows, sends
+-- them to every registered device and reschedules failures with backoff.
+CREATE TABLE IF NOT EXISTS notification_outbox (
+    id               BIGSERIAL PRIMARY KEY,
+    user_id          UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind             TEXT NOT NULL,
+    title            TEXT NOT NULL,
+    body             TEXT NOT NULL,
+    data             JSONB NOT NULL DEFAULT '{}',
+    -- Same key for the same user is only ever queued once (e.g. one alert per item per day)
+    dedupe_key       TEXT,
+    status           TEXT NOT NULL DEFAULT 'pending'
+                     CHECK (status IN ('pending', 'sent', 'failed', 'skipped')),
+    attempts         INT NOT NULL DEFAULT 0,
+    next_attempt_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error       TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at          TIMESTAMPTZ,
+    UNIQUE (user_id, dedupe_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_outbox_due
+    ON notification_outbox(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_notification_outbox_user ON notification_outbox(user_id, created_at DESC);
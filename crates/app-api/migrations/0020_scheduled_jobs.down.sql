@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_scheduled_job_runs_failed;
+DROP INDEX IF EXISTS idx_scheduled_job_runs_job;
+DROP TABLE IF EXISTS scheduled_job_runs;
+DROP TABLE IF EXISTS scheduled_jobs;
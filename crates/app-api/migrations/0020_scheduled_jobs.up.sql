@@ -0,0 +1,34 @@
+-- Recurring background jobs. Rows are created by the app for every job it
+-- knows; admins can pause a job, change its interval or run it now.
+-- A replica runs a job by leasing its row (locked_until) — see
+-- services::scheduler.
+CREATE TABLE IF NOT EXISTS scheduled_jobs (
+    name                  TEXT PRIMARY KEY,
+    enabled               BOOLEAN NOT NULL DEFAULT TRUE,
+    interval_secs         INT NOT NULL CHECK (interval_secs > 0),
+    next_run_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until          TIMESTAMPTZ,
+    locked_by             TEXT,
+    last_started_at       TIMESTAMPTZ,
+    last_finished_at      TIMESTAMPTZ,
+    last_status           TEXT,
+    last_error            TEXT,
+    consecutive_failures  INT NOT NULL DEFAULT 0,
+    updated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One row per run, for the admin history
+CREATE TABLE IF NOT EXISTS scheduled_job_runs (
+    id           BIGSERIAL PRIMARY KEY,
+    job_name     TEXT NOT NULL REFERENCES scheduled_jobs(name) ON DELETE CASCADE,
+    instance     TEXT NOT NULL,
+    status       TEXT NOT NULL DEFAULT 'running'
+                 CHECK (status IN ('running', 'succeeded', 'failed')),
+    started_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at  TIMESTAMPTZ,
+    summary      JSONB,
+    error        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_job_runs_job ON scheduled_job_runs(job_name, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_scheduled_job_runs_failed ON scheduled_job_runs(started_at DESC) WHERE status = 'failed';
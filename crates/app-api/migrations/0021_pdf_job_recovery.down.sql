@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS pdf_job_pages;
+DROP INDEX IF EXISTS idx_pdf_jobs_due;
+ALTER TABLE pdf_processing_jobs
+    DROP COLUMN IF EXISTS page_count,
+    DROP COLUMN IF EXISTS claimed_by,
+    DROP COLUMN IF EXISTS next_attempt_at;
@@ -0,0 +1,23 @@
+-- Durable flyer processing: workers claim pending jobs from the table, keep
+-- heartbeat_at fresh while they run, and a reaper requeues jobs whose
+-- worker went silent. retry_count counts failed attempts.
+ALTER TABLE pdf_processing_jobs
+    ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN IF NOT EXISTS claimed_by TEXT,
+    ADD COLUMN IF NOT EXISTS page_count INT;
+
+CREATE INDEX IF NOT EXISTS idx_pdf_jobs_due
+    ON pdf_processing_jobs(next_attempt_at) WHERE status = 'pending';
+
+-- Per-page progress, so a retried job only redoes the pages that failed.
+-- A page's candidates and its 'done' row are written in one transaction.
+CREATE TABLE IF NOT EXISTS pdf_job_pages (
+    job_id       UUID NOT NULL REFERENCES pdf_processing_jobs(id) ON DELETE CASCADE,
+    page_number  INT NOT NULL CHECK (page_number > 0),
+    status       TEXT NOT NULL CHECK (status IN ('done', 'failed')),
+    attempts     INT NOT NULL DEFAULT 1,
+    candidates   INT NOT NULL DEFAULT 0,
+    error        TEXT,
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, page_number)
+);
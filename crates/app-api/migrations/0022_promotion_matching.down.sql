@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_store_promotions_match_status;
+ALTER TABLE store_promotions DROP COLUMN IF EXISTS match_status;
+DROP TABLE IF EXISTS ingredient_aliases;
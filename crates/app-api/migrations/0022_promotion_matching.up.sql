@@ -0,0 +1,28 @@
+-- Promotion → ingredient matching. Approved promotions are matched to
+-- ingredients by pg_trgm similarity against ingredient names and aliases;
+-- admins confirm or override the result.
+
+-- Other names an ingredient is sold under ("semi-skimmed milk", "leite").
+-- Stored normalised (lowercase, no brand or pack size).
+CREATE TABLE IF NOT EXISTS ingredient_aliases (
+    id              BIGSERIAL PRIMARY KEY,
+    ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
+    alias           TEXT NOT NULL CHECK (alias <> ''),
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (ingredient_id, alias)
+);
+CREATE INDEX IF NOT EXISTS idx_ingredient_aliases_trgm
+    ON ingredient_aliases USING GIN (alias gin_trgm_ops);
+
+-- pending   — not matched yet (promotions approved before this migration)
+-- auto      — linked by the matcher, awaiting review
+-- unmatched — the matcher found nothing close enough
+-- confirmed — an admin confirmed or set the links (possibly none)
+ALTER TABLE store_promotions
+    ADD COLUMN IF NOT EXISTS match_status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (match_status IN ('pending', 'auto', 'unmatched', 'confirmed'));
+-- Links that already exist were set up by hand; keep them as they are
+UPDATE store_promotions SET match_status = 'confirmed'
+WHERE id IN (SELECT promotion_id FROM store_promotion_ingredients);
+CREATE INDEX IF NOT EXISTS idx_store_promotions_match_status
+    ON store_promotions(match_status) WHERE match_status <> 'confirmed';
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_candidates_store_review;
+ALTER TABLE store_promotion_candidates
+    DROP COLUMN IF EXISTS duplicate_of,
+    DROP COLUMN IF EXISTS edited_at,
+    DROP COLUMN IF EXISTS edited_by;
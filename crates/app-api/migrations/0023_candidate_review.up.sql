@@ -0,0 +1,12 @@
+-- Admin review tooling for promotion candidates: edits before approval are
+-- recorded (how often the extraction needed fixing), and candidates that
+-- duplicate a live promotion are set aside as 'duplicate' rather than
+-- counted as rejections.
+ALTER TABLE store_promotion_candidates
+    ADD COLUMN IF NOT EXISTS edited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS edited_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS duplicate_of UUID REFERENCES store_promotions(id) ON DELETE SET NULL;
+
+-- Review queues and bulk actions select a store's candidates by status
+CREATE INDEX IF NOT EXISTS idx_candidates_store_review
+    ON store_promotion_candidates(store_id, review_status);
@@ -4,10 +4,29 @@
 //! Required variables cause a hard startup failure so misconfiguration is
 //! caught at launch time rather than at the first request.
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use secrecy::{ExposeSecret, SecretString};
 use cookest_shared::config::ConfigError;
+use cookest_shared::middleware::rate_limit::TrustedProxies;
 use std::env;
 
+/// Where the AI features send their model calls (`LLM_PROVIDER`)
+#[derive(Clone)]
+pub enum LlmBackend {
+    Ollama { url: String },
+    OpenAi { base_url: String, api_key: Option<SecretString> },
+    /// Scripted offline replies, optionally from a rules file
+    Fake { script: Option<String> },
+}
+
+/// Where push notifications are sent (`PUSH_PROVIDER`)
+#[derive(Clone)]
+pub enum PushBackend {
+    Expo { url: String, access_token: Option<SecretString> },
+    /// Logged instead of sent
+    Mock,
+}
+
 /// Validated, immutable snapshot of every env-var this service needs.
 ///
 /// Constructed once at startup via [`Config::from_env`] and then shared as
@@ -22,16 +41,34 @@ pub struct Config {
     pub host: String,
     pub port: u16,
     pub cors_origin: String,
-    pub ollama_url: String,
-    pub ollama_model: String,
+    pub llm_backend: LlmBackend,
+    pub llm_chat_model: String,
+    pub llm_vision_model: String,
+    pub llm_timeout_secs: u64,
+    pub llm_stream_timeout_secs: u64,
+    pub llm_context_tokens: Option<usize>,
     pub pdf_upload_dir: String,
     pub stripe_webhook_secret: Option<String>,
+    pub stripe_secret_key: Option<SecretString>,
+    pub stripe_api_base: String,
+    pub stripe_price_pro: Option<String>,
+    pub stripe_price_family: Option<String>,
+    pub stripe_redirect_origins: Vec<String>,
     pub food_api_url: String,
     pub food_api_key: Option<String>,
     pub resend_api_key: Option<SecretString>,
     pub resend_from_email: String,
+    pub require_verified_email: bool,
+    pub totp_encryption_key: Option<SecretString>,
     pub image_gen_url: String,
     pub image_gen_token: Option<String>,
+    pub rate_limit_auth_rpm: u32,
+    pub rate_limit_api_rpm: u32,
+    pub trusted_proxies: TrustedProxies,
+    pub push_backend: PushBackend,
+    pub notification_dispatch_secs: u64,
+    pub scheduler_enabled: bool,
+    pub scheduler_poll_secs: u64,
 }
 
 impl Config {
@@ -45,11 +82,35 @@ impl Config {
     /// - `JWT_ACCESS_EXPIRY_SECONDS` (900 = 15 min)
     /// - `JWT_REFRESH_EXPIRY_SECONDS` (604800 = 7 days)
     /// - `HOST` (127.0.0.1), `PORT` (8080)
-    /// - `CORS_ORIGIN`, `OLLAMA_URL`, `OLLAMA_MODEL`
+    /// - `CORS_ORIGIN`
+    /// - `LLM_PROVIDER` (ollama) — `ollama`, `openai` or `fake`
+    ///   - ollama: `OLLAMA_URL`, `OLLAMA_MODEL` (llama3.2), `OLLAMA_VISION_MODEL` (qwen2.5vl:7b)
+    ///   - openai: `OPENAI_BASE_URL` (https://api.openai.com/v1), `OPENAI_API_KEY`,
+    ///     `OPENAI_MODEL` (gpt-4o-mini), `OPENAI_VISION_MODEL` (same as `OPENAI_MODEL`)
+    ///   - fake: `LLM_FAKE_SCRIPT` — JSON rules file (see `services::llm::fake`)
+    /// - `LLM_TIMEOUT_SECS` (180) — per non-streaming model call, and the longest
+    ///   a stream may go quiet; falls back to the older `OLLAMA_VISION_TIMEOUT_SECS`
+    /// - `LLM_STREAM_TIMEOUT_SECS` (600) — per streaming model call, start to end
+    /// - `LLM_CONTEXT_TOKENS` — chat model context window; defaults per model
+    ///   (8192 on Ollama, where it is sent as `num_ctx`)
     /// - `PDF_UPLOAD_DIR`, `FOOD_API_URL`, `FOOD_API_KEY`
     /// - `RESEND_API_KEY`, `RESEND_FROM_EMAIL`
+    /// - `REQUIRE_VERIFIED_EMAIL` (false) — gate the AI chat on a verified email
+    /// - `TOTP_ENCRYPTION_KEY` — base64 32-byte key encrypting 2FA secrets; 2FA
+    ///   enrollment is unavailable without it
     /// - `IMAGE_GEN_URL`, `IMAGE_GEN_TOKEN`
-    /// - `STRIPE_WEBHOOK_SECRET`
+    /// - `RATE_LIMIT_AUTH_RPM` (20 per client IP), `RATE_LIMIT_API_RPM` (300 per user)
+    /// - `TRUSTED_PROXIES` — comma-separated IPs/CIDRs allowed to set `X-Forwarded-For`
+    /// - `STRIPE_WEBHOOK_SECRET`, `STRIPE_SECRET_KEY`
+    /// - `STRIPE_API_BASE` (https://api.stripe.com — point at a mock server in tests)
+    /// - `STRIPE_PRICE_PRO`, `STRIPE_PRICE_FAMILY` — price IDs that grant each tier
+    /// - `STRIPE_REDIRECT_ORIGINS` (https://m.cookest.app) — comma-separated
+    ///   origins Checkout and the Billing Portal may send users back to
+    /// - `PUSH_PROVIDER` (expo) — `expo` or `mock`
+    ///   - expo: `EXPO_PUSH_URL` (https://exp.host/--/api/v2/push/send), `EXPO_ACCESS_TOKEN`
+    /// - `NOTIFICATION_DISPATCH_SECS` (15) — how often the notification outbox is drained
+    /// - `SCHEDULER_ENABLED` (true) — run scheduled jobs on this replica
+    /// - `SCHEDULER_POLL_SECS` (30) — how often to look for due jobs
     pub fn from_env() -> Result<Self, ConfigError> {
         dotenvy::dotenv().ok();
 
@@ -87,17 +148,85 @@ impl Config {
         let cors_origin = env::var("CORS_ORIGIN")
             .unwrap_or_else(|_| "http://localhost:3000".to_string());
 
-        let ollama_url = env::var("OLLAMA_URL")
-            .unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let (llm_backend, llm_chat_model, llm_vision_model) =
+            match env::var("LLM_PROVIDER").unwrap_or_else(|_| "ollama".to_string()).trim() {
+                "ollama" => (
+                    LlmBackend::Ollama {
+                        url: env::var("OLLAMA_URL").unwrap_or_else(|_| "http://localhost:11434".to_string()),
+                    },
+                    env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.2".to_string()),
+                    env::var("OLLAMA_VISION_MODEL").unwrap_or_else(|_| "qwen2.5vl:7b".to_string()),
+                ),
+                "openai" => {
+                    let model = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+                    (
+                        LlmBackend::OpenAi {
+                            base_url: env::var("OPENAI_BASE_URL")
+                                .unwrap_or_else(|_| "https://api.openai.com/v1".to_string()),
+                            api_key: env::var("OPENAI_API_KEY")
+                                .ok()
+                                .filter(|k| !k.is_empty())
+                                .map(SecretString::from),
+                        },
+                        model.clone(),
+                        env::var("OPENAI_VISION_MODEL").unwrap_or(model),
+                    )
+                }
+                "fake" => (
+                    LlmBackend::Fake {
+                        script: env::var("LLM_FAKE_SCRIPT").ok().filter(|p| !p.is_empty()),
+                    },
+                    "fake".to_string(),
+                    "fake".to_string(),
+                ),
+                _ => {
+                    return Err(ConfigError::InvalidValue(
+                        "LLM_PROVIDER must be one of ollama, openai, fake",
+                    ))
+                }
+            };
 
-        let ollama_model = env::var("OLLAMA_MODEL")
-            .unwrap_or_else(|_| "llava".to_string());
+        let llm_timeout_secs: u64 = env::var("LLM_TIMEOUT_SECS")
+            .or_else(|_| env::var("OLLAMA_VISION_TIMEOUT_SECS"))
+            .unwrap_or_else(|_| "180".to_string())
+            .parse()
+            .map_err(|_| ConfigError::InvalidValue("LLM_TIMEOUT_SECS must be a number"))?;
+
+        let llm_stream_timeout_secs: u64 = env::var("LLM_STREAM_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse()
+            .map_err(|_| ConfigError::InvalidValue("LLM_STREAM_TIMEOUT_SECS must be a number"))?;
+
+        let llm_context_tokens: Option<usize> = env::var("LLM_CONTEXT_TOKENS")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().ok().filter(|n| *n >= 2_048))
+            .map(|n| n.ok_or(ConfigError::InvalidValue("LLM_CONTEXT_TOKENS must be a number of at least 2048")))
+            .transpose()?;
 
         let pdf_upload_dir = env::var("PDF_UPLOAD_DIR")
             .unwrap_or_else(|_| "./cookest_pdfs".to_string());
 
         let stripe_webhook_secret = env::var("STRIPE_WEBHOOK_SECRET").ok();
 
+        let stripe_secret_key = env::var("STRIPE_SECRET_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .map(SecretString::from);
+
+        let stripe_api_base = env::var("STRIPE_API_BASE")
+            .unwrap_or_else(|_| "https://api.stripe.com".to_string());
+
+        let stripe_price_pro = env::var("STRIPE_PRICE_PRO").ok().filter(|p| !p.is_empty());
+        let stripe_price_family = env::var("STRIPE_PRICE_FAMILY").ok().filter(|p| !p.is_empty());
+
+        let stripe_redirect_origins: Vec<String> = env::var("STRIPE_REDIRECT_ORIGINS")
+            .unwrap_or_else(|_| "https://m.cookest.app".to_string())
+            .split(',')
+            .map(|o| o.trim().trim_end_matches('/').to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+
         let food_api_url = env::var("FOOD_API_URL")
             .unwrap_or_else(|_| "http://localhost:8081".to_string());
 
@@ -110,11 +239,70 @@ impl Config {
         let resend_from_email = env::var("RESEND_FROM_EMAIL")
             .unwrap_or_else(|_| "noreply@m.cookest.app".to_string());
 
+        let require_verified_email = env::var("REQUIRE_VERIFIED_EMAIL")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        let totp_encryption_key = env::var("TOTP_ENCRYPTION_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .map(|k| match BASE64.decode(k.trim()) {
+                Ok(bytes) if bytes.len() == 32 => Ok(SecretString::from(k.trim().to_string())),
+                _ => Err(ConfigError::InvalidValue(
+                    "TOTP_ENCRYPTION_KEY must be 32 bytes, base64-encoded",
+                )),
+            })
+            .transpose()?;
+
         let image_gen_url = env::var("IMAGE_GEN_URL")
             .unwrap_or_else(|_| "http://localhost:8082".to_string());
 
         let image_gen_token = env::var("IMAGE_GEN_TOKEN").ok();
 
+        let rate_limit_auth_rpm: u32 = env::var("RATE_LIMIT_AUTH_RPM")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .map_err(|_| ConfigError::InvalidValue("RATE_LIMIT_AUTH_RPM must be a number"))?;
+
+        let rate_limit_api_rpm: u32 = env::var("RATE_LIMIT_API_RPM")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()
+            .map_err(|_| ConfigError::InvalidValue("RATE_LIMIT_API_RPM must be a number"))?;
+
+        let trusted_proxies = TrustedProxies::parse(&env::var("TRUSTED_PROXIES").unwrap_or_default())
+            .map_err(|_| ConfigError::InvalidValue("TRUSTED_PROXIES must be a list of IPs or CIDRs"))?;
+
+        let push_backend = match env::var("PUSH_PROVIDER").unwrap_or_else(|_| "expo".to_string()).trim() {
+            "expo" => PushBackend::Expo {
+                url: env::var("EXPO_PUSH_URL")
+                    .unwrap_or_else(|_| "https://exp.host/--/api/v2/push/send".to_string()),
+                access_token: env::var("EXPO_ACCESS_TOKEN")
+                    .ok()
+                    .filter(|t| !t.is_empty())
+                    .map(SecretString::from),
+            },
+            "mock" => PushBackend::Mock,
+            _ => return Err(ConfigError::InvalidValue("PUSH_PROVIDER must be one of expo, mock")),
+        };
+
+        let notification_dispatch_secs: u64 = env::var("NOTIFICATION_DISPATCH_SECS")
+            .unwrap_or_else(|_| "15".to_string())
+            .parse()
+            .ok()
+            .filter(|s| *s > 0)
+            .ok_or(ConfigError::InvalidValue("NOTIFICATION_DISPATCH_SECS must be a positive number"))?;
+
+        let scheduler_enabled = env::var("SCHEDULER_ENABLED")
+            .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+            .unwrap_or(true);
+
+        let scheduler_poll_secs: u64 = env::var("SCHEDULER_POLL_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .ok()
+            .filter(|s| *s > 0)
+            .ok_or(ConfigError::InvalidValue("SCHEDULER_POLL_SECS must be a positive number"))?;
+
         Ok(Self {
             database_url: SecretString::from(database_url),
             jwt_secret: SecretString::from(jwt_secret),
@@ -123,16 +311,34 @@ impl Config {
             host,
             port,
             cors_origin,
-            ollama_url,
-            ollama_model,
+            llm_backend,
+            llm_chat_model,
+            llm_vision_model,
+            llm_timeout_secs,
+            llm_stream_timeout_secs,
+            llm_context_tokens,
             pdf_upload_dir,
             stripe_webhook_secret,
+            stripe_secret_key,
+            stripe_api_base,
+            stripe_price_pro,
+            stripe_price_family,
+            stripe_redirect_origins,
             food_api_url,
             food_api_key,
             resend_api_key,
             resend_from_email,
+            require_verified_email,
+            totp_encryption_key,
             image_gen_url,
             image_gen_token,
+            rate_limit_auth_rpm,
+            rate_limit_api_rpm,
+            trusted_proxies,
+            push_backend,
+            notification_dispatch_secs,
+            scheduler_enabled,
+            scheduler_poll_secs,
         })
     }
 
@@ -151,4 +357,10 @@ impl Config {
     pub fn jwt_secret(&self) -> &str {
         self.jwt_secret.expose_secret()
     }
+
+    /// Decoded 2FA secret encryption key (length checked in `from_env`).
+    pub fn totp_key(&self) -> Option<[u8; 32]> {
+        let key = self.totp_encryption_key.as_ref()?;
+        BASE64.decode(key.expose_secret()).ok()?.try_into().ok()
+    }
 }
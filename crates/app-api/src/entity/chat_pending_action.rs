@@ -0,0 +1,57 @@
+//! Chat pending action entity — a destructive tool call the assistant
+//! proposed, waiting for the user to confirm or reject it
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "chat_pending_actions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub session_id: i64,
+
+    pub user_id: Uuid,
+
+    #[sea_orm(column_type = "Text")]
+    pub tool_name: String,
+
+    /// Arguments exactly as the model sent them; replayed on confirmation
+    #[sea_orm(column_type = "JsonBinary")]
+    pub arguments: Json,
+
+    /// What will happen, in words shown to the user
+    #[sea_orm(column_type = "Text")]
+    pub preview: String,
+
+    /// "pending" | "confirmed" | "rejected" | "failed"
+    #[sea_orm(column_type = "Text")]
+    pub status: String,
+
+    pub expires_at: DateTimeWithTimeZone,
+
+    pub resolved_at: Option<DateTimeWithTimeZone>,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::chat_session::Entity",
+        from = "Column::SessionId",
+        to = "super::chat_session::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    ChatSession,
+}
+
+impl Related<super::chat_session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChatSession.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
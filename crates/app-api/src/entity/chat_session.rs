@@ -20,6 +20,12 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub title: Option<String>,
 
+    /// Rolling summary of the turns up to `summary_through_id`, sent to the
+    /// model in place of those messages
+    #[sea_orm(column_type = "Text", nullable)]
+    pub summary: Option<String>,
+    pub summary_through_id: Option<i64>,
+
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
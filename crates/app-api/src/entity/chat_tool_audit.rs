@@ -0,0 +1,41 @@
+//! Chat tool audit entity — one row per tool the assistant invoked
+//! (or proposed), with its arguments and outcome
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "chat_tool_audit")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    pub user_id: Uuid,
+
+    /// Null once the session is deleted; the audit row stays
+    pub session_id: Option<i64>,
+
+    #[sea_orm(column_type = "Text")]
+    pub tool_name: String,
+
+    #[sea_orm(column_type = "JsonBinary")]
+    pub arguments: Json,
+
+    /// "executed" | "failed" | "proposed" | "confirmed" | "rejected"
+    #[sea_orm(column_type = "Text")]
+    pub outcome: String,
+
+    /// The pending action this row proposed or resolved
+    pub action_id: Option<Uuid>,
+
+    /// Tool output as the model saw it (truncated)
+    #[sea_orm(column_type = "Text", nullable)]
+    pub result: Option<String>,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+//! Email token entity — single-use verification and password-reset tokens
+//! Only the SHA-256 hash is stored; the raw token is only ever sent by email
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "email_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub user_id: Uuid,
+
+    /// "verify_email" | "reset_password"
+    #[sea_orm(column_type = "Text")]
+    pub purpose: String,
+
+    #[sea_orm(column_type = "Text", unique)]
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+
+    pub expires_at: DateTimeWithTimeZone,
+
+    /// Set when the token is redeemed — a used token is never accepted again
+    pub used_at: Option<DateTimeWithTimeZone>,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+//! Household entity — a Family-tier group sharing pantry, meal plan and shopping list
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "households")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub name: String,
+
+    /// The member who created the household and holds the Family subscription
+    pub owner_id: Uuid,
+
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::OwnerId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Owner,
+
+    #[sea_orm(has_many = "super::household_member::Entity")]
+    Members,
+
+    #[sea_orm(has_many = "super::household_invite::Entity")]
+    Invites,
+}
+
+impl Related<super::household_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Members.def()
+    }
+}
+
+impl Related<super::household_invite::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Invites.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
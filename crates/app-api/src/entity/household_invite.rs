@@ -0,0 +1,53 @@
+//! Household invite entity — an expiring, single-use email invitation
+//! Only the SHA-256 hash is stored; the raw token is only ever sent by email
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "household_invites")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub household_id: Uuid,
+
+    /// Lowercased address the invite was sent to — only that account may accept it
+    pub email: String,
+
+    /// Role granted on acceptance: "admin" | "member"
+    #[sea_orm(column_type = "Text")]
+    pub role: String,
+
+    #[sea_orm(column_type = "Text", unique)]
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+
+    pub invited_by: Option<Uuid>,
+
+    pub expires_at: DateTimeWithTimeZone,
+
+    pub accepted_at: Option<DateTimeWithTimeZone>,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::household::Entity",
+        from = "Column::HouseholdId",
+        to = "super::household::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Household,
+}
+
+impl Related<super::household::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Household.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
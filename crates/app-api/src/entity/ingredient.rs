@@ -24,6 +24,14 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub off_id: Option<String>,
 
+    /// Grams per millilitre — converts volume units ("l", "cup") to grams
+    pub density_g_per_ml: Option<Decimal>,
+
+    /// Reference shelf price per kg — cost estimates fall back to this when
+    /// no current store promotion is matched to the ingredient
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))", nullable)]
+    pub reference_price_per_kg: Option<Decimal>,
+
     pub created_at: DateTimeWithTimeZone,
 }
 
@@ -35,11 +43,17 @@ pub enum Relation {
     #[sea_orm(has_many = "super::portion_size::Entity")]
     PortionSizes,
 
+    #[sea_orm(has_many = "super::ingredient_allergen::Entity")]
+    IngredientAllergens,
+
     #[sea_orm(has_many = "super::recipe_ingredient::Entity")]
     RecipeIngredients,
 
     #[sea_orm(has_many = "super::inventory_item::Entity")]
     InventoryItems,
+
+    #[sea_orm(has_many = "super::ingredient_alias::Entity")]
+    Aliases,
 }
 
 impl Related<super::ingredient_nutrient::Entity> for Entity {
@@ -54,6 +68,12 @@ impl Related<super::portion_size::Entity> for Entity {
     }
 }
 
+impl Related<super::ingredient_allergen::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IngredientAllergens.def()
+    }
+}
+
 impl Related<super::recipe_ingredient::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::RecipeIngredients.def()
@@ -66,4 +86,10 @@ impl Related<super::inventory_item::Entity> for Entity {
     }
 }
 
+impl Related<super::ingredient_alias::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Aliases.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+//! Ingredient alias entity
+//! Other names an ingredient is sold under, used when matching store promotions
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ingredient_aliases")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    pub ingredient_id: i64,
+
+    /// Normalised name: lowercase, without brand or pack size
+    #[sea_orm(column_type = "Text")]
+    pub alias: String,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::ingredient::Entity",
+        from = "Column::IngredientId",
+        to = "super::ingredient::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Ingredient,
+}
+
+impl Related<super::ingredient::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Ingredient.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -12,6 +12,9 @@ pub struct Model {
 
     pub user_id: Uuid,
 
+    /// Household sharing this item (NULL = personal pantry)
+    pub household_id: Option<Uuid>,
+
     pub ingredient_id: i64,
 
     /// Custom name override if the user entered it manually
@@ -0,0 +1,65 @@
+//! Leftover entity
+//! Cooked servings of a recipe kept for later, with the date they must be eaten by
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "leftovers")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    pub user_id: Uuid,
+
+    /// Household sharing these leftovers (NULL = personal)
+    pub household_id: Option<Uuid>,
+
+    pub recipe_id: i64,
+
+    /// Servings left
+    pub servings: i32,
+
+    /// "fridge" or "freezer"
+    #[sea_orm(column_type = "Text")]
+    pub storage_location: String,
+
+    pub expiry_date: Date,
+
+    pub cooked_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+
+    #[sea_orm(
+        belongs_to = "super::recipe::Entity",
+        from = "Column::RecipeId",
+        to = "super::recipe::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Recipe,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl Related<super::recipe::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Recipe.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
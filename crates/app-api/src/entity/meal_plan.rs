@@ -12,12 +12,18 @@ pub struct Model {
 
     pub user_id: Uuid,
 
+    /// Household this plan belongs to (NULL = personal plan)
+    pub household_id: Option<Uuid>,
+
     /// Start of the week this plan covers (always a Monday)
     pub week_start: Date,
 
     /// Whether the AI generated this plan automatically
     pub is_ai_generated: bool,
 
+    /// Optimiser seed the plan was generated with (NULL for hand-built plans)
+    pub generation_seed: Option<i64>,
+
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -31,6 +31,15 @@ pub struct Model {
     /// Whether this is a flex/relief day slot (no recipe, intentional rest)
     pub is_flex: bool,
 
+    /// Kept as-is when the plan is generated or regenerated
+    pub is_locked: bool,
+
+    /// Eats leftovers instead of cooking — nothing to buy
+    pub is_leftover: bool,
+
+    /// The batch-cook slot whose extra servings this slot eats
+    pub leftover_of_slot_id: Option<i64>,
+
     /// Type of flex day: "effort" | "nutrition" | "mental" | "social"
     pub flex_type: Option<String>,
 
@@ -1,10 +1,15 @@
 //! SeaORM entity modules — one per database table.
 pub mod user;
+pub mod email_token;
+pub mod user_recovery_code;
+pub mod user_session;
 
 // Ingredient & nutrition layer
 pub mod ingredient;
 pub mod ingredient_nutrient;
 pub mod portion_size;
+pub mod ingredient_allergen;
+pub mod ingredient_alias;
 
 // Recipe system
 pub mod recipe;
@@ -18,8 +23,14 @@ pub mod user_favorite;
 pub mod recipe_rating;
 pub mod cooking_history;
 
+// Households (Family tier)
+pub mod household;
+pub mod household_member;
+pub mod household_invite;
+
 // Inventory
 pub mod inventory_item;
+pub mod leftover;
 
 // Meal planning
 pub mod meal_plan;
@@ -30,10 +41,14 @@ pub mod shopping_list_item;
 
 // Push notifications
 pub mod user_push_token;
+pub mod notification_preference;
+pub mod notification_outbox;
 
 // AI Chat
 pub mod chat_session;
 pub mod chat_message;
+pub mod chat_pending_action;
+pub mod chat_tool_audit;
 
 // ML Preferences
 pub mod user_preference;
@@ -41,8 +56,14 @@ pub mod user_preference;
 // Store & price system
 pub mod store;
 pub mod store_promotion;
+pub mod store_promotion_ingredient;
 pub mod store_promotion_candidate;
 pub mod pdf_processing_job;
+pub mod pdf_job_page;
 
 // Stripe idempotency
 pub mod stripe_processed_event;
+
+// Background jobs
+pub mod scheduled_job;
+pub mod scheduled_job_run;
@@ -0,0 +1,69 @@
+//! Notification outbox entity
+//! Push notifications queued for delivery, with their retry state
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "notification_outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    pub user_id: Uuid,
+
+    /// "expiry" | "meal_plan" | "household" | "test"
+    #[sea_orm(column_type = "Text")]
+    pub kind: String,
+
+    #[sea_orm(column_type = "Text")]
+    pub title: String,
+
+    #[sea_orm(column_type = "Text")]
+    pub body: String,
+
+    /// Payload handed to the app when the notification is opened
+    #[sea_orm(column_type = "JsonBinary")]
+    pub data: Json,
+
+    /// Queued at most once per user — later duplicates are dropped
+    #[sea_orm(column_type = "Text", nullable)]
+    pub dedupe_key: Option<String>,
+
+    /// "pending" | "sent" | "failed" | "skipped"
+    #[sea_orm(column_type = "Text")]
+    pub status: String,
+
+    /// Delivery attempts so far
+    pub attempts: i32,
+
+    /// When a pending notification is next due (pushed back by quiet hours and retries)
+    pub next_attempt_at: DateTimeWithTimeZone,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+
+    pub created_at: DateTimeWithTimeZone,
+
+    pub sent_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,55 @@
+//! Notification preferences entity
+//! Which push notifications a user receives, and their quiet hours
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "notification_preferences")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: Uuid,
+
+    /// Master switch — nothing is pushed when false
+    pub enabled: bool,
+
+    /// Food and leftovers about to expire
+    pub expiry_alerts: bool,
+
+    /// New or regenerated meal plans
+    pub meal_plan_updates: bool,
+
+    /// Members joining or leaving the household
+    pub household_activity: bool,
+
+    /// Local time quiet hours begin (both ends set, or neither)
+    pub quiet_hours_start: Option<Time>,
+
+    /// Local time quiet hours end; earlier than the start means overnight
+    pub quiet_hours_end: Option<Time>,
+
+    /// The user's offset from UTC, used to read quiet hours as local times
+    pub utc_offset_minutes: i16,
+
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
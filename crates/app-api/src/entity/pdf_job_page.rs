@@ -0,0 +1,51 @@
+//! PDF job page entity
+//! Outcome of extracting one page of a flyer, so retries skip finished pages
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pdf_job_pages")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub job_id: Uuid,
+
+    /// 1-based page number
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub page_number: i32,
+
+    /// "done" | "failed"
+    #[sea_orm(column_type = "Text")]
+    pub status: String,
+
+    /// Times the page has been sent to the model
+    pub attempts: i32,
+
+    /// Candidates extracted from the page
+    pub candidates: i32,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error: Option<String>,
+
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::pdf_processing_job::Entity",
+        from = "Column::JobId",
+        to = "super::pdf_processing_job::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Job,
+}
+
+impl Related<super::pdf_processing_job::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Job.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
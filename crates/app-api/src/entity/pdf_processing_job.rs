@@ -22,12 +22,23 @@ pub struct Model {
     /// Error message if status = "failed"
     pub error: Option<String>,
 
-    /// Number of processing attempts (for retry logic)
+    /// Failed processing attempts; the job fails for good at the worker's limit
     pub retry_count: i32,
 
     pub started_at: Option<DateTimeWithTimeZone>,
     pub heartbeat_at: Option<DateTimeWithTimeZone>,
     pub processed_at: Option<DateTimeWithTimeZone>,
+
+    /// A pending job is not claimed before this (retry backoff)
+    pub next_attempt_at: DateTimeWithTimeZone,
+
+    /// Worker instance processing the job
+    #[sea_orm(column_type = "Text", nullable)]
+    pub claimed_by: Option<String>,
+
+    /// Pages in the PDF, known once it has been rasterised
+    pub page_count: Option<i32>,
+
     pub created_at: DateTimeWithTimeZone,
 }
 
@@ -44,6 +55,9 @@ pub enum Relation {
 
     #[sea_orm(has_many = "super::store_promotion_candidate::Entity")]
     Candidates,
+
+    #[sea_orm(has_many = "super::pdf_job_page::Entity")]
+    Pages,
 }
 
 impl Related<super::store::Entity> for Entity {
@@ -58,4 +72,10 @@ impl Related<super::store_promotion_candidate::Entity> for Entity {
     }
 }
 
+impl Related<super::pdf_job_page::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pages.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
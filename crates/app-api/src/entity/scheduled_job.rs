@@ -0,0 +1,57 @@
+//! Scheduled job entity
+//! A recurring background job, its schedule and the lease of the replica running it
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "scheduled_jobs")]
+pub struct Model {
+    /// Job name as registered in code, e.g. "expiry_alerts"
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub name: String,
+
+    /// Paused jobs are never claimed
+    pub enabled: bool,
+
+    /// Time between the end of one successful run and the next
+    pub interval_secs: i32,
+
+    pub next_run_at: DateTimeWithTimeZone,
+
+    /// Lease held by the replica running the job; expired leases are free
+    pub locked_until: Option<DateTimeWithTimeZone>,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub locked_by: Option<String>,
+
+    pub last_started_at: Option<DateTimeWithTimeZone>,
+
+    pub last_finished_at: Option<DateTimeWithTimeZone>,
+
+    /// "succeeded" | "failed"
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_status: Option<String>,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+
+    /// Failed runs in a row — drives the retry backoff
+    pub consecutive_failures: i32,
+
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::scheduled_job_run::Entity")]
+    Runs,
+}
+
+impl Related<super::scheduled_job_run::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Runs.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,54 @@
+//! Scheduled job run entity
+//! One execution of a scheduled job, with its outcome
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "scheduled_job_runs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    #[sea_orm(column_type = "Text")]
+    pub job_name: String,
+
+    /// Replica that ran the job
+    #[sea_orm(column_type = "Text")]
+    pub instance: String,
+
+    /// "running" | "succeeded" | "failed"
+    #[sea_orm(column_type = "Text")]
+    pub status: String,
+
+    pub started_at: DateTimeWithTimeZone,
+
+    pub finished_at: Option<DateTimeWithTimeZone>,
+
+    /// What the job did, e.g. {"notified": 3}
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub summary: Option<Json>,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::scheduled_job::Entity",
+        from = "Column::JobName",
+        to = "super::scheduled_job::Column::Name",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Job,
+}
+
+impl Related<super::scheduled_job::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Job.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -13,6 +13,9 @@ pub struct Model {
 
     pub user_id: Uuid,
 
+    /// Household sharing this list (NULL = personal list)
+    pub household_id: Option<Uuid>,
+
     /// Linked ingredient (NULL for manually-added free-text items)
     pub ingredient_id: Option<i64>,
 
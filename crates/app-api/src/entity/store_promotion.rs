@@ -42,6 +42,10 @@ pub struct Model {
     #[sea_orm(column_type = "Decimal(Some((4, 3)))", nullable)]
     pub confidence: Option<Decimal>,
 
+    /// Ingredient matching state: "pending" | "auto" | "unmatched" | "confirmed"
+    #[sea_orm(column_type = "Text")]
+    pub match_status: String,
+
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -35,12 +35,19 @@ pub struct Model {
     #[sea_orm(column_type = "Decimal(Some((4, 3)))", nullable)]
     pub confidence: Option<Decimal>,
 
-    /// "pending" | "approved" | "rejected"
+    /// "pending" | "approved" | "rejected" | "duplicate"
     pub review_status: String,
 
     pub reviewed_by: Option<Uuid>,
     pub reviewed_at: Option<DateTimeWithTimeZone>,
 
+    /// Admin who last corrected the extracted fields
+    pub edited_by: Option<Uuid>,
+    pub edited_at: Option<DateTimeWithTimeZone>,
+
+    /// Live promotion this candidate repeated, when set aside as a duplicate
+    pub duplicate_of: Option<Uuid>,
+
     pub created_at: DateTimeWithTimeZone,
 }
 
@@ -0,0 +1,54 @@
+//! Store promotion ↔ ingredient link — which master ingredient a flyer product is
+
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "store_promotion_ingredients")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub promotion_id: Uuid,
+
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub ingredient_id: i64,
+
+    /// Name-match similarity 0.0–1.0
+    #[sea_orm(column_type = "Decimal(Some((4, 3)))", nullable)]
+    pub similarity_score: Option<Decimal>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::store_promotion::Entity",
+        from = "Column::PromotionId",
+        to = "super::store_promotion::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    StorePromotion,
+
+    #[sea_orm(
+        belongs_to = "super::ingredient::Entity",
+        from = "Column::IngredientId",
+        to = "super::ingredient::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Ingredient,
+}
+
+impl Related<super::store_promotion::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StorePromotion.def()
+    }
+}
+
+impl Related<super::ingredient::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Ingredient.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,7 +2,7 @@
 //!
 //! Security notes:
 //! - password_hash is never serialized to JSON
-//! - refresh_token_hash stored for secure token rotation
+//! - refresh tokens live per device in user_sessions
 //! - stripe_customer_id excluded from public responses
 
 use rust_decimal::Decimal;
@@ -24,10 +24,6 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub password_hash: String,
 
-    /// Hashed refresh token for secure token rotation
-    #[sea_orm(column_type = "Text", nullable)]
-    pub refresh_token_hash: Option<String>,
-
     /// Number of people in the household — used for auto-scaling recipe portions
     pub household_size: i32,
 
@@ -51,6 +47,9 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub totp_secret: Option<String>,
 
+    /// Last TOTP time step accepted — codes at or before it are replays
+    pub totp_last_used_step: Option<i64>,
+
     /// Tracks failed login attempts for account lockout
     pub failed_login_attempts: i32,
 
@@ -85,6 +84,31 @@ pub struct Model {
     /// Maximum preferred cooking time per meal in minutes
     pub preferred_time_per_meal_min: Option<i32>,
 
+    // ── Body metrics & nutrition targets ─────────────────────────────────
+    pub birth_year: Option<i32>,
+
+    /// "female" or "male" — selects the BMR formula; averaged when unset
+    pub sex: Option<String>,
+
+    #[sea_orm(column_type = "Decimal(Some((5, 1)))", nullable)]
+    pub weight_kg: Option<Decimal>,
+
+    #[sea_orm(column_type = "Decimal(Some((5, 1)))", nullable)]
+    pub height_cm: Option<Decimal>,
+
+    /// "sedentary", "light", "moderate", "active", "very_active"
+    pub activity_level: Option<String>,
+
+    /// Daily targets — computed by `services::nutrition` unless set by hand
+    pub target_calories: Option<i32>,
+    pub target_protein_g: Option<i32>,
+    pub target_carbs_g: Option<i32>,
+    pub target_fat_g: Option<i32>,
+    pub target_fiber_g: Option<i32>,
+
+    /// Targets were entered by the user and are not recomputed
+    pub nutrition_targets_manual: bool,
+
     /// Whether the user has completed the onboarding flow
     pub onboarding_completed: bool,
 
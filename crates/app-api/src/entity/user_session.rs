@@ -0,0 +1,55 @@
+//! User session entity — one row per signed-in device
+//! The refresh token hash is rotated on every refresh; presenting a stale
+//! token of a session revokes it (token reuse detection)
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_sessions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub user_id: Uuid,
+
+    /// SHA-256 of the current refresh token — never exposed
+    #[sea_orm(column_type = "Text")]
+    #[serde(skip_serializing)]
+    pub refresh_token_hash: String,
+
+    /// Client-supplied label, e.g. "Pixel 8"
+    pub device_name: Option<String>,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub user_agent: Option<String>,
+
+    pub ip_address: Option<String>,
+
+    pub created_at: DateTimeWithTimeZone,
+    pub last_used_at: DateTimeWithTimeZone,
+    pub expires_at: DateTimeWithTimeZone,
+
+    /// Set on logout, remote revoke, password change or token reuse
+    pub revoked_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,82 @@
+//! Scheduled job admin — schedules, run history and manual triggers
+//!
+//! Admin endpoints verify is_admin from DB (not JWT), like the store admin.
+
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use validator::Validate;
+
+use cookest_shared::errors::AppError;
+use crate::handlers::store::verify_admin;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::scheduler::{RunsQuery, UpdateJobRequest};
+use crate::services::SchedulerService;
+
+/// Register the job admin routes onto `cfg`. Must be configured before
+/// `configure_stores`, whose `/api/admin` scope would otherwise match first.
+///
+/// - `GET  /api/admin/jobs`
+/// - `GET  /api/admin/jobs/runs?job=&status=&limit=`
+/// - `PUT  /api/admin/jobs/{name}`
+/// - `POST /api/admin/jobs/{name}/run`
+pub fn configure_admin(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/admin/jobs")
+            .route("", web::get().to(list_jobs))
+            .route("/runs", web::get().to(list_runs))
+            .route("/{name}", web::put().to(update_job))
+            .route("/{name}/run", web::post().to(run_job)),
+    );
+}
+
+/// `GET /api/admin/jobs` — every scheduled job with its schedule and last outcome.
+async fn list_jobs(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    scheduler: web::Data<Arc<SchedulerService>>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let jobs = scheduler.list_jobs().await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "jobs": jobs })))
+}
+
+/// `GET /api/admin/jobs/runs` — recent runs, newest first; filter by job or
+/// status (`status=failed` for the failures).
+async fn list_runs(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    scheduler: web::Data<Arc<SchedulerService>>,
+    query: web::Query<RunsQuery>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let runs = scheduler.list_runs(query.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "runs": runs })))
+}
+
+/// `PUT /api/admin/jobs/{name}` — pause/resume a job or change its interval.
+async fn update_job(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    scheduler: web::Data<Arc<SchedulerService>>,
+    path: web::Path<String>,
+    body: web::Json<UpdateJobRequest>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    body.validate()?;
+    let job = scheduler.update_job(&path.into_inner(), body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// `POST /api/admin/jobs/{name}/run` — make a job due now. It runs on the
+/// next scheduler pass of whichever replica claims it.
+async fn run_job(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    scheduler: web::Data<Arc<SchedulerService>>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let job = scheduler.trigger(&path.into_inner()).await?;
+    Ok(HttpResponse::Accepted().json(job))
+}
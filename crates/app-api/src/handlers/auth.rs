@@ -1,25 +1,35 @@
-//! Authentication handlers for register, login, refresh, and logout
+//! Authentication handlers for register, login, refresh, logout, email
+//! verification and password reset
 //! 
 //! Security features:
 //! - Request body size limits
 //! - Input validation before processing
 //! - HttpOnly cookies for refresh tokens
-//! - Generic error messages
+//! - Generic error messages (forgot-password / resend never reveal whether
+//!   an account exists)
+//! - Emails are sent in the background so response timing doesn't either
 
 use actix_web::{cookie::{Cookie, SameSite}, web, HttpRequest, HttpResponse};
 use std::sync::Arc;
 use validator::Validate;
 
 use cookest_shared::errors::AppError;
-use crate::middleware::{RateLimit, RateLimitConfig};
-use crate::services::AuthService;
-use crate::validation::{LoginRequest, RegisterRequest};
+use crate::middleware::RateLimit;
+use crate::services::auth::LoginOutcome;
+use crate::services::session::DeviceInfo;
+use cookest_shared::middleware::rate_limit::{client_ip, TrustedProxies};
+use crate::services::{AuthService, EmailService};
+use crate::validation::{
+    EmailRequest, LoginRequest, RegisterRequest, ResetPasswordRequest, TwoFactorLoginRequest,
+    VerifyEmailRequest,
+};
 
 /// POST /api/auth/register
 /// 
 /// Creates a new user account with validated email and password
 pub async fn register(
     auth_service: web::Data<Arc<AuthService>>,
+    email_service: web::Data<Arc<EmailService>>,
     body: web::Json<RegisterRequest>,
 ) -> Result<HttpResponse, AppError> {
     // Validate input
@@ -27,6 +37,13 @@ pub async fn register(
 
     let user = auth_service.register(body.into_inner()).await?;
 
+    let token = auth_service.create_verification_token(user.id).await?;
+    let email_service = email_service.get_ref().clone();
+    let to = user.email.clone();
+    actix_web::rt::spawn(async move {
+        let _ = email_service.send_verification_email(to, token).await;
+    });
+
     Ok(HttpResponse::Created().json(serde_json::json!({
         "message": "User registered successfully",
         "user": user
@@ -35,30 +52,53 @@ pub async fn register(
 
 /// POST /api/auth/login
 /// 
-/// Authenticates user and returns access token + refresh token (in cookie)
+/// Authenticates user and returns access token + refresh token (in cookie).
+/// With 2FA enabled it returns `{ mfa_required, mfa_token, expires_in }`
+/// instead; finish at `POST /api/auth/2fa/verify`.
 pub async fn login(
     auth_service: web::Data<Arc<AuthService>>,
+    trusted_proxies: web::Data<TrustedProxies>,
     req: HttpRequest,
     body: web::Json<LoginRequest>,
 ) -> Result<HttpResponse, AppError> {
     // Validate input
     body.validate()?;
 
-    let (token_pair, refresh_token, _user) = auth_service.login(body.into_inner()).await?;
+    let body = body.into_inner();
+    let device = device_info(&req, &trusted_proxies, body.device_name.clone());
 
-    let secure_cookie = should_use_secure_cookie(&req);
+    match auth_service.login(body, device).await? {
+        LoginOutcome::Authenticated { tokens, refresh_token } => Ok(HttpResponse::Ok()
+            .cookie(refresh_cookie(&req, refresh_token))
+            .json(tokens)),
+        LoginOutcome::MfaRequired { mfa_token, expires_in } => {
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "mfa_required": true,
+                "mfa_token": mfa_token,
+                "expires_in": expires_in
+            })))
+        }
+    }
+}
 
-    // Create HttpOnly cookie for refresh token
-    let refresh_cookie = Cookie::build("refresh_token", refresh_token)
-        .path("/api/auth")
-        .http_only(true)
-        .secure(secure_cookie)
-        .same_site(SameSite::Strict)
-        .max_age(cookie::time::Duration::days(7))
-        .finish();
+/// POST /api/auth/2fa/verify
+///
+/// Second login step: TOTP or recovery code → access token + refresh cookie
+pub async fn verify_two_factor(
+    auth_service: web::Data<Arc<AuthService>>,
+    trusted_proxies: web::Data<TrustedProxies>,
+    req: HttpRequest,
+    body: web::Json<TwoFactorLoginRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+
+    let device = device_info(&req, &trusted_proxies, body.device_name.clone());
+    let (token_pair, refresh_token) = auth_service
+        .verify_two_factor(&body.mfa_token, &body.code, device)
+        .await?;
 
     Ok(HttpResponse::Ok()
-        .cookie(refresh_cookie)
+        .cookie(refresh_cookie(&req, refresh_token))
         .json(token_pair))
 }
 
@@ -77,34 +117,25 @@ pub async fn refresh(
 
     let (token_pair, new_refresh_token, _user) = auth_service.refresh_token(&refresh_token).await?;
 
-    let secure_cookie = should_use_secure_cookie(&req);
-
     // Rotate refresh token cookie
-    let refresh_cookie = Cookie::build("refresh_token", new_refresh_token)
-        .path("/api/auth")
-        .http_only(true)
-        .secure(secure_cookie)
-        .same_site(SameSite::Strict)
-        .max_age(cookie::time::Duration::days(7))
-        .finish();
-
     Ok(HttpResponse::Ok()
-        .cookie(refresh_cookie)
+        .cookie(refresh_cookie(&req, new_refresh_token))
         .json(token_pair))
 }
 
 /// POST /api/auth/logout
 /// 
-/// Invalidates refresh token
+/// Revokes this device's session and clears the refresh cookie
 pub async fn logout(
-    _auth_service: web::Data<Arc<AuthService>>,
+    auth_service: web::Data<Arc<AuthService>>,
     req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
-    // Extract user_id from refresh token if present
+    // Revoke the session if the cookie still identifies one; an invalid or
+    // expired token has nothing left to revoke, so the cookie is cleared anyway
     if let Some(refresh_cookie) = req.cookie("refresh_token") {
-        // We could validate and extract user_id, but for security
-        // we'll just clear the cookie regardless
-        let _ = refresh_cookie.value();
+        if let Err(e) = auth_service.logout(refresh_cookie.value()).await {
+            tracing::debug!("Logout without a live session: {}", e);
+        }
     }
 
     let secure_cookie = should_use_secure_cookie(&req);
@@ -125,6 +156,125 @@ pub async fn logout(
         })))
 }
 
+/// POST /api/auth/verify-email
+///
+/// Redeems the token from the verification email and sends the welcome email
+pub async fn verify_email(
+    auth_service: web::Data<Arc<AuthService>>,
+    email_service: web::Data<Arc<EmailService>>,
+    body: web::Json<VerifyEmailRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+
+    let user = auth_service.verify_email(&body.token).await?;
+
+    let email_service = email_service.get_ref().clone();
+    let name = user.name.clone().unwrap_or_else(|| "there".to_string());
+    actix_web::rt::spawn(async move {
+        let _ = email_service.send_welcome_email(user.email, name).await;
+    });
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Email verified successfully"
+    })))
+}
+
+/// POST /api/auth/resend-verification
+///
+/// Sends a new verification link; the response is the same for every address
+pub async fn resend_verification(
+    auth_service: web::Data<Arc<AuthService>>,
+    email_service: web::Data<Arc<EmailService>>,
+    body: web::Json<EmailRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+
+    if let Some((to, token)) = auth_service.resend_verification(&body.email).await? {
+        let email_service = email_service.get_ref().clone();
+        actix_web::rt::spawn(async move {
+            let _ = email_service.send_verification_email(to, token).await;
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If that account needs verification, a new link has been sent"
+    })))
+}
+
+/// POST /api/auth/forgot-password
+///
+/// Emails a single-use reset link; the response is the same for every address
+pub async fn forgot_password(
+    auth_service: web::Data<Arc<AuthService>>,
+    email_service: web::Data<Arc<EmailService>>,
+    body: web::Json<EmailRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+
+    if let Some((to, token)) = auth_service.forgot_password(&body.email).await? {
+        let email_service = email_service.get_ref().clone();
+        actix_web::rt::spawn(async move {
+            let _ = email_service.send_password_reset_email(to, token).await;
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If an account exists for that email, a reset link has been sent"
+    })))
+}
+
+/// POST /api/auth/reset-password
+///
+/// Sets a new password from a reset token and signs out every session
+pub async fn reset_password(
+    auth_service: web::Data<Arc<AuthService>>,
+    req: HttpRequest,
+    body: web::Json<ResetPasswordRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+
+    auth_service.reset_password(&body.token, &body.new_password).await?;
+
+    // Drop this device's refresh cookie too — the stored hash is already gone
+    let mut clear_cookie = Cookie::build("refresh_token", "")
+        .path("/api/auth")
+        .http_only(true)
+        .secure(should_use_secure_cookie(&req))
+        .same_site(SameSite::Strict)
+        .finish();
+    clear_cookie.make_removal();
+
+    Ok(HttpResponse::Ok()
+        .cookie(clear_cookie)
+        .json(serde_json::json!({
+            "message": "Password reset successfully. Please log in again."
+        })))
+}
+
+/// Device details recorded on the new session
+fn device_info(req: &HttpRequest, trusted: &TrustedProxies, device_name: Option<String>) -> DeviceInfo {
+    DeviceInfo {
+        device_name: device_name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        user_agent: req
+            .headers()
+            .get(actix_web::http::header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|ua| ua.chars().take(512).collect()),
+        ip_address: client_ip(req, trusted).map(|ip| ip.to_string()),
+    }
+}
+
+/// HttpOnly cookie carrying the refresh token
+fn refresh_cookie(req: &HttpRequest, refresh_token: String) -> Cookie<'static> {
+    Cookie::build("refresh_token", refresh_token)
+        .path("/api/auth")
+        .http_only(true)
+        .secure(should_use_secure_cookie(req))
+        .same_site(SameSite::Strict)
+        .max_age(cookie::time::Duration::days(7))
+        .finish()
+}
+
 fn should_use_secure_cookie(req: &HttpRequest) -> bool {
     if let Ok(value) = std::env::var("COOKIE_SECURE") {
         let normalized = value.trim().to_ascii_lowercase();
@@ -133,14 +283,21 @@ fn should_use_secure_cookie(req: &HttpRequest) -> bool {
     req.connection_info().scheme() == "https"
 }
 
-/// Configure auth routes
-pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api/auth")
-            .wrap(RateLimit::new(RateLimitConfig { requests_per_minute: 300 }))
-            .route("/register", web::post().to(register))
-            .route("/login", web::post().to(login))
-            .route("/refresh", web::post().to(refresh))
-            .route("/logout", web::post().to(logout))
-    );
+/// Configure auth routes behind the given per-IP rate limit
+pub fn configure(rate_limit: RateLimit) -> impl FnOnce(&mut web::ServiceConfig) {
+    move |cfg| {
+        cfg.service(
+            web::scope("/api/auth")
+                .wrap(rate_limit)
+                .route("/register", web::post().to(register))
+                .route("/login", web::post().to(login))
+                .route("/refresh", web::post().to(refresh))
+                .route("/logout", web::post().to(logout))
+                .route("/verify-email", web::post().to(verify_email))
+                .route("/resend-verification", web::post().to(resend_verification))
+                .route("/forgot-password", web::post().to(forgot_password))
+                .route("/reset-password", web::post().to(reset_password))
+                .route("/2fa/verify", web::post().to(verify_two_factor))
+        );
+    }
 }
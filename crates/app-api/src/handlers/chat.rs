@@ -2,22 +2,70 @@
 
 use actix_web::{web, HttpResponse};
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use cookest_shared::errors::AppError;
 use crate::middleware::Claims;
-use crate::services::chat::{ChatRequest, ChatService};
+use crate::services::chat::{ChatEvent, ChatRequest, ChatService};
+use crate::services::AuthService;
 
 /// POST /api/chat
-/// Send a message to the AI assistant (creates or continues a session)
+/// Send a message to the AI assistant (creates or continues a session).
+/// Needs a verified email when `REQUIRE_VERIFIED_EMAIL` is on.
 pub async fn send_message(
     chat_svc: web::Data<Arc<ChatService>>,
+    auth_svc: web::Data<Arc<AuthService>>,
     claims: web::ReqData<Claims>,
     body: web::Json<ChatRequest>,
 ) -> Result<HttpResponse, AppError> {
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    auth_svc.require_verified_email(user_id).await?;
+    validate_message(&body)?;
+    let reply = chat_svc.chat(user_id, body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(reply))
+}
+
+/// POST /api/chat/stream
+/// Same as `POST /api/chat`, answered as Server-Sent Events: `session`,
+/// then `token` / `tool_start` / `tool_end` / `action_proposed` (and `reset`
+/// if a streamed claim is withdrawn), then `done` — or `error`. Errors before the model is
+/// called (bad session, unverified email) are normal JSON error responses.
+pub async fn stream_message(
+    chat_svc: web::Data<Arc<ChatService>>,
+    auth_svc: web::Data<Arc<AuthService>>,
+    claims: web::ReqData<Claims>,
+    body: web::Json<ChatRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    auth_svc.require_verified_email(user_id).await?;
+    validate_message(&body)?;
+
+    let prepared = chat_svc.prepare(user_id, &body).await?;
+
+    // The reply is generated on its own task so it is saved even if the
+    // client disconnects mid-stream
+    let (tx, rx) = mpsc::channel::<ChatEvent>(64);
+    let svc = chat_svc.get_ref().clone();
+    actix_web::rt::spawn(async move {
+        svc.chat_stream(user_id, prepared, tx).await;
+    });
+
+    let frames = futures::stream::unfold(rx, |mut rx| async move {
+        let event = rx.recv().await?;
+        Some((Ok::<_, actix_web::Error>(web::Bytes::from(event.to_sse())), rx))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(frames))
+}
+
+fn validate_message(body: &ChatRequest) -> Result<(), AppError> {
     if body.message.trim().is_empty() {
-        return Err(cookest_shared::errors::AppError::Validation({
+        return Err(AppError::Validation({
             let mut errors = validator::ValidationErrors::new();
             let mut e = validator::ValidationError::new("empty");
             e.message = Some("Message cannot be empty".into());
@@ -25,8 +73,7 @@ pub async fn send_message(
             errors
         }));
     }
-    let reply = chat_svc.chat(user_id, body.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(reply))
+    Ok(())
 }
 
 /// GET /api/chat/sessions
@@ -64,13 +111,41 @@ pub async fn delete_session(
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// POST /api/chat/actions/{id}/confirm
+/// Apply a destructive change the assistant proposed (clear plan, remove
+/// pantry item, regenerate plan). Each action resolves once and lapses after 24h.
+pub async fn confirm_action(
+    chat_svc: web::Data<Arc<ChatService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let outcome = chat_svc.confirm_action(user_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(outcome))
+}
+
+/// POST /api/chat/actions/{id}/reject
+/// Discard a proposed action without changing anything
+pub async fn reject_action(
+    chat_svc: web::Data<Arc<ChatService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let action = chat_svc.reject_action(user_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(action))
+}
+
 /// Route configuration
 pub fn configure_chat(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/chat")
             .route("", web::post().to(send_message))
+            .route("/stream", web::post().to(stream_message))
             .route("/sessions", web::get().to(list_sessions))
             .route("/sessions/{id}/messages", web::get().to(get_messages))
-            .route("/sessions/{id}", web::delete().to(delete_session)),
+            .route("/sessions/{id}", web::delete().to(delete_session))
+            .route("/actions/{id}/confirm", web::post().to(confirm_action))
+            .route("/actions/{id}/reject", web::post().to(reject_action)),
     );
 }
@@ -0,0 +1,151 @@
+//! Household handlers — Family-tier shared pantry, meal plan and shopping list
+//!
+//!   POST   /api/households                       — create (Family tier)
+//!   GET    /api/households/me                    — my household and its members
+//!   DELETE /api/households/me                    — delete (owner)
+//!   POST   /api/households/me/invites            — invite by email (owner/admin)
+//!   GET    /api/households/me/invites            — pending invites
+//!   DELETE /api/households/me/invites/{id}       — withdraw an invite (owner/admin)
+//!   POST   /api/households/invites/accept        — join with an emailed token
+//!   PUT    /api/households/me/members/{user_id}  — change a member's role (owner)
+//!   DELETE /api/households/me/members/{user_id}  — remove a member, or leave
+
+use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use cookest_shared::errors::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::household::{
+    AcceptInviteRequest, CreateHouseholdRequest, InviteMemberRequest, UpdateMemberRoleRequest,
+};
+use crate::services::household::HouseholdRole;
+use crate::services::{EmailService, HouseholdService};
+
+pub fn configure_households(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/households")
+            .route("", web::post().to(create_household))
+            .route("/me", web::get().to(get_household))
+            .route("/me", web::delete().to(delete_household))
+            .route("/me/invites", web::post().to(invite_member))
+            .route("/me/invites", web::get().to(list_invites))
+            .route("/me/invites/{id}", web::delete().to(revoke_invite))
+            .route("/invites/accept", web::post().to(accept_invite))
+            .route("/me/members/{user_id}", web::put().to(update_member_role))
+            .route("/me/members/{user_id}", web::delete().to(remove_member)),
+    );
+}
+
+async fn create_household(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+    body: web::Json<CreateHouseholdRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+    let household = service
+        .create(user.id, user.claims.tier.as_ref(), &body.name)
+        .await?;
+    Ok(HttpResponse::Created().json(household))
+}
+
+async fn get_household(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+) -> Result<HttpResponse, AppError> {
+    let household = service.get(user.id).await?;
+    Ok(HttpResponse::Ok().json(household))
+}
+
+async fn delete_household(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+) -> Result<HttpResponse, AppError> {
+    service.delete(user.id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The token is only ever delivered by email, never in the response
+async fn invite_member(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+    email_service: web::Data<Arc<EmailService>>,
+    body: web::Json<InviteMemberRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+    let role = parse_role(body.role.as_deref().unwrap_or("member"))?;
+
+    let created = service.invite(user.id, &body.email, role).await?;
+
+    let email_service = email_service.get_ref().clone();
+    let to = created.invite.email.clone();
+    actix_web::rt::spawn(async move {
+        let _ = email_service
+            .send_household_invite_email(to, created.household_name, created.inviter_name, created.token)
+            .await;
+    });
+
+    Ok(HttpResponse::Created().json(created.invite))
+}
+
+async fn list_invites(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+) -> Result<HttpResponse, AppError> {
+    let invites = service.list_invites(user.id).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "invites": invites })))
+}
+
+async fn revoke_invite(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    service.revoke_invite(user.id, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn accept_invite(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+    body: web::Json<AcceptInviteRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+    let household = service.accept_invite(user.id, &body.token).await?;
+    Ok(HttpResponse::Ok().json(household))
+}
+
+async fn update_member_role(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+    path: web::Path<Uuid>,
+    body: web::Json<UpdateMemberRoleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let role = parse_role(&body.role)?;
+    let household = service.set_member_role(user.id, path.into_inner(), role).await?;
+    Ok(HttpResponse::Ok().json(household))
+}
+
+async fn remove_member(
+    user: AuthenticatedUser,
+    service: web::Data<Arc<HouseholdService>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    service.remove_member(user.id, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Only "admin" and "member" can be granted; ownership stays with the creator
+fn parse_role(role: &str) -> Result<HouseholdRole, AppError> {
+    match HouseholdRole::parse(role) {
+        Some(role) if role != HouseholdRole::Owner => Ok(role),
+        _ => {
+            let mut errors = validator::ValidationErrors::new();
+            let mut e = validator::ValidationError::new("role");
+            e.message = Some("Role must be \"admin\" or \"member\"".into());
+            errors.add("role", e);
+            Err(AppError::Validation(errors))
+        }
+    }
+}
@@ -6,8 +6,10 @@ pub mod user;
 pub mod chat;
 pub mod onboarding;
 pub mod shopping_list;
+pub mod household;
 pub mod subscription;
 pub mod store;
+pub mod admin;
 pub mod browse;
 pub mod image_gen;
 pub mod recipe_gen;
@@ -20,9 +22,11 @@ pub use user::configure as configure_user;
 pub use chat::configure_chat;
 pub use onboarding::configure_onboarding;
 pub use shopping_list::configure_shopping_list;
+pub use household::configure_households;
 pub use subscription::configure_subscription;
 pub use subscription::configure_subscription_protected;
 pub use store::configure_stores;
+pub use admin::configure_admin;
 pub use browse::configure_browse;
 pub use browse::FoodApiClient;
 pub use image_gen::configure_image_gen;
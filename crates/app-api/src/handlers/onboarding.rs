@@ -1,17 +1,19 @@
 //! Onboarding and account-management handlers.
 //!
 //! These endpoints handle post-registration setup (dietary prefs, skill level)
-//! as well as sensitive account actions (password change, account deletion)
-//! that sit outside the main auth flow.
+//! as well as sensitive account actions (password change, account deletion,
+//! two-factor enrollment) that sit outside the main auth flow.
 
 use actix_web::{web, HttpResponse};
 use serde::Deserialize;
 use std::sync::Arc;
+use validator::Validate;
 
 use cookest_shared::errors::AppError;
 use crate::middleware::auth::AuthenticatedUser;
 use crate::services::auth::AuthService;
 use crate::services::onboarding::{OnboardingRequest, OnboardingService};
+use crate::validation::{PasswordConfirmRequest, TwoFactorCodeRequest};
 
 /// Register onboarding routes onto `cfg`.
 ///
@@ -78,3 +80,47 @@ pub async fn delete_account(
     auth_service.delete_account(user.id, &body.password).await?;
     Ok(HttpResponse::NoContent().finish())
 }
+
+/// `POST /api/me/2fa/setup` — begin TOTP enrollment.
+///
+/// JWT required.  Returns the base32 secret and an `otpauth://` URI for the
+/// authenticator app.  2FA stays off until confirmed via `/2fa/enable`;
+/// calling setup again replaces the pending secret.
+pub async fn setup_two_factor(
+    user: AuthenticatedUser,
+    auth_service: web::Data<Arc<AuthService>>,
+) -> Result<HttpResponse, AppError> {
+    let setup = auth_service.setup_two_factor(user.id).await?;
+    Ok(HttpResponse::Ok().json(setup))
+}
+
+/// `POST /api/me/2fa/enable` — confirm enrollment with a code from the app.
+///
+/// JWT required.  Responds with one-time recovery codes; they are never shown
+/// again, so the client must prompt the user to save them.
+pub async fn enable_two_factor(
+    user: AuthenticatedUser,
+    auth_service: web::Data<Arc<AuthService>>,
+    body: web::Json<TwoFactorCodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+    let recovery_codes = auth_service.enable_two_factor(user.id, &body.code).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Two-factor authentication enabled",
+        "recovery_codes": recovery_codes
+    })))
+}
+
+/// `POST /api/me/2fa/disable` — turn 2FA off.
+///
+/// JWT required.  **SECURITY**: requires the current password, like other
+/// sensitive account actions.  Removes the secret and all recovery codes.
+pub async fn disable_two_factor(
+    user: AuthenticatedUser,
+    auth_service: web::Data<Arc<AuthService>>,
+    body: web::Json<PasswordConfirmRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+    auth_service.disable_two_factor(user.id, &body.password).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "Two-factor authentication disabled" })))
+}
@@ -7,13 +7,19 @@ use actix_multipart::Multipart;
 use actix_web::{web, HttpResponse};
 use futures::StreamExt;
 use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::Deserialize;
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::entity::user::Entity as User;
 use cookest_shared::errors::AppError;
 use crate::middleware::auth::AuthenticatedUser;
-use crate::services::store::{CreateStoreRequest, StoreService};
+use crate::services::pdf_worker::PdfWorker;
+use crate::services::store::{
+    ApproveOutcome, ApproveQuery, BulkReviewRequest, CandidatesQuery, CreateAliasRequest,
+    CreateStoreRequest, MatchQueueQuery, SetMatchesRequest, StoreService, UpdateCandidateRequest,
+};
 use crate::services::token::SubscriptionTier;
 
 /// Register all store-related routes onto `cfg`.
@@ -25,9 +31,21 @@ use crate::services::token::SubscriptionTier;
 /// - `POST /api/admin/stores`
 /// - `POST /api/admin/stores/{store_id}/promotions/upload`
 /// - `GET  /api/admin/stores/{store_id}/jobs`
+/// - `GET  /api/admin/stores/{store_id}/jobs/{job_id}/pages`
+/// - `POST /api/admin/stores/{store_id}/jobs/{job_id}/retry`
+/// - `GET  /api/admin/stores/{store_id}/jobs/{job_id}/stats`
 /// - `GET  /api/admin/stores/{store_id}/candidates`
+/// - `POST /api/admin/stores/{store_id}/candidates/bulk`
+/// - `PUT  /api/admin/candidates/{id}`
 /// - `POST /api/admin/candidates/{id}/approve`
 /// - `POST /api/admin/candidates/{id}/reject`
+/// - `GET  /api/admin/promotions/matches`
+/// - `POST /api/admin/promotions/match-pending`
+/// - `GET  /api/admin/promotions/{id}/matches`
+/// - `PUT  /api/admin/promotions/{id}/matches`
+/// - `POST /api/admin/promotions/{id}/matches/confirm`
+/// - `POST /api/admin/ingredients/{id}/aliases`
+/// - `DELETE /api/admin/ingredients/{id}/aliases/{alias_id}`
 pub fn configure_stores(cfg: &mut web::ServiceConfig) {
     // Public list
     cfg.route("/api/stores", web::get().to(list_stores));
@@ -38,9 +56,21 @@ pub fn configure_stores(cfg: &mut web::ServiceConfig) {
             .route("/stores", web::post().to(create_store))
             .route("/stores/{store_id}/promotions/upload", web::post().to(upload_pdf))
             .route("/stores/{store_id}/jobs", web::get().to(list_jobs))
+            .route("/stores/{store_id}/jobs/{job_id}/pages", web::get().to(list_job_pages))
+            .route("/stores/{store_id}/jobs/{job_id}/retry", web::post().to(retry_job))
+            .route("/stores/{store_id}/jobs/{job_id}/stats", web::get().to(job_stats))
             .route("/stores/{store_id}/candidates", web::get().to(list_candidates))
+            .route("/stores/{store_id}/candidates/bulk", web::post().to(bulk_review))
+            .route("/candidates/{id}", web::put().to(update_candidate))
             .route("/candidates/{id}/approve", web::post().to(approve_candidate))
-            .route("/candidates/{id}/reject", web::post().to(reject_candidate)),
+            .route("/candidates/{id}/reject", web::post().to(reject_candidate))
+            .route("/promotions/matches", web::get().to(match_queue))
+            .route("/promotions/match-pending", web::post().to(match_pending))
+            .route("/promotions/{id}/matches", web::get().to(promotion_matches))
+            .route("/promotions/{id}/matches", web::put().to(set_matches))
+            .route("/promotions/{id}/matches/confirm", web::post().to(confirm_matches))
+            .route("/ingredients/{id}/aliases", web::post().to(add_alias))
+            .route("/ingredients/{id}/aliases/{alias_id}", web::delete().to(delete_alias)),
     );
 
     // Pro-gated price routes
@@ -58,7 +88,7 @@ async fn list_stores(
 }
 
 /// Verify the authenticated user is an admin by checking the DB
-async fn verify_admin(user_id: Uuid, db: &DatabaseConnection) -> Result<(), AppError> {
+pub(crate) async fn verify_admin(user_id: Uuid, db: &DatabaseConnection) -> Result<(), AppError> {
     let user = User::find_by_id(user_id)
         .one(db)
         .await?
@@ -85,13 +115,15 @@ async fn create_store(
 
 /// `POST /api/admin/stores/{store_id}/promotions/upload` — upload a promotions PDF.
 ///
-/// Requires admin privileges.  The PDF is saved and a background
-/// `pdf_processing_job` is spawned immediately; the handler returns 202
-/// Accepted with the job record so the caller can poll job status.
+/// Requires admin privileges.  The PDF is saved as a pending
+/// `pdf_processing_job` and the PDF worker is woken to pick it up; the
+/// handler returns 202 Accepted with the job record so the caller can poll
+/// job status.
 async fn upload_pdf(
     user: AuthenticatedUser,
     db: web::Data<DatabaseConnection>,
     service: web::Data<Arc<StoreService>>,
+    worker: web::Data<Arc<PdfWorker>>,
     path: web::Path<Uuid>,
     mut payload: Multipart,
 ) -> Result<HttpResponse, AppError> {
@@ -129,9 +161,9 @@ async fn upload_pdf(
         return Err(AppError::Validation(validator::ValidationErrors::new()));
     }
 
-    // Create the job and immediately spawn processing in background
+    // Queue the job; the worker on this replica starts on it right away
     let job = service.create_pdf_job(store_id, pdf_bytes, &filename).await?;
-    service.spawn_pdf_processing(job.id);
+    worker.wake();
 
     Ok(HttpResponse::Accepted().json(serde_json::json!({
         "job": job,
@@ -154,35 +186,132 @@ async fn list_jobs(
     Ok(HttpResponse::Ok().json(serde_json::json!({ "jobs": jobs })))
 }
 
+/// `GET /api/admin/stores/{store_id}/jobs/{job_id}/pages` — per-page results.
+///
+/// Requires admin privileges.  Lists each processed page with its status,
+/// attempt count, candidates extracted and last error.
+async fn list_job_pages(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let (store_id, job_id) = path.into_inner();
+    let pages = service.list_job_pages(store_id, job_id).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "pages": pages })))
+}
+
+/// `POST /api/admin/stores/{store_id}/jobs/{job_id}/retry` — requeue a job.
+///
+/// Requires admin privileges.  Only jobs that failed, or finished with
+/// unreadable pages, can be retried; pages already extracted are skipped.
+async fn retry_job(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    worker: web::Data<Arc<PdfWorker>>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let (store_id, job_id) = path.into_inner();
+    let job = service.retry_job(store_id, job_id).await?;
+    worker.wake();
+    Ok(HttpResponse::Accepted().json(serde_json::json!({ "job": job })))
+}
+
 /// `GET /api/admin/stores/{store_id}/candidates` — list promotion candidates.
 ///
 /// Requires admin privileges.  Candidates are price/promotion records
 /// extracted from a PDF that are awaiting human review before being
-/// published as live store promotions.
+/// published as live store promotions.  Filter with `job_id`; `status`
+/// lists already-reviewed candidates instead.
 async fn list_candidates(
     user: AuthenticatedUser,
     db: web::Data<DatabaseConnection>,
     service: web::Data<Arc<StoreService>>,
     path: web::Path<Uuid>,
+    query: web::Query<CandidatesQuery>,
 ) -> Result<HttpResponse, AppError> {
     verify_admin(user.id, db.get_ref()).await?;
-    let candidates = service.list_candidates(path.into_inner()).await?;
+    let candidates = service.list_candidates(path.into_inner(), query.into_inner()).await?;
     Ok(HttpResponse::Ok().json(serde_json::json!({ "candidates": candidates })))
 }
 
+/// `GET /api/admin/stores/{store_id}/jobs/{job_id}/stats` — extraction stats.
+///
+/// Requires admin privileges.  Candidate counts by review outcome, how many
+/// were edited, the model's average confidence and the rejection rate.
+async fn job_stats(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let (store_id, job_id) = path.into_inner();
+    let stats = service.job_stats(store_id, job_id).await?;
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// `POST /api/admin/stores/{store_id}/candidates/bulk` — review many candidates.
+///
+/// Requires admin privileges.  Approves or rejects the store's pending
+/// candidates, optionally only one job's (`job_id`) or those on one side of
+/// a confidence threshold (`min_confidence` / `max_confidence`).  Bulk
+/// approval sets duplicates aside unless `on_duplicate` is `replace` or
+/// `keep`; `fail` is refused.
+async fn bulk_review(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<Uuid>,
+    body: web::Json<BulkReviewRequest>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    body.validate()?;
+    let result = service.bulk_review(path.into_inner(), user.id, body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// `PUT /api/admin/candidates/{id}` — correct a candidate before review.
+///
+/// Requires admin privileges.  Only pending candidates can be edited; send
+/// field names in `clear` to empty optional fields.
+async fn update_candidate(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<Uuid>,
+    body: web::Json<UpdateCandidateRequest>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    body.validate()?;
+    let candidate = service.update_candidate(path.into_inner(), user.id, body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(candidate))
+}
+
 /// `POST /api/admin/candidates/{id}/approve` — approve a promotion candidate.
 ///
 /// Requires admin privileges.  Promotes the candidate record into a live
-/// `store_promotion` row and records the approving admin’s ID.
+/// `store_promotion` row, records the approving admin’s ID and links the
+/// promotion to its best-matching ingredient (returned as `ingredient_match`).
+/// A candidate repeating a live promotion of the store is refused unless
+/// `on_duplicate` is `skip` (set it aside), `replace` or `keep`.
 async fn approve_candidate(
     user: AuthenticatedUser,
     db: web::Data<DatabaseConnection>,
     service: web::Data<Arc<StoreService>>,
     path: web::Path<Uuid>,
+    query: web::Query<ApproveQuery>,
 ) -> Result<HttpResponse, AppError> {
     verify_admin(user.id, db.get_ref()).await?;
-    let promotion = service.approve_candidate(path.into_inner(), user.id).await?;
-    Ok(HttpResponse::Created().json(promotion))
+    match service.approve_candidate(path.into_inner(), user.id, query.on_duplicate).await? {
+        ApproveOutcome::Approved(promotion) => Ok(HttpResponse::Created().json(promotion)),
+        ApproveOutcome::Duplicate { duplicate_of } => {
+            Ok(HttpResponse::Ok().json(serde_json::json!({ "duplicate_of": duplicate_of })))
+        }
+    }
 }
 
 /// `POST /api/admin/candidates/{id}/reject` — reject a promotion candidate.
@@ -200,6 +329,121 @@ async fn reject_candidate(
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// `GET /api/admin/promotions/matches` — ingredient match review queue.
+///
+/// Requires admin privileges.  Defaults to promotions matched automatically
+/// or left unmatched; filter with `status`, `store_id` and `limit`.
+async fn match_queue(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    query: web::Query<MatchQueueQuery>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let promotions = service.match_queue(query.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "promotions": promotions })))
+}
+
+#[derive(Debug, Deserialize)]
+struct MatchPendingQuery {
+    limit: Option<u64>,
+}
+
+/// `POST /api/admin/promotions/match-pending` — match never-matched promotions.
+///
+/// Requires admin privileges.  Backfills links for active promotions
+/// approved before matching existed, up to `limit` (default 500) per call.
+async fn match_pending(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    query: web::Query<MatchPendingQuery>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let (matched, unmatched) = service.match_pending(query.limit.unwrap_or(500).min(5_000)).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "matched": matched, "unmatched": unmatched })))
+}
+
+/// `GET /api/admin/promotions/{id}/matches` — a promotion's ingredient links.
+///
+/// Requires admin privileges.  Includes the five closest ingredients as
+/// `suggestions` for overriding the match.
+async fn promotion_matches(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let matches = service.promotion_matches(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(matches))
+}
+
+/// `PUT /api/admin/promotions/{id}/matches` — override a promotion's ingredients.
+///
+/// Requires admin privileges.  Replaces the links and marks the match
+/// confirmed; with `remember_alias` the product name becomes an alias of
+/// each chosen ingredient.
+async fn set_matches(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<Uuid>,
+    body: web::Json<SetMatchesRequest>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    body.validate()?;
+    let matches = service.set_matches(path.into_inner(), body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(matches))
+}
+
+/// `POST /api/admin/promotions/{id}/matches/confirm` — accept the current match.
+///
+/// Requires admin privileges.  Confirmed promotions leave the review queue
+/// and are never re-matched automatically.
+async fn confirm_matches(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let matches = service.confirm_matches(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(matches))
+}
+
+/// `POST /api/admin/ingredients/{id}/aliases` — add an ingredient alias.
+///
+/// Requires admin privileges.  Aliases are matched against flyer product
+/// names alongside the ingredient's own name.
+async fn add_alias(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<i64>,
+    body: web::Json<CreateAliasRequest>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    body.validate()?;
+    let alias = service.add_alias(path.into_inner(), body.into_inner()).await?;
+    Ok(HttpResponse::Created().json(alias))
+}
+
+/// `DELETE /api/admin/ingredients/{id}/aliases/{alias_id}` — remove an alias.
+///
+/// Requires admin privileges.  Existing links are kept.
+async fn delete_alias(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    service: web::Data<Arc<StoreService>>,
+    path: web::Path<(i64, i64)>,
+) -> Result<HttpResponse, AppError> {
+    verify_admin(user.id, db.get_ref()).await?;
+    let (ingredient_id, alias_id) = path.into_inner();
+    service.delete_alias(ingredient_id, alias_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
 /// Get active promotions for an ingredient — requires Pro tier
 pub async fn get_prices_for_ingredient(
     user: AuthenticatedUser,
@@ -1,4 +1,4 @@
-//! Subscription handlers — tier info, Stripe checkout, webhooks
+//! Subscription handlers — tier info, Stripe checkout / billing portal, webhooks
 
 use actix_web::{web, HttpRequest, HttpResponse};
 use sea_orm::{DatabaseConnection, EntityTrait};
@@ -22,7 +22,8 @@ pub fn configure_subscription_protected(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/subscription")
             .route("", web::get().to(get_subscription))
-            .route("/checkout", web::post().to(create_checkout)),
+            .route("/checkout", web::post().to(create_checkout))
+            .route("/portal", web::post().to(create_portal)),
     );
 }
 
@@ -58,30 +59,47 @@ struct CheckoutRequest {
 /// Create a Stripe checkout session → returns URL for client to redirect to
 async fn create_checkout(
     user: AuthenticatedUser,
-    _sub_service: web::Data<Arc<SubscriptionService>>,
+    sub_service: web::Data<Arc<SubscriptionService>>,
     body: web::Json<CheckoutRequest>,
 ) -> Result<HttpResponse, AppError> {
     // Validate tier selection
     let tier = match body.tier.as_str() {
-        "pro" | "family" => body.tier.clone(),
+        "pro" | "family" => SubscriptionTier::from_str(&body.tier),
         _ => return Err(AppError::Validation(validator::ValidationErrors::new())),
     };
 
-    // In production: call Stripe API here to create a checkout session
-    // For now we return a placeholder that makes the structure clear
-    tracing::info!(
-        "Checkout requested: user={}, tier={}, success_url={}",
-        user.id, tier, body.success_url
-    );
+    let session = sub_service
+        .create_checkout_session(user.id, &tier, &body.success_url, &body.cancel_url)
+        .await?;
+
+    tracing::info!("Checkout session {} created: user={}, tier={}", session.id, user.id, tier.as_str());
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Stripe integration pending — configure STRIPE_SECRET_KEY",
-        "tier": tier,
-        "success_url": body.success_url,
-        "cancel_url": body.cancel_url,
+        "session_id": session.id,
+        "url": session.url,
+        "tier": tier.as_str(),
     })))
 }
 
+#[derive(Deserialize)]
+struct PortalRequest {
+    return_url: String,
+}
+
+/// Create a Stripe Billing Portal session → returns URL where the user can
+/// update payment details, switch plans or cancel
+async fn create_portal(
+    user: AuthenticatedUser,
+    sub_service: web::Data<Arc<SubscriptionService>>,
+    body: web::Json<PortalRequest>,
+) -> Result<HttpResponse, AppError> {
+    let session = sub_service
+        .create_portal_session(user.id, &body.return_url)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "url": session.url })))
+}
+
 /// Stripe webhook — verify signature, then handle subscription events
 async fn stripe_webhook(
     req: HttpRequest,
@@ -116,10 +134,16 @@ async fn stripe_webhook(
             let status = data["status"].as_str().unwrap_or("active");
             let period_end = data["current_period_end"].as_i64();
 
-            // Determine tier from price/product metadata or plan interval
-            // For now we derive from the subscription metadata field
-            let tier_str = data["metadata"]["tier"].as_str().unwrap_or("free");
-            let tier = SubscriptionTier::from_str(tier_str);
+            // Tier comes from the configured price IDs — metadata is editable
+            // from the Stripe dashboard and must not grant access by itself
+            let Some(tier) = sub_service.prices().tier_for_subscription(data) else {
+                tracing::warn!(
+                    "Stripe subscription for customer {} has no configured price; tier unchanged",
+                    customer_id
+                );
+                sub_service.mark_event_processed(event_id).await?;
+                return Ok(HttpResponse::Ok().json(serde_json::json!({ "received": true })));
+            };
 
             let valid_until = period_end.map(|ts| {
                 chrono::DateTime::from_timestamp(ts, 0)
@@ -5,16 +5,21 @@ use actix_multipart::Multipart;
 use futures::StreamExt;
 use std::sync::Arc;
 use uuid::Uuid;
+use validator::Validate;
 
 use cookest_shared::errors::AppError;
-use crate::models::inventory::{AddInventoryItem, UpdateInventoryItem, QuickAddItem};
+use crate::models::inventory::{AddInventoryItem, UpdateInventoryItem, QuickAddItem, EatLeftoversRequest};
 use crate::models::profile::UpdateProfileRequest;
-use crate::models::interaction::RateRecipeRequest;
-use crate::models::meal_plan::GenerateMealPlanRequest;
-use crate::services::{InventoryService, ProfileService, InteractionService, MealPlanService, PushTokenService, PreferenceService, ScanService};
+use crate::models::interaction::{CookRecipeRequest, RateRecipeRequest};
+use crate::models::meal_plan::{GenerateMealPlanRequest, RegeneratePlanRequest};
+use crate::models::notification::UpdateNotificationPreferencesRequest;
+use crate::services::{InventoryService, ProfileService, InteractionService, MealPlanService, PushTokenService, NotificationService, PreferenceService, ScanService, SessionService};
 use crate::services::scan::BulkAddItem;
 use crate::middleware::Claims;
-use crate::handlers::onboarding::{complete_onboarding, change_password, delete_account};
+use crate::handlers::onboarding::{
+    complete_onboarding, change_password, delete_account,
+    setup_two_factor, enable_two_factor, disable_two_factor,
+};
 
 // ── Inventory ────────────────────────────────────────────────────────────────
 
@@ -74,6 +79,44 @@ pub async fn delete_inventory_item(
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// `GET /api/inventory/leftovers` — cooked servings kept for later.
+///
+/// JWT required.  Shared with the household; soonest to expire first.
+pub async fn list_leftovers(
+    inv: web::Data<Arc<InventoryService>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let leftovers = inv.list_leftovers(user_id).await?;
+    Ok(HttpResponse::Ok().json(leftovers))
+}
+
+/// `POST /api/inventory/leftovers/{id}/eat` — eat some servings.
+///
+/// JWT required.  Returns what is left, or `null` once they are finished.
+pub async fn eat_leftovers(
+    inv: web::Data<Arc<InventoryService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<i64>,
+    body: web::Json<EatLeftoversRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    body.validate()?;
+    let remaining = inv.eat_leftovers(user_id, path.into_inner(), body.servings).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "remaining": remaining })))
+}
+
+/// `DELETE /api/inventory/leftovers/{id}` — throw leftovers away.
+pub async fn delete_leftover(
+    inv: web::Data<Arc<InventoryService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    inv.delete_leftover(user_id, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
 /// `GET /api/inventory/expiring` — items expiring within the next N days.
 ///
 /// JWT required.  Currently hardcoded to a 5-day window; items without
@@ -188,6 +231,7 @@ pub async fn update_profile(
     claims: web::ReqData<Claims>,
     body: web::Json<UpdateProfileRequest>,
 ) -> Result<HttpResponse, AppError> {
+    body.validate()?;
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
     let p = profile.update_profile(user_id, body.into_inner()).await?;
     Ok(HttpResponse::Ok().json(p))
@@ -231,10 +275,17 @@ pub async fn mark_cooked(
     profile: web::Data<Arc<ProfileService>>,
     claims: web::ReqData<Claims>,
     path: web::Path<i64>,
+    body: Option<web::Json<CookRecipeRequest>>,
 ) -> Result<HttpResponse, AppError> {
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    body.validate()?;
     let household_size = profile.get_profile(user_id).await?.household_size;
-    let res = interaction.mark_cooked(user_id, path.into_inner(), household_size).await?;
+    let servings_made = body.servings_made.unwrap_or(household_size);
+    let servings_eaten = body.servings_eaten.unwrap_or(household_size).min(servings_made);
+    let res = interaction
+        .mark_cooked(user_id, path.into_inner(), servings_made, servings_eaten)
+        .await?;
     Ok(HttpResponse::Ok().json(res))
 }
 
@@ -257,17 +308,45 @@ pub async fn generate_meal_plan(
 ) -> Result<HttpResponse, AppError> {
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
     let profile = profile_svc.get_profile(user_id).await?;
-    let plan = meal_svc
-        .generate_week_plan(user_id, profile.household_size, body.week_start)
+    let generated = meal_svc
+        .generate_week_plan(user_id, profile.household_size, body.week_start, body.seed)
         .await?;
+    let plan = generated.plan;
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "id": plan.id,
         "week_start": plan.week_start,
         "is_ai_generated": plan.is_ai_generated,
+        "seed": plan.generation_seed,
+        "excluded_count": generated.excluded.len(),
+        "excluded": generated.excluded,
+        "cost": generated.cost,
         "message": "Meal plan generated successfully"
     })))
 }
 
+/// `POST /api/meal-plans/{id}/regenerate` — re-optimise a day, a meal type
+/// or every unlocked slot, keeping locked, completed and flex slots.
+pub async fn regenerate_meal_plan(
+    meal_svc: web::Data<Arc<MealPlanService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<i64>,
+    body: web::Json<RegeneratePlanRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let body = body.into_inner();
+    let generated = meal_svc
+        .regenerate(user_id, path.into_inner(), &body.target, body.seed)
+        .await?;
+    let plan = meal_svc.get_plan(user_id, generated.plan.id).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "plan": plan,
+        "seed": generated.plan.generation_seed,
+        "excluded_count": generated.excluded.len(),
+        "excluded": generated.excluded,
+        "message": "Meal plan regenerated successfully"
+    })))
+}
+
 pub async fn get_current_meal_plan(
     meal_svc: web::Data<Arc<MealPlanService>>,
     claims: web::ReqData<Claims>,
@@ -288,8 +367,9 @@ pub async fn get_shopping_list(
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
     let list = meal_svc.get_shopping_list(user_id).await?;
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "count": list.len(),
-        "items": list
+        "count": list.items.len(),
+        "items": list.items,
+        "unconverted": list.unconverted,
     })))
 }
 
@@ -437,6 +517,25 @@ pub async fn swap_slot(
     Ok(HttpResponse::Ok().json(result))
 }
 
+#[derive(serde::Deserialize)]
+pub struct LockSlotBody {
+    pub locked: bool,
+}
+
+pub async fn lock_slot(
+    meal_svc: web::Data<Arc<MealPlanService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<SlotPath>,
+    body: web::Json<LockSlotBody>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let p = path.into_inner();
+    let result = meal_svc
+        .set_slot_locked(user_id, p.plan_id, p.slot_id, body.locked)
+        .await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
 #[derive(serde::Deserialize)]
 pub struct MarkFlexBody {
     pub flex_type: String,
@@ -525,6 +624,76 @@ pub async fn delete_push_token(
     Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "Push token removed" })))
 }
 
+// ── Notification settings ─────────────────────────────────────────────────────
+
+/// `GET /api/me/notifications` — which pushes the user gets, and quiet hours.
+pub async fn get_notification_preferences(
+    notif_svc: web::Data<Arc<NotificationService>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let prefs = notif_svc.get_preferences(user_id).await?;
+    Ok(HttpResponse::Ok().json(prefs))
+}
+
+/// `PUT /api/me/notifications` — partial update; equal quiet-hour times clear them.
+pub async fn update_notification_preferences(
+    notif_svc: web::Data<Arc<NotificationService>>,
+    claims: web::ReqData<Claims>,
+    body: web::Json<UpdateNotificationPreferencesRequest>,
+) -> Result<HttpResponse, AppError> {
+    body.validate()?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let prefs = notif_svc.update_preferences(user_id, body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(prefs))
+}
+
+/// `POST /api/me/notifications/test` — queue a test push to every device.
+pub async fn send_test_notification(
+    notif_svc: web::Data<Arc<NotificationService>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    notif_svc.send_test(user_id).await?;
+    Ok(HttpResponse::Accepted().json(serde_json::json!({ "message": "Test notification queued" })))
+}
+
+// ── Sessions ──────────────────────────────────────────────────────────────────
+
+/// `GET /api/me/sessions` — signed-in devices; `current` marks this one.
+pub async fn list_sessions(
+    session_svc: web::Data<Arc<SessionService>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let sessions = session_svc.list(user_id, claims.session_id()).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// `DELETE /api/me/sessions/{id}` — sign out one device. Its access token
+/// keeps working until it expires; it can no longer be refreshed.
+pub async fn revoke_session(
+    session_svc: web::Data<Arc<SessionService>>,
+    claims: web::ReqData<Claims>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    if !session_svc.revoke(user_id, path.into_inner()).await? {
+        return Err(AppError::NotFound("Session".to_string()));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// `DELETE /api/me/sessions` — sign out every other device.
+pub async fn revoke_other_sessions(
+    session_svc: web::Data<Arc<SessionService>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let revoked = session_svc.revoke_all(user_id, claims.session_id()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "revoked": revoked })))
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg
         // Inventory
@@ -537,10 +706,13 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 .route("/bulk", web::post().to(bulk_add_items))
                 .route("/suggestions", web::get().to(recipe_suggestions))
                 .route("/scan", web::post().to(scan_groceries))
+                .route("/leftovers", web::get().to(list_leftovers))
+                .route("/leftovers/{id}/eat", web::post().to(eat_leftovers))
+                .route("/leftovers/{id}", web::delete().to(delete_leftover))
                 .route("/{id}", web::put().to(update_inventory_item))
                 .route("/{id}", web::delete().to(delete_inventory_item)),
         )
-        // Profile + history + favourites + push tokens + preferences
+        // Profile + history + favourites + push tokens + notifications + preferences
         .service(
             web::scope("/api/me")
                 .route("", web::get().to(get_profile))
@@ -551,10 +723,19 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 .route("/push-tokens", web::get().to(list_push_tokens))
                 .route("/push-tokens", web::post().to(register_push_token))
                 .route("/push-tokens/{id}", web::delete().to(delete_push_token))
+                .route("/notifications", web::get().to(get_notification_preferences))
+                .route("/notifications", web::put().to(update_notification_preferences))
+                .route("/notifications/test", web::post().to(send_test_notification))
+                .route("/sessions", web::get().to(list_sessions))
+                .route("/sessions", web::delete().to(revoke_other_sessions))
+                .route("/sessions/{id}", web::delete().to(revoke_session))
                 .route("/preferences", web::get().to(get_preferences))
                 .route("/preferences", web::delete().to(reset_preferences))
                 .route("/onboarding", web::post().to(complete_onboarding))
-                .route("/change-password", web::post().to(change_password)),
+                .route("/change-password", web::post().to(change_password))
+                .route("/2fa/setup", web::post().to(setup_two_factor))
+                .route("/2fa/enable", web::post().to(enable_two_factor))
+                .route("/2fa/disable", web::post().to(disable_two_factor)),
         )
         // Recipe interactions
         .service(
@@ -573,9 +754,11 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 .route("/{id}", web::get().to(get_meal_plan))
                 .route("/{id}", web::delete().to(delete_meal_plan))
                 .route("/{id}/nutrition", web::get().to(get_nutrition_summary))
+                .route("/{id}/regenerate", web::post().to(regenerate_meal_plan))
                 .route("/{id}/slots", web::post().to(add_slot))
                 .route("/{plan_id}/slots/{slot_id}", web::put().to(swap_slot))
                 .route("/{plan_id}/slots/{slot_id}/complete", web::put().to(mark_slot_complete))
+                .route("/{plan_id}/slots/{slot_id}/lock", web::put().to(lock_slot))
                 .route("/{plan_id}/slots/{slot_id}/flex", web::put().to(mark_slot_flex)),
         );
 }
@@ -13,12 +13,17 @@ mod db;
 mod entity;
 mod handlers;
 mod middleware;
+mod migrations;
 mod models;
 mod services;
 mod validation;
 
+#[cfg(test)]
+mod test_db;
+
 use actix_cors::Cors;
 use actix_web::{http::header, middleware::Logger, web, App, HttpServer};
+use cookest_shared::migrate::{MigrateCommand, Migrator};
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use secrecy::ExposeSecret;
@@ -26,20 +31,45 @@ use secrecy::ExposeSecret;
 use crate::config::Config;
 use crate::handlers::{
     configure_auth, configure_recipes, configure_ingredients, configure_user, configure_chat,
-    configure_onboarding, configure_shopping_list, configure_subscription, configure_stores,
+    configure_onboarding, configure_shopping_list, configure_households, configure_subscription, configure_stores,
     configure_recipes_protected, configure_subscription_protected,
     configure_browse, FoodApiClient,
     configure_image_gen, ImageGenClient,
     configure_recipe_gen,
+    configure_admin,
+};
+use crate::middleware::{
+    jwt_subject, JwtAuth, RateLimit, RateLimitConfig, RateLimitKey, SecurityHeaders,
 };
-use crate::middleware::{JwtAuth, SecurityHeaders};
 use crate::services::{
-    AuthService, TokenService, RecipeService, IngredientService,
+    AuthService, SessionService, TokenService, RecipeService, IngredientService,
     RecipeGenService,
     MealPlanService, InventoryService, ProfileService, InteractionService, ChatService,
-    OnboardingService, ShoppingListService, SubscriptionService, StoreService, PushTokenService,
-    PreferenceService, EmailService, ScanService,
+    OnboardingService, ShoppingListService, HouseholdService, SubscriptionService, StoreService, PushTokenService,
+    PreferenceService, EmailService, ScanService, NotificationService, SchedulerService,
 };
+use crate::services::llm;
+use crate::services::pdf_worker::PdfWorker;
+use crate::services::push;
+use crate::services::scheduler::jobs::{Cleanup, ExpiryAlerts, WeeklyPlans};
+use crate::services::stripe::StripeClient;
+use crate::services::totp::SecretCipher;
+use crate::services::subscription::StripePrices;
+
+/// Parse `<binary> migrate [ARGS]`; `None` means start the server
+fn migrate_command() -> Option<MigrateCommand> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("migrate") {
+        return None;
+    }
+    match MigrateCommand::parse(&args[1..]) {
+        Ok(command) => Some(command),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -64,520 +94,31 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to connect to database");
 
-    // Run all migrations in dependency order
-    tracing::info!("Running database migrations...");
-    use sea_orm::{ConnectionTrait, Statement};
-
-    let migrations: &[&str] = &[
-        // ── Extensions ──────────────────────────────────────────────────────────
-        r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp";"#,
-        r#"CREATE EXTENSION IF NOT EXISTS pg_trgm;"#,
-
-        // ── Users (extended with profile fields) ────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id                      UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            email                   VARCHAR(255) UNIQUE NOT NULL,
-            name                    VARCHAR(255),
-            password_hash           TEXT NOT NULL,
-            refresh_token_hash      TEXT,
-            household_size          INTEGER NOT NULL DEFAULT 1,
-            dietary_restrictions    TEXT[] DEFAULT '{}',
-            allergies               TEXT[] DEFAULT '{}',
-            avatar_url              TEXT,
-            is_email_verified       BOOLEAN NOT NULL DEFAULT FALSE,
-            two_factor_enabled      BOOLEAN NOT NULL DEFAULT FALSE,
-            totp_secret             TEXT,
-            failed_login_attempts   INTEGER NOT NULL DEFAULT 0,
-            locked_until            TIMESTAMPTZ,
-            created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
-        "#,
-
-        // ── Ingredients ──────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS ingredients (
-            id          BIGSERIAL PRIMARY KEY,
-            name        TEXT UNIQUE NOT NULL,
-            category    TEXT,
-            fdc_id      INTEGER,
-            off_id      TEXT,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_ingredients_name_trgm
-            ON ingredients USING GIN (name gin_trgm_ops);
-        CREATE INDEX IF NOT EXISTS idx_ingredients_category
-            ON ingredients(category);
-        CREATE INDEX IF NOT EXISTS idx_ingredients_fdc_id
-            ON ingredients(fdc_id) WHERE fdc_id IS NOT NULL;
-        "#,
-
-        // ── Ingredient Nutrients ─────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS ingredient_nutrients (
-            id                  BIGSERIAL PRIMARY KEY,
-            ingredient_id       BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
-            calories            NUMERIC(10,4),
-            protein_g           NUMERIC(10,4),
-            carbs_g             NUMERIC(10,4),
-            fat_g               NUMERIC(10,4),
-            fiber_g             NUMERIC(10,4),
-            sugar_g             NUMERIC(10,4),
-            sodium_mg           NUMERIC(10,4),
-            saturated_fat_g     NUMERIC(10,4),
-            cholesterol_mg      NUMERIC(10,4),
-            micronutrients      JSONB,
-            UNIQUE(ingredient_id)
-        );
-        CREATE INDEX IF NOT EXISTS idx_ingredient_nutrients_ingredient
-            ON ingredient_nutrients(ingredient_id);
-        CREATE INDEX IF NOT EXISTS idx_ingredient_nutrients_micros
-            ON ingredient_nutrients USING GIN (micronutrients);
-        "#,
-
-        // ── Portion Sizes ────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS portion_sizes (
-            id              BIGSERIAL PRIMARY KEY,
-            ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
-            description     TEXT NOT NULL,
-            weight_grams    NUMERIC(10,3) NOT NULL,
-            unit            TEXT
-        );
-        CREATE INDEX IF NOT EXISTS idx_portion_sizes_ingredient
-            ON portion_sizes(ingredient_id);
-        "#,
-
-        // ── Recipes ──────────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS recipes (
-            id              BIGSERIAL PRIMARY KEY,
-            name            TEXT NOT NULL,
-            slug            TEXT UNIQUE NOT NULL,
-            description     TEXT,
-            cuisine         TEXT,
-            category        TEXT,
-            difficulty      TEXT,
-            servings        INTEGER NOT NULL DEFAULT 2,
-            prep_time_min   INTEGER,
-            cook_time_min   INTEGER,
-            total_time_min  INTEGER,
-            is_vegetarian   BOOLEAN NOT NULL DEFAULT FALSE,
-            is_vegan        BOOLEAN NOT NULL DEFAULT FALSE,
-            is_gluten_free  BOOLEAN NOT NULL DEFAULT FALSE,
-            is_dairy_free   BOOLEAN NOT NULL DEFAULT FALSE,
-            is_nut_free     BOOLEAN NOT NULL DEFAULT FALSE,
-            source_url      TEXT,
-            average_rating  NUMERIC(3,2),
-            rating_count    INTEGER NOT NULL DEFAULT 0,
-            created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_recipes_name_trgm
-            ON recipes USING GIN (name gin_trgm_ops);
-        CREATE INDEX IF NOT EXISTS idx_recipes_cuisine    ON recipes(cuisine);
-        CREATE INDEX IF NOT EXISTS idx_recipes_category   ON recipes(category);
-        CREATE INDEX IF NOT EXISTS idx_recipes_difficulty ON recipes(difficulty);
-        CREATE INDEX IF NOT EXISTS idx_recipes_dietary
-            ON recipes(is_vegetarian, is_vegan, is_gluten_free, is_dairy_free, is_nut_free);
-        "#,
-
-        // ── Recipe Ingredients ───────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_ingredients (
-            id              BIGSERIAL PRIMARY KEY,
-            recipe_id       BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE RESTRICT,
-            quantity        NUMERIC(10,3),
-            unit            TEXT,
-            quantity_grams  NUMERIC(10,3),
-            notes           TEXT,
-            display_order   INTEGER NOT NULL DEFAULT 0
-        );
-        CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_recipe
-            ON recipe_ingredients(recipe_id);
-        CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_ingredient
-            ON recipe_ingredients(ingredient_id);
-        "#,
-
-        // ── Recipe Steps ─────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_steps (
-            id              BIGSERIAL PRIMARY KEY,
-            recipe_id       BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            step_number     INTEGER NOT NULL,
-            instruction     TEXT NOT NULL,
-            duration_min    INTEGER,
-            image_url       TEXT,
-            tip             TEXT,
-            UNIQUE(recipe_id, step_number)
-        );
-        CREATE INDEX IF NOT EXISTS idx_recipe_steps_recipe
-            ON recipe_steps(recipe_id);
-        "#,
-
-        // ── Recipe Images ────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_images (
-            id          BIGSERIAL PRIMARY KEY,
-            recipe_id   BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            url         TEXT NOT NULL,
-            image_type  TEXT,
-            is_primary  BOOLEAN NOT NULL DEFAULT FALSE,
-            width       INTEGER,
-            height      INTEGER,
-            source      TEXT,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_recipe_images_recipe
-            ON recipe_images(recipe_id);
-        CREATE INDEX IF NOT EXISTS idx_recipe_images_primary
-            ON recipe_images(recipe_id, is_primary) WHERE is_primary = TRUE;
-        "#,
-
-        // ── Recipe Nutrition (precomputed) ───────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_nutrition (
-            id                  BIGSERIAL PRIMARY KEY,
-            recipe_id           BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            per_serving         BOOLEAN NOT NULL DEFAULT TRUE,
-            calories            NUMERIC(10,4),
-            protein_g           NUMERIC(10,4),
-            carbs_g             NUMERIC(10,4),
-            fat_g               NUMERIC(10,4),
-            fiber_g             NUMERIC(10,4),
-            sugar_g             NUMERIC(10,4),
-            sodium_mg           NUMERIC(10,4),
-            saturated_fat_g     NUMERIC(10,4),
-            cholesterol_mg      NUMERIC(10,4),
-            micronutrients      JSONB,
-            calculated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            UNIQUE(recipe_id)
-        );
-        CREATE INDEX IF NOT EXISTS idx_recipe_nutrition_recipe
-            ON recipe_nutrition(recipe_id);
-        "#,
-
-        // ── User Favorites ───────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS user_favorites (
-            id          BIGSERIAL PRIMARY KEY,
-            user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            recipe_id   BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            saved_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            UNIQUE(user_id, recipe_id)
-        );
-        CREATE INDEX IF NOT EXISTS idx_user_favorites_user   ON user_favorites(user_id);
-        CREATE INDEX IF NOT EXISTS idx_user_favorites_recipe ON user_favorites(recipe_id);
-        "#,
-
-        // ── Recipe Ratings ───────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_ratings (
-            id          BIGSERIAL PRIMARY KEY,
-            user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            recipe_id   BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            rating      SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
-            comment     TEXT,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            UNIQUE(user_id, recipe_id)
-        );
-        CREATE INDEX IF NOT EXISTS idx_recipe_ratings_recipe ON recipe_ratings(recipe_id);
-        CREATE INDEX IF NOT EXISTS idx_recipe_ratings_user   ON recipe_ratings(user_id);
-        "#,
-
-        // ── Cooking History ──────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS cooking_history (
-            id                  BIGSERIAL PRIMARY KEY,
-            user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            recipe_id           BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            servings_made       INTEGER NOT NULL DEFAULT 1,
-            inventory_deducted  BOOLEAN NOT NULL DEFAULT FALSE,
-            cooked_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_cooking_history_user   ON cooking_history(user_id);
-        CREATE INDEX IF NOT EXISTS idx_cooking_history_recipe ON cooking_history(recipe_id);
-        CREATE INDEX IF NOT EXISTS idx_cooking_history_date   ON cooking_history(user_id, cooked_at DESC);
-        "#,
-
-        // ── Inventory Items ──────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS inventory_items (
-            id                  BIGSERIAL PRIMARY KEY,
-            user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            ingredient_id       BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE RESTRICT,
-            custom_name         TEXT,
-            quantity            NUMERIC(10,3) NOT NULL,
-            unit                TEXT NOT NULL,
-            expiry_date         DATE,
-            storage_location    TEXT,
-            added_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_inventory_user        ON inventory_items(user_id);
-        CREATE INDEX IF NOT EXISTS idx_inventory_ingredient  ON inventory_items(ingredient_id);
-        CREATE INDEX IF NOT EXISTS idx_inventory_expiry
-            ON inventory_items(user_id, expiry_date) WHERE expiry_date IS NOT NULL;
-        "#,
-
-        // ── Meal Plans ───────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS meal_plans (
-            id                  BIGSERIAL PRIMARY KEY,
-            user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            week_start          DATE NOT NULL,
-            is_ai_generated     BOOLEAN NOT NULL DEFAULT FALSE,
-            created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            UNIQUE(user_id, week_start)
-        );
-        CREATE INDEX IF NOT EXISTS idx_meal_plans_user ON meal_plans(user_id);
-        "#,
-
-        // ── Meal Plan Slots ──────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS meal_plan_slots (
-            id                  BIGSERIAL PRIMARY KEY,
-            meal_plan_id        BIGINT NOT NULL REFERENCES meal_plans(id) ON DELETE CASCADE,
-            recipe_id           BIGINT NOT NULL REFERENCES recipes(id) ON DELETE RESTRICT,
-            day_of_week         SMALLINT NOT NULL CHECK (day_of_week BETWEEN 0 AND 6),
-            meal_type           TEXT NOT NULL,
-            servings_override   INTEGER,
-            is_completed        BOOLEAN NOT NULL DEFAULT FALSE,
-            UNIQUE(meal_plan_id, day_of_week, meal_type)
-        );
-        CREATE INDEX IF NOT EXISTS idx_meal_plan_slots_plan   ON meal_plan_slots(meal_plan_id);
-        CREATE INDEX IF NOT EXISTS idx_meal_plan_slots_recipe ON meal_plan_slots(recipe_id);
-        "#,
-
-        // ── Chat Sessions ────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS chat_sessions (
-            id                  BIGSERIAL PRIMARY KEY,
-            user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            current_recipe_id   BIGINT REFERENCES recipes(id) ON DELETE SET NULL,
-            title               TEXT,
-            created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_chat_sessions_user ON chat_sessions(user_id);
-        "#,
-
-        // ── Chat Messages ────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS chat_messages (
-            id          BIGSERIAL PRIMARY KEY,
-            session_id  BIGINT NOT NULL REFERENCES chat_sessions(id) ON DELETE CASCADE,
-            role        TEXT NOT NULL CHECK (role IN ('user', 'assistant', 'system')),
-            content     TEXT NOT NULL,
-            tokens_used INTEGER,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_chat_messages_session
-            ON chat_messages(session_id, created_at ASC);
-        "#,
-
-        // ── User Preferences (ML vector) ─────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS user_preferences (
-            user_id             UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
-            cuisine_weights     JSONB NOT NULL DEFAULT '{}',
-            ingredient_weights  JSONB NOT NULL DEFAULT '{}',
-            macro_bias          JSONB NOT NULL DEFAULT '{"protein":0.0,"carbs":0.0,"fat":0.0}',
-            difficulty_weights  JSONB NOT NULL DEFAULT '{"easy":0.0,"medium":0.0,"hard":0.0}',
-            preferred_time_min  INTEGER NOT NULL DEFAULT 30,
-            interaction_count   INTEGER NOT NULL DEFAULT 0,
-            updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_user_preferences_updated
-            ON user_preferences(updated_at);
-        "#,
-
-        // ── Schema v2: Subscription + onboarding fields on users ────────────
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_tier TEXT NOT NULL DEFAULT 'free';"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_valid_until TIMESTAMPTZ;"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS stripe_customer_id TEXT;"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS cooking_skill_level TEXT;"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS preferred_cuisines TEXT[] DEFAULT '{}';"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS health_goals TEXT[] DEFAULT '{}';"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS weekly_budget NUMERIC(10,2);"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS preferred_time_per_meal_min INTEGER;"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS onboarding_completed BOOLEAN NOT NULL DEFAULT FALSE;"#,
-        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT FALSE;"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_users_stripe ON users(stripe_customer_id) WHERE stripe_customer_id IS NOT NULL;"#,
-
-        // ── Schema v2: Flex / energy fields on meal_plan_slots ──────────────
-        r#"ALTER TABLE meal_plan_slots ALTER COLUMN recipe_id DROP NOT NULL;"#,
-        r#"ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS is_flex BOOLEAN NOT NULL DEFAULT FALSE;"#,
-        r#"ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS flex_type TEXT;"#,
-        r#"ALTER TABLE meal_plan_slots ADD COLUMN IF NOT EXISTS energy_level TEXT;"#,
-
-        // ── Schema v2: author_id on recipes ──────────────────────────────────
-        r#"ALTER TABLE recipes ADD COLUMN IF NOT EXISTS author_id UUID REFERENCES users(id) ON DELETE SET NULL;"#,
-        r#"ALTER TABLE recipes ADD COLUMN IF NOT EXISTS is_public BOOLEAN NOT NULL DEFAULT TRUE;"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipes_author ON recipes(author_id) WHERE author_id IS NOT NULL;"#,
-
-        // ── Stores ─────────────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS stores (
-            id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            name        TEXT NOT NULL,
-            slug        TEXT UNIQUE NOT NULL,
-            website     TEXT,
-            logo_url    TEXT,
-            country     TEXT,
-            city        TEXT,
-            lat         DOUBLE PRECISION,
-            lng         DOUBLE PRECISION,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_stores_slug ON stores(slug);
-        "#,
-
-        // ── PDF Processing Jobs ───────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS pdf_processing_jobs (
-            id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            store_id        UUID NOT NULL REFERENCES stores(id) ON DELETE CASCADE,
-            file_path       TEXT NOT NULL,
-            status          TEXT NOT NULL DEFAULT 'pending',
-            error           TEXT,
-            retry_count     INTEGER NOT NULL DEFAULT 0,
-            started_at      TIMESTAMPTZ,
-            heartbeat_at    TIMESTAMPTZ,
-            processed_at    TIMESTAMPTZ,
-            created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_pdf_jobs_store  ON pdf_processing_jobs(store_id);
-        CREATE INDEX IF NOT EXISTS idx_pdf_jobs_status ON pdf_processing_jobs(status);
-        "#,
-
-        // ── Store Promotions (published, admin-approved) ──────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS store_promotions (
-            id                  UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            store_id            UUID NOT NULL REFERENCES stores(id) ON DELETE CASCADE,
-            product_name        TEXT NOT NULL,
-            brand               TEXT,
-            original_price      NUMERIC(10,2),
-            discounted_price    NUMERIC(10,2) NOT NULL,
-            discount_pct        NUMERIC(5,2),
-            unit                TEXT,
-            valid_from          TIMESTAMPTZ,
-            valid_until         TIMESTAMPTZ,
-            is_active           BOOLEAN NOT NULL DEFAULT TRUE,
-            source_pdf_url      TEXT,
-            confidence          NUMERIC(4,3),
-            created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_store_promotions_store  ON store_promotions(store_id);
-        CREATE INDEX IF NOT EXISTS idx_store_promotions_active ON store_promotions(store_id, is_active, valid_until);
-        CREATE INDEX IF NOT EXISTS idx_store_promotions_name_trgm
-            ON store_promotions USING GIN (product_name gin_trgm_ops);
-        "#,
-
-        // ── Store Promotion Ingredients (cross-reference) ─────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS store_promotion_ingredients (
-            promotion_id    UUID NOT NULL REFERENCES store_promotions(id) ON DELETE CASCADE,
-            ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
-            similarity_score NUMERIC(4,3),
-            PRIMARY KEY (promotion_id, ingredient_id)
-        );
-        CREATE INDEX IF NOT EXISTS idx_promo_ingredients_ingredient
-            ON store_promotion_ingredients(ingredient_id);
-        "#,
-
-        // ── Store Promotion Candidates (AI staging, awaiting admin review) ────
-        r#"
-        CREATE TABLE IF NOT EXISTS store_promotion_candidates (
-            id                  UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            store_id            UUID NOT NULL REFERENCES stores(id) ON DELETE CASCADE,
-            job_id              UUID NOT NULL REFERENCES pdf_processing_jobs(id) ON DELETE CASCADE,
-            product_name        TEXT NOT NULL,
-            brand               TEXT,
-            original_price      NUMERIC(10,2),
-            discounted_price    NUMERIC(10,2) NOT NULL,
-            discount_pct        NUMERIC(5,2),
-            unit                TEXT,
-            valid_from          TIMESTAMPTZ,
-            valid_until         TIMESTAMPTZ,
-            confidence          NUMERIC(4,3),
-            review_status       TEXT NOT NULL DEFAULT 'pending',
-            reviewed_by         UUID REFERENCES users(id) ON DELETE SET NULL,
-            reviewed_at         TIMESTAMPTZ,
-            created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_candidates_job    ON store_promotion_candidates(job_id);
-        CREATE INDEX IF NOT EXISTS idx_candidates_review ON store_promotion_candidates(review_status);
-        "#,
-
-        // ── Shopping List ─────────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS shopping_list_items (
-            id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            user_id         UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            ingredient_id   BIGINT REFERENCES ingredients(id) ON DELETE SET NULL,
-            name            TEXT NOT NULL,
-            quantity        NUMERIC(10,3),
-            unit            TEXT,
-            is_checked      BOOLEAN NOT NULL DEFAULT FALSE,
-            is_manual       BOOLEAN NOT NULL DEFAULT FALSE,
-            meal_plan_id    BIGINT REFERENCES meal_plans(id) ON DELETE SET NULL,
-            created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_shopping_list_user       ON shopping_list_items(user_id);
-        CREATE INDEX IF NOT EXISTS idx_shopping_list_ingredient ON shopping_list_items(ingredient_id) WHERE ingredient_id IS NOT NULL;
-        "#,
-
-        // ── User Push Tokens ─────────────────────────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS user_push_tokens (
-            id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            token       TEXT UNIQUE NOT NULL,
-            platform    TEXT NOT NULL,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        CREATE INDEX IF NOT EXISTS idx_push_tokens_user ON user_push_tokens(user_id);
-        "#,
-
-        // ── Stripe Processed Events (idempotency) ─────────────────────────────
-        r#"
-        CREATE TABLE IF NOT EXISTS stripe_processed_events (
-            event_id        TEXT PRIMARY KEY,
-            processed_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        );
-        "#,
-    ];
-
-    for sql in migrations {
-        for statement in sql.split(';') {
-            let stmt = statement.trim();
-            if stmt.is_empty() {
-                continue;
-            }
-            db.execute(Statement::from_string(
-                sea_orm::DatabaseBackend::Postgres,
-                stmt.to_string(),
-            ))
-            .await
-            .expect("Failed to run migration");
+    // Schema changes are applied explicitly with `cookest-app-api migrate <up|down|status>`
+    let migrator = Migrator::new(migrations::MIGRATIONS).expect("Invalid migration list");
+    if let Some(command) = migrate_command() {
+        if let Err(e) = command.run(&migrator, &db).await {
+            tracing::error!("Migration failed: {}", e);
+            std::process::exit(1);
         }
+        return Ok(());
     }
 
-    tracing::info!("All {} migrations complete", migrations.len());
+    // Never migrate implicitly on boot — refuse to serve against an outdated schema
+    migrator
+        .ensure_up_to_date(&db)
+        .await
+        .expect("Database schema is not up to date");
 
     // Initialize services
     let token_service = Arc::new(TokenService::new(&config));
-    let auth_service = Arc::new(AuthService::new(db.clone(), TokenService::new(&config)));
+    let auth_service = Arc::new(AuthService::new(
+        db.clone(),
+        TokenService::new(&config),
+        config.require_verified_email,
+        config.totp_key().map(|key| SecretCipher::new(&key)),
+    ));
+    let session_service = Arc::new(SessionService::new(db.clone()));
     let recipe_service = Arc::new(RecipeService::new(db.clone()));
     let ingredient_service = Arc::new(IngredientService::new(db.clone()));
     let meal_plan_service = Arc::new(MealPlanService::new(db.clone()));
@@ -585,12 +126,21 @@ async fn main() -> std::io::Result<()> {
     let profile_service = Arc::new(ProfileService::new(db.clone()));
     let interaction_service = Arc::new(InteractionService::new(db.clone()));
     let preference_service = Arc::new(PreferenceService::new(db.clone()));
-    let chat_service = Arc::new(ChatService::new(db.clone()));
+    // One LLM provider shared by chat, recipe generation, scanning and flyer parsing
+    let llm = llm::from_config(&config).expect("Failed to set up the LLM provider");
+    let chat_service = Arc::new(ChatService::new(db.clone(), llm.clone()));
     let onboarding_service = Arc::new(OnboardingService::new(db.clone()));
     let shopping_list_service = Arc::new(ShoppingListService::new(db.clone()));
+    let household_service = Arc::new(HouseholdService::new(db.clone()));
     let subscription_service = Arc::new(SubscriptionService::new(
         db.clone(),
         config.stripe_webhook_secret.clone(),
+        StripeClient::new(config.stripe_api_base.clone(), config.stripe_secret_key.clone()),
+        StripePrices {
+            pro: config.stripe_price_pro.clone(),
+            family: config.stripe_price_family.clone(),
+        },
+        config.stripe_redirect_origins.clone(),
     ));
 
     // Ensure PDF upload directory exists
@@ -600,13 +150,36 @@ async fn main() -> std::io::Result<()> {
     let store_service = Arc::new(StoreService::new(
         db.clone(),
         std::path::PathBuf::from(&config.pdf_upload_dir),
-        config.ollama_url.clone(),
-        config.ollama_model.clone(),
     ));
+    // Claims flyer jobs from pdf_processing_jobs; every replica runs one
+    let pdf_worker = Arc::new(PdfWorker::new(db.clone(), llm.clone()));
+    pdf_worker.clone().spawn();
 
     let push_token_service = Arc::new(PushTokenService::new(db.clone()));
-    let scan_service = Arc::new(ScanService::new());
-    let recipe_gen_service = Arc::new(RecipeGenService::new(db.clone()));
+    let notification_service = Arc::new(NotificationService::new(db.clone(), push::from_config(&config)));
+    // Drains notification_outbox; safe to run on every replica
+    notification_service
+        .clone()
+        .spawn_dispatcher(std::time::Duration::from_secs(config.notification_dispatch_secs));
+
+    // Recurring jobs, leased through Postgres so replicas never double-run them
+    let scheduler_service = Arc::new(SchedulerService::new(
+        db.clone(),
+        vec![
+            Arc::new(ExpiryAlerts::new(db.clone(), inventory_service.clone())),
+            Arc::new(WeeklyPlans::new(db.clone(), meal_plan_service.clone())),
+            Arc::new(Cleanup::new(db.clone())),
+        ],
+    ));
+    if config.scheduler_enabled {
+        scheduler_service
+            .clone()
+            .spawn(std::time::Duration::from_secs(config.scheduler_poll_secs));
+    } else {
+        tracing::warn!("SCHEDULER_ENABLED=false — scheduled jobs will not run on this replica");
+    }
+    let scan_service = Arc::new(ScanService::new(llm.clone()));
+    let recipe_gen_service = Arc::new(RecipeGenService::new(db.clone(), llm.clone()));
     let food_api_client = FoodApiClient::new(config.food_api_url.clone(), config.food_api_key.clone());
     let image_gen_client = ImageGenClient::new(config.image_gen_url.clone(), config.image_gen_token.clone());
 
@@ -624,6 +197,23 @@ async fn main() -> std::io::Result<()> {
         ))
     };
 
+    // Rate limits per route group — built once so every worker shares the buckets
+    let auth_rate_limit = RateLimit::new(RateLimitConfig {
+        requests_per_minute: config.rate_limit_auth_rpm,
+        key: RateLimitKey::Ip,
+        trusted_proxies: config.trusted_proxies.clone(),
+        ..Default::default()
+    });
+    let api_rate_limit = RateLimit::new(RateLimitConfig {
+        requests_per_minute: config.rate_limit_api_rpm,
+        key: RateLimitKey::User(jwt_subject),
+        trusted_proxies: config.trusted_proxies.clone(),
+        ..Default::default()
+    });
+
+    // Lets handlers resolve the real client IP (recorded on login sessions)
+    let trusted_proxies = config.trusted_proxies.clone();
+
     tracing::info!("Server starting on {}", bind_address);
 
     HttpServer::new(move || {
@@ -662,6 +252,7 @@ async fn main() -> std::io::Result<()> {
             // Services
             .app_data(web::Data::new(auth_service.clone()))
             .app_data(web::Data::new(token_service.clone()))
+            .app_data(web::Data::new(session_service.clone()))
             .app_data(web::Data::new(recipe_service.clone()))
             .app_data(web::Data::new(ingredient_service.clone()))
             .app_data(web::Data::new(meal_plan_service.clone()))
@@ -672,17 +263,22 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(chat_service.clone()))
             .app_data(web::Data::new(onboarding_service.clone()))
             .app_data(web::Data::new(shopping_list_service.clone()))
+            .app_data(web::Data::new(household_service.clone()))
             .app_data(web::Data::new(subscription_service.clone()))
             .app_data(web::Data::new(store_service.clone()))
+            .app_data(web::Data::new(pdf_worker.clone()))
             .app_data(web::Data::new(push_token_service.clone()))
+            .app_data(web::Data::new(notification_service.clone()))
+            .app_data(web::Data::new(scheduler_service.clone()))
             .app_data(web::Data::new(email_service.clone()))
             .app_data(web::Data::new(scan_service.clone()))
             .app_data(web::Data::new(recipe_gen_service.clone()))
             .app_data(web::Data::new(food_api_client.clone()))
             .app_data(web::Data::new(image_gen_client.clone()))
             .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::new(trusted_proxies.clone()))
             // ── Public routes (no JWT required) ──────────────────────────────
-            .configure(configure_auth)        // /api/auth/*
+            .configure(configure_auth(auth_rate_limit.clone())) // /api/auth/* (per-IP limit)
             .configure(configure_recipes)     // /api/recipes/* (read-only browsing)
             .configure(configure_ingredients) // /api/ingredients/* (search)
             .configure(configure_subscription) // /api/webhooks/stripe (raw body, no JWT)
@@ -699,11 +295,15 @@ async fn main() -> std::io::Result<()> {
             // ── Protected routes (JWT required) ──────────────────────────────
             .service(
                 web::scope("")
+                    // Registered first → runs after JwtAuth, so it can key on the user id
+                    .wrap(api_rate_limit.clone())
                     .wrap(JwtAuth::new(token_service.clone()))
                     .configure(configure_user)
                     .configure(configure_chat)
                     .configure(configure_onboarding)
                     .configure(configure_shopping_list)
+                    .configure(configure_households)
+                    .configure(configure_admin) // before stores: its /api/admin scope matches first
                     .configure(configure_stores)
                     .configure(configure_recipes_protected)
                     .configure(configure_subscription_protected)
@@ -68,6 +68,11 @@ impl FromRequest for AuthenticatedUser {
     }
 }
 
+/// Authenticated user id for `RateLimitKey::User` — only set once `JwtAuth` has run
+pub fn jwt_subject(req: &ServiceRequest) -> Option<String> {
+    req.extensions().get::<Claims>().map(|c| c.sub.clone())
+}
+
 /// Middleware factory — wrap a scope with `.wrap(JwtAuth::new(token_service))`
 pub struct JwtAuth {
     token_service: Arc<TokenService>,
@@ -1,10 +1,10 @@
 //! Actix-Web middleware: JWT auth, rate limiting, and security headers.
 pub mod auth;
 
-pub use auth::JwtAuth;
+pub use auth::{jwt_subject, JwtAuth};
 
 // Re-export shared middleware
-pub use cookest_shared::middleware::rate_limit::{RateLimit, RateLimitConfig};
+pub use cookest_shared::middleware::rate_limit::{RateLimit, RateLimitConfig, RateLimitKey};
 pub use cookest_shared::middleware::security_headers::SecurityHeaders;
 
 // Re-export Claims so handlers can import it from crate::middleware::Claims
@@ -0,0 +1,35 @@
+//! Embedded schema migrations, applied with `cookest-app-api migrate up`.
+//!
+//! Add a new change as the next `NNNN_name.up.sql` / `.down.sql` pair under
+//! `crates/app-api/migrations/` and list it here. Never edit a file that has
+//! already shipped — its checksum is recorded in `schema_migrations`.
+
+use cookest_shared::migrate::Migration;
+use cookest_shared::migration;
+
+/// Every schema migration for this service, in version order
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(1, "initial_schema", "0001_initial_schema"),
+    migration!(2, "schema_v2", "0002_schema_v2"),
+    migration!(3, "ingredient_density", "0003_ingredient_density"),
+    migration!(4, "stores_and_promotions", "0004_stores_and_promotions"),
+    migration!(5, "shopping_list", "0005_shopping_list"),
+    migration!(6, "push_tokens", "0006_push_tokens"),
+    migration!(7, "stripe_processed_events", "0007_stripe_processed_events"),
+    migration!(8, "email_tokens", "0008_email_tokens"),
+    migration!(9, "two_factor", "0009_two_factor"),
+    migration!(10, "user_sessions", "0010_user_sessions"),
+    migration!(11, "households", "0011_households"),
+    migration!(12, "chat_summaries", "0012_chat_summaries"),
+    migration!(13, "chat_actions", "0013_chat_actions"),
+    migration!(14, "ingredient_prices", "0014_ingredient_prices"),
+    migration!(15, "nutrition_targets", "0015_nutrition_targets"),
+    migration!(16, "plan_seed", "0016_plan_seed"),
+    migration!(17, "slot_locks", "0017_slot_locks"),
+    migration!(18, "leftovers", "0018_leftovers"),
+    migration!(19, "notifications", "0019_notifications"),
+    migration!(20, "scheduled_jobs", "0020_scheduled_jobs"),
+    migration!(21, "pdf_job_recovery", "0021_pdf_job_recovery"),
+    migration!(22, "promotion_matching", "0022_promotion_matching"),
+    migration!(23, "candidate_review", "0023_candidate_review"),
+];
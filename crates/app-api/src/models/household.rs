@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use validator::Validate;
+
+/// Request to create a household (Family tier)
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateHouseholdRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+/// Request to invite someone to the household by email
+#[derive(Debug, Deserialize, Validate)]
+pub struct InviteMemberRequest {
+    #[validate(email)]
+    pub email: String,
+
+    /// "admin" or "member" (default)
+    pub role: Option<String>,
+}
+
+/// Request to accept an emailed household invite
+#[derive(Debug, Deserialize, Validate)]
+pub struct AcceptInviteRequest {
+    #[validate(length(min = 1, max = 128))]
+    pub token: String,
+}
+
+/// Request to change a member's role
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateMemberRoleRequest {
+    /// "admin" or "member"
+    pub role: String,
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::models::inventory::LeftoverResponse;
+use crate::services::units::UnconvertedItem;
+
 /// Request to rate a recipe
 #[derive(Debug, Deserialize, Validate)]
 pub struct RateRecipeRequest {
@@ -11,12 +14,34 @@ pub struct RateRecipeRequest {
     pub comment: Option<String>,
 }
 
+/// Optional body for marking a recipe as cooked. Both default to the
+/// household size; servings made beyond those eaten are kept as leftovers.
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct CookRecipeRequest {
+    #[validate(range(min = 1, max = 100))]
+    pub servings_made: Option<i32>,
+
+    #[validate(range(min = 0, max = 100))]
+    pub servings_eaten: Option<i32>,
+}
+
 /// Response after rating / cooking
 #[derive(Debug, Serialize)]
 pub struct InteractionResponse {
     pub message: String,
 }
 
+/// Response after marking a recipe as cooked
+#[derive(Debug, Serialize)]
+pub struct CookedResponse {
+    pub message: String,
+    /// Recipe or pantry quantities whose units could not be converted; these
+    /// pantry items were left unchanged
+    pub unconverted: Vec<UnconvertedItem>,
+    /// Servings stored for later, when more were made than eaten
+    pub leftover: Option<LeftoverResponse>,
+}
+
 /// Response for favourite status
 #[derive(Debug, Serialize)]
 pub struct FavouriteResponse {
@@ -1,6 +1,7 @@
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 /// Request to add an item to inventory
 #[derive(Debug, Deserialize)]
@@ -41,6 +42,8 @@ pub struct InventoryItemResponse {
     pub custom_name: Option<String>,
     pub quantity: Decimal,
     pub unit: String,
+    /// Quantity normalised to grams; None when the unit cannot be converted
+    pub quantity_grams: Option<Decimal>,
     pub expiry_date: Option<NaiveDate>,
     pub storage_location: Option<String>,
     /// Days until expiry: negative = already expired, None = no expiry date
@@ -49,6 +52,28 @@ pub struct InventoryItemResponse {
     pub expiry_warning: bool,
 }
 
+/// Cooked servings kept for later
+#[derive(Debug, Serialize)]
+pub struct LeftoverResponse {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub recipe_name: String,
+    pub servings: i32,
+    pub storage_location: String,
+    pub expiry_date: NaiveDate,
+    /// Days until expiry: negative = already expired
+    pub days_until_expiry: i64,
+    /// True if they should be eaten today or tomorrow
+    pub expiry_warning: bool,
+}
+
+/// Request to eat some stored leftovers
+#[derive(Debug, Deserialize, Validate)]
+pub struct EatLeftoversRequest {
+    #[validate(range(min = 1, max = 100, message = "servings must be 1 to 100"))]
+    pub servings: i32,
+}
+
 /// A recipe suggestion based on pantry contents
 #[derive(Debug, Serialize)]
 pub struct RecipeSuggestion {
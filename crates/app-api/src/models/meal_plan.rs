@@ -1,5 +1,7 @@
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use validator::Validate;
 
 /// Request to generate a meal plan
@@ -7,6 +9,33 @@ use validator::Validate;
 pub struct GenerateMealPlanRequest {
     /// Week start date (must be a Monday)
     pub week_start: NaiveDate,
+
+    /// Optimiser seed — the same seed and data give the same plan.
+    /// A random seed is used (and returned) when omitted.
+    pub seed: Option<u32>,
+}
+
+/// Which slots of a plan to regenerate. Locked, completed and flex slots
+/// are always kept.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "scope", rename_all = "snake_case")]
+pub enum RegenerateScope {
+    /// Every slot that is not kept
+    Unlocked,
+    /// One day, 0 = Monday
+    Day { day_of_week: i16 },
+    /// One meal type across the week
+    MealType { meal_type: String },
+}
+
+/// Request to regenerate part of a meal plan
+#[derive(Debug, Deserialize)]
+pub struct RegeneratePlanRequest {
+    #[serde(flatten)]
+    pub target: RegenerateScope,
+
+    /// Optimiser seed; a random one is used (and returned) when omitted
+    pub seed: Option<u32>,
 }
 
 /// Meal plan slot response
@@ -31,3 +60,45 @@ pub struct MealPlanResponse {
     pub is_ai_generated: bool,
     pub slots: Vec<MealPlanSlotResponse>,
 }
+
+/// Estimated cost of a meal plan (see `services::pricing`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanCost {
+    pub total: Decimal,
+    /// Part of `total` priced from current store promotions; the rest comes
+    /// from reference or category prices
+    pub from_promotions: Decimal,
+    pub weekly_budget: Option<Decimal>,
+    /// Budget minus total — negative when the plan is over budget
+    pub remaining: Option<Decimal>,
+    pub over_budget: bool,
+    /// Monday first
+    pub by_day: Vec<Decimal>,
+    pub by_meal_type: BTreeMap<String, Decimal>,
+    /// Ingredients left out of the estimate because their quantities could
+    /// not be converted to grams
+    pub unpriced_ingredient_ids: Vec<i64>,
+}
+
+/// Why a recipe was removed from the generator's candidate pool
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// A required recipe flag (e.g. `is_nut_free`) is not set
+    DietaryFlag { flag: String, source: String },
+    /// An ingredient is mapped to an allergen the user avoids
+    Allergen {
+        allergen: String,
+        ingredient: String,
+        severity: String,
+        source: String,
+    },
+}
+
+/// A candidate recipe filtered out by the user's allergies or dietary restrictions
+#[derive(Debug, Serialize)]
+pub struct ExcludedRecipe {
+    pub recipe_id: i64,
+    pub recipe_name: String,
+    pub reasons: Vec<ExclusionReason>,
+}
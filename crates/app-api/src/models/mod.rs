@@ -6,3 +6,5 @@ pub mod inventory;
 pub mod profile;
 pub mod interaction;
 pub mod meal_plan;
+pub mod household;
+pub mod notification;
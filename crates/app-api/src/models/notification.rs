@@ -0,0 +1,37 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Local-time window in which notifications are held back. An end earlier
+/// than the start spans midnight (22:00 → 07:00); equal times mean no window.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationPreferencesResponse {
+    pub enabled: bool,
+    pub expiry_alerts: bool,
+    pub meal_plan_updates: bool,
+    pub household_activity: bool,
+    pub quiet_hours: Option<QuietHours>,
+    pub utc_offset_minutes: i16,
+}
+
+/// Partial update — omitted fields are left as they are
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub enabled: Option<bool>,
+    pub expiry_alerts: Option<bool>,
+    pub meal_plan_updates: Option<bool>,
+    pub household_activity: Option<bool>,
+
+    /// Send equal start and end times to turn quiet hours off
+    pub quiet_hours: Option<QuietHours>,
+
+    /// The device's current offset from UTC, e.g. 60 for CET
+    #[validate(range(min = -840, max = 840))]
+    pub utc_offset_minutes: Option<i16>,
+}
@@ -1,6 +1,10 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::entity::user;
+use crate::services::nutrition::NutritionTargets;
+
 /// Request to update user profile
 #[derive(Debug, Deserialize, Validate)]
 pub struct UpdateProfileRequest {
@@ -19,6 +23,46 @@ pub struct UpdateProfileRequest {
 
     /// URL to avatar image (set by separate upload endpoint)
     pub avatar_url: Option<String>,
+
+    /// e.g. ["weight_loss", "high_protein", "low_carb"]
+    pub health_goals: Option<Vec<String>>,
+
+    #[validate(range(min = 1900, max = 2100))]
+    pub birth_year: Option<i32>,
+
+    /// "female" or "male"
+    pub sex: Option<String>,
+
+    #[validate(range(min = 20.0, max = 400.0))]
+    pub weight_kg: Option<f64>,
+
+    #[validate(range(min = 80.0, max = 260.0))]
+    pub height_cm: Option<f64>,
+
+    /// "sedentary", "light", "moderate", "active" or "very_active"
+    pub activity_level: Option<String>,
+
+    /// Daily targets set by hand — kept as-is when metrics or goals change
+    #[validate(nested)]
+    pub nutrition_targets: Option<NutritionTargetsRequest>,
+
+    /// Drop hand-set targets and go back to computed ones
+    pub reset_nutrition_targets: Option<bool>,
+}
+
+/// Hand-set daily nutrition targets
+#[derive(Debug, Deserialize, Validate)]
+pub struct NutritionTargetsRequest {
+    #[validate(range(min = 800, max = 6000))]
+    pub calories: i32,
+    #[validate(range(min = 0, max = 500))]
+    pub protein_g: i32,
+    #[validate(range(min = 0, max = 1000))]
+    pub carbs_g: i32,
+    #[validate(range(min = 0, max = 400))]
+    pub fat_g: i32,
+    #[validate(range(min = 0, max = 150))]
+    pub fiber_g: i32,
 }
 
 /// Full user profile response
@@ -33,5 +77,40 @@ pub struct ProfileResponse {
     pub avatar_url: Option<String>,
     pub is_email_verified: bool,
     pub two_factor_enabled: bool,
+    pub health_goals: Option<Vec<String>>,
+    pub birth_year: Option<i32>,
+    pub sex: Option<String>,
+    pub weight_kg: Option<Decimal>,
+    pub height_cm: Option<Decimal>,
+    pub activity_level: Option<String>,
+    /// Daily targets — stored, or computed from the profile if none are stored yet
+    pub nutrition_targets: NutritionTargets,
+    pub nutrition_targets_manual: bool,
     pub created_at: String,
 }
+
+impl From<user::Model> for ProfileResponse {
+    fn from(user: user::Model) -> Self {
+        let nutrition_targets = NutritionTargets::for_user(&user);
+        Self {
+            id: user.id.to_string(),
+            email: user.email,
+            name: user.name,
+            household_size: user.household_size,
+            dietary_restrictions: user.dietary_restrictions,
+            allergies: user.allergies,
+            avatar_url: user.avatar_url,
+            is_email_verified: user.is_email_verified,
+            two_factor_enabled: user.two_factor_enabled,
+            health_goals: user.health_goals,
+            birth_year: user.birth_year,
+            sex: user.sex,
+            weight_kg: user.weight_kg,
+            height_cm: user.height_cm,
+            activity_level: user.activity_level,
+            nutrition_targets,
+            nutrition_targets_manual: user.nutrition_targets_manual,
+            created_at: user.created_at.to_rfc3339(),
+        }
+    }
+}
@@ -4,36 +4,108 @@
 //! - Argon2id with OWASP-recommended parameters
 //! - Timing-safe password verification
 //! - Account lockout after failed attempts
-//! - Refresh token rotation with SHA-256 hashing (not DefaultHasher)
+//! - Per-device sessions with refresh token rotation (SHA-256 hashes) and
+//!   reuse detection that revokes the session
 //! - Subscription tier always read from DB when issuing access tokens
+//! - Single-use, expiring email tokens (verification, password reset) stored
+//!   only as SHA-256 hashes
+//! - Optional TOTP 2FA: login returns an "mfa pending" token that is exchanged
+//!   for real tokens with a TOTP or one-time recovery code
 
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2, Algorithm, Params, Version,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{Duration, Utc};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use rand::RngCore;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    QueryFilter, Set,
+};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::entity::{email_token, user_recovery_code};
 use crate::entity::user::{self, ActiveModel, Entity as User, Model as UserModel, UserResponse};
 use cookest_shared::errors::AppError;
+use crate::services::session::{DeviceInfo, SessionService};
 use crate::services::token::{SubscriptionTier, TokenPair, TokenService};
+use crate::services::totp::{self, SecretCipher};
 use crate::validation::{normalize_email, LoginRequest, RegisterRequest};
 
 /// Maximum failed login attempts before lockout
 const MAX_FAILED_ATTEMPTS: i32 = 5;
 /// Lockout duration in minutes
 const LOCKOUT_DURATION_MINUTES: i64 = 15;
+/// Email verification links stay valid for a day
+const VERIFY_EMAIL_TOKEN_HOURS: i64 = 24;
+/// Password reset links are short-lived
+const RESET_PASSWORD_TOKEN_MINUTES: i64 = 60;
+/// Recovery codes issued when 2FA is enabled
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// What an email token may be redeemed for — stored in `email_tokens.purpose`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTokenPurpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+impl EmailTokenPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::VerifyEmail => "verify_email",
+            Self::ResetPassword => "reset_password",
+        }
+    }
+
+    fn lifetime(self) -> Duration {
+        match self {
+            Self::VerifyEmail => Duration::hours(VERIFY_EMAIL_TOKEN_HOURS),
+            Self::ResetPassword => Duration::minutes(RESET_PASSWORD_TOKEN_MINUTES),
+        }
+    }
+}
+
+/// Result of the password step of login
+pub enum LoginOutcome {
+    /// No 2FA — the session is live
+    Authenticated {
+        tokens: TokenPair,
+        refresh_token: String,
+    },
+    /// 2FA enabled — exchange `mfa_token` plus a code at `/api/auth/2fa/verify`
+    MfaRequired { mfa_token: String, expires_in: i64 },
+}
+
+/// Returned by 2FA setup: show as a QR code (`otpauth_uri`) or for manual entry
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
 
 pub struct AuthService {
     db: DatabaseConnection,
     token_service: TokenService,
+    sessions: SessionService,
     argon2: Argon2<'static>,
+    require_verified_email: bool,
+    totp_cipher: Option<SecretCipher>,
 }
 
 impl AuthService {
-    pub fn new(db: DatabaseConnection, token_service: TokenService) -> Self {
+    /// `require_verified_email` (`REQUIRE_VERIFIED_EMAIL`) makes
+    /// [`AuthService::require_verified_email`] reject unverified accounts.
+    /// `totp_cipher` encrypts 2FA secrets; without it 2FA can't be set up.
+    pub fn new(
+        db: DatabaseConnection,
+        token_service: TokenService,
+        require_verified_email: bool,
+        totp_cipher: Option<SecretCipher>,
+    ) -> Self {
         // Configure Argon2id with OWASP-recommended parameters
         // Memory: 19 MiB, Iterations: 2, Parallelism: 1
         let params = Params::new(
@@ -47,9 +119,12 @@ impl AuthService {
         let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
 
         Self {
+            sessions: SessionService::new(db.clone()),
             db,
             token_service,
             argon2,
+            require_verified_email,
+            totp_cipher,
         }
     }
 
@@ -78,7 +153,6 @@ impl AuthService {
             id: Set(user_id),
             email: Set(email),
             password_hash: Set(password_hash),
-            refresh_token_hash: Set(None),
             name: Set(None),
             household_size: Set(1),
             dietary_restrictions: Set(None),
@@ -87,6 +161,7 @@ impl AuthService {
             is_email_verified: Set(false),
             two_factor_enabled: Set(false),
             totp_secret: Set(None),
+            totp_last_used_step: Set(None),
             failed_login_attempts: Set(0),
             locked_until: Set(None),
             subscription_tier: Set("free".to_string()),
@@ -97,6 +172,17 @@ impl AuthService {
             health_goals: Set(None),
             weekly_budget: Set(None),
             preferred_time_per_meal_min: Set(None),
+            birth_year: Set(None),
+            sex: Set(None),
+            weight_kg: Set(None),
+            height_cm: Set(None),
+            activity_level: Set(None),
+            target_calories: Set(None),
+            target_protein_g: Set(None),
+            target_carbs_g: Set(None),
+            target_fat_g: Set(None),
+            target_fiber_g: Set(None),
+            nutrition_targets_manual: Set(false),
             onboarding_completed: Set(false),
             is_admin: Set(false),
             created_at: Set(now),
@@ -110,8 +196,9 @@ impl AuthService {
         Ok(UserResponse::from(user))
     }
 
-    /// Authenticate user and return tokens
-    pub async fn login(&self, request: LoginRequest) -> Result<(TokenPair, String, UserModel), AppError> {
+    /// Authenticate user — returns tokens, or an "mfa pending" token when
+    /// 2FA is enabled
+    pub async fn login(&self, request: LoginRequest, device: DeviceInfo) -> Result<LoginOutcome, AppError> {
         let email = normalize_email(&request.email);
 
         // Find user
@@ -136,26 +223,91 @@ impl AuthService {
             return Err(AppError::AuthenticationFailed);
         }
 
+        // Second factor outstanding — no session until a code is verified
+        if user.two_factor_enabled {
+            let mfa_token = self.token_service.generate_mfa_token(user.id, &user.email)?;
+            tracing::info!("Password verified, awaiting 2FA code: {}", user.id);
+            return Ok(LoginOutcome::MfaRequired {
+                mfa_token,
+                expires_in: self.token_service.mfa_expiry_seconds(),
+            });
+        }
+
+        let (tokens, refresh_token) = self.start_session(user, device).await?;
+        Ok(LoginOutcome::Authenticated { tokens, refresh_token })
+    }
+
+    /// Second login step: exchange an "mfa pending" token and a TOTP or
+    /// recovery code for a normal token pair
+    pub async fn verify_two_factor(
+        &self,
+        mfa_token: &str,
+        code: &str,
+        device: DeviceInfo,
+    ) -> Result<(TokenPair, String), AppError> {
+        let claims = self.token_service.validate_mfa_token(mfa_token)?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        if let Some(locked_until) = user.locked_until {
+            if Utc::now() < locked_until {
+                tracing::warn!("2FA attempt on locked account: {}", user.id);
+                return Err(AppError::AuthenticationFailed);
+            }
+        }
+        if !user.two_factor_enabled {
+            return Err(AppError::InvalidToken);
+        }
+
+        let accepted = if is_totp_code(code) {
+            self.accept_totp_code(&user, code).await?
+        } else {
+            self.redeem_recovery_code(user.id, code).await?
+        };
+
+        if !accepted {
+            self.increment_failed_attempts(&user).await?;
+            return Err(AppError::AuthenticationFailed);
+        }
+
+        self.start_session(user, device).await
+    }
+
+    /// Issue tokens for a fully authenticated user on a new device session
+    async fn start_session(&self, user: UserModel, device: DeviceInfo) -> Result<(TokenPair, String), AppError> {
         // Read tier and admin status from DB (authoritative source)
         let tier = SubscriptionTier::from_str(&user.subscription_tier);
         let is_admin = user.is_admin;
+        let session_id = Uuid::new_v4();
 
         // Generate tokens — tier embedded in access token only
-        let access_token = self.token_service.generate_access_token(user.id, &user.email, tier, is_admin)?;
-        let refresh_token = self.token_service.generate_refresh_token(user.id, &user.email)?;
+        let access_token = self.token_service.generate_access_token(user.id, &user.email, tier, is_admin, session_id)?;
+        let refresh_token = self.token_service.generate_refresh_token(user.id, &user.email, session_id)?;
 
         // Hash and store refresh token for rotation tracking (SHA-256)
-        let refresh_token_hash = hash_token_sha256(&refresh_token);
+        self.sessions
+            .create(
+                session_id,
+                user.id,
+                hash_token_sha256(&refresh_token),
+                device,
+                Utc::now() + Duration::seconds(self.token_service.refresh_expiry_seconds()),
+            )
+            .await?;
 
-        // Reset failed attempts and store refresh token hash
-        let mut active_user: ActiveModel = user.clone().into();
+        // Reset failed attempts
+        let user_id = user.id;
+        let mut active_user: ActiveModel = user.into();
         active_user.failed_login_attempts = Set(0);
         active_user.locked_until = Set(None);
-        active_user.refresh_token_hash = Set(Some(refresh_token_hash));
         active_user.updated_at = Set(Utc::now().fixed_offset());
         active_user.update(&self.db).await?;
 
-        tracing::info!("User logged in: {}", user.id);
+        tracing::info!("User logged in: {} (session {})", user_id, session_id);
 
         let token_pair = TokenPair {
             access_token,
@@ -163,16 +315,17 @@ impl AuthService {
             expires_in: self.token_service.access_expiry_seconds(),
         };
 
-        Ok((token_pair, refresh_token, user))
+        Ok((token_pair, refresh_token))
     }
 
     /// Refresh access token using refresh token — always reads tier from DB
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<(TokenPair, String, UserModel), AppError> {
         // Validate refresh token structure
         let claims = self.token_service.validate_refresh_token(refresh_token)?;
-        
+
         let user_id = Uuid::parse_str(&claims.sub)
             .map_err(|_| AppError::InvalidToken)?;
+        let session_id = claims.session_id().ok_or(AppError::InvalidToken)?;
 
         // Find user — tier is always read fresh from DB here
         let user = User::find_by_id(user_id)
@@ -180,34 +333,24 @@ impl AuthService {
             .await?
             .ok_or(AppError::InvalidToken)?;
 
-        // Verify refresh token hash matches (token rotation via SHA-256)
-        let current_hash = hash_token_sha256(refresh_token);
-        match &user.refresh_token_hash {
-            Some(stored_hash) if stored_hash == &current_hash => {}
-            _ => {
-                // Token reuse detected or invalid — invalidate all sessions
-                tracing::warn!("Refresh token reuse or invalid token for user: {}", user.id);
-                let mut active_user: ActiveModel = user.clone().into();
-                active_user.refresh_token_hash = Set(None);
-                active_user.update(&self.db).await?;
-                return Err(AppError::InvalidToken);
-            }
-        }
-
         // Read current tier from DB (subscription may have changed since last login)
         let tier = SubscriptionTier::from_str(&user.subscription_tier);
         let is_admin = user.is_admin;
 
         // Generate new token pair (token rotation)
-        let new_access_token = self.token_service.generate_access_token(user.id, &user.email, tier, is_admin)?;
-        let new_refresh_token = self.token_service.generate_refresh_token(user.id, &user.email)?;
-
-        // Store new refresh token hash
-        let new_refresh_hash = hash_token_sha256(&new_refresh_token);
-        let mut active_user: ActiveModel = user.clone().into();
-        active_user.refresh_token_hash = Set(Some(new_refresh_hash));
-        active_user.updated_at = Set(Utc::now().fixed_offset());
-        active_user.update(&self.db).await?;
+        let new_access_token = self.token_service.generate_access_token(user.id, &user.email, tier, is_admin, session_id)?;
+        let new_refresh_token = self.token_service.generate_refresh_token(user.id, &user.email, session_id)?;
+
+        // Swap the session's hash; a stale token here revokes the session
+        self.sessions
+            .rotate(
+                session_id,
+                user.id,
+                &hash_token_sha256(refresh_token),
+                hash_token_sha256(&new_refresh_token),
+                Utc::now() + Duration::seconds(self.token_service.refresh_expiry_seconds()),
+            )
+            .await?;
 
         let token_pair = TokenPair {
             access_token: new_access_token,
@@ -218,19 +361,15 @@ impl AuthService {
         Ok((token_pair, new_refresh_token, user))
     }
 
-    /// Logout user by invalidating refresh token
-    pub async fn logout(&self, user_id: Uuid) -> Result<(), AppError> {
-        let user = User::find_by_id(user_id)
-            .one(&self.db)
-            .await?
-            .ok_or(AppError::InvalidToken)?;
+    /// Logout one device by revoking the session its refresh token belongs to
+    pub async fn logout(&self, refresh_token: &str) -> Result<(), AppError> {
+        let claims = self.token_service.validate_refresh_token(refresh_token)?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+        let session_id = claims.session_id().ok_or(AppError::InvalidToken)?;
 
-        let mut active_user: ActiveModel = user.into();
-        active_user.refresh_token_hash = Set(None);
-        active_user.updated_at = Set(Utc::now().fixed_offset());
-        active_user.update(&self.db).await?;
+        self.sessions.revoke(user_id, session_id).await?;
 
-        tracing::info!("User logged out: {}", user_id);
+        tracing::info!("User logged out: {} (session {})", user_id, session_id);
 
         Ok(())
     }
@@ -262,10 +401,11 @@ impl AuthService {
 
         let mut active_user: ActiveModel = user.into();
         active_user.password_hash = Set(new_hash);
-        active_user.refresh_token_hash = Set(None); // invalidate all sessions
         active_user.updated_at = Set(Utc::now().fixed_offset());
         active_user.update(&self.db).await?;
 
+        self.sessions.revoke_all(user_id, None).await?; // invalidate all sessions
+
         tracing::info!("Password changed for user: {}", user_id);
         Ok(())
     }
@@ -286,6 +426,335 @@ impl AuthService {
         Ok(())
     }
 
+    /// Issue a fresh email-verification token for a user.
+    ///
+    /// Returns the raw token to put in the email link; only its hash is stored.
+    pub async fn create_verification_token(&self, user_id: Uuid) -> Result<String, AppError> {
+        self.issue_email_token(user_id, EmailTokenPurpose::VerifyEmail).await
+    }
+
+    /// Re-issue a verification token by email address.
+    ///
+    /// Returns `None` for unknown or already-verified accounts so the handler
+    /// can answer identically either way (no enumeration).
+    pub async fn resend_verification(&self, email: &str) -> Result<Option<(String, String)>, AppError> {
+        let email = normalize_email(email);
+        let Some(user) = User::find()
+            .filter(user::Column::Email.eq(&email))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        if user.is_email_verified {
+            return Ok(None);
+        }
+
+        let token = self.issue_email_token(user.id, EmailTokenPurpose::VerifyEmail).await?;
+        Ok(Some((user.email, token)))
+    }
+
+    /// Redeem a verification token and mark the account as verified
+    pub async fn verify_email(&self, token: &str) -> Result<UserModel, AppError> {
+        let user_id = self.consume_email_token(token, EmailTokenPurpose::VerifyEmail).await?;
+
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        let mut active_user: ActiveModel = user.into();
+        active_user.is_email_verified = Set(true);
+        active_user.updated_at = Set(Utc::now().fixed_offset());
+        let user = active_user.update(&self.db).await?;
+
+        tracing::info!("Email verified for user: {}", user.id);
+        Ok(user)
+    }
+
+    /// Start a password reset. Returns `(email, token)` when the account
+    /// exists; `None` otherwise — callers must respond the same in both cases.
+    pub async fn forgot_password(&self, email: &str) -> Result<Option<(String, String)>, AppError> {
+        let email = normalize_email(email);
+        let Some(user) = User::find()
+            .filter(user::Column::Email.eq(&email))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let token = self.issue_email_token(user.id, EmailTokenPurpose::ResetPassword).await?;
+        tracing::info!("Password reset requested for user: {}", user.id);
+        Ok(Some((user.email, token)))
+    }
+
+    /// Redeem a reset token and set a new password — invalidates all sessions
+    /// and clears any login lockout.
+    pub async fn reset_password(&self, token: &str, new_password: &str) -> Result<(), AppError> {
+        let user_id = self.consume_email_token(token, EmailTokenPurpose::ResetPassword).await?;
+
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        let new_hash = self.hash_password(new_password)?;
+
+        let mut active_user: ActiveModel = user.into();
+        active_user.password_hash = Set(new_hash);
+        active_user.failed_login_attempts = Set(0);
+        active_user.locked_until = Set(None);
+        // Receiving the reset link proves ownership of the address
+        active_user.is_email_verified = Set(true);
+        active_user.updated_at = Set(Utc::now().fixed_offset());
+        active_user.update(&self.db).await?;
+
+        self.sessions.revoke_all(user_id, None).await?; // invalidate all sessions
+
+        tracing::info!("Password reset for user: {}", user_id);
+        Ok(())
+    }
+
+    /// Start 2FA enrollment: store a new encrypted secret (not yet active)
+    /// and return it for the authenticator app
+    pub async fn setup_two_factor(&self, user_id: Uuid) -> Result<TwoFactorSetup, AppError> {
+        let cipher = self.totp_cipher()?;
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        if user.two_factor_enabled {
+            return Err(two_factor_error("already_enabled", "Two-factor authentication is already enabled"));
+        }
+
+        let secret = totp::generate_secret();
+        let setup = TwoFactorSetup {
+            secret: totp::base32_encode(&secret),
+            otpauth_uri: totp::provisioning_uri(&user.email, &secret),
+        };
+
+        let mut active_user: ActiveModel = user.into();
+        active_user.totp_secret = Set(Some(cipher.encrypt(&secret)?));
+        active_user.totp_last_used_step = Set(None);
+        active_user.updated_at = Set(Utc::now().fixed_offset());
+        active_user.update(&self.db).await?;
+
+        Ok(setup)
+    }
+
+    /// Confirm enrollment with a code from the app. Turns 2FA on and returns
+    /// fresh recovery codes — the only time they are shown.
+    pub async fn enable_two_factor(&self, user_id: Uuid, code: &str) -> Result<Vec<String>, AppError> {
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        if user.two_factor_enabled {
+            return Err(two_factor_error("already_enabled", "Two-factor authentication is already enabled"));
+        }
+        if user.totp_secret.is_none() {
+            return Err(two_factor_error("not_set_up", "Start two-factor setup first"));
+        }
+        if !self.accept_totp_code(&user, code).await? {
+            return Err(two_factor_error("invalid_code", "Invalid authentication code"));
+        }
+
+        let mut active_user: ActiveModel = user.into();
+        active_user.two_factor_enabled = Set(true);
+        active_user.updated_at = Set(Utc::now().fixed_offset());
+        active_user.update(&self.db).await?;
+
+        let codes = self.replace_recovery_codes(user_id).await?;
+        tracing::info!("2FA enabled for user: {}", user_id);
+        Ok(codes)
+    }
+
+    /// Turn 2FA off — requires the account password
+    pub async fn disable_two_factor(&self, user_id: Uuid, password: &str) -> Result<(), AppError> {
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::AuthenticationFailed)?;
+
+        if !self.verify_password(password, &user.password_hash)? {
+            return Err(AppError::AuthenticationFailed);
+        }
+
+        let mut active_user: ActiveModel = user.into();
+        active_user.two_factor_enabled = Set(false);
+        active_user.totp_secret = Set(None);
+        active_user.totp_last_used_step = Set(None);
+        active_user.updated_at = Set(Utc::now().fixed_offset());
+        active_user.update(&self.db).await?;
+
+        user_recovery_code::Entity::delete_many()
+            .filter(user_recovery_code::Column::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+
+        tracing::info!("2FA disabled for user: {}", user_id);
+        Ok(())
+    }
+
+    fn totp_cipher(&self) -> Result<&SecretCipher, AppError> {
+        self.totp_cipher
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("TOTP_ENCRYPTION_KEY not configured".to_string()))
+    }
+
+    /// Check a TOTP code and record its time step.
+    ///
+    /// The step is claimed with a conditional UPDATE, so the same code can't
+    /// be used twice even by concurrent requests.
+    async fn accept_totp_code(&self, user: &UserModel, code: &str) -> Result<bool, AppError> {
+        let encrypted = user
+            .totp_secret
+            .as_deref()
+            .ok_or_else(|| AppError::Internal("2FA enabled without a TOTP secret".to_string()))?;
+        let secret = self.totp_cipher()?.decrypt(encrypted)?;
+
+        let Some(step) = totp::verify(&secret, code, Utc::now().timestamp(), user.totp_last_used_step) else {
+            return Ok(false);
+        };
+
+        let claimed = User::update_many()
+            .col_expr(user::Column::TotpLastUsedStep, Expr::value(step))
+            .filter(user::Column::Id.eq(user.id))
+            .filter(
+                Condition::any()
+                    .add(user::Column::TotpLastUsedStep.is_null())
+                    .add(user::Column::TotpLastUsedStep.lt(step)),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(claimed.rows_affected == 1)
+    }
+
+    /// Burn a recovery code if it is valid and unused
+    async fn redeem_recovery_code(&self, user_id: Uuid, code: &str) -> Result<bool, AppError> {
+        let claimed = user_recovery_code::Entity::update_many()
+            .col_expr(user_recovery_code::Column::UsedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(user_recovery_code::Column::UserId.eq(user_id))
+            .filter(user_recovery_code::Column::CodeHash.eq(hash_recovery_code(code)))
+            .filter(user_recovery_code::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        if claimed.rows_affected > 0 {
+            tracing::warn!("Recovery code used for user: {}", user_id);
+        }
+        Ok(claimed.rows_affected > 0)
+    }
+
+    /// Drop any existing recovery codes and issue a new set
+    async fn replace_recovery_codes(&self, user_id: Uuid) -> Result<Vec<String>, AppError> {
+        user_recovery_code::Entity::delete_many()
+            .filter(user_recovery_code::Column::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+
+        let now = Utc::now().fixed_offset();
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+
+        user_recovery_code::Entity::insert_many(codes.iter().map(|code| user_recovery_code::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            code_hash: Set(hash_recovery_code(code)),
+            used_at: Set(None),
+            created_at: Set(now),
+        }))
+        .exec(&self.db)
+        .await?;
+
+        Ok(codes)
+    }
+
+    /// Gate for features that need a verified email (e.g. the AI chat).
+    /// A no-op unless `REQUIRE_VERIFIED_EMAIL` is enabled.
+    pub async fn require_verified_email(&self, user_id: Uuid) -> Result<(), AppError> {
+        if !self.require_verified_email {
+            return Ok(());
+        }
+
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        if user.is_email_verified {
+            Ok(())
+        } else {
+            Err(AppError::EmailNotVerified)
+        }
+    }
+
+    /// Create a token for `purpose`, replacing any unused one of the same kind
+    async fn issue_email_token(&self, user_id: Uuid, purpose: EmailTokenPurpose) -> Result<String, AppError> {
+        email_token::Entity::delete_many()
+            .filter(email_token::Column::UserId.eq(user_id))
+            .filter(email_token::Column::Purpose.eq(purpose.as_str()))
+            .filter(email_token::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        let token = generate_email_token();
+        let now = Utc::now();
+
+        email_token::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            purpose: Set(purpose.as_str().to_string()),
+            token_hash: Set(hash_token_sha256(&token)),
+            expires_at: Set((now + purpose.lifetime()).fixed_offset()),
+            used_at: Set(None),
+            created_at: Set(now.fixed_offset()),
+        }
+        .insert(&self.db)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Mark a token as used and return its user.
+    ///
+    /// The conditional UPDATE only succeeds for an unused, unexpired token, so
+    /// two concurrent redemptions cannot both win.
+    async fn consume_email_token(&self, token: &str, purpose: EmailTokenPurpose) -> Result<Uuid, AppError> {
+        let token_hash = hash_token_sha256(token);
+
+        let stored = email_token::Entity::find()
+            .filter(email_token::Column::TokenHash.eq(&token_hash))
+            .filter(email_token::Column::Purpose.eq(purpose.as_str()))
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        let now = Utc::now();
+        if stored.used_at.is_some() {
+            return Err(AppError::InvalidToken);
+        }
+        if stored.expires_at < now {
+            return Err(AppError::TokenExpired);
+        }
+
+        let claimed = email_token::Entity::update_many()
+            .col_expr(email_token::Column::UsedAt, sea_orm::sea_query::Expr::value(now.fixed_offset()))
+            .filter(email_token::Column::Id.eq(stored.id))
+            .filter(email_token::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        if claimed.rows_affected != 1 {
+            return Err(AppError::InvalidToken);
+        }
+
+        Ok(stored.user_id)
+    }
+
     /// Hash password using Argon2id
     fn hash_password(&self, password: &str) -> Result<String, AppError> {
         let salt = SaltString::generate(&mut OsRng);
@@ -324,6 +793,45 @@ impl AuthService {
     }
 }
 
+/// 256 random bits, URL-safe so it can go straight into an email link
+pub(crate) fn generate_email_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Recovery codes look like `abcd-efgh` (40 random bits)
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let code = totp::base32_encode(&bytes).to_ascii_lowercase();
+    format!("{}-{}", &code[..4], &code[4..])
+}
+
+/// Hash a recovery code, ignoring case, spaces and dashes
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hash_token_sha256(&normalized)
+}
+
+/// Six digits → TOTP; anything else is treated as a recovery code
+fn is_totp_code(code: &str) -> bool {
+    let code = code.trim();
+    code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn two_factor_error(code: &'static str, message: &'static str) -> AppError {
+    let mut errors = validator::ValidationErrors::new();
+    let mut e = validator::ValidationError::new(code);
+    e.message = Some(message.into());
+    errors.add("two_factor", e);
+    AppError::Validation(errors)
+}
+
 /// Hash a token using SHA-256 (cryptographically secure, replaces DefaultHasher)
 pub fn hash_token_sha256(token: &str) -> String {
     let mut hasher = Sha256::new();
@@ -331,3 +839,73 @@ pub fn hash_token_sha256(token: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    fn service(db: &DatabaseConnection, require_verified_email: bool) -> AuthService {
+        AuthService::new(db.clone(), TokenService::new(&test_db::config()), require_verified_email, None)
+    }
+
+    #[test]
+    fn email_tokens_are_random_and_stored_hashed() {
+        let token = generate_email_token();
+        assert_eq!(token.len(), 43);
+        assert!(token.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+        assert_ne!(token, generate_email_token());
+
+        assert_eq!(hash_token_sha256(&token), hash_token_sha256(&token));
+        assert_ne!(hash_token_sha256(&token), token);
+        assert!(EmailTokenPurpose::ResetPassword.lifetime() < EmailTokenPurpose::VerifyEmail.lifetime());
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn verification_tokens_are_single_use() {
+        let db = test_db::connect().await;
+        let auth = service(&db, true);
+        let user = test_db::create_user(&db).await;
+
+        assert!(matches!(auth.require_verified_email(user.id).await, Err(AppError::EmailNotVerified)));
+
+        // Issuing again replaces the unused token
+        let stale = auth.create_verification_token(user.id).await.unwrap();
+        let token = auth.create_verification_token(user.id).await.unwrap();
+        assert!(matches!(auth.verify_email(&stale).await, Err(AppError::InvalidToken)));
+
+        assert!(auth.verify_email(&token).await.unwrap().is_email_verified);
+        assert!(matches!(auth.verify_email(&token).await, Err(AppError::InvalidToken)));
+        auth.require_verified_email(user.id).await.unwrap();
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn reset_tokens_expire_and_only_reset_passwords() {
+        let db = test_db::connect().await;
+        let auth = service(&db, false);
+        let user = test_db::create_user(&db).await;
+
+        let (_, token) = auth.forgot_password(&user.email.to_uppercase()).await.unwrap().unwrap();
+        // A reset token is no verification token
+        assert!(matches!(auth.verify_email(&token).await, Err(AppError::InvalidToken)));
+
+        email_token::Entity::update_many()
+            .col_expr(email_token::Column::ExpiresAt, sea_orm::sea_query::Expr::value((Utc::now() - Duration::minutes(1)).fixed_offset()))
+            .filter(email_token::Column::UserId.eq(user.id))
+            .exec(&db)
+            .await
+            .unwrap();
+        assert!(matches!(auth.reset_password(&token, "N3w-Secret-pass!").await, Err(AppError::TokenExpired)));
+
+        let (_, token) = auth.forgot_password(&user.email).await.unwrap().unwrap();
+        auth.reset_password(&token, "N3w-Secret-pass!").await.unwrap();
+        let user = User::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+        assert!(user.is_email_verified);
+        assert!(auth.verify_password("N3w-Secret-pass!", &user.password_hash).unwrap());
+        assert!(matches!(auth.reset_password(&token, "An0ther-pass!").await, Err(AppError::InvalidToken)));
+
+        assert!(auth.forgot_password("nobody@example.com").await.unwrap().is_none());
+    }
+}
@@ -1,18 +1,22 @@
-//! Chat Service — AI assistant powered by Ollama
+//! Chat Service — AI assistant backed by the configured `LlmProvider`
 //!
 //! Features:
 //! - Context-aware system prompt built from user's inventory, preferences, meal plan
 //! - Persistent sessions and message history
-//! - Full message history sent to Ollama per request (stateless LLM with stateful DB)
-//! - Agentic tool-calling loop (up to MAX_TOOL_ROUNDS) using Ollama's native tools API
-//! - Configurable Ollama model (defaults to "llama3.2")
+//! - History fitted to the model's context window; older turns are folded
+//!   into a rolling per-session summary (see `chat_context`)
+//! - Pantry and meal plan included in full only when the conversation is about them
+//! - Agentic tool-calling loop (up to MAX_TOOL_ROUNDS) using the provider's tools API
+//! - Optional token streaming (`chat_stream`) with typed events for tool calls
 
 use chrono::Utc;
-use reqwest::Client;
+use futures::StreamExt;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set,
 };
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::entity::{
@@ -20,47 +24,27 @@ use crate::entity::{
     cooking_history, inventory_item, meal_plan, meal_plan_slot, recipe,
     user, ingredient,
 };
-use crate::services::chat_tools::{tool_definitions, ToolDispatch};
+use crate::services::chat_context::{
+    estimate_tokens, keep_from, summary_request, ContextBudget, ContextNeeds,
+};
+use crate::services::chat_tools::{
+    tool_definitions, tool_label, ActionOutcome, PendingAction, ToolDispatch,
+};
+use crate::services::household::DataScope;
+use crate::services::llm::{LlmMessage, LlmRequest, LlmResponse, Role, SharedLlm, ToolCall};
 use cookest_shared::errors::AppError;
 
 const MAX_TOOL_ROUNDS: usize = 6;
+/// Pantries this small are always listed in the system prompt
+const PANTRY_INLINE_ITEMS: usize = 15;
+/// Upper bound on pantry items listed even when the pantry is relevant
+const PANTRY_MAX_ITEMS: usize = 80;
+const EXPIRING_MAX_ITEMS: usize = 10;
 
-// ── Ollama API types ──────────────────────────────────────────────────────────
-
-#[derive(Debug, Serialize)]
-struct OllamaRequest {
-    model: String,
-    messages: Vec<OllamaMessage>,
-    stream: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tools: Option<Vec<serde_json::Value>>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct OllamaMessage {
-    pub role: String,
-    pub content: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_calls: Option<Vec<OllamaToolCall>>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct OllamaToolCall {
-    pub function: OllamaToolCallFunction,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct OllamaToolCallFunction {
-    pub name: String,
-    pub arguments: serde_json::Value,
-}
-
-#[derive(Debug, Deserialize)]
-struct OllamaResponse {
-    message: OllamaMessage,
-    #[serde(default)]
-    eval_count: Option<i32>,
-}
+/// Sent when a reply claims an action but no tool was called
+const CORRECTION_PROMPT: &str = "SYSTEM CORRECTION: You said you performed an action but you did not call \
+     any tool. If you have a tool that can do this, call it now. If you do not \
+     have that tool, tell the user honestly that you cannot do it.";
 
 // ── Public request/response types ─────────────────────────────────────────────
 
@@ -81,6 +65,71 @@ pub struct ChatResponse {
     pub tokens_used: Option<i32>,
     /// Tool names called during this response (e.g. ["search_recipes","update_meal_plan_slot"])
     pub actions_taken: Vec<String>,
+    /// Destructive changes the assistant proposed; nothing happens until the
+    /// user confirms one via `POST /api/chat/actions/{id}/confirm`
+    pub pending_actions: Vec<PendingAction>,
+}
+
+/// Events sent by `POST /api/chat/stream`, one SSE frame each
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatEvent {
+    /// Always first — the session the reply belongs to
+    Session { session_id: i64 },
+    /// A piece of assistant text, in order
+    Token { content: String },
+    /// Discard the text streamed so far; a corrected reply follows
+    Reset,
+    /// A tool started, with a user-facing label ("Updating your meal plan…")
+    ToolStart { name: String, label: &'static str },
+    ToolEnd { name: String, success: bool },
+    /// A destructive tool call is waiting for the user to confirm or reject it
+    ActionProposed { action: PendingAction },
+    /// Always last on success — the reply has been saved
+    Done {
+        session_id: i64,
+        message_id: i64,
+        tokens_used: Option<i32>,
+        actions_taken: Vec<String>,
+    },
+    Error { message: String },
+}
+
+impl ChatEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            ChatEvent::Session { .. } => "session",
+            ChatEvent::Token { .. } => "token",
+            ChatEvent::Reset => "reset",
+            ChatEvent::ToolStart { .. } => "tool_start",
+            ChatEvent::ToolEnd { .. } => "tool_end",
+            ChatEvent::ActionProposed { .. } => "action_proposed",
+            ChatEvent::Done { .. } => "done",
+            ChatEvent::Error { .. } => "error",
+        }
+    }
+
+    /// Encode as a Server-Sent Events frame (`event:` + single-line JSON `data:`)
+    pub fn to_sse(&self) -> String {
+        let data = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
+        format!("event: {}\ndata: {}\n\n", self.name(), data)
+    }
+}
+
+/// A session with its prompt assembled and the user message saved — ready
+/// for the model. Built up front so request errors surface as HTTP errors
+/// before a stream starts.
+pub struct PreparedChat {
+    session: chat_session::Model,
+    messages: Vec<LlmMessage>,
+}
+
+/// One tool call as the chat loop sees it
+struct ToolRun {
+    /// The `tool` message for the model
+    message: LlmMessage,
+    success: bool,
+    pending: Option<PendingAction>,
 }
 
 #[derive(Debug, Serialize)]
@@ -103,38 +152,25 @@ pub struct MessageItem {
 
 pub struct ChatService {
     db: DatabaseConnection,
-    http: Client,
-    ollama_url: String,
-    model: String,
+    llm: SharedLlm,
 }
 
 impl ChatService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        let ollama_url = std::env::var("OLLAMA_URL")
-            .unwrap_or_else(|_| "http://localhost:11434".to_string());
-        let model = std::env::var("OLLAMA_MODEL")
-            .unwrap_or_else(|_| "llama3.2".to_string());
-
-        Self {
-            db,
-            http: Client::new(),
-            ollama_url,
-            model,
-        }
+    pub fn new(db: DatabaseConnection, llm: SharedLlm) -> Self {
+        Self { db, llm }
     }
 
-    /// Send a message — creates or continues a session, returns AI reply.
-    /// Runs a tool-calling loop (up to MAX_TOOL_ROUNDS) so the AI can read
-    /// and modify the user's meal plan, pantry, and recipes via natural language.
-    pub async fn chat(
+    /// Steps shared by `chat` and `chat_stream`: get or create the session,
+    /// build the prompt from history and save the user's message.
+    pub async fn prepare(
         &self,
         user_id: Uuid,
-        req: ChatRequest,
-    ) -> Result<ChatResponse, AppError> {
+        req: &ChatRequest,
+    ) -> Result<PreparedChat, AppError> {
         let now = Utc::now().fixed_offset();
 
         // ── 1. Get or create session ──────────────────────────────────────────
-        let session = match req.session_id {
+        let mut session = match req.session_id {
             Some(id) => {
                 chat_session::Entity::find_by_id(id)
                     .one(&self.db)
@@ -156,40 +192,47 @@ impl ChatService {
             }
         };
 
-        // ── 2. Build system prompt from user context ──────────────────────────
-        let system_prompt = self.build_system_prompt(user_id, session.current_recipe_id).await?;
-
-        // ── 3. Load message history for this session ──────────────────────────
-        let history = chat_message::Entity::find()
+        // ── 2. Load the turns not yet covered by the session summary ──────────
+        let mut history_query = chat_message::Entity::find()
             .filter(chat_message::Column::SessionId.eq(session.id))
+            .filter(chat_message::Column::Role.ne("system"));
+        if let Some(through) = session.summary_through_id {
+            history_query = history_query.filter(chat_message::Column::Id.gt(through));
+        }
+        let history = history_query
             .order_by_asc(chat_message::Column::CreatedAt)
+            .order_by_asc(chat_message::Column::Id)
             .all(&self.db)
             .await?;
 
-        // ── 4. Build initial Ollama messages array ────────────────────────────
-        let mut ollama_messages: Vec<OllamaMessage> = Vec::with_capacity(history.len() + 2);
-
-        ollama_messages.push(OllamaMessage {
-            role: "system".to_string(),
-            content: system_prompt,
-            tool_calls: None,
-        });
-
-        for msg in &history {
-            if msg.role != "system" {
-                ollama_messages.push(OllamaMessage {
-                    role: msg.role.clone(),
-                    content: msg.content.clone(),
-                    tool_calls: None,
-                });
+        // ── 3. Build system prompt with the context this turn needs ───────────
+        let recent = history.iter().rev().take(2).map(|m| m.content.as_str());
+        let needs = ContextNeeds::from_texts(std::iter::once(req.message.as_str()).chain(recent));
+        let system_prompt = self
+            .build_system_prompt(user_id, session.current_recipe_id, needs)
+            .await?;
+
+        // ── 4. Fit history into the model's context window ────────────────────
+        let fixed_tokens = estimate_tokens(&system_prompt)
+            + session.summary.as_deref().map(estimate_tokens).unwrap_or(0)
+            + estimate_tokens(&serde_json::Value::Array(tool_definitions()).to_string())
+            + estimate_tokens(&req.message);
+        let start = self.fit_history(&mut session, &history, fixed_tokens).await;
+
+        let mut messages: Vec<LlmMessage> = Vec::with_capacity(history.len() - start + 3);
+        messages.push(LlmMessage::system(system_prompt));
+        if let Some(summary) = &session.summary {
+            messages.push(LlmMessage::system(format!(
+                "Summary of the earlier conversation:\n{}",
+                summary
+            )));
+        }
+        for msg in &history[start..] {
+            if let Some(role @ (Role::User | Role::Assistant)) = Role::parse(&msg.role) {
+                messages.push(LlmMessage::new(role, msg.content.clone()));
             }
         }
-
-        ollama_messages.push(OllamaMessage {
-            role: "user".to_string(),
-            content: req.message.clone(),
-            tool_calls: None,
-        });
+        messages.push(LlmMessage::user(req.message.clone()));
 
         // ── 5. Save user message to DB ────────────────────────────────────────
         let user_msg = chat_message::ActiveModel {
@@ -202,77 +245,110 @@ impl ChatService {
         };
         user_msg.insert(&self.db).await?;
 
+        Ok(PreparedChat { session, messages })
+    }
+
+    /// Index of the first turn of `history` to send verbatim. Turns that
+    /// overflow the window are folded into the session summary — down to
+    /// half the history budget, so summarising doesn't rerun every turn. If
+    /// summarising fails the overflow is simply left out.
+    async fn fit_history(
+        &self,
+        session: &mut chat_session::Model,
+        history: &[chat_message::Model],
+        fixed_tokens: usize,
+    ) -> usize {
+        let budget = ContextBudget::new(self.llm.context_tokens());
+        let available = budget.history(fixed_tokens);
+        let costs: Vec<usize> = history.iter().map(|m| estimate_tokens(&m.content)).collect();
+
+        let start = keep_from(&costs, available);
+        if start == 0 {
+            return 0;
+        }
+
+        let fold = keep_from(&costs, available / 2);
+        match self.summarise(session, &history[..fold], budget).await {
+            Ok(()) => fold,
+            Err(e) => {
+                tracing::warn!("Could not summarise chat session {}: {:?}", session.id, e);
+                start
+            }
+        }
+    }
+
+    /// Merge `turns` into the session's rolling summary
+    async fn summarise(
+        &self,
+        session: &mut chat_session::Model,
+        turns: &[chat_message::Model],
+        budget: ContextBudget,
+    ) -> Result<(), AppError> {
+        let Some(last) = turns.last() else {
+            return Ok(());
+        };
+
+        let req = summary_request(session.summary.as_deref(), turns, budget.summary_input());
+        let summary = self.llm.chat(req).await?.message.content.trim().to_string();
+        if summary.is_empty() {
+            return Err(AppError::Internal("Model returned an empty summary".into()));
+        }
+
+        // Only replace the summary we started from, so a concurrent turn
+        // can't roll it back to an older state
+        let mut update = chat_session::Entity::update_many()
+            .col_expr(chat_session::Column::Summary, Expr::value(summary.clone()))
+            .col_expr(chat_session::Column::SummaryThroughId, Expr::value(last.id))
+            .filter(chat_session::Column::Id.eq(session.id));
+        update = match session.summary_through_id {
+            Some(through) => update.filter(chat_session::Column::SummaryThroughId.eq(through)),
+            None => update.filter(chat_session::Column::SummaryThroughId.is_null()),
+        };
+        update.exec(&self.db).await?;
+
+        tracing::info!("Summarised chat session {} through message {}", session.id, last.id);
+        session.summary = Some(summary);
+        session.summary_through_id = Some(last.id);
+        Ok(())
+    }
+
+    /// Send a message — creates or continues a session, returns AI reply.
+    /// Runs a tool-calling loop (up to MAX_TOOL_ROUNDS) so the AI can read
+    /// and modify the user's meal plan, pantry, and recipes via natural language.
+    pub async fn chat(
+        &self,
+        user_id: Uuid,
+        req: ChatRequest,
+    ) -> Result<ChatResponse, AppError> {
+        // ── 1–5. Session, prompt, history, user message ──────────────────────
+        let PreparedChat { session, mut messages } = self.prepare(user_id, &req).await?;
+
         // ── 6. Tool-calling loop ──────────────────────────────────────────────
         let tools = ToolDispatch::new(self.db.clone());
         let mut reply = String::new();
         let mut tokens: Option<i32> = None;
         let mut actions_taken: Vec<String> = Vec::new();
+        let mut pending_actions: Vec<PendingAction> = Vec::new();
 
         for _round in 0..MAX_TOOL_ROUNDS {
-            let ollama_req = OllamaRequest {
-                model: self.model.clone(),
-                messages: ollama_messages.clone(),
-                stream: false,
-                tools: Some(tool_definitions()),
-            };
-
-            let resp = self
-                .http
-                .post(format!("{}/api/chat", self.ollama_url))
-                .json(&ollama_req)
-                .send()
-                .await
-                .map_err(|e| {
-                    tracing::error!("Ollama request failed: {}", e);
-                    AppError::Internal("AI service unavailable".into())
-                })?;
-
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let body = resp.text().await.unwrap_or_default();
-                tracing::error!("Ollama error {}: {}", status, body);
-                return Err(AppError::Internal("AI service returned an error".into()));
+            let LlmResponse { message, tokens_used } = self.llm.chat(Self::tool_request(&messages)).await?;
+            tokens = tokens_used;
+
+            if message.tool_calls.is_empty() {
+                // No tool calls — this is the final response
+                reply = message.content;
+                break;
             }
 
-            let ollama_resp: OllamaResponse = resp.json().await.map_err(|e| {
-                tracing::error!("Failed to parse Ollama response: {}", e);
-                AppError::Internal("Failed to parse AI response".into())
-            })?;
-
-            tokens = ollama_resp.eval_count;
-
-            match ollama_resp.message.tool_calls {
-                Some(ref calls) if !calls.is_empty() => {
-                    // Add assistant message (with tool_calls) so Ollama tracks context
-                    ollama_messages.push(ollama_resp.message.clone());
-
-                    for call in calls {
-                        let name = &call.function.name;
-                        let args = call.function.arguments.clone();
-                        tracing::info!("AI tool call: {} {:?}", name, args);
-                        actions_taken.push(name.clone());
-
-                        let result = tools.execute(user_id, name, args).await;
-
-                        // Prefix errors clearly so the model cannot miss them
-                        let result_with_status = if result.contains("\"error\"") {
-                            format!("TOOL_ERROR: {}", result)
-                        } else {
-                            format!("TOOL_SUCCESS: {}", result)
-                        };
-
-                        ollama_messages.push(OllamaMessage {
-                            role: "tool".to_string(),
-                            content: result_with_status,
-                            tool_calls: None,
-                        });
-                    }
-                }
-                _ => {
-                    // No tool calls — this is the final response
-                    reply = ollama_resp.message.content.clone();
-                    break;
-                }
+            // Add assistant message (with tool_calls) so the model tracks context
+            let calls = message.tool_calls.clone();
+            messages.push(message);
+            for call in &calls {
+                tracing::info!("AI tool call: {} {:?}", call.name, call.arguments);
+                actions_taken.push(call.name.clone());
+                let run = Self::run_tool(&tools, user_id, session.id, call).await;
+                pending_actions.extend(run.pending);
+                messages.push(run.message);
             }
         }
 
@@ -284,91 +360,237 @@ impl ChatService {
         // If the AI claims it performed an action but called no tools, catch it.
         if actions_taken.is_empty() && Self::reply_claims_action(&reply) {
             tracing::warn!("Hallucination detected — reply claims action but no tools were called");
-            ollama_messages.push(OllamaMessage {
-                role: "assistant".to_string(),
-                content: reply.clone(),
-                tool_calls: None,
-            });
-            ollama_messages.push(OllamaMessage {
-                role: "user".to_string(),
-                content: "SYSTEM CORRECTION: You said you performed an action but you did not call \
-                           any tool. If you have a tool that can do this, call it now. If you do not \
-                           have that tool, tell the user honestly that you cannot do it.".to_string(),
-                tool_calls: None,
-            });
-
-            // One corrective round
-            let req = OllamaRequest {
-                model: self.model.clone(),
-                messages: ollama_messages.clone(),
-                stream: false,
-                tools: Some(tool_definitions()),
-            };
-            if let Ok(body) = serde_json::to_string(&req) {
-                if let Ok(resp) = reqwest::Client::new()
-                    .post(format!("{}/api/chat", self.ollama_url))
-                    .header("Content-Type", "application/json")
-                    .body(body)
-                    .send()
-                    .await
-                {
-                    if let Ok(corrected) = resp.json::<OllamaResponse>().await {
-                        // If the correction spawned a tool call, run the full dispatch
-                        if let Some(ref calls) = corrected.message.tool_calls {
-                            if !calls.is_empty() {
-                                ollama_messages.push(corrected.message.clone());
-                                for call in calls {
-                                    let name = &call.function.name;
-                                    let args = call.function.arguments.clone();
-                                    actions_taken.push(name.clone());
-                                    let result = tools.execute(user_id, name, args).await;
-                                    let result_with_status = if result.contains("\"error\"") {
-                                        format!("TOOL_ERROR: {}", result)
-                                    } else {
-                                        format!("TOOL_SUCCESS: {}", result)
-                                    };
-                                    ollama_messages.push(OllamaMessage {
-                                        role: "tool".to_string(),
-                                        content: result_with_status,
-                                        tool_calls: None,
-                                    });
-                                }
-                                // One final reply after the corrected tool calls
-                                let final_req = OllamaRequest {
-                                    model: self.model.clone(),
-                                    messages: ollama_messages,
-                                    stream: false,
-                                    tools: Some(tool_definitions()),
-                                };
-                                if let Ok(final_body) = serde_json::to_string(&final_req) {
-                                    if let Ok(final_resp) = reqwest::Client::new()
-                                        .post(format!("{}/api/chat", self.ollama_url))
-                                        .header("Content-Type", "application/json")
-                                        .body(final_body)
-                                        .send()
-                                        .await
-                                    {
-                                        if let Ok(final_msg) = final_resp.json::<OllamaResponse>().await {
-                                            reply = final_msg.message.content;
-                                        }
-                                    }
-                                }
-                            } else {
-                                reply = corrected.message.content;
-                            }
-                        } else {
-                            reply = corrected.message.content;
-                        }
+            messages.push(LlmMessage::assistant(reply.clone()));
+            messages.push(LlmMessage::user(CORRECTION_PROMPT));
+
+            // One corrective round; failures keep the original reply
+            if let Ok(corrected) = self.llm.chat(Self::tool_request(&messages)).await {
+                if corrected.message.tool_calls.is_empty() {
+                    reply = corrected.message.content;
+                } else {
+                    // The correction spawned tool calls — run them, then ask for a final reply
+                    let calls = corrected.message.tool_calls.clone();
+                    messages.push(corrected.message);
+                    for call in &calls {
+                        actions_taken.push(call.name.clone());
+                        let run = Self::run_tool(&tools, user_id, session.id, call).await;
+                        pending_actions.extend(run.pending);
+                        messages.push(run.message);
+                    }
+                    if let Ok(final_resp) = self.llm.chat(Self::tool_request(&messages)).await {
+                        reply = final_resp.message.content;
                     }
                 }
             }
         }
 
         // ── 7. Save assistant reply to DB ─────────────────────────────────────
+        let saved_reply = self.save_reply(session, &reply, tokens).await?;
+
+        Ok(ChatResponse {
+            session_id: saved_reply.session_id,
+            message_id: saved_reply.id,
+            reply,
+            tokens_used: tokens,
+            actions_taken,
+            pending_actions,
+        })
+    }
+
+    /// Streaming variant of `chat`: sends `ChatEvent`s as the model produces
+    /// tokens and runs tools, then saves the reply exactly like `chat`.
+    ///
+    /// Keeps going if the receiver hangs up, so a dropped connection still
+    /// leaves a complete conversation in `chat_messages`.
+    pub async fn chat_stream(
+        &self,
+        user_id: Uuid,
+        prepared: PreparedChat,
+        events: mpsc::Sender<ChatEvent>,
+    ) {
+        if let Err(e) = self.run_stream(user_id, prepared, &events).await {
+            tracing::error!("Streaming chat failed for user {}: {:?}", user_id, e);
+            let _ = events.send(ChatEvent::Error { message: e.to_string() }).await;
+        }
+    }
+
+    async fn run_stream(
+        &self,
+        user_id: Uuid,
+        prepared: PreparedChat,
+        events: &mpsc::Sender<ChatEvent>,
+    ) -> Result<(), AppError> {
+        let PreparedChat { session, mut messages } = prepared;
+        let _ = events.send(ChatEvent::Session { session_id: session.id }).await;
+
+        let tools = ToolDispatch::new(self.db.clone());
+        let mut reply = String::new();
+        let mut tokens: Option<i32> = None;
+        let mut actions_taken: Vec<String> = Vec::new();
+        let mut corrected = false;
+
+        for _round in 0..MAX_TOOL_ROUNDS {
+            let (message, eval_count) = self.stream_round(&messages, events).await?;
+            tokens = eval_count;
+
+            if message.tool_calls.is_empty() {
+                reply = message.content;
+
+                // Same hallucination guard as `chat`, but the claim has
+                // already been streamed — withdraw it, then retry once
+                if !corrected && actions_taken.is_empty() && Self::reply_claims_action(&reply) {
+                    tracing::warn!("Hallucination detected — reply claims action but no tools were called");
+                    corrected = true;
+                    let _ = events.send(ChatEvent::Reset).await;
+                    messages.push(LlmMessage::assistant(std::mem::take(&mut reply)));
+                    messages.push(LlmMessage::user(CORRECTION_PROMPT));
+                    continue;
+                }
+                break;
+            }
+
+            let calls = message.tool_calls.clone();
+            messages.push(message);
+            for call in &calls {
+                tracing::info!("AI tool call: {} {:?}", call.name, call.arguments);
+                let _ = events
+                    .send(ChatEvent::ToolStart { name: call.name.clone(), label: tool_label(&call.name) })
+                    .await;
+
+                let run = Self::run_tool(&tools, user_id, session.id, call).await;
+                let _ = events
+                    .send(ChatEvent::ToolEnd { name: call.name.clone(), success: run.success })
+                    .await;
+                if let Some(action) = run.pending {
+                    let _ = events.send(ChatEvent::ActionProposed { action }).await;
+                }
+                actions_taken.push(call.name.clone());
+                messages.push(run.message);
+            }
+        }
+
+        if reply.is_empty() {
+            reply = "I'm sorry, I wasn't able to complete that action. Please try again.".to_string();
+            let _ = events.send(ChatEvent::Token { content: reply.clone() }).await;
+        }
+
+        let saved_reply = self.save_reply(session, &reply, tokens).await?;
+
+        let _ = events
+            .send(ChatEvent::Done {
+                session_id: saved_reply.session_id,
+                message_id: saved_reply.id,
+                tokens_used: tokens,
+                actions_taken,
+            })
+            .await;
+        Ok(())
+    }
+
+    /// One streaming model call. Content is forwarded as `Token` events
+    /// while it arrives; returns the assembled message and token count.
+    async fn stream_round(
+        &self,
+        messages: &[LlmMessage],
+        events: &mpsc::Sender<ChatEvent>,
+    ) -> Result<(LlmMessage, Option<i32>), AppError> {
+        let mut stream = self.llm.chat_stream(Self::tool_request(messages)).await?;
+
+        let mut message = LlmMessage::assistant("");
+        let mut tokens = None;
+        while let Some(delta) = stream.next().await {
+            let delta = delta?;
+            if !delta.content.is_empty() {
+                message.content.push_str(&delta.content);
+                let _ = events.send(ChatEvent::Token { content: delta.content }).await;
+            }
+            message.tool_calls.extend(delta.tool_calls);
+            if delta.tokens_used.is_some() {
+                tokens = delta.tokens_used;
+            }
+        }
+
+        Ok((message, tokens))
+    }
+
+    /// The conversation so far, with the assistant's tools attached
+    fn tool_request(messages: &[LlmMessage]) -> LlmRequest {
+        LlmRequest::new(messages.to_vec()).with_tools(tool_definitions())
+    }
+
+    /// Execute one tool call and wrap its result as the `tool` message the
+    /// model sees next. Errors are prefixed clearly so the model cannot miss
+    /// them.
+    async fn run_tool(tools: &ToolDispatch, user_id: Uuid, session_id: i64, call: &ToolCall) -> ToolRun {
+        let output = tools.execute(user_id, session_id, &call.name, call.arguments.clone()).await;
+        let success = !output.failed;
+        let content = if success {
+            format!("TOOL_SUCCESS: {}", output.content)
+        } else {
+            format!("TOOL_ERROR: {}", output.content)
+        };
+        ToolRun {
+            message: LlmMessage::tool(&call.id, content),
+            success,
+            pending: output.pending,
+        }
+    }
+
+    /// Apply an action the assistant proposed, then note the outcome in the
+    /// conversation so the assistant knows about it next turn
+    pub async fn confirm_action(&self, user_id: Uuid, action_id: Uuid) -> Result<ActionOutcome, AppError> {
+        let outcome = ToolDispatch::new(self.db.clone()).confirm(user_id, action_id).await?;
+        let note = if outcome.success {
+            format!("Confirmed and done: {}.", outcome.action.preview)
+        } else {
+            format!("Confirmed, but it failed: {}. Nothing was changed.", outcome.action.preview)
+        };
+        self.add_note(outcome.action.session_id, note).await?;
+        Ok(outcome)
+    }
+
+    /// Discard an action the assistant proposed
+    pub async fn reject_action(&self, user_id: Uuid, action_id: Uuid) -> Result<PendingAction, AppError> {
+        let action = ToolDispatch::new(self.db.clone()).reject(user_id, action_id).await?;
+        self.add_note(action.session_id, format!("Cancelled by the user: {}.", action.preview))
+            .await?;
+        Ok(action)
+    }
+
+    /// Append an assistant message recording something that happened
+    /// outside a model turn
+    async fn add_note(&self, session_id: i64, content: String) -> Result<(), AppError> {
+        let now = Utc::now().fixed_offset();
+        chat_message::ActiveModel {
+            session_id: Set(session_id),
+            role: Set("assistant".to_string()),
+            content: Set(content),
+            tokens_used: Set(None),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        chat_session::Entity::update_many()
+            .col_expr(chat_session::Column::UpdatedAt, Expr::value(now))
+            .filter(chat_session::Column::Id.eq(session_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Persist the assistant's reply and bump the session's `updated_at`
+    async fn save_reply(
+        &self,
+        session: chat_session::Model,
+        reply: &str,
+        tokens: Option<i32>,
+    ) -> Result<chat_message::Model, AppError> {
         let reply_msg = chat_message::ActiveModel {
             session_id: Set(session.id),
             role: Set("assistant".to_string()),
-            content: Set(reply.clone()),
+            content: Set(reply.to_string()),
             tokens_used: Set(tokens),
             created_at: Set(Utc::now().fixed_offset()),
             ..Default::default()
@@ -379,13 +601,7 @@ impl ChatService {
         active_session.updated_at = Set(Utc::now().fixed_offset());
         active_session.update(&self.db).await?;
 
-        Ok(ChatResponse {
-            session_id: saved_reply.session_id,
-            message_id: saved_reply.id,
-            reply,
-            tokens_used: tokens,
-            actions_taken,
-        })
+        Ok(saved_reply)
     }
 
     /// List all chat sessions for a user
@@ -461,10 +677,14 @@ impl ChatService {
 
     // ── Context builder ───────────────────────────────────────────────────────
 
+    /// Pantry and meal plan are listed in full only when `needs` says the
+    /// conversation is about them (or they are small); otherwise a one-line
+    /// summary points the model at `get_pantry` / `get_meal_plan`.
     async fn build_system_prompt(
         &self,
         user_id: Uuid,
         recipe_id: Option<i64>,
+        needs: ContextNeeds,
     ) -> Result<String, AppError> {
         let mut ctx = String::with_capacity(4096);
 
@@ -489,9 +709,10 @@ impl ChatService {
             ctx.push_str(&format!("Household size: {} people.\n", user.household_size));
         }
 
-        // Current inventory
+        // Current inventory (shared with the household, if any)
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let inventory = inventory_item::Entity::find()
-            .filter(inventory_item::Column::UserId.eq(user_id))
+            .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
             .all(&self.db)
             .await?;
 
@@ -516,10 +737,24 @@ impl ChatService {
                 })
                 .collect();
 
-            ctx.push_str(&format!(
-                "\nCurrent pantry/fridge inventory:\n{}\n",
-                items.join(", ")
-            ));
+            if needs.pantry || items.len() <= PANTRY_INLINE_ITEMS {
+                let shown = items.len().min(PANTRY_MAX_ITEMS);
+                ctx.push_str(&format!(
+                    "\nCurrent pantry/fridge inventory:\n{}\n",
+                    items[..shown].join(", ")
+                ));
+                if items.len() > shown {
+                    ctx.push_str(&format!(
+                        "…and {} more items (call get_pantry for the full list).\n",
+                        items.len() - shown
+                    ));
+                }
+            } else {
+                ctx.push_str(&format!(
+                    "\nThe user has {} items in their pantry/fridge (call get_pantry for the list).\n",
+                    items.len()
+                ));
+            }
 
             let expiry_threshold = Utc::now().date_naive() + chrono::Duration::days(5);
             let expiring: Vec<String> = inventory
@@ -533,6 +768,7 @@ impl ChatService {
                         .cloned()
                         .unwrap_or_else(|| i.custom_name.clone().unwrap_or_default())
                 })
+                .take(EXPIRING_MAX_ITEMS)
                 .collect();
 
             if !expiring.is_empty() {
@@ -552,7 +788,7 @@ impl ChatService {
         let week_start = today - chrono::Duration::days(days_since_monday);
 
         if let Some(plan) = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(scope.condition(meal_plan::Column::UserId, meal_plan::Column::HouseholdId))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await?
@@ -573,8 +809,10 @@ impl ChatService {
                     .collect();
 
                 let day_names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+                let today_index = days_since_monday as i16;
                 let plan_lines: Vec<String> = slots
                     .iter()
+                    .filter(|s| needs.meal_plan || s.day_of_week == today_index)
                     .map(|s| {
                         let day = day_names[s.day_of_week as usize % 7];
                         let recipe_name = s.recipe_id
@@ -586,7 +824,19 @@ impl ChatService {
                     })
                     .collect();
 
-                ctx.push_str(&format!("\nThis week's meal plan:\n{}\n", plan_lines.join("\n")));
+                if needs.meal_plan {
+                    ctx.push_str(&format!("\nThis week's meal plan:\n{}\n", plan_lines.join("\n")));
+                } else {
+                    let cooked = slots.iter().filter(|s| s.is_completed).count();
+                    ctx.push_str(&format!(
+                        "\nThis week's meal plan has {} meals ({} cooked so far; call get_meal_plan for the full week).\n",
+                        slots.len(),
+                        cooked
+                    ));
+                    if !plan_lines.is_empty() {
+                        ctx.push_str(&format!("Today:\n{}\n", plan_lines.join("\n")));
+                    }
+                }
             }
         }
 
@@ -647,6 +897,14 @@ impl ChatService {
              - add_to_pantry: Add a new ingredient to the pantry\n\
              - remove_from_pantry: Remove an item from the pantry by ID\n\
              - get_recipe_details: Full recipe info including ingredients, steps, and nutrition\n\
+             - generate_meal_plan: Build a fresh personalised plan for this week or next (replaces the existing one, keeping locked and completed meals)\n\
+             - get_shopping_list: See the shopping list and what's already checked off\n\
+             - add_to_shopping_list: Add an item to the shopping list\n\
+             - check_shopping_item: Tick an item off the shopping list (or untick it)\n\
+             - remove_from_shopping_list: Remove an item from the shopping list by ID\n\
+             - rate_recipe: Rate a recipe 1-5 stars for the user\n\
+             - set_favourite: Save a recipe to favourites or remove it\n\
+             - log_cooked: Record that the user cooked a recipe and deduct its ingredients from the pantry\n\
              \n\
              ## WHAT YOU CAN HELP WITH\n\
              \n\
@@ -657,8 +915,12 @@ impl ChatService {
              4. Mark meals as completed\n\
              5. Check what's in their pantry and what's about to expire\n\
              6. Add or remove pantry items\n\
-             7. Answer cooking questions and give step-by-step guidance\n\
-             8. Suggest what to cook based on what they already have\n\
+             7. Generate a new meal plan for this week or next\n\
+             8. Manage their shopping list (add, tick off, remove items)\n\
+             9. Rate recipes and save favourites\n\
+             10. Log what they cooked so the pantry stays up to date\n\
+             11. Answer cooking questions and give step-by-step guidance\n\
+             12. Suggest what to cook based on what they already have\n\
              \n\
              ## HOW TO HANDLE MEAL PLAN CHANGES\n\
              \n\
@@ -669,6 +931,16 @@ impl ChatService {
              4. Tell the user EXACTLY what you're going to change: \"I'll replace [current recipe] on [day] [meal_type] with [new recipe]. Shall I go ahead?\"\n\
              5. ONLY call update_meal_plan_slot after the user confirms (says \"yes\", \"sure\", \"go ahead\", \"do it\", etc.) OR if their original message was clearly an unambiguous direct command (e.g. \"change Wednesday dinner to pasta\")\n\
              \n\
+             ## ACTIONS THAT NEED CONFIRMATION\n\
+             - clear_meal_plan, remove_from_pantry and generate_meal_plan (when it would replace a plan) do NOT happen straight away: they return status \"pending_confirmation\" with a preview\n\
+             - When that happens, tell the user in one sentence what will change and that they need to tap Confirm. NEVER say it is done\n\
+             - You will see a \"Confirmed and done\" or \"Cancelled by the user\" note in the conversation once they decide\n\
+             \n\
+             ## RATINGS, FAVOURITES AND COOKING LOG\n\
+             - \"Rate last night's dinner\" / \"I cooked the lasagna\": find the recipe_id with get_meal_plan or search_recipes first — never guess an ID\n\
+             - log_cooked deducts ingredients from the pantry, so only call it when the user says they actually cooked the dish\n\
+             - If log_cooked reports items it could not deduct, tell the user which ones so they can adjust the pantry\n\
+             \n\
              ## DAY AND MEAL MAPPING\n\
              - Monday = 0, Tuesday = 1, Wednesday = 2, Thursday = 3, Friday = 4, Saturday = 5, Sunday = 6\n\
              - Meal types: breakfast, lunch, dinner, snack\n\
@@ -680,7 +952,7 @@ impl ChatService {
              - If a search returns nothing, broaden the criteria and try again\n\
              - NEVER make up recipe names — always use IDs returned by search_recipes\n\
              - For the meal plan, always work with real data from get_meal_plan\n\
-             - If no meal plan exists for the week, tell the user they need to generate one first\n\
+             - If no meal plan exists for the week, offer to generate one with generate_meal_plan\n\
              - Respect user's dietary restrictions and allergies at ALL times (they are in your context)\n\
              - Keep responses concise but warm — max 3-4 sentences for routine changes, more for complex questions\n\
              \n\
@@ -715,14 +987,64 @@ impl ChatService {
             "i've cleared", "i've updated", "i've added", "i've removed",
             "i've deleted", "i've created", "i've changed", "i've replaced",
             "i've set", "i've scheduled", "i've marked", "i've reset",
+            "i've saved", "i've rated", "i've logged", "i've generated", "i've checked off",
             "i have cleared", "i have updated", "i have added", "i have removed",
             "i have deleted", "i have created", "i have changed",
             "all done", "done!", "successfully cleared", "successfully updated",
             "successfully added", "successfully removed",
             "your meal plan has been cleared", "your plan has been",
-            "your pantry has been", "i cleared", "i updated", "i deleted",
+            "your pantry has been", "your shopping list has been", "i cleared", "i updated", "i deleted",
             "i removed", "i added", "i replaced", "i changed",
         ];
         action_phrases.iter().any(|p| lower.contains(p))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm::{fake::{FakeRule, FakeToolCall}, FakeLlm};
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn stream_round_forwards_tokens_and_collects_tool_calls() {
+        let llm = FakeLlm::new(vec![FakeRule {
+            pattern: "pantry".into(),
+            reply: "Let me check.".into(),
+            tool_calls: vec![FakeToolCall { name: "get_pantry".into(), arguments: serde_json::json!({}) }],
+        }]);
+        let service = ChatService::new(DatabaseConnection::Disconnected, Arc::new(llm));
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let messages = [LlmMessage::user("What's in my pantry?")];
+        let (message, tokens) = service.stream_round(&messages, &tx).await.unwrap();
+        drop(tx);
+
+        assert_eq!(message.content, "Let me check.");
+        assert_eq!(message.tool_calls[0].name, "get_pantry");
+        assert_eq!(tokens, Some(3));
+
+        let mut streamed = Vec::new();
+        while let Some(event) = rx.recv().await {
+            streamed.push(event);
+        }
+        assert_eq!(
+            streamed,
+            vec![
+                ChatEvent::Token { content: "Let ".into() },
+                ChatEvent::Token { content: "me ".into() },
+                ChatEvent::Token { content: "check.".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn events_are_framed_as_sse() {
+        let frame = ChatEvent::ToolStart { name: "clear_meal_plan".into(), label: "Clearing your meal plan…" }.to_sse();
+        assert_eq!(
+            frame,
+            "event: tool_start\ndata: {\"type\":\"tool_start\",\"name\":\"clear_meal_plan\",\"label\":\"Clearing your meal plan…\"}\n\n"
+        );
+        assert_eq!(ChatEvent::Reset.to_sse(), "event: reset\ndata: {\"type\":\"reset\"}\n\n");
+    }
+}
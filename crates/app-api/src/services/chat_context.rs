@@ -0,0 +1,161 @@
+//! Context-window budgeting for chat sessions.
+//!
+//! Every turn must fit the chat model's window: system prompt, session
+//! summary, tool schemas, history and the new message, with a quarter of
+//! the window held back for tool results and the reply. History is kept
+//! newest-first until the budget runs out; older turns are folded into the
+//! session's rolling summary instead of being sent verbatim.
+//!
+//! Token counts are estimates (~4 characters per token) — close enough to
+//! budget with, and provider-independent.
+
+use crate::entity::chat_message;
+use crate::services::llm::{LlmMessage, LlmRequest};
+
+/// Rough characters-per-token ratio for English text and JSON
+const CHARS_PER_TOKEN: usize = 4;
+/// Per-message framing overhead (role, separators)
+const MESSAGE_OVERHEAD: usize = 4;
+/// Summaries are asked to stay under this many words
+const SUMMARY_WORDS: usize = 250;
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) + MESSAGE_OVERHEAD
+}
+
+/// How a model's window is shared out for one turn
+#[derive(Debug, Clone, Copy)]
+pub struct ContextBudget {
+    total: usize,
+}
+
+impl ContextBudget {
+    pub fn new(context_tokens: usize) -> Self {
+        Self { total: context_tokens }
+    }
+
+    /// Held back for tool results and the reply
+    pub fn reserved(&self) -> usize {
+        self.total / 4
+    }
+
+    /// Tokens left for history once `fixed` (system prompt, summary, tool
+    /// schemas, new message) is accounted for
+    pub fn history(&self, fixed: usize) -> usize {
+        self.total.saturating_sub(self.reserved() + fixed)
+    }
+
+    /// Cap for the transcript sent to the summariser
+    pub fn summary_input(&self) -> usize {
+        self.total.saturating_sub(self.reserved() + estimate_tokens(SUMMARY_INSTRUCTIONS))
+    }
+}
+
+/// Index of the first message to keep so that the kept suffix of `costs`
+/// (oldest first) fits in `budget`. Returns `costs.len()` if none fit.
+pub fn keep_from(costs: &[usize], budget: usize) -> usize {
+    let mut used = 0;
+    for (i, cost) in costs.iter().enumerate().rev() {
+        used += cost;
+        if used > budget {
+            return i + 1;
+        }
+    }
+    0
+}
+
+/// Which parts of the user's data the conversation is about. Relevant
+/// parts go into the system prompt in full; the rest as a one-line summary
+/// the model can expand with `get_pantry` / `get_meal_plan`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextNeeds {
+    pub pantry: bool,
+    pub meal_plan: bool,
+}
+
+impl ContextNeeds {
+    const PANTRY_WORDS: [&'static str; 14] = [
+        "pantry", "fridge", "freezer", "inventory", "in stock", "have", "left", "expir",
+        "ingredient", "use up", "leftover", "cook with", "what can i make", "shopping",
+    ];
+    const PLAN_WORDS: [&'static str; 20] = [
+        "plan", "week", "schedule", "today", "tonight", "tomorrow", "monday", "tuesday",
+        "wednesday", "thursday", "friday", "saturday", "sunday", "breakfast", "lunch",
+        "dinner", "supper", "snack", "meal", "swap",
+    ];
+
+    /// Judge from the latest user message and the turns just before it, so
+    /// a follow-up like "yes, do it" keeps the context it refers to
+    pub fn from_texts<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut needs = Self::default();
+        for text in texts {
+            let lower = text.to_lowercase();
+            needs.pantry |= Self::PANTRY_WORDS.iter().any(|w| lower.contains(w));
+            needs.meal_plan |= Self::PLAN_WORDS.iter().any(|w| lower.contains(w));
+        }
+        needs
+    }
+}
+
+const SUMMARY_INSTRUCTIONS: &str = "You maintain the running summary of a conversation between a user and \
+     Cookest AI, a cooking assistant. Merge the previous summary (if any) with the new turns into one \
+     updated summary. Keep facts that matter later: the user's stated preferences, dislikes and goals, \
+     recipes discussed or chosen, changes made to their meal plan, pantry or shopping list, and open \
+     questions. Drop greetings and small talk. Write plain prose, third person, no headings.";
+
+/// Request that folds `turns` (oldest first) into `previous`. Only the newest
+/// turns that fit `budget` are included.
+pub fn summary_request(previous: Option<&str>, turns: &[chat_message::Model], budget: usize) -> LlmRequest {
+    let costs: Vec<usize> = turns.iter().map(|m| estimate_tokens(&m.content)).collect();
+    let previous_cost = previous.map(estimate_tokens).unwrap_or(0);
+    let first = keep_from(&costs, budget.saturating_sub(previous_cost));
+
+    let mut input = String::new();
+    if let Some(previous) = previous {
+        input.push_str(&format!("Previous summary:\n{}\n\n", previous));
+    }
+    input.push_str("New turns:\n");
+    for turn in &turns[first..] {
+        input.push_str(&format!("{}: {}\n", turn.role, turn.content));
+    }
+    input.push_str(&format!("\nWrite the updated summary in at most {} words.", SUMMARY_WORDS));
+
+    LlmRequest::new(vec![LlmMessage::system(SUMMARY_INSTRUCTIONS), LlmMessage::user(input)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_newest_messages_that_fit() {
+        let costs = [50, 10, 20, 30];
+        assert_eq!(keep_from(&costs, 1000), 0);
+        assert_eq!(keep_from(&costs, 50), 2);
+        assert_eq!(keep_from(&costs, 59), 2);
+        assert_eq!(keep_from(&costs, 60), 1);
+        assert_eq!(keep_from(&costs, 10), 4);
+        assert_eq!(keep_from(&[], 10), 0);
+    }
+
+    #[test]
+    fn budget_reserves_a_quarter_for_the_reply() {
+        let budget = ContextBudget::new(8_192);
+        assert_eq!(budget.reserved(), 2_048);
+        assert_eq!(budget.history(1_000), 5_144);
+        assert_eq!(budget.history(9_000), 0);
+    }
+
+    #[test]
+    fn needs_follow_the_conversation() {
+        assert_eq!(
+            ContextNeeds::from_texts(["What can I make with what's in my fridge?"]),
+            ContextNeeds { pantry: true, meal_plan: false }
+        );
+        assert_eq!(
+            ContextNeeds::from_texts(["yes, do it", "Shall I swap Wednesday dinner for the curry?"]),
+            ContextNeeds { pantry: false, meal_plan: true }
+        );
+        assert_eq!(ContextNeeds::from_texts(["How long do I rest a steak?"]), ContextNeeds::default());
+    }
+}
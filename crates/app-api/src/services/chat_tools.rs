@@ -3,13 +3,31 @@
 //! SECURITY: Every tool function receives `user_id: Uuid` derived from the
 //! validated JWT token. The AI cannot specify a different user_id.
 //! All database reads/writes are filtered by this user_id.
+//!
+//! Destructive tools (`CONFIRM_TOOLS`) never run straight from a model call:
+//! they become a pending action the user confirms or rejects via
+//! `POST /api/chat/actions/{id}/confirm|reject`. Every invocation is recorded
+//! in `chat_tool_audit`.
 
-use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::Serialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::entity::{ingredient, recipe, recipe_ingredient, recipe_nutrition, recipe_step};
-use crate::services::{InventoryService, MealPlanService};
+use crate::entity::{
+    chat_pending_action, chat_tool_audit, ingredient, meal_plan, meal_plan_slot, recipe,
+    recipe_ingredient, recipe_nutrition, recipe_step, user_favorite,
+};
+use crate::services::household::DataScope;
+use crate::services::shopping_list::AddItemRequest;
+use crate::services::{
+    InteractionService, InventoryService, MealPlanService, ProfileService, ShoppingListService,
+};
 use cookest_shared::errors::AppError;
 
 // ── Tool definitions ──────────────────────────────────────────────────────────
@@ -107,7 +125,7 @@ pub fn tool_definitions() -> Vec<Value> {
             "type": "function",
             "function": {
                 "name": "remove_from_pantry",
-                "description": "Remove an item from the user's pantry by its inventory item ID.",
+                "description": "Remove an item from the user's pantry by its inventory item ID. The user must confirm in the app before it happens.",
                 "parameters": {
                     "type": "object",
                     "properties": {
@@ -121,7 +139,7 @@ pub fn tool_definitions() -> Vec<Value> {
             "type": "function",
             "function": {
                 "name": "clear_meal_plan",
-                "description": "Remove ALL recipes from the user's current week meal plan, leaving it completely empty so they can start fresh. Use this when the user explicitly asks to clear, reset, or start over their meal plan.",
+                "description": "Remove ALL recipes from the user's current week meal plan, leaving it completely empty so they can start fresh. Use this when the user explicitly asks to clear, reset, or start over their meal plan. The user must confirm in the app before it happens.",
                 "parameters": { "type": "object", "properties": {}, "required": [] }
             }
         }),
@@ -139,11 +157,247 @@ pub fn tool_definitions() -> Vec<Value> {
                 }
             }
         }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "generate_meal_plan",
+                "description": "Generate a new personalised meal plan for this week (or next week), respecting the user's diet, allergies and pantry. REPLACES the existing plan's meals for that week, except locked, completed and flex slots; if one exists the user must confirm in the app before it happens.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "next_week": { "type": "boolean", "description": "Plan next week instead of the current one (default false)" }
+                    },
+                    "required": []
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "get_shopping_list",
+                "description": "Get the user's shopping list, including which items are already checked off.",
+                "parameters": { "type": "object", "properties": {}, "required": [] }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "add_to_shopping_list",
+                "description": "Add an item to the user's shopping list.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "name":     { "type": "string" },
+                        "quantity": { "type": "number" },
+                        "unit":     { "type": "string", "description": "g, kg, ml, l, pieces, etc." }
+                    },
+                    "required": ["name"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "check_shopping_item",
+                "description": "Tick an item on the shopping list as bought, or untick it.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "item_id": { "type": "string",  "description": "The shopping list item ID from get_shopping_list" },
+                        "checked": { "type": "boolean", "description": "true = bought (default), false = not bought yet" }
+                    },
+                    "required": ["item_id"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "remove_from_shopping_list",
+                "description": "Remove an item from the shopping list by its ID.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "item_id": { "type": "string", "description": "The shopping list item ID from get_shopping_list" }
+                    },
+                    "required": ["item_id"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "rate_recipe",
+                "description": "Rate a recipe from 1 to 5 stars on the user's behalf. Replaces any earlier rating of the same recipe.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "recipe_id": { "type": "integer" },
+                        "rating":    { "type": "integer", "description": "1 (worst) to 5 (best)" },
+                        "comment":   { "type": "string" }
+                    },
+                    "required": ["recipe_id", "rating"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "set_favourite",
+                "description": "Save a recipe to the user's favourites, or remove it from them.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "recipe_id": { "type": "integer" },
+                        "favourite": { "type": "boolean", "description": "true = save (default), false = remove" }
+                    },
+                    "required": ["recipe_id"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "log_cooked",
+                "description": "Record that the user cooked a recipe. Deducts the ingredients used from their pantry and adds it to their cooking history. Servings beyond the household size are stored as leftovers.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "recipe_id": { "type": "integer" },
+                        "servings":  { "type": "integer", "description": "Servings made (default: the user's household size)" }
+                    },
+                    "required": ["recipe_id"]
+                }
+            }
+        }),
     ]
 }
 
+/// Short progress label shown while a tool runs (streamed chat)
+pub fn tool_label(name: &str) -> &'static str {
+    match name {
+        "search_recipes"        => "Searching recipes…",
+        "get_meal_plan"         => "Checking your meal plan…",
+        "update_meal_plan_slot" => "Updating your meal plan…",
+        "mark_meal_completed"   => "Marking the meal as done…",
+        "get_pantry"            => "Checking your pantry…",
+        "add_to_pantry"         => "Adding to your pantry…",
+        "remove_from_pantry"    => "Removing from your pantry…",
+        "clear_meal_plan"       => "Clearing your meal plan…",
+        "get_recipe_details"    => "Looking up the recipe…",
+        "generate_meal_plan"    => "Planning your week…",
+        "get_shopping_list"     => "Checking your shopping list…",
+        "add_to_shopping_list"  => "Adding to your shopping list…",
+        "check_shopping_item"   => "Updating your shopping list…",
+        "remove_from_shopping_list" => "Removing from your shopping list…",
+        "rate_recipe"           => "Saving your rating…",
+        "set_favourite"         => "Updating your favourites…",
+        "log_cooked"            => "Logging your cooking…",
+        _                       => "Working on it…",
+    }
+}
+
 // ── Tool dispatch ─────────────────────────────────────────────────────────────
 
+/// Tools that destroy data the user can't easily get back. The model only
+/// proposes these; they run once the user confirms in the app.
+const CONFIRM_TOOLS: [&str; 3] = ["clear_meal_plan", "remove_from_pantry", "generate_meal_plan"];
+/// How long a proposal waits for the user before it lapses
+const PENDING_ACTION_HOURS: i64 = 24;
+/// Tool output kept per audit row
+const AUDIT_RESULT_CHARS: usize = 2000;
+
+pub fn requires_confirmation(name: &str) -> bool {
+    CONFIRM_TOOLS.contains(&name)
+}
+
+/// What running a tool returned: the JSON the model sees, and whether the
+/// call failed (bad arguments, missing rows, database errors)
+#[derive(Debug, Clone)]
+pub struct ToolResult {
+    pub content: String,
+    pub failed: bool,
+}
+
+impl ToolResult {
+    fn ok(value: Value) -> Self {
+        Self { content: value.to_string(), failed: false }
+    }
+
+    fn failed(value: Value) -> Self {
+        Self { content: value.to_string(), failed: true }
+    }
+}
+
+/// A destructive tool call waiting for the user, as shown to the client
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingAction {
+    pub id: Uuid,
+    pub session_id: i64,
+    pub tool: String,
+    /// What will happen, e.g. "Remove all 12 meals from this week's meal plan"
+    pub preview: String,
+    /// "pending" | "confirmed" | "rejected" | "failed"
+    pub status: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<chat_pending_action::Model> for PendingAction {
+    fn from(m: chat_pending_action::Model) -> Self {
+        Self {
+            id: m.id,
+            session_id: m.session_id,
+            tool: m.tool_name,
+            preview: m.preview,
+            status: m.status,
+            expires_at: m.expires_at.with_timezone(&Utc),
+        }
+    }
+}
+
+/// A confirmed action and what running it returned
+#[derive(Debug, Serialize)]
+pub struct ActionOutcome {
+    pub action: PendingAction,
+    pub success: bool,
+    pub result: Value,
+}
+
+/// What a tool call produced: the JSON the model sees next and, for tools
+/// that need confirmation, the action now waiting for the user
+pub struct ToolOutput {
+    pub content: String,
+    pub failed: bool,
+    pub pending: Option<PendingAction>,
+}
+
+impl From<ToolResult> for ToolOutput {
+    fn from(result: ToolResult) -> Self {
+        Self { content: result.content, failed: result.failed, pending: None }
+    }
+}
+
+/// How a tool that needs confirmation is handled for these arguments
+enum Preview {
+    /// Ask the user first, describing the change
+    Confirm(String),
+    /// Nothing would be lost — run it straight away
+    RunNow,
+    /// Answer the model with this instead (bad arguments, nothing to do)
+    Reply(ToolResult),
+}
+
+/// One `chat_tool_audit` row
+struct AuditEntry<'a> {
+    user_id: Uuid,
+    session_id: i64,
+    tool: &'a str,
+    arguments: &'a Value,
+    outcome: &'static str,
+    action_id: Option<Uuid>,
+    result: Option<&'a str>,
+}
+
 pub struct ToolDispatch {
     db: DatabaseConnection,
 }
@@ -153,7 +407,44 @@ impl ToolDispatch {
         Self { db }
     }
 
-    pub async fn execute(&self, user_id: Uuid, name: &str, args: Value) -> String {
+    /// Run (or, for destructive tools, propose) one tool call and audit it
+    pub async fn execute(&self, user_id: Uuid, session_id: i64, name: &str, args: Value) -> ToolOutput {
+        let args = pin_week_start(name, args, Utc::now().date_naive());
+        if requires_confirmation(name) {
+            match self.preview(user_id, name, &args).await {
+                Preview::Confirm(preview) => return self.propose(user_id, session_id, name, args, preview).await,
+                Preview::Reply(result) => {
+                    self.audit(AuditEntry {
+                        user_id,
+                        session_id,
+                        tool: name,
+                        arguments: &args,
+                        outcome: if result.failed { "failed" } else { "executed" },
+                        action_id: None,
+                        result: Some(&result.content),
+                    })
+                    .await;
+                    return result.into();
+                }
+                Preview::RunNow => {}
+            }
+        }
+
+        let result = self.run(user_id, name, args.clone()).await;
+        self.audit(AuditEntry {
+            user_id,
+            session_id,
+            tool: name,
+            arguments: &args,
+            outcome: if result.failed { "failed" } else { "executed" },
+            action_id: None,
+            result: Some(&result.content),
+        })
+        .await;
+        result.into()
+    }
+
+    async fn run(&self, user_id: Uuid, name: &str, args: Value) -> ToolResult {
         match name {
             "search_recipes"       => self.search_recipes(args).await,
             "get_meal_plan"        => self.get_meal_plan(user_id).await,
@@ -162,15 +453,243 @@ impl ToolDispatch {
             "get_pantry"           => self.get_pantry(user_id).await,
             "add_to_pantry"        => self.add_to_pantry(user_id, args).await,
             "remove_from_pantry"   => self.remove_from_pantry(user_id, args).await,
-            "clear_meal_plan"      => self.clear_meal_plan(user_id).await,
+            "clear_meal_plan"      => self.clear_meal_plan(user_id, args).await,
             "get_recipe_details"   => self.get_recipe_details(args).await,
-            _ => format!("{{\"error\": \"Unknown tool: {}\"}}", name),
+            "generate_meal_plan"   => self.generate_meal_plan(user_id, args).await,
+            "get_shopping_list"    => self.get_shopping_list(user_id).await,
+            "add_to_shopping_list" => self.add_to_shopping_list(user_id, args).await,
+            "check_shopping_item"  => self.check_shopping_item(user_id, args).await,
+            "remove_from_shopping_list" => self.remove_from_shopping_list(user_id, args).await,
+            "rate_recipe"          => self.rate_recipe(user_id, args).await,
+            "set_favourite"        => self.set_favourite(user_id, args).await,
+            "log_cooked"           => self.log_cooked(user_id, args).await,
+            _ => ToolResult::failed(json!({"error": format!("Unknown tool: {}", name)})),
         }
     }
 
+    // ── Confirmation and audit ───────────────────────────────────────────────
+
+    /// Decide whether this call needs the user's go-ahead and describe it
+    async fn preview(&self, user_id: Uuid, name: &str, args: &Value) -> Preview {
+        match name {
+            "clear_meal_plan" => match self.week_plan_slots(user_id, args_week_start(args)).await {
+                Ok(Some(slots)) if slots > 0 => Preview::Confirm(format!(
+                    "Remove all {} meals from your meal plan for the week of {}",
+                    slots,
+                    args_week_start(args)
+                )),
+                Ok(_) => Preview::Reply(
+                    ToolResult::ok(json!({"success": true, "slots_removed": 0, "note": "This week's meal plan is already empty"})),
+                ),
+                Err(e) => Preview::Reply(ToolResult::failed(json!({"error": e.to_string()}))),
+            },
+            "remove_from_pantry" => {
+                let Some(item_id) = args["item_id"].as_i64() else {
+                    return Preview::Reply(ToolResult::failed(json!({"status": "error", "message": "Missing item_id"})));
+                };
+                match InventoryService::new(self.db.clone()).list(user_id).await {
+                    Ok(items) => match items.into_iter().find(|i| i.id == item_id) {
+                        Some(item) => Preview::Confirm(format!(
+                            "Remove {} ({} {}) from your pantry",
+                            item.custom_name.as_deref().unwrap_or(&item.ingredient_name),
+                            item.quantity.normalize(),
+                            item.unit,
+                        )),
+                        None => Preview::Reply(
+                            ToolResult::failed(json!({"status": "error", "message": "Item not found in your pantry"})),
+                        ),
+                    },
+                    Err(e) => Preview::Reply(ToolResult::failed(json!({"error": e.to_string()}))),
+                }
+            }
+            "generate_meal_plan" => {
+                let week_start = args_week_start(args);
+                match self.week_plan_slots(user_id, week_start).await {
+                    Ok(Some(slots)) if slots > 0 => Preview::Confirm(format!(
+                        "Regenerate your meal plan for the week of {} ({} meals); locked and completed meals are kept",
+                        week_start, slots
+                    )),
+                    Ok(_) => Preview::RunNow,
+                    Err(e) => Preview::Reply(ToolResult::failed(json!({"error": e.to_string()}))),
+                }
+            }
+            _ => Preview::RunNow,
+        }
+    }
+
+    async fn propose(&self, user_id: Uuid, session_id: i64, name: &str, args: Value, preview: String) -> ToolOutput {
+        let now = Utc::now().fixed_offset();
+        let action = chat_pending_action::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            session_id: Set(session_id),
+            user_id: Set(user_id),
+            tool_name: Set(name.to_string()),
+            arguments: Set(args.clone()),
+            preview: Set(preview),
+            status: Set("pending".to_string()),
+            expires_at: Set(now + Duration::hours(PENDING_ACTION_HOURS)),
+            resolved_at: Set(None),
+            created_at: Set(now),
+        }
+        .insert(&self.db)
+        .await;
+
+        let pending = match action {
+            Ok(action) => PendingAction::from(action),
+            Err(e) => {
+                tracing::error!("{} proposal error: {}", name, e);
+                return ToolResult::failed(json!({"error": "Failed to prepare the action"})).into();
+            }
+        };
+
+        let content = json!({
+            "status": "pending_confirmation",
+            "action_id": pending.id,
+            "preview": pending.preview,
+            "message": "Nothing has changed yet. The user must confirm this in the app before it happens. \
+                        Tell them what will happen and ask them to confirm.",
+        })
+        .to_string();
+        self.audit(AuditEntry {
+            user_id,
+            session_id,
+            tool: name,
+            arguments: &args,
+            outcome: "proposed",
+            action_id: Some(pending.id),
+            result: Some(&content),
+        })
+        .await;
+
+        ToolOutput { content, failed: false, pending: Some(pending) }
+    }
+
+    /// Apply a pending action the user confirmed
+    pub async fn confirm(&self, user_id: Uuid, action_id: Uuid) -> Result<ActionOutcome, AppError> {
+        let mut action = self.claim(user_id, action_id, "confirmed").await?;
+        let ToolResult { content, failed } = self.run(user_id, &action.tool_name, action.arguments.clone()).await;
+        let success = !failed;
+
+        if !success {
+            chat_pending_action::Entity::update_many()
+                .col_expr(chat_pending_action::Column::Status, Expr::value("failed"))
+                .filter(chat_pending_action::Column::Id.eq(action.id))
+                .exec(&self.db)
+                .await?;
+            action.status = "failed".to_string();
+        }
+        self.audit(AuditEntry {
+            user_id,
+            session_id: action.session_id,
+            tool: &action.tool_name,
+            arguments: &action.arguments,
+            outcome: if success { "confirmed" } else { "failed" },
+            action_id: Some(action.id),
+            result: Some(&content),
+        })
+        .await;
+
+        let result = serde_json::from_str(&content).unwrap_or(Value::String(content));
+        Ok(ActionOutcome { action: action.into(), success, result })
+    }
+
+    /// Drop a pending action the user turned down
+    pub async fn reject(&self, user_id: Uuid, action_id: Uuid) -> Result<PendingAction, AppError> {
+        let action = self.claim(user_id, action_id, "rejected").await?;
+        self.audit(AuditEntry {
+            user_id,
+            session_id: action.session_id,
+            tool: &action.tool_name,
+            arguments: &action.arguments,
+            outcome: "rejected",
+            action_id: Some(action.id),
+            result: None,
+        })
+        .await;
+        Ok(action.into())
+    }
+
+    /// Move one of the user's pending, unexpired actions to `status`. The
+    /// conditional update means each action resolves exactly once.
+    async fn claim(
+        &self,
+        user_id: Uuid,
+        action_id: Uuid,
+        status: &str,
+    ) -> Result<chat_pending_action::Model, AppError> {
+        let now = Utc::now().fixed_offset();
+        let action = chat_pending_action::Entity::find_by_id(action_id)
+            .filter(chat_pending_action::Column::UserId.eq(user_id))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Action".to_string()))?;
+
+        if action.status != "pending" {
+            return Err(action_error("This action has already been handled"));
+        }
+        if action.expires_at <= now {
+            return Err(action_error("This action has expired — ask the assistant again"));
+        }
+
+        let claimed = chat_pending_action::Entity::update_many()
+            .col_expr(chat_pending_action::Column::Status, Expr::value(status))
+            .col_expr(chat_pending_action::Column::ResolvedAt, Expr::value(now))
+            .filter(chat_pending_action::Column::Id.eq(action.id))
+            .filter(chat_pending_action::Column::Status.eq("pending"))
+            .exec(&self.db)
+            .await?;
+        if claimed.rows_affected == 0 {
+            return Err(action_error("This action has already been handled"));
+        }
+
+        Ok(chat_pending_action::Model {
+            status: status.to_string(),
+            resolved_at: Some(now),
+            ..action
+        })
+    }
+
+    /// Record a tool invocation. Failures are logged, never surfaced — the
+    /// user's action has already happened.
+    async fn audit(&self, entry: AuditEntry<'_>) {
+        let row = chat_tool_audit::ActiveModel {
+            user_id: Set(entry.user_id),
+            session_id: Set(Some(entry.session_id)),
+            tool_name: Set(entry.tool.to_string()),
+            arguments: Set(entry.arguments.clone()),
+            outcome: Set(entry.outcome.to_string()),
+            action_id: Set(entry.action_id),
+            result: Set(entry.result.map(|r| r.chars().take(AUDIT_RESULT_CHARS).collect())),
+            created_at: Set(Utc::now().fixed_offset()),
+            ..Default::default()
+        };
+        if let Err(e) = row.insert(&self.db).await {
+            tracing::error!("Failed to audit tool call {} for user {}: {}", entry.tool, entry.user_id, e);
+        }
+    }
+
+    /// Number of slots in the user's (or household's) plan for a week, if
+    /// there is one
+    async fn week_plan_slots(&self, user_id: Uuid, week_start: NaiveDate) -> Result<Option<u64>, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let Some(plan) = meal_plan::Entity::find()
+            .filter(scope.condition(meal_plan::Column::UserId, meal_plan::Column::HouseholdId))
+            .filter(meal_plan::Column::WeekStart.eq(week_start))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let slots = meal_plan_slot::Entity::find()
+            .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
+            .count(&self.db)
+            .await?;
+        Ok(Some(slots))
+    }
+
     // ── Individual tool implementations ──────────────────────────────────────
 
-    async fn search_recipes(&self, args: Value) -> String {
+    async fn search_recipes(&self, args: Value) -> ToolResult {
         let mut condition = Condition::all();
 
         if let Some(q) = args["query"].as_str() {
@@ -215,7 +734,7 @@ impl ToolDispatch {
             Ok(r) => r,
             Err(e) => {
                 tracing::error!("search_recipes DB error: {}", e);
-                return json!({"error": "Failed to search recipes"}).to_string();
+                return ToolResult::failed(json!({"error": "Failed to search recipes"}));
             }
         };
 
@@ -233,17 +752,16 @@ impl ToolDispatch {
             })
             .collect();
 
-        serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string())
+        ToolResult::ok(json!(results))
     }
 
-    async fn get_meal_plan(&self, user_id: Uuid) -> String {
+    async fn get_meal_plan(&self, user_id: Uuid) -> ToolResult {
         let svc = MealPlanService::new(self.db.clone());
         match svc.get_current_week_plan(user_id).await {
-            Ok(None) => json!({
+            Ok(None) => ToolResult::ok(json!({
                 "status": "no_plan",
                 "message": "No meal plan for this week. The user can generate one."
-            })
-            .to_string(),
+            })),
             Ok(Some(plan)) => {
                 const DAY_NAMES: [&str; 7] =
                     ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
@@ -261,27 +779,27 @@ impl ToolDispatch {
                         })
                     })
                     .collect();
-                serde_json::to_string(&slots).unwrap_or_else(|_| "[]".to_string())
+                ToolResult::ok(json!(slots))
             }
             Err(e) => {
                 tracing::error!("get_meal_plan tool error: {}", e);
-                json!({"error": "Failed to get meal plan"}).to_string()
+                ToolResult::failed(json!({"error": "Failed to get meal plan"}))
             }
         }
     }
 
-    async fn update_meal_plan_slot(&self, user_id: Uuid, args: Value) -> String {
+    async fn update_meal_plan_slot(&self, user_id: Uuid, args: Value) -> ToolResult {
         let day_of_week = match args["day_of_week"].as_i64() {
             Some(d) => d as i16,
-            None => return json!({"status": "error", "message": "Missing day_of_week"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing day_of_week"})),
         };
         let meal_type = match args["meal_type"].as_str() {
             Some(m) => m.to_string(),
-            None => return json!({"status": "error", "message": "Missing meal_type"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing meal_type"})),
         };
         let recipe_id = match args["recipe_id"].as_i64() {
             Some(r) => r,
-            None => return json!({"status": "error", "message": "Missing recipe_id"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing recipe_id"})),
         };
 
         const DAY_NAMES: [&str; 7] =
@@ -289,7 +807,7 @@ impl ToolDispatch {
 
         let svc = MealPlanService::new(self.db.clone());
         match svc.update_slot_recipe(user_id, day_of_week, &meal_type, recipe_id).await {
-            Ok(recipe_name) => json!({
+            Ok(recipe_name) => ToolResult::ok(json!({
                 "status": "success",
                 "message": format!(
                     "Updated {} {} to {}",
@@ -298,53 +816,50 @@ impl ToolDispatch {
                     recipe_name
                 ),
                 "recipe_name": recipe_name,
-            })
-            .to_string(),
-            Err(AppError::NotFound(ref msg)) if msg.contains("week") => json!({
+            })),
+            Err(AppError::NotFound(ref msg)) if msg.contains("week") => ToolResult::ok(json!({
                 "status": "error",
                 "message": "No meal plan for this week. Please generate a meal plan first."
-            })
-            .to_string(),
+            })),
             Err(e) => {
                 tracing::error!("update_meal_plan_slot tool error: {}", e);
-                json!({"status": "error", "message": format!("{}", e)}).to_string()
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
             }
         }
     }
 
-    async fn mark_meal_completed(&self, user_id: Uuid, args: Value) -> String {
+    async fn mark_meal_completed(&self, user_id: Uuid, args: Value) -> ToolResult {
         let day_of_week = match args["day_of_week"].as_i64() {
             Some(d) => d as i16,
-            None => return json!({"status": "error", "message": "Missing day_of_week"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing day_of_week"})),
         };
         let meal_type = match args["meal_type"].as_str() {
             Some(m) => m.to_string(),
-            None => return json!({"status": "error", "message": "Missing meal_type"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing meal_type"})),
         };
 
         let svc = MealPlanService::new(self.db.clone());
         match svc.mark_slot_completed(user_id, day_of_week, &meal_type).await {
-            Ok(()) => json!({"status": "success", "message": "Meal marked as completed"}).to_string(),
-            Err(AppError::NotFound(ref msg)) if msg.contains("week") => json!({
+            Ok(()) => ToolResult::ok(json!({"status": "success", "message": "Meal marked as completed"})),
+            Err(AppError::NotFound(ref msg)) if msg.contains("week") => ToolResult::ok(json!({
                 "status": "error",
                 "message": "No meal plan for this week."
-            })
-            .to_string(),
+            })),
             Err(AppError::NotFound(ref msg)) => {
-                json!({"status": "error", "message": format!("Not found: {}", msg)}).to_string()
+                ToolResult::failed(json!({"status": "error", "message": format!("Not found: {}", msg)}))
             }
             Err(e) => {
                 tracing::error!("mark_meal_completed tool error: {}", e);
-                json!({"status": "error", "message": format!("{}", e)}).to_string()
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
             }
         }
     }
 
-    async fn get_pantry(&self, user_id: Uuid) -> String {
+    async fn get_pantry(&self, user_id: Uuid) -> ToolResult {
         let svc = InventoryService::new(self.db.clone());
         match svc.list(user_id).await {
             Ok(items) if items.is_empty() => {
-                json!({"status": "empty", "message": "Pantry is empty."}).to_string()
+                ToolResult::ok(json!({"status": "empty", "message": "Pantry is empty."}))
             }
             Ok(items) => {
                 let result: Vec<Value> = items
@@ -365,27 +880,27 @@ impl ToolDispatch {
                         })
                     })
                     .collect();
-                serde_json::to_string(&result).unwrap_or_else(|_| "[]".to_string())
+                ToolResult::ok(json!(result))
             }
             Err(e) => {
                 tracing::error!("get_pantry tool error: {}", e);
-                json!({"error": "Failed to get pantry"}).to_string()
+                ToolResult::failed(json!({"error": "Failed to get pantry"}))
             }
         }
     }
 
-    async fn add_to_pantry(&self, user_id: Uuid, args: Value) -> String {
+    async fn add_to_pantry(&self, user_id: Uuid, args: Value) -> ToolResult {
         let name = match args["name"].as_str() {
             Some(n) => n.to_string(),
-            None => return json!({"status": "error", "message": "Missing name"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing name"})),
         };
         let quantity = match args["quantity"].as_f64() {
             Some(q) => q,
-            None => return json!({"status": "error", "message": "Missing quantity"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing quantity"})),
         };
         let unit = match args["unit"].as_str() {
             Some(u) => u.to_string(),
-            None => return json!({"status": "error", "message": "Missing unit"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing unit"})),
         };
         let storage_location = args["storage_location"].as_str().map(|s| s.to_string());
         let expiry_date = args["expiry_date"]
@@ -397,50 +912,49 @@ impl ToolDispatch {
             .quick_add(user_id, name.clone(), quantity, unit.clone(), storage_location, expiry_date)
             .await
         {
-            Ok(item) => json!({
+            Ok(item) => ToolResult::ok(json!({
                 "status": "success",
                 "message": format!("Added {} {} {} to pantry", quantity, unit, name),
                 "id": item.id,
-            })
-            .to_string(),
+            })),
             Err(e) => {
                 tracing::error!("add_to_pantry tool error: {}", e);
-                json!({"status": "error", "message": format!("{}", e)}).to_string()
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
             }
         }
     }
 
-    async fn remove_from_pantry(&self, user_id: Uuid, args: Value) -> String {
+    async fn remove_from_pantry(&self, user_id: Uuid, args: Value) -> ToolResult {
         let item_id = match args["item_id"].as_i64() {
             Some(id) => id,
-            None => return json!({"status": "error", "message": "Missing item_id"}).to_string(),
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing item_id"})),
         };
 
         let svc = InventoryService::new(self.db.clone());
         match svc.delete(user_id, item_id).await {
-            Ok(()) => json!({"status": "success", "message": "Item removed from pantry"}).to_string(),
+            Ok(()) => ToolResult::ok(json!({"status": "success", "message": "Item removed from pantry"})),
             Err(AppError::NotFound(_)) => {
-                json!({"status": "error", "message": "Item not found in your pantry"}).to_string()
+                ToolResult::failed(json!({"status": "error", "message": "Item not found in your pantry"}))
             }
             Err(e) => {
                 tracing::error!("remove_from_pantry tool error: {}", e);
-                json!({"status": "error", "message": format!("{}", e)}).to_string()
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
             }
         }
     }
 
-    async fn get_recipe_details(&self, args: Value) -> String {
+    async fn get_recipe_details(&self, args: Value) -> ToolResult {
         let recipe_id = match args["recipe_id"].as_i64() {
             Some(id) => id,
-            None => return json!({"error": "Missing recipe_id"}).to_string(),
+            None => return ToolResult::failed(json!({"error": "Missing recipe_id"})),
         };
 
         let r = match recipe::Entity::find_by_id(recipe_id).one(&self.db).await {
             Ok(Some(r)) => r,
-            Ok(None) => return json!({"error": "Recipe not found"}).to_string(),
+            Ok(None) => return ToolResult::failed(json!({"error": "Recipe not found"})),
             Err(e) => {
                 tracing::error!("get_recipe_details DB error: {}", e);
-                return json!({"error": "Failed to get recipe"}).to_string();
+                return ToolResult::failed(json!({"error": "Failed to get recipe"}));
             }
         };
 
@@ -524,7 +1038,7 @@ impl ToolDispatch {
             })
         });
 
-        json!({
+        ToolResult::ok(json!({
             "id": r.id,
             "name": r.name,
             "cuisine": r.cuisine,
@@ -534,21 +1048,19 @@ impl ToolDispatch {
             "ingredients": ingredients,
             "nutrition": nutrition_json,
             "steps": steps_json,
-        })
-        .to_string()
+        }))
     }
 
-    async fn clear_meal_plan(&self, user_id: Uuid) -> String {
-        use chrono::{Datelike, Duration, Utc};
-        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-        use crate::entity::{meal_plan, meal_plan_slot};
+    async fn clear_meal_plan(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let week_start = args_week_start(&args);
 
-        let today = Utc::now().date_naive();
-        let days_since_monday = today.weekday().num_days_from_monday() as i64;
-        let week_start = today - Duration::days(days_since_monday);
+        let scope = match DataScope::for_user(&self.db, user_id).await {
+            Ok(scope) => scope,
+            Err(e) => return ToolResult::failed(json!({"error": e.to_string()})),
+        };
 
         let plan = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(scope.condition(meal_plan::Column::UserId, meal_plan::Column::HouseholdId))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await;
@@ -561,21 +1073,389 @@ impl ToolDispatch {
                     .await;
 
                 match res {
-                    Ok(r) => json!({
+                    Ok(r) => ToolResult::ok(json!({
                         "success": true,
                         "slots_removed": r.rows_affected
-                    })
-                    .to_string(),
-                    Err(e) => json!({"error": e.to_string()}).to_string(),
+                    })),
+                    Err(e) => ToolResult::failed(json!({"error": e.to_string()})),
                 }
             }
-            Ok(None) => json!({
+            Ok(None) => ToolResult::ok(json!({
                 "success": true,
                 "slots_removed": 0,
                 "note": "No meal plan exists for this week"
-            })
-            .to_string(),
-            Err(e) => json!({"error": e.to_string()}).to_string(),
+            })),
+            Err(e) => ToolResult::failed(json!({"error": e.to_string()})),
+        }
+    }
+
+    async fn generate_meal_plan(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let week_start = args_week_start(&args);
+
+        let household_size = match ProfileService::new(self.db.clone()).get_profile(user_id).await {
+            Ok(p) => p.household_size,
+            Err(e) => {
+                tracing::error!("generate_meal_plan tool error: {}", e);
+                return ToolResult::failed(json!({"status": "error", "message": "Failed to load the user's profile"}));
+            }
+        };
+
+        let svc = MealPlanService::new(self.db.clone());
+        match svc.generate_week_plan(user_id, household_size, week_start, None).await {
+            Ok(generated) => ToolResult::ok(json!({
+                "status": "success",
+                "message": format!("Generated a meal plan for the week of {}", week_start),
+                "plan_id": generated.plan.id,
+                "week_start": week_start.to_string(),
+                "recipes_excluded_for_diet": generated.excluded.len(),
+                "estimated_cost": generated.cost.total,
+                "weekly_budget": generated.cost.weekly_budget,
+                "over_budget": generated.cost.over_budget,
+            })),
+            Err(e) => {
+                tracing::error!("generate_meal_plan tool error: {}", e);
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
+            }
+        }
+    }
+
+    async fn get_shopping_list(&self, user_id: Uuid) -> ToolResult {
+        let svc = ShoppingListService::new(self.db.clone());
+        match svc.get_list(user_id).await {
+            Ok(items) if items.is_empty() => {
+                ToolResult::ok(json!({"status": "empty", "message": "Shopping list is empty."}))
+            }
+            Ok(items) => {
+                let result: Vec<Value> = items
+                    .iter()
+                    .map(|item| {
+                        json!({
+                            "id": item.id,
+                            "name": item.name,
+                            "quantity": item.quantity.map(|q| q.to_string()),
+                            "unit": item.unit,
+                            "is_checked": item.is_checked,
+                        })
+                    })
+                    .collect();
+                ToolResult::ok(json!(result))
+            }
+            Err(e) => {
+                tracing::error!("get_shopping_list tool error: {}", e);
+                ToolResult::failed(json!({"error": "Failed to get shopping list"}))
+            }
+        }
+    }
+
+    async fn add_to_shopping_list(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let name = match args["name"].as_str().map(str::trim) {
+            Some(n) if !n.is_empty() => n.to_string(),
+            _ => return ToolResult::failed(json!({"status": "error", "message": "Missing name"})),
+        };
+        let quantity = args["quantity"].as_f64().and_then(|q| Decimal::try_from(q).ok());
+        let unit = args["unit"].as_str().map(|s| s.to_string());
+
+        let svc = ShoppingListService::new(self.db.clone());
+        let req = AddItemRequest { ingredient_id: None, name: name.clone(), quantity, unit };
+        match svc.add_item(user_id, req).await {
+            Ok(item) => ToolResult::ok(json!({
+                "status": "success",
+                "message": format!("Added {} to the shopping list", name),
+                "id": item.id,
+            })),
+            Err(e) => {
+                tracing::error!("add_to_shopping_list tool error: {}", e);
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
+            }
+        }
+    }
+
+    async fn check_shopping_item(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let item_id = match args["item_id"].as_str().and_then(|s| Uuid::parse_str(s).ok()) {
+            Some(id) => id,
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing or invalid item_id"})),
+        };
+        let checked = args["checked"].as_bool().unwrap_or(true);
+
+        // The service only toggles, so look the item up first to make this idempotent
+        let svc = ShoppingListService::new(self.db.clone());
+        let current = match svc.get_list(user_id).await {
+            Ok(items) => items.into_iter().find(|i| i.id == item_id),
+            Err(e) => {
+                tracing::error!("check_shopping_item tool error: {}", e);
+                return ToolResult::failed(json!({"error": "Failed to get shopping list"}));
+            }
+        };
+        let Some(item) = current else {
+            return ToolResult::failed(json!({"status": "error", "message": "Item not found on your shopping list"}));
+        };
+        if item.is_checked != checked {
+            if let Err(e) = svc.toggle_check(user_id, item_id).await {
+                tracing::error!("check_shopping_item tool error: {}", e);
+                return ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}));
+            }
+        }
+
+        ToolResult::ok(json!({
+            "status": "success",
+            "message": format!("{} {}", item.name, if checked { "checked off" } else { "unchecked" }),
+        }))
+    }
+
+    async fn remove_from_shopping_list(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let item_id = match args["item_id"].as_str().and_then(|s| Uuid::parse_str(s).ok()) {
+            Some(id) => id,
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing or invalid item_id"})),
+        };
+
+        let svc = ShoppingListService::new(self.db.clone());
+        match svc.delete_item(user_id, item_id).await {
+            Ok(()) => ToolResult::ok(json!({"status": "success", "message": "Item removed from shopping list"})),
+            Err(AppError::NotFound(_)) => {
+                ToolResult::failed(json!({"status": "error", "message": "Item not found on your shopping list"}))
+            }
+            Err(e) => {
+                tracing::error!("remove_from_shopping_list tool error: {}", e);
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
+            }
+        }
+    }
+
+    async fn rate_recipe(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let recipe_id = match args["recipe_id"].as_i64() {
+            Some(r) => r,
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing recipe_id"})),
+        };
+        // Same bounds as RateRecipeRequest
+        let rating = match args["rating"].as_i64() {
+            Some(r) if (1..=5).contains(&r) => r as i16,
+            _ => return ToolResult::failed(json!({"status": "error", "message": "rating must be 1 to 5"})),
+        };
+        let comment = args["comment"]
+            .as_str()
+            .filter(|c| !c.trim().is_empty())
+            .map(|c| c.chars().take(1000).collect::<String>());
+
+        let svc = InteractionService::new(self.db.clone());
+        match svc.rate_recipe(user_id, recipe_id, rating, comment).await {
+            Ok(res) => ToolResult::ok(json!({"status": "success", "message": res.message})),
+            Err(AppError::NotFound(_)) => {
+                ToolResult::failed(json!({"status": "error", "message": "Recipe not found"}))
+            }
+            Err(e) => {
+                tracing::error!("rate_recipe tool error: {}", e);
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
+            }
+        }
+    }
+
+    async fn set_favourite(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let recipe_id = match args["recipe_id"].as_i64() {
+            Some(r) => r,
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing recipe_id"})),
+        };
+        let favourite = args["favourite"].as_bool().unwrap_or(true);
+
+        // toggle_favourite flips the current state; only call it when it differs
+        let existing = user_favorite::Entity::find()
+            .filter(user_favorite::Column::UserId.eq(user_id))
+            .filter(user_favorite::Column::RecipeId.eq(recipe_id))
+            .one(&self.db)
+            .await;
+        let is_favourite = match existing {
+            Ok(f) => f.is_some(),
+            Err(e) => {
+                tracing::error!("set_favourite DB error: {}", e);
+                return ToolResult::failed(json!({"error": "Failed to check favourites"}));
+            }
+        };
+        if is_favourite != favourite {
+            let svc = InteractionService::new(self.db.clone());
+            match svc.toggle_favourite(user_id, recipe_id).await {
+                Ok(_) => {}
+                Err(AppError::NotFound(_)) => {
+                    return ToolResult::failed(json!({"status": "error", "message": "Recipe not found"}));
+                }
+                Err(e) => {
+                    tracing::error!("set_favourite tool error: {}", e);
+                    return ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}));
+                }
+            }
+        }
+
+        ToolResult::ok(json!({
+            "status": "success",
+            "message": if favourite { "Recipe saved to favourites" } else { "Recipe removed from favourites" },
+            "is_favourited": favourite,
+        }))
+    }
+
+    async fn log_cooked(&self, user_id: Uuid, args: Value) -> ToolResult {
+        let recipe_id = match args["recipe_id"].as_i64() {
+            Some(r) => r,
+            None => return ToolResult::failed(json!({"status": "error", "message": "Missing recipe_id"})),
+        };
+        let household_size = match ProfileService::new(self.db.clone()).get_profile(user_id).await {
+            Ok(p) => p.household_size,
+            Err(e) => {
+                tracing::error!("log_cooked tool error: {}", e);
+                return ToolResult::failed(json!({"status": "error", "message": "Failed to load the user's profile"}));
+            }
+        };
+        let servings = match args["servings"].as_i64() {
+            Some(s) if (1..=100).contains(&s) => s as i32,
+            Some(_) => return ToolResult::failed(json!({"status": "error", "message": "servings must be 1 to 100"})),
+            None => household_size,
+        };
+
+        let svc = InteractionService::new(self.db.clone());
+        match svc.mark_cooked(user_id, recipe_id, servings, servings.min(household_size)).await {
+            Ok(res) => ToolResult::ok(json!({
+                "status": "success",
+                "message": res.message,
+                "servings": servings,
+                "not_deducted": res.unconverted,
+                "leftover": res.leftover,
+            })),
+            Err(AppError::NotFound(_)) => {
+                ToolResult::failed(json!({"status": "error", "message": "Recipe not found"}))
+            }
+            Err(e) => {
+                tracing::error!("log_cooked tool error: {}", e);
+                ToolResult::failed(json!({"status": "error", "message": format!("{}", e)}))
+            }
+        }
+    }
+}
+
+fn week_start_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Record in the arguments which week a plan tool acts on, worked out when
+/// the model calls it. A confirmation can arrive the next day — after the
+/// Monday rollover — and must still touch the week the user was shown.
+fn pin_week_start(name: &str, mut args: Value, today: NaiveDate) -> Value {
+    let this_week = week_start_of(today);
+    let week_start = match name {
+        "clear_meal_plan" => this_week,
+        "generate_meal_plan" if args["next_week"].as_bool() == Some(true) => this_week + Duration::days(7),
+        "generate_meal_plan" => this_week,
+        _ => return args,
+    };
+    if !args.is_object() {
+        args = json!({});
+    }
+    args["week_start"] = json!(week_start);
+    args
+}
+
+/// The week pinned by [`pin_week_start`]
+fn args_week_start(args: &Value) -> NaiveDate {
+    args["week_start"]
+        .as_str()
+        .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| week_start_of(Utc::now().date_naive()))
+}
+
+fn action_error(message: &'static str) -> AppError {
+    let mut errors = validator::ValidationErrors::new();
+    let mut e = validator::ValidationError::new("action");
+    e.message = Some(message.into());
+    errors.add("action", e);
+    AppError::Validation(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    #[test]
+    fn every_tool_is_labelled() {
+        let names: Vec<String> = tool_definitions()
+            .iter()
+            .map(|t| t["function"]["name"].as_str().unwrap().to_string())
+            .collect();
+        for name in &names {
+            assert_ne!(tool_label(name), tool_label("unknown"), "{} has no label", name);
+        }
+        let unique: std::collections::HashSet<&String> = names.iter().collect();
+        assert_eq!(unique.len(), names.len());
+    }
+
+    #[test]
+    fn confirmation_tools_exist() {
+        let names: Vec<Value> = tool_definitions().iter().map(|t| t["function"]["name"].clone()).collect();
+        for tool in CONFIRM_TOOLS {
+            assert!(names.contains(&json!(tool)), "{} is not a defined tool", tool);
         }
+        assert!(requires_confirmation("clear_meal_plan"));
+        assert!(!requires_confirmation("get_pantry"));
+    }
+
+    #[test]
+    fn plan_tools_pin_the_week_they_were_called_in() {
+        let sunday = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+
+        let clear = pin_week_start("clear_meal_plan", json!({}), sunday);
+        assert_eq!(clear["week_start"], "2026-10-12");
+        assert_eq!(args_week_start(&clear), NaiveDate::from_ymd_opt(2026, 10, 12).unwrap());
+
+        let next = pin_week_start("generate_meal_plan", json!({"next_week": true}), sunday);
+        assert_eq!(next["week_start"], "2026-10-19");
+        // The model can't aim at another week by passing one itself
+        let forged = pin_week_start("generate_meal_plan", json!({"week_start": "2020-01-06"}), sunday);
+        assert_eq!(forged["week_start"], "2026-10-12");
+
+        assert_eq!(pin_week_start("get_pantry", json!({}), sunday), json!({}));
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn confirming_after_the_week_rolls_over_clears_the_proposed_week() {
+        use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+        let db = test_db::connect().await;
+        let tools = ToolDispatch::new(db.clone());
+        let user = test_db::create_user(&db).await;
+        let session_id: i64 = db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO chat_sessions (user_id) VALUES ($1) RETURNING id",
+                [user.id.into()],
+            ))
+            .await
+            .unwrap()
+            .unwrap()
+            .try_get("", "id")
+            .unwrap();
+
+        let this_week = week_start_of(Utc::now().date_naive());
+        let last_week = this_week - Duration::days(7);
+        let proposed = test_db::create_plan(&db, user.id, last_week).await;
+        test_db::add_slot(&db, &proposed, 0, "dinner", None).await;
+        test_db::add_slot(&db, &proposed, 1, "dinner", None).await;
+        let current = test_db::create_plan(&db, user.id, this_week).await;
+        let kept = test_db::add_slot(&db, &current, 0, "dinner", None).await;
+
+        // Proposed last Sunday, confirmed now
+        let args = pin_week_start("clear_meal_plan", json!({}), last_week + Duration::days(6));
+        let pending = tools
+            .propose(user.id, session_id, "clear_meal_plan", args, "Remove 2 meals".into())
+            .await
+            .pending
+            .unwrap();
+        let outcome = tools.confirm(user.id, pending.id).await.unwrap();
+
+        assert!(outcome.success);
+        assert_eq!(outcome.result["slots_removed"], 2);
+        let remaining = meal_plan_slot::Entity::find()
+            .filter(meal_plan_slot::Column::MealPlanId.is_in([proposed.id, current.id]))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(remaining.iter().map(|s| s.id).collect::<Vec<_>>(), vec![kept.id]);
     }
 }
+
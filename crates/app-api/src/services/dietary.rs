@@ -0,0 +1,280 @@
+//! Dietary constraints — hard filters derived from a user's allergies and
+//! dietary restrictions.
+//!
+//! Two independent checks are applied to every candidate recipe:
+//!   1. Recipe-level flags (`is_vegan`, `is_gluten_free`, `is_nut_free`, …)
+//!   2. Ingredient-level allergen mapping from `ingredient_allergens`
+//!
+//! Allergies are strict: traces (`may_contain`) exclude a recipe too.
+//! Restrictions are lifestyle choices, so only `contains` excludes.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::entity::{recipe, user};
+use crate::models::meal_plan::ExclusionReason;
+
+/// Severity value stored in `ingredient_allergens.severity` for trace amounts
+const SEVERITY_MAY_CONTAIN: &str = "may_contain";
+
+/// An allergen attached to one of a recipe's ingredients
+#[derive(Debug, Clone)]
+pub struct RecipeAllergen {
+    pub ingredient_name: String,
+    pub allergen: String,
+    pub severity: String,
+}
+
+/// Why an allergen is being avoided — the user-entered term plus its strictness
+#[derive(Debug, Clone)]
+struct AvoidSource {
+    term: String,
+    include_traces: bool,
+}
+
+/// Hard constraints for one user, normalised once and checked per recipe.
+#[derive(Debug, Default)]
+pub struct DietaryConstraints {
+    /// Canonical allergen name → why it is avoided
+    avoided_allergens: BTreeMap<String, AvoidSource>,
+    /// Recipe flag name → user-entered term that requires it
+    required_flags: BTreeMap<&'static str, String>,
+}
+
+impl DietaryConstraints {
+    pub fn from_user(user: &user::Model) -> Self {
+        Self::new(
+            user.allergies.as_deref().unwrap_or_default(),
+            user.dietary_restrictions.as_deref().unwrap_or_default(),
+        )
+    }
+
+    pub fn new(allergies: &[String], restrictions: &[String]) -> Self {
+        let mut constraints = Self::default();
+
+        for term in allergies {
+            let term = normalise(term);
+            if term.is_empty() {
+                continue;
+            }
+            for allergen in allergens_for_allergy(&term) {
+                if let Some(flag) = flag_for_allergen(&allergen) {
+                    constraints.required_flags.entry(flag).or_insert_with(|| term.clone());
+                }
+                // An allergy always wins over a restriction for the same allergen
+                constraints.avoided_allergens.insert(
+                    allergen,
+                    AvoidSource { term: term.clone(), include_traces: true },
+                );
+            }
+        }
+
+        for term in restrictions {
+            let term = normalise(term);
+            let Some((flag, allergens)) = restriction_rule(&term) else {
+                tracing::debug!("Ignoring unknown dietary restriction '{}'", term);
+                continue;
+            };
+            constraints.required_flags.entry(flag).or_insert_with(|| term.clone());
+            for allergen in allergens {
+                constraints
+                    .avoided_allergens
+                    .entry(allergen.to_string())
+                    .or_insert_with(|| AvoidSource { term: term.clone(), include_traces: false });
+            }
+        }
+
+        constraints
+    }
+
+    /// True when the user has nothing to filter on
+    pub fn is_empty(&self) -> bool {
+        self.avoided_allergens.is_empty() && self.required_flags.is_empty()
+    }
+
+    /// Whether any ingredient-level allergen lookup is needed at all
+    pub fn needs_allergen_data(&self) -> bool {
+        !self.avoided_allergens.is_empty()
+    }
+
+    /// Every reason this recipe violates the constraints (empty = allowed)
+    pub fn check(
+        &self,
+        recipe: &recipe::Model,
+        allergens: &[RecipeAllergen],
+    ) -> Vec<ExclusionReason> {
+        let mut reasons = Vec::new();
+
+        for (flag, term) in &self.required_flags {
+            if !recipe_flag(recipe, flag) {
+                reasons.push(ExclusionReason::DietaryFlag {
+                    flag: flag.to_string(),
+                    source: term.clone(),
+                });
+            }
+        }
+
+        for a in allergens {
+            let Some(source) = self.avoided_allergens.get(&normalise(&a.allergen)) else {
+                continue;
+            };
+            if a.severity == SEVERITY_MAY_CONTAIN && !source.include_traces {
+                continue;
+            }
+            reasons.push(ExclusionReason::Allergen {
+                allergen: a.allergen.clone(),
+                ingredient: a.ingredient_name.clone(),
+                severity: a.severity.clone(),
+                source: source.term.clone(),
+            });
+        }
+
+        reasons
+    }
+}
+
+/// Group allergen rows by recipe so each recipe can be checked in O(its ingredients)
+pub fn allergens_by_recipe(
+    recipe_ingredients: &[(i64, i64)],
+    allergens_by_ingredient: &HashMap<i64, Vec<RecipeAllergen>>,
+) -> HashMap<i64, Vec<RecipeAllergen>> {
+    let mut out: HashMap<i64, Vec<RecipeAllergen>> = HashMap::new();
+    for (recipe_id, ingredient_id) in recipe_ingredients {
+        if let Some(list) = allergens_by_ingredient.get(ingredient_id) {
+            out.entry(*recipe_id).or_default().extend(list.iter().cloned());
+        }
+    }
+    out
+}
+
+/// Lowercase, trim and snake_case a user-entered term ("Tree nuts" → "tree_nuts")
+fn normalise(term: &str) -> String {
+    term.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+/// Expand a user-entered allergy into the canonical allergen names used by
+/// `ingredient_allergens`. Unknown terms are passed through unchanged so a
+/// custom allergen still matches if the table uses the same spelling.
+fn allergens_for_allergy(term: &str) -> Vec<String> {
+    let canonical: &[&str] = match term {
+        "nut" | "nuts" => &["peanut", "tree_nut"],
+        "peanut" | "peanuts" => &["peanut"],
+        "tree_nut" | "tree_nuts" => &["tree_nut"],
+        "gluten" | "wheat" | "coeliac" | "celiac" => &["gluten", "wheat"],
+        "dairy" | "milk" => &["dairy", "lactose"],
+        "lactose" => &["lactose", "dairy"],
+        "egg" | "eggs" => &["egg"],
+        "shellfish" => &["shellfish", "crustacean", "mollusk"],
+        "crustacean" | "crustaceans" => &["crustacean"],
+        "mollusk" | "mollusks" | "mollusc" | "molluscs" => &["mollusk"],
+        "fish" => &["fish"],
+        "soy" | "soya" => &["soy"],
+        "sesame" => &["sesame"],
+        "sulfite" | "sulfites" | "sulphite" | "sulphites" => &["sulfite"],
+        "mustard" => &["mustard"],
+        "celery" => &["celery"],
+        "lupin" | "lupine" => &["lupine"],
+        other => return vec![other.to_string()],
+    };
+    canonical.iter().map(|s| s.to_string()).collect()
+}
+
+/// Recipe flag that must be true for a recipe to be safe for this allergen
+fn flag_for_allergen(allergen: &str) -> Option<&'static str> {
+    match allergen {
+        "peanut" | "tree_nut" => Some("is_nut_free"),
+        "gluten" | "wheat" => Some("is_gluten_free"),
+        "dairy" | "lactose" => Some("is_dairy_free"),
+        _ => None,
+    }
+}
+
+/// Required recipe flag plus allergens implied by a dietary restriction
+fn restriction_rule(term: &str) -> Option<(&'static str, &'static [&'static str])> {
+    const ANIMAL: &[&str] = &["fish", "shellfish", "crustacean", "mollusk"];
+    const VEGAN: &[&str] = &["fish", "shellfish", "crustacean", "mollusk", "egg", "dairy", "lactose"];
+    match term {
+        "vegetarian" => Some(("is_vegetarian", ANIMAL)),
+        "vegan" => Some(("is_vegan", VEGAN)),
+        "gluten_free" => Some(("is_gluten_free", &["gluten", "wheat"])),
+        "dairy_free" | "lactose_free" => Some(("is_dairy_free", &["dairy", "lactose"])),
+        "nut_free" => Some(("is_nut_free", &["peanut", "tree_nut"])),
+        _ => None,
+    }
+}
+
+fn recipe_flag(recipe: &recipe::Model, flag: &str) -> bool {
+    match flag {
+        // Vegan recipes are vegetarian even if the importer only set one flag
+        "is_vegetarian" => recipe.is_vegetarian || recipe.is_vegan,
+        "is_vegan" => recipe.is_vegan,
+        "is_gluten_free" => recipe.is_gluten_free,
+        "is_dairy_free" => recipe.is_dairy_free,
+        "is_nut_free" => recipe.is_nut_free,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn recipe(vegan: bool, nut_free: bool) -> recipe::Model {
+        let now = Utc::now().fixed_offset();
+        recipe::Model {
+            id: 1,
+            name: "Satay".into(),
+            slug: "satay".into(),
+            description: None,
+            cuisine: None,
+            category: None,
+            difficulty: None,
+            servings: 2,
+            prep_time_min: None,
+            cook_time_min: None,
+            total_time_min: None,
+            is_vegetarian: vegan,
+            is_vegan: vegan,
+            is_gluten_free: true,
+            is_dairy_free: true,
+            is_nut_free: nut_free,
+            source_url: None,
+            average_rating: None,
+            rating_count: 0,
+            author_id: None,
+            is_public: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn allergen(name: &str, severity: &str) -> RecipeAllergen {
+        RecipeAllergen {
+            ingredient_name: "peanut butter".into(),
+            allergen: name.into(),
+            severity: severity.into(),
+        }
+    }
+
+    #[test]
+    fn nut_allergy_checks_flag_and_ingredients() {
+        let c = DietaryConstraints::new(&["Nuts".into()], &[]);
+        let reasons = c.check(&recipe(true, false), &[allergen("peanut", "contains")]);
+        assert_eq!(reasons.len(), 2);
+        assert!(c.check(&recipe(true, true), &[]).is_empty());
+    }
+
+    #[test]
+    fn restrictions_ignore_traces_but_allergies_do_not() {
+        let vegan = DietaryConstraints::new(&[], &["vegan".into()]);
+        assert!(vegan.check(&recipe(true, true), &[allergen("dairy", "may_contain")]).is_empty());
+
+        let allergic = DietaryConstraints::new(&["milk".into()], &["vegan".into()]);
+        assert_eq!(allergic.check(&recipe(true, true), &[allergen("dairy", "may_contain")]).len(), 1);
+    }
+
+    #[test]
+    fn unknown_restriction_is_ignored() {
+        assert!(DietaryConstraints::new(&[], &["keto".into()]).is_empty());
+    }
+}
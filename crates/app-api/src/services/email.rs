@@ -140,6 +140,43 @@ impl EmailService {
         }
     }
 
+    /// Invite someone to join a household (Family plan).
+    ///
+    /// `household_name` and `inviter_name` are user-supplied and are escaped
+    /// before being placed in the HTML.  The `token` expires after 7 days.
+    pub async fn send_household_invite_email(
+        &self,
+        to_email: String,
+        household_name: String,
+        inviter_name: String,
+        token: String,
+    ) -> Result<String, String> {
+        if self.api_key.is_empty() {
+            return Err("RESEND_API_KEY not configured".to_string());
+        }
+
+        // TODO: move to config — domain differs between staging and production
+        let invite_link = format!("https://m.cookest.app/household/join?token={}", token);
+
+        let request = ResendEmailRequest {
+            from: self.from_email.clone(),
+            to: to_email.clone(),
+            subject: format!("{} invited you to their Cookest household", inviter_name),
+            html: self.household_invite_email_html(&escape_html(&household_name), &escape_html(&inviter_name), &invite_link),
+        };
+
+        match self.send_email(request).await {
+            Ok(response_id) => {
+                info!("Household invite email sent to {}: {}", to_email, response_id);
+                Ok(response_id)
+            }
+            Err(e) => {
+                error!("Failed to send household invite email: {}", e);
+                Err(e)
+            }
+        }
+    }
+
     async fn send_email(&self, request: ResendEmailRequest) -> Result<String, String> {
         match self.client
             .post("https://api.resend.com/emails")
@@ -241,7 +278,7 @@ impl EmailService {
         <p>Or copy and paste this link in your browser:</p>
         <p style="word-break: break-all; color: #666; font-size: 14px;">{}</p>
         <div class="warning">
-          <p><strong>Note:</strong> This link will expire in 24 hours. If you didn't request a password reset, please ignore this email.</p>
+          <p><strong>Note:</strong> This link will expire in 1 hour. If you didn't request a password reset, please ignore this email.</p>
         </div>
       </div>
       <div class="footer">
@@ -306,5 +343,60 @@ impl EmailService {
             name
         )
     }
+
+    fn household_invite_email_html(&self, household_name: &str, inviter_name: &str, invite_link: &str) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8" />
+    <style>
+      body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; }}
+      .container {{ max-width: 600px; margin: 0 auto; padding: 20px; }}
+      .header {{ text-align: center; margin-bottom: 30px; }}
+      .content {{ background-color: #f9fafb; padding: 20px; border-radius: 8px; }}
+      .button {{ 
+        background-color: #10b981; 
+        color: white; 
+        padding: 12px 30px; 
+        text-decoration: none; 
+        border-radius: 6px; 
+        display: inline-block; 
+        margin: 20px 0;
+      }}
+      .footer {{ text-align: center; color: #666; font-size: 12px; margin-top: 20px; }}
+    </style>
+  </head>
+  <body>
+    <div class="container">
+      <div class="header">
+        <h1>Join {}</h1>
+      </div>
+      <div class="content">
+        <p>{} invited you to share their Cookest household. Members share one pantry, one weekly meal plan and one shopping list.</p>
+        <p>
+          <a href="{}" class="button">Accept Invitation</a>
+        </p>
+        <p>Or copy and paste this link in your browser:</p>
+        <p style="word-break: break-all; color: #666; font-size: 14px;">{}</p>
+        <p>This invitation expires in 7 days. Sign in with this email address to accept it.</p>
+      </div>
+      <div class="footer">
+        <p>© 2026 Cookest. All rights reserved.</p>
+      </div>
+    </div>
+  </body>
+</html>"#,
+            household_name, inviter_name, invite_link, invite_link
+        )
+    }
 }
 
+/// Minimal escaping for user-supplied text placed in email HTML
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,669 @@
+//! Household service — Family-tier groups sharing pantry, meal plan and shopping list
+//!
+//! - A user belongs to at most one household; the creator is its owner
+//! - Owners and admins invite by email; the invite token expires after 7 days
+//!   and can only be accepted by the account with that email address
+//! - While a user is in a household, inventory, meal plans and the shopping
+//!   list resolve to the household's rows (see [`DataScope`]). Joining moves
+//!   the user's personal rows into the household; leaving leaves them there.
+//!   Deleting the household hands every row back to the member who added it;
+//!   a returned plan replaces that member's hidden personal plan for the week.
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbBackend, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, Statement,
+    TransactionTrait,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::entity::{household, household_invite, household_member, user};
+use crate::services::auth::{generate_email_token, hash_token_sha256};
+use crate::services::notification::{self, Notification, NotificationKind};
+use crate::services::subscription::FEATURE_HOUSEHOLDS;
+use crate::services::token::SubscriptionTier;
+use cookest_shared::errors::AppError;
+
+/// Members plus pending invites may not exceed this
+pub const MAX_MEMBERS: u64 = 6;
+/// How long an emailed invite stays valid
+const INVITE_LIFETIME_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HouseholdRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl HouseholdRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HouseholdRole::Owner => "owner",
+            HouseholdRole::Admin => "admin",
+            HouseholdRole::Member => "member",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "owner" => Some(HouseholdRole::Owner),
+            "admin" => Some(HouseholdRole::Admin),
+            "member" => Some(HouseholdRole::Member),
+            _ => None,
+        }
+    }
+
+    /// Owners and admins may invite and remove members
+    pub fn can_manage_members(&self) -> bool {
+        matches!(self, HouseholdRole::Owner | HouseholdRole::Admin)
+    }
+}
+
+/// Whose inventory / meal plans / shopping list a request operates on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataScope {
+    /// Not in a household: the user's own rows (`household_id IS NULL`)
+    Personal(Uuid),
+    /// Every row shared with the household, whoever added it
+    Household(Uuid),
+}
+
+impl DataScope {
+    /// Resolve the scope for a user from their household membership
+    pub async fn for_user<C: ConnectionTrait>(db: &C, user_id: Uuid) -> Result<Self, AppError> {
+        let membership = household_member::Entity::find()
+            .filter(household_member::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+
+        Ok(match membership {
+            Some(m) => DataScope::Household(m.household_id),
+            None => DataScope::Personal(user_id),
+        })
+    }
+
+    /// Value for the `household_id` column of rows created in this scope
+    pub fn household_id(&self) -> Option<Uuid> {
+        match self {
+            DataScope::Personal(_) => None,
+            DataScope::Household(id) => Some(*id),
+        }
+    }
+
+    /// Filter for a table with `user_id` / `household_id` columns
+    pub fn condition<C: ColumnTrait>(&self, user_col: C, household_col: C) -> Condition {
+        match self {
+            DataScope::Personal(user_id) => Condition::all()
+                .add(user_col.eq(*user_id))
+                .add(household_col.is_null()),
+            DataScope::Household(household_id) => Condition::all().add(household_col.eq(*household_id)),
+        }
+    }
+
+    /// Whether a row with these owner columns is visible in this scope
+    pub fn contains(&self, user_id: Uuid, household_id: Option<Uuid>) -> bool {
+        match self {
+            DataScope::Personal(me) => household_id.is_none() && user_id == *me,
+            DataScope::Household(id) => household_id == Some(*id),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HouseholdMemberResponse {
+    pub user_id: Uuid,
+    pub name: Option<String>,
+    pub email: String,
+    pub role: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HouseholdResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub owner_id: Uuid,
+    /// The caller's role in this household
+    pub role: String,
+    pub members: Vec<HouseholdMemberResponse>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HouseholdInviteResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A freshly created invite plus what's needed to email it
+pub struct CreatedInvite {
+    pub invite: HouseholdInviteResponse,
+    /// Raw token — only ever sent by email
+    pub token: String,
+    pub household_name: String,
+    pub inviter_name: String,
+}
+
+pub struct HouseholdService {
+    db: DatabaseConnection,
+}
+
+impl HouseholdService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Create a household owned by the caller. Requires the Family tier.
+    ///
+    /// The caller's personal pantry, plans and shopping list become the
+    /// household's.
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        tier: Option<&SubscriptionTier>,
+        name: &str,
+    ) -> Result<HouseholdResponse, AppError> {
+        if tier != Some(&SubscriptionTier::Family) {
+            return Err(AppError::SubscriptionRequired {
+                feature: FEATURE_HOUSEHOLDS.to_string(),
+            });
+        }
+        if self.membership(user_id).await?.is_some() {
+            return Err(household_error("You are already in a household"));
+        }
+
+        let now = Utc::now().fixed_offset();
+        let household_id = Uuid::new_v4();
+        let txn = self.db.begin().await?;
+
+        household::ActiveModel {
+            id: Set(household_id),
+            name: Set(name.trim().to_string()),
+            owner_id: Set(user_id),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+
+        household_member::ActiveModel {
+            household_id: Set(household_id),
+            user_id: Set(user_id),
+            role: Set(HouseholdRole::Owner.as_str().to_string()),
+            joined_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+
+        share_personal_rows(&txn, user_id, household_id).await?;
+        txn.commit().await?;
+
+        tracing::info!("Household {} created by {}", household_id, user_id);
+        self.get(user_id).await
+    }
+
+    /// The caller's household with its members
+    pub async fn get(&self, user_id: Uuid) -> Result<HouseholdResponse, AppError> {
+        let me = self.require_membership(user_id).await?;
+
+        let household = household::Entity::find_by_id(me.household_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Household".to_string()))?;
+
+        let members = household_member::Entity::find()
+            .filter(household_member::Column::HouseholdId.eq(household.id))
+            .order_by_asc(household_member::Column::JoinedAt)
+            .find_also_related(user::Entity)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .filter_map(|(m, u)| {
+                let u = u?;
+                Some(HouseholdMemberResponse {
+                    user_id: m.user_id,
+                    name: u.name,
+                    email: u.email,
+                    role: m.role,
+                    joined_at: m.joined_at.with_timezone(&Utc),
+                })
+            })
+            .collect();
+
+        Ok(HouseholdResponse {
+            id: household.id,
+            name: household.name,
+            owner_id: household.owner_id,
+            role: me.role,
+            members,
+            created_at: household.created_at.with_timezone(&Utc),
+        })
+    }
+
+    /// Delete the household (owner only). Shared rows go back to whoever added them.
+    pub async fn delete(&self, user_id: Uuid) -> Result<(), AppError> {
+        let me = self.require_membership(user_id).await?;
+        if role_of(&me) != HouseholdRole::Owner {
+            return Err(AppError::Forbidden);
+        }
+
+        // Shared rows fall back to whoever added them (ON DELETE SET NULL).
+        // A member may still hold a personal plan for a week the household
+        // also planned — hidden since they joined — and handing the shared
+        // one back would break the one-plan-per-week index, so it goes first.
+        let txn = self.db.begin().await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"DELETE FROM meal_plans personal
+               WHERE personal.household_id IS NULL
+                 AND EXISTS (
+                     SELECT 1 FROM meal_plans shared
+                     WHERE shared.household_id = $1
+                       AND shared.user_id = personal.user_id
+                       AND shared.week_start = personal.week_start
+                 )"#,
+            [me.household_id.into()],
+        ))
+        .await?;
+        household::Entity::delete_by_id(me.household_id)
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        tracing::info!("Household {} deleted by {}", me.household_id, user_id);
+        Ok(())
+    }
+
+    /// Invite an email address to the caller's household (owner/admin).
+    /// A pending invite for the same address is replaced.
+    pub async fn invite(
+        &self,
+        user_id: Uuid,
+        email: &str,
+        role: HouseholdRole,
+    ) -> Result<CreatedInvite, AppError> {
+        let me = self.require_membership(user_id).await?;
+        if !role_of(&me).can_manage_members() {
+            return Err(AppError::Forbidden);
+        }
+        if role == HouseholdRole::Owner {
+            return Err(household_error("Invites can grant the admin or member role"));
+        }
+
+        let email = email.trim().to_lowercase();
+        let now = Utc::now();
+
+        let invitee = user::Entity::find()
+            .filter(user::Column::Email.eq(&email))
+            .one(&self.db)
+            .await?;
+        if let Some(invitee) = invitee {
+            if self.membership(invitee.id).await?.is_some_and(|m| m.household_id == me.household_id) {
+                return Err(household_error("That person is already a member"));
+            }
+        }
+
+        household_invite::Entity::delete_many()
+            .filter(household_invite::Column::HouseholdId.eq(me.household_id))
+            .filter(household_invite::Column::Email.eq(&email))
+            .filter(household_invite::Column::AcceptedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        if self.seats_taken(me.household_id).await? >= MAX_MEMBERS {
+            return Err(household_error("This household is full"));
+        }
+
+        let household = household::Entity::find_by_id(me.household_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Household".to_string()))?;
+        let inviter = user::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+
+        let token = generate_email_token();
+        let invite = household_invite::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            household_id: Set(me.household_id),
+            email: Set(email),
+            role: Set(role.as_str().to_string()),
+            token_hash: Set(hash_token_sha256(&token)),
+            invited_by: Set(Some(user_id)),
+            expires_at: Set((now + Duration::days(INVITE_LIFETIME_DAYS)).fixed_offset()),
+            accepted_at: Set(None),
+            created_at: Set(now.fixed_offset()),
+        }
+        .insert(&self.db)
+        .await?;
+
+        Ok(CreatedInvite {
+            invite: invite_response(invite),
+            token,
+            household_name: household.name,
+            inviter_name: inviter.name.unwrap_or(inviter.email),
+        })
+    }
+
+    /// Pending, unexpired invites of the caller's household
+    pub async fn list_invites(&self, user_id: Uuid) -> Result<Vec<HouseholdInviteResponse>, AppError> {
+        let me = self.require_membership(user_id).await?;
+
+        let invites = household_invite::Entity::find()
+            .filter(household_invite::Column::HouseholdId.eq(me.household_id))
+            .filter(household_invite::Column::AcceptedAt.is_null())
+            .filter(household_invite::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+            .order_by_desc(household_invite::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+
+        Ok(invites.into_iter().map(invite_response).collect())
+    }
+
+    /// Withdraw a pending invite (owner/admin)
+    pub async fn revoke_invite(&self, user_id: Uuid, invite_id: Uuid) -> Result<(), AppError> {
+        let me = self.require_membership(user_id).await?;
+        if !role_of(&me).can_manage_members() {
+            return Err(AppError::Forbidden);
+        }
+
+        let deleted = household_invite::Entity::delete_many()
+            .filter(household_invite::Column::Id.eq(invite_id))
+            .filter(household_invite::Column::HouseholdId.eq(me.household_id))
+            .filter(household_invite::Column::AcceptedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        if deleted.rows_affected == 0 {
+            return Err(AppError::NotFound("Invite".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Accept an emailed invite. The caller's account email must match the
+    /// invited address and they must not already be in a household.
+    pub async fn accept_invite(&self, user_id: Uuid, token: &str) -> Result<HouseholdResponse, AppError> {
+        let now = Utc::now().fixed_offset();
+
+        let invite = household_invite::Entity::find()
+            .filter(household_invite::Column::TokenHash.eq(hash_token_sha256(token)))
+            .filter(household_invite::Column::AcceptedAt.is_null())
+            .filter(household_invite::Column::ExpiresAt.gt(now))
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        let me = user::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+        if !me.email.eq_ignore_ascii_case(&invite.email) {
+            return Err(AppError::Forbidden);
+        }
+        if self.membership(user_id).await?.is_some() {
+            return Err(household_error("Leave your current household before joining another"));
+        }
+
+        let role = HouseholdRole::parse(&invite.role).unwrap_or(HouseholdRole::Member);
+        let txn = self.db.begin().await?;
+
+        // Conditional update: a token can only be redeemed once
+        let claimed = household_invite::Entity::update_many()
+            .col_expr(household_invite::Column::AcceptedAt, Expr::value(now))
+            .filter(household_invite::Column::Id.eq(invite.id))
+            .filter(household_invite::Column::AcceptedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if claimed.rows_affected == 0 {
+            return Err(AppError::InvalidToken);
+        }
+
+        household_member::ActiveModel {
+            household_id: Set(invite.household_id),
+            user_id: Set(user_id),
+            role: Set(role.as_str().to_string()),
+            joined_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+
+        share_personal_rows(&txn, user_id, invite.household_id).await?;
+
+        let others = household_member::Entity::find()
+            .filter(household_member::Column::HouseholdId.eq(invite.household_id))
+            .filter(household_member::Column::UserId.ne(user_id))
+            .all(&txn)
+            .await?;
+        let who = me.name.clone().unwrap_or_else(|| me.email.clone());
+        for member in others {
+            notification::enqueue(
+                &txn,
+                member.user_id,
+                Notification::new(NotificationKind::Household, "New household member", format!("{} joined your household", who))
+                    .with_data(serde_json::json!({ "household_id": invite.household_id })),
+            )
+            .await?;
+        }
+        txn.commit().await?;
+
+        tracing::info!("User {} joined household {}", user_id, invite.household_id);
+        self.get(user_id).await
+    }
+
+    /// Change a member's role (owner only). Ownership itself is not transferable here.
+    pub async fn set_member_role(
+        &self,
+        user_id: Uuid,
+        member_id: Uuid,
+        role: HouseholdRole,
+    ) -> Result<HouseholdResponse, AppError> {
+        let me = self.require_membership(user_id).await?;
+        if role_of(&me) != HouseholdRole::Owner {
+            return Err(AppError::Forbidden);
+        }
+        if role == HouseholdRole::Owner || member_id == user_id {
+            return Err(household_error("The owner's role cannot be changed"));
+        }
+
+        let updated = household_member::Entity::update_many()
+            .col_expr(household_member::Column::Role, Expr::value(role.as_str()))
+            .filter(household_member::Column::HouseholdId.eq(me.household_id))
+            .filter(household_member::Column::UserId.eq(member_id))
+            .exec(&self.db)
+            .await?;
+        if updated.rows_affected == 0 {
+            return Err(AppError::NotFound("Household member".to_string()));
+        }
+
+        self.get(user_id).await
+    }
+
+    /// Remove a member, or leave when `member_id` is the caller.
+    ///
+    /// Admins may remove plain members; the owner may remove anyone but
+    /// cannot leave (delete the household instead). Rows the member added
+    /// stay with the household.
+    pub async fn remove_member(&self, user_id: Uuid, member_id: Uuid) -> Result<(), AppError> {
+        let me = self.require_membership(user_id).await?;
+        let my_role = role_of(&me);
+
+        if member_id == user_id {
+            if my_role == HouseholdRole::Owner {
+                return Err(household_error("The owner cannot leave; delete the household instead"));
+            }
+        } else {
+            let target = household_member::Entity::find()
+                .filter(household_member::Column::HouseholdId.eq(me.household_id))
+                .filter(household_member::Column::UserId.eq(member_id))
+                .one(&self.db)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Household member".to_string()))?;
+
+            let allowed = match my_role {
+                HouseholdRole::Owner => true,
+                HouseholdRole::Admin => role_of(&target) == HouseholdRole::Member,
+                HouseholdRole::Member => false,
+            };
+            if !allowed {
+                return Err(AppError::Forbidden);
+            }
+        }
+
+        household_member::Entity::delete_many()
+            .filter(household_member::Column::HouseholdId.eq(me.household_id))
+            .filter(household_member::Column::UserId.eq(member_id))
+            .exec(&self.db)
+            .await?;
+
+        tracing::info!("User {} left household {}", member_id, me.household_id);
+        Ok(())
+    }
+
+    // ── Internal helpers ──────────────────────────────────────────────────────
+
+    async fn membership(&self, user_id: Uuid) -> Result<Option<household_member::Model>, AppError> {
+        Ok(household_member::Entity::find()
+            .filter(household_member::Column::UserId.eq(user_id))
+            .one(&self.db)
+            .await?)
+    }
+
+    async fn require_membership(&self, user_id: Uuid) -> Result<household_member::Model, AppError> {
+        self.membership(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Household".to_string()))
+    }
+
+    /// Members plus pending, unexpired invites
+    async fn seats_taken(&self, household_id: Uuid) -> Result<u64, AppError> {
+        let members = household_member::Entity::find()
+            .filter(household_member::Column::HouseholdId.eq(household_id))
+            .count(&self.db)
+            .await?;
+        let pending = household_invite::Entity::find()
+            .filter(household_invite::Column::HouseholdId.eq(household_id))
+            .filter(household_invite::Column::AcceptedAt.is_null())
+            .filter(household_invite::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+            .count(&self.db)
+            .await?;
+        Ok(members + pending)
+    }
+}
+
+/// Move a user's personal rows into a household.
+///
+/// Personal plans for weeks the household already planned stay personal —
+/// there is one shared plan per week.
+async fn share_personal_rows<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+    household_id: Uuid,
+) -> Result<(), AppError> {
+    for sql in [
+        "UPDATE inventory_items SET household_id = $1 WHERE user_id = $2 AND household_id IS NULL",
+        "UPDATE shopping_list_items SET household_id = $1 WHERE user_id = $2 AND household_id IS NULL",
+        r#"UPDATE meal_plans mp SET household_id = $1
+           WHERE mp.user_id = $2 AND mp.household_id IS NULL
+             AND NOT EXISTS (
+                 SELECT 1 FROM meal_plans shared
+                 WHERE shared.household_id = $1 AND shared.week_start = mp.week_start
+             )"#,
+    ] {
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [household_id.into(), user_id.into()],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+fn role_of(member: &household_member::Model) -> HouseholdRole {
+    HouseholdRole::parse(&member.role).unwrap_or(HouseholdRole::Member)
+}
+
+fn invite_response(invite: household_invite::Model) -> HouseholdInviteResponse {
+    HouseholdInviteResponse {
+        id: invite.id,
+        email: invite.email,
+        role: invite.role,
+        expires_at: invite.expires_at.with_timezone(&Utc),
+        created_at: invite.created_at.with_timezone(&Utc),
+    }
+}
+
+fn household_error(message: &'static str) -> AppError {
+    let mut errors = validator::ValidationErrors::new();
+    let mut e = validator::ValidationError::new("household");
+    e.message = Some(message.into());
+    errors.add("household", e);
+    AppError::Validation(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    #[test]
+    fn scope_membership_checks() {
+        let me = Uuid::new_v4();
+        let partner = Uuid::new_v4();
+        let hh = Uuid::new_v4();
+
+        let personal = DataScope::Personal(me);
+        assert!(personal.contains(me, None));
+        assert!(!personal.contains(me, Some(hh)), "shared rows are not personal");
+        assert!(!personal.contains(partner, None));
+        assert_eq!(personal.household_id(), None);
+
+        let shared = DataScope::Household(hh);
+        assert!(shared.contains(partner, Some(hh)), "a partner's purchases are visible");
+        assert!(!shared.contains(me, None), "personal rows are hidden while in a household");
+        assert!(!shared.contains(me, Some(Uuid::new_v4())));
+        assert_eq!(shared.household_id(), Some(hh));
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn deleting_returns_plans_over_hidden_personal_ones() {
+        use crate::entity::meal_plan;
+
+        let db = test_db::connect().await;
+        let service = HouseholdService::new(db.clone());
+        let owner = test_db::create_user(&db).await;
+        let week = chrono::NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let plan = |household_id: Option<Uuid>| meal_plan::ActiveModel {
+            user_id: Set(owner.id),
+            household_id: Set(household_id),
+            week_start: Set(week),
+            is_ai_generated: Set(false),
+            created_at: Set(Utc::now().fixed_offset()),
+            updated_at: Set(Utc::now().fixed_offset()),
+            ..Default::default()
+        };
+
+        let household = service.create(owner.id, Some(&SubscriptionTier::Family), "Home").await.unwrap();
+        let shared = plan(Some(household.id)).insert(&db).await.unwrap();
+        // Planned alone before the household had this week, then kept personal
+        plan(None).insert(&db).await.unwrap();
+
+        service.delete(owner.id).await.unwrap();
+
+        let plans = meal_plan::Entity::find()
+            .filter(meal_plan::Column::UserId.eq(owner.id))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(plans.len(), 1);
+        assert_eq!((plans[0].id, plans[0].household_id), (shared.id, None));
+    }
+}
+
@@ -126,20 +126,23 @@ impl InteractionService {
         }
     }
 
-    /// Log that a user cooked a recipe — deducts inventory + triggers ML update
+    /// Log that a user cooked a recipe — deducts inventory + triggers ML update.
+    /// Servings made beyond `servings_eaten` are stored as leftovers.
     pub async fn mark_cooked(
         &self,
         user_id: Uuid,
         recipe_id: i64,
         servings_made: i32,
-    ) -> Result<InteractionResponse, AppError> {
+        servings_eaten: i32,
+    ) -> Result<CookedResponse, AppError> {
         let recipe = recipe::Entity::find_by_id(recipe_id)
             .one(&self.db)
             .await?
             .ok_or(AppError::NotFound("Recipe".into()))?;
 
-        // Deduct ingredients from inventory
-        self.inventory_service
+        // Deduct ingredients from inventory (unit-converted; unconvertible items are reported)
+        let unconverted = self
+            .inventory_service
             .deduct_for_recipe(user_id, recipe_id, servings_made, recipe.servings)
             .await?;
 
@@ -155,14 +158,30 @@ impl InteractionService {
         };
         history.insert(&self.db).await?;
 
+        let leftover = if servings_made > servings_eaten {
+            Some(InventoryService::store_leftovers(&self.db, user_id, &recipe, servings_made - servings_eaten).await?)
+        } else {
+            None
+        };
+
         // Trigger ML update
         self.preference_service
             .record_interaction(user_id, recipe_id, PreferenceSignal::Cooked)
             .await?;
 
-        Ok(InteractionResponse {
-            message: "Recipe marked as cooked. Inventory updated.".to_string(),
-        })
+        let mut message = if unconverted.is_empty() {
+            "Recipe marked as cooked. Inventory updated.".to_string()
+        } else {
+            format!(
+                "Recipe marked as cooked. {} item(s) could not be unit-converted and were left unchanged.",
+                unconverted.len()
+            )
+        };
+        if let Some(l) = &leftover {
+            message.push_str(&format!(" {} leftover serving(s) stored until {}.", l.servings, l.expiry_date));
+        }
+
+        Ok(CookedResponse { message, unconverted, leftover })
     }
 
     /// Get user's cooking history
@@ -1,18 +1,29 @@
 //! Inventory Service — CRUD for user food stock with expiry tracking
+//!
+//! Household members share one pantry: every query is scoped by
+//! [`DataScope`], so items added by any member are visible to all of them.
+//!
+//! Leftovers — cooked servings kept for later — are tracked here too, per
+//! recipe rather than per ingredient.
 
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
-    QueryOrder, PaginatorTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    QueryOrder, QuerySelect, PaginatorTrait,
 };
 use uuid::Uuid;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 
-use crate::entity::{inventory_item, ingredient, recipe_ingredient, recipe};
+use crate::entity::{inventory_item, ingredient, leftover, recipe_ingredient, recipe};
 use cookest_shared::errors::AppError;
 use crate::models::inventory::*;
+use crate::services::household::DataScope;
 use crate::services::scan::BulkAddItem;
+use crate::services::units::{QuantitySource, UnconvertedItem, UnitConverter};
+
+/// Cooked food keeps this many days in the fridge
+pub const LEFTOVER_FRIDGE_DAYS: i64 = 3;
 
 pub struct InventoryService {
     db: DatabaseConnection,
@@ -23,10 +34,11 @@ impl InventoryService {
         Self { db }
     }
 
-    /// List all inventory items for a user with expiry metadata
+    /// List all inventory items for a user (or their household) with expiry metadata
     pub async fn list(&self, user_id: Uuid) -> Result<Vec<InventoryItemResponse>, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let items = inventory_item::Entity::find()
-            .filter(inventory_item::Column::UserId.eq(user_id))
+            .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
             .all(&self.db)
             .await?;
 
@@ -41,30 +53,17 @@ impl InventoryService {
                 .map(|ing| (ing.id, ing.name))
                 .collect();
 
-        let today = Utc::now().date_naive();
+        let ids: Vec<i64> = ingredients.keys().copied().collect();
+        let converter = UnitConverter::load(&self.db, &ids).await?;
 
         let responses = items
             .into_iter()
             .map(|item| {
-                let days_until_expiry = item.expiry_date.map(|d| (d - today).num_days());
-                let expiry_warning = days_until_expiry.map(|d| d <= 5).unwrap_or(false);
                 let ingredient_name = ingredients
                     .get(&item.ingredient_id)
                     .cloned()
                     .unwrap_or_default();
-
-                InventoryItemResponse {
-                    id: item.id,
-                    ingredient_id: item.ingredient_id,
-                    ingredient_name,
-                    custom_name: item.custom_name,
-                    quantity: item.quantity,
-                    unit: item.unit,
-                    expiry_date: item.expiry_date,
-                    storage_location: item.storage_location,
-                    days_until_expiry,
-                    expiry_warning,
-                }
+                Self::to_response(item, ingredient_name, &converter)
             })
             .collect();
 
@@ -83,11 +82,12 @@ impl InventoryService {
             .await?
             .ok_or(AppError::NotFound("Ingredient".into()))?;
 
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let now = Utc::now().fixed_offset();
-        let today = Utc::now().date_naive();
 
         let new_item = inventory_item::ActiveModel {
             user_id: Set(user_id),
+            household_id: Set(scope.household_id()),
             ingredient_id: Set(req.ingredient_id),
             custom_name: Set(req.custom_name.clone()),
             quantity: Set(req.quantity),
@@ -101,21 +101,8 @@ impl InventoryService {
 
         let saved = new_item.insert(&self.db).await?;
 
-        let days_until_expiry = saved.expiry_date.map(|d| (d - today).num_days());
-        let expiry_warning = days_until_expiry.map(|d| d <= 5).unwrap_or(false);
-
-        Ok(InventoryItemResponse {
-            id: saved.id,
-            ingredient_id: saved.ingredient_id,
-            ingredient_name: ing.name,
-            custom_name: saved.custom_name,
-            quantity: saved.quantity,
-            unit: saved.unit,
-            expiry_date: saved.expiry_date,
-            storage_location: saved.storage_location,
-            days_until_expiry,
-            expiry_warning,
-        })
+        let converter = UnitConverter::load(&self.db, &[saved.ingredient_id]).await?;
+        Ok(Self::to_response(saved, ing.name, &converter))
     }
 
     /// Update an existing inventory item (quantity, expiry, etc.)
@@ -130,8 +117,9 @@ impl InventoryService {
             .await?
             .ok_or(AppError::NotFound("Inventory item".into()))?;
 
-        // Only allow user to update their own items
-        if item.user_id != user_id {
+        // Only allow user to update their own (or their household's) items
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        if !scope.contains(item.user_id, item.household_id) {
             return Err(AppError::AuthenticationFailed);
         }
 
@@ -141,7 +129,6 @@ impl InventoryService {
             .ok_or(AppError::NotFound("Ingredient".into()))?;
 
         let now = Utc::now().fixed_offset();
-        let today = Utc::now().date_naive();
 
         let mut active: inventory_item::ActiveModel = item.into();
         if let Some(q) = req.quantity {
@@ -160,21 +147,8 @@ impl InventoryService {
 
         let saved = active.update(&self.db).await?;
 
-        let days_until_expiry = saved.expiry_date.map(|d| (d - today).num_days());
-        let expiry_warning = days_until_expiry.map(|d| d <= 5).unwrap_or(false);
-
-        Ok(InventoryItemResponse {
-            id: saved.id,
-            ingredient_id: saved.ingredient_id,
-            ingredient_name: ing.name,
-            custom_name: saved.custom_name,
-            quantity: saved.quantity,
-            unit: saved.unit,
-            expiry_date: saved.expiry_date,
-            storage_location: saved.storage_location,
-            days_until_expiry,
-            expiry_warning,
-        })
+        let converter = UnitConverter::load(&self.db, &[saved.ingredient_id]).await?;
+        Ok(Self::to_response(saved, ing.name, &converter))
     }
 
     /// Remove an item from inventory
@@ -184,7 +158,8 @@ impl InventoryService {
             .await?
             .ok_or(AppError::NotFound("Inventory item".into()))?;
 
-        if item.user_id != user_id {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        if !scope.contains(item.user_id, item.household_id) {
             return Err(AppError::AuthenticationFailed);
         }
 
@@ -212,54 +187,217 @@ impl InventoryService {
             .collect())
     }
 
+    /// Leftovers for a user (or their household), soonest to expire first
+    pub async fn list_leftovers(&self, user_id: Uuid) -> Result<Vec<LeftoverResponse>, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let items = leftover::Entity::find()
+            .filter(scope.condition(leftover::Column::UserId, leftover::Column::HouseholdId))
+            .order_by_asc(leftover::Column::ExpiryDate)
+            .all(&self.db)
+            .await?;
+
+        let names: std::collections::HashMap<i64, String> = recipe::Entity::find()
+            .filter(recipe::Column::Id.is_in(items.iter().map(|l| l.recipe_id).collect::<Vec<_>>()))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|r| (r.id, r.name))
+            .collect();
+
+        Ok(items
+            .into_iter()
+            .map(|l| {
+                let name = names.get(&l.recipe_id).cloned().unwrap_or_default();
+                Self::leftover_response(l, name)
+            })
+            .collect())
+    }
+
+    /// Store servings cooked beyond what was eaten; they keep
+    /// [`LEFTOVER_FRIDGE_DAYS`] in the fridge
+    pub async fn store_leftovers<C: ConnectionTrait>(
+        db: &C,
+        user_id: Uuid,
+        recipe: &recipe::Model,
+        servings: i32,
+    ) -> Result<LeftoverResponse, AppError> {
+        let scope = DataScope::for_user(db, user_id).await?;
+        let now = Utc::now().fixed_offset();
+
+        let saved = leftover::ActiveModel {
+            user_id: Set(user_id),
+            household_id: Set(scope.household_id()),
+            recipe_id: Set(recipe.id),
+            servings: Set(servings),
+            storage_location: Set("fridge".into()),
+            expiry_date: Set(now.date_naive() + chrono::Duration::days(LEFTOVER_FRIDGE_DAYS)),
+            cooked_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(Self::leftover_response(saved, recipe.name.clone()))
+    }
+
+    /// Eat servings from one leftover; it is removed once nothing is left.
+    /// Returns what remains.
+    pub async fn eat_leftovers(
+        &self,
+        user_id: Uuid,
+        leftover_id: i64,
+        servings: i32,
+    ) -> Result<Option<LeftoverResponse>, AppError> {
+        let item = self.find_leftover(user_id, leftover_id).await?;
+        let name = recipe::Entity::find_by_id(item.recipe_id)
+            .one(&self.db)
+            .await?
+            .map(|r| r.name)
+            .unwrap_or_default();
+
+        Ok(Self::take_servings(&self.db, item, servings).await?.map(|l| Self::leftover_response(l, name)))
+    }
+
+    /// Eat servings of a recipe's unexpired leftovers, earliest expiry first.
+    /// Returns how many servings were actually available. In a transaction,
+    /// the rows stay locked until it ends.
+    pub async fn eat_leftovers_of_recipe<C: ConnectionTrait>(
+        db: &C,
+        user_id: Uuid,
+        recipe_id: i64,
+        servings: i32,
+    ) -> Result<i32, AppError> {
+        let scope = DataScope::for_user(db, user_id).await?;
+        let items = leftover::Entity::find()
+            .filter(scope.condition(leftover::Column::UserId, leftover::Column::HouseholdId))
+            .filter(leftover::Column::RecipeId.eq(recipe_id))
+            .filter(leftover::Column::ExpiryDate.gte(Utc::now().date_naive()))
+            .order_by_asc(leftover::Column::ExpiryDate)
+            .lock_exclusive()
+            .all(db)
+            .await?;
+
+        let mut eaten = 0;
+        for item in items {
+            if eaten >= servings {
+                break;
+            }
+            let take = item.servings.min(servings - eaten);
+            Self::take_servings(db, item, take).await?;
+            eaten += take;
+        }
+        Ok(eaten)
+    }
+
+    /// Throw leftovers away
+    pub async fn delete_leftover(&self, user_id: Uuid, leftover_id: i64) -> Result<(), AppError> {
+        let item = self.find_leftover(user_id, leftover_id).await?;
+        leftover::Entity::delete_by_id(item.id).exec(&self.db).await?;
+        Ok(())
+    }
+
     /// Deduct ingredients from inventory after cooking a recipe
     /// Called automatically when user marks a recipe as cooked
+    ///
+    /// Each recipe line is normalised to grams and then converted into the
+    /// pantry item's own unit (earliest expiry consumed first). Quantities that
+    /// cannot be converted are left untouched and returned to the caller.
     pub async fn deduct_for_recipe(
         &self,
         user_id: Uuid,
         recipe_id: i64,
         servings_made: i32,
         recipe_servings: i32,
-    ) -> Result<(), AppError> {
-        use crate::entity::recipe_ingredient;
-
+    ) -> Result<Vec<UnconvertedItem>, AppError> {
         let recipe_ings = recipe_ingredient::Entity::find()
             .filter(recipe_ingredient::Column::RecipeId.eq(recipe_id))
             .all(&self.db)
             .await?;
 
-        let scaling = servings_made as f64 / recipe_servings.max(1) as f64;
+        let ingredient_ids: Vec<i64> = recipe_ings.iter().map(|ri| ri.ingredient_id).collect();
+        let converter = UnitConverter::load(&self.db, &ingredient_ids).await?;
+        let names: std::collections::HashMap<i64, String> = ingredient::Entity::find()
+            .filter(ingredient::Column::Id.is_in(ingredient_ids))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|i| (i.id, i.name))
+            .collect();
+
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let scaling = Decimal::try_from(servings_made as f64 / recipe_servings.max(1) as f64)
+            .unwrap_or_default();
+        let mut unconverted = Vec::new();
 
         for ri in recipe_ings {
-            if let Some(grams) = ri.quantity_grams {
-                let needed = grams * rust_decimal::Decimal::try_from(scaling).unwrap_or_default();
-
-                // Find this ingredient in user's inventory (prioritise earliest expiry)
-                if let Some(inv_item) = inventory_item::Entity::find()
-                    .filter(inventory_item::Column::UserId.eq(user_id))
-                    .filter(inventory_item::Column::IngredientId.eq(ri.ingredient_id))
-                    .one(&self.db)
-                    .await?
-                {
-                    let new_quantity = (inv_item.quantity - needed).max(rust_decimal::Decimal::ZERO);
-                    let now = Utc::now().fixed_offset();
-
-                    if new_quantity == rust_decimal::Decimal::ZERO {
-                        // Remove item if fully consumed
-                        inventory_item::Entity::delete_by_id(inv_item.id)
-                            .exec(&self.db)
-                            .await?;
-                    } else {
-                        let mut active: inventory_item::ActiveModel = inv_item.into();
-                        active.quantity = Set(new_quantity);
-                        active.updated_at = Set(now);
-                        active.update(&self.db).await?;
+            let name = names.get(&ri.ingredient_id).cloned().unwrap_or_default();
+            let mut remaining = match converter.recipe_grams(&ri) {
+                Ok(Some(grams)) => grams * scaling,
+                Ok(None) => continue,
+                Err(error) => {
+                    unconverted.push(UnconvertedItem {
+                        ingredient_id: ri.ingredient_id,
+                        name,
+                        quantity: ri.quantity.unwrap_or_default() * scaling,
+                        unit: ri.unit.unwrap_or_default(),
+                        source: QuantitySource::Recipe,
+                        error,
+                    });
+                    continue;
+                }
+            };
+
+            // Consume this ingredient from the (shared) pantry, earliest expiry first
+            let pantry = inventory_item::Entity::find()
+                .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
+                .filter(inventory_item::Column::IngredientId.eq(ri.ingredient_id))
+                .order_by_asc(inventory_item::Column::ExpiryDate)
+                .all(&self.db)
+                .await?;
+
+            for inv_item in pantry {
+                if remaining <= Decimal::ZERO {
+                    break;
+                }
+
+                let have = match converter.to_grams(inv_item.ingredient_id, inv_item.quantity, &inv_item.unit) {
+                    Ok(grams) => grams,
+                    Err(error) => {
+                        unconverted.push(UnconvertedItem {
+                            ingredient_id: inv_item.ingredient_id,
+                            name: name.clone(),
+                            quantity: inv_item.quantity,
+                            unit: inv_item.unit,
+                            source: QuantitySource::Inventory,
+                            error,
+                        });
+                        continue;
                     }
+                };
+
+                if have <= remaining {
+                    // Remove item if fully consumed
+                    remaining -= have;
+                    inventory_item::Entity::delete_by_id(inv_item.id)
+                        .exec(&self.db)
+                        .await?;
+                    continue;
                 }
+
+                // Convertible to grams implies convertible back; keep the old quantity otherwise
+                let new_quantity = converter
+                    .grams_to_unit(inv_item.ingredient_id, have - remaining, &inv_item.unit)
+                    .unwrap_or(inv_item.quantity);
+                remaining = Decimal::ZERO;
+
+                let mut active: inventory_item::ActiveModel = inv_item.into();
+                active.quantity = Set(new_quantity);
+                active.updated_at = Set(Utc::now().fixed_offset());
+                active.update(&self.db).await?;
             }
         }
 
-        Ok(())
+        Ok(unconverted)
     }
 
     /// Find-or-create an ingredient by name, then add to inventory.
@@ -301,11 +439,12 @@ impl InventoryService {
 
         let dec_qty = Decimal::from_str(&quantity.to_string())
             .unwrap_or(Decimal::ONE);
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let now = Utc::now().fixed_offset();
-        let today = Utc::now().date_naive();
 
         let new_item = inventory_item::ActiveModel {
             user_id: Set(user_id),
+            household_id: Set(scope.household_id()),
             ingredient_id: Set(ing.id),
             custom_name: Set(None),
             quantity: Set(dec_qty),
@@ -318,21 +457,8 @@ impl InventoryService {
         };
 
         let saved = new_item.insert(&self.db).await?;
-        let days_until_expiry = saved.expiry_date.map(|d| (d - today).num_days());
-        let expiry_warning = days_until_expiry.map(|d| d <= 5).unwrap_or(false);
-
-        Ok(InventoryItemResponse {
-            id: saved.id,
-            ingredient_id: saved.ingredient_id,
-            ingredient_name: ing.name,
-            custom_name: saved.custom_name,
-            quantity: saved.quantity,
-            unit: saved.unit,
-            expiry_date: saved.expiry_date,
-            storage_location: saved.storage_location,
-            days_until_expiry,
-            expiry_warning,
-        })
+        let converter = UnitConverter::load(&self.db, &[saved.ingredient_id]).await?;
+        Ok(Self::to_response(saved, ing.name, &converter))
     }
 
     /// Bulk-add items (from scan results). Find-or-create ingredients by name.
@@ -360,6 +486,73 @@ impl InventoryService {
         Ok(results)
     }
 
+    /// Build the API response for a stored item, with expiry metadata and
+    /// the quantity normalised to grams where the unit allows it
+    async fn find_leftover(&self, user_id: Uuid, leftover_id: i64) -> Result<leftover::Model, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        leftover::Entity::find_by_id(leftover_id)
+            .one(&self.db)
+            .await?
+            .filter(|l| scope.contains(l.user_id, l.household_id))
+            .ok_or(AppError::NotFound("Leftover".into()))
+    }
+
+    async fn take_servings<C: ConnectionTrait>(
+        db: &C,
+        item: leftover::Model,
+        servings: i32,
+    ) -> Result<Option<leftover::Model>, AppError> {
+        if servings >= item.servings {
+            leftover::Entity::delete_by_id(item.id).exec(db).await?;
+            return Ok(None);
+        }
+        let remaining = item.servings - servings;
+        let mut active: leftover::ActiveModel = item.into();
+        active.servings = Set(remaining);
+        Ok(Some(active.update(db).await?))
+    }
+
+    fn leftover_response(item: leftover::Model, recipe_name: String) -> LeftoverResponse {
+        let days_until_expiry = (item.expiry_date - Utc::now().date_naive()).num_days();
+        LeftoverResponse {
+            id: item.id,
+            recipe_id: item.recipe_id,
+            recipe_name,
+            servings: item.servings,
+            storage_location: item.storage_location,
+            expiry_date: item.expiry_date,
+            days_until_expiry,
+            expiry_warning: days_until_expiry <= 1,
+        }
+    }
+
+    fn to_response(
+        item: inventory_item::Model,
+        ingredient_name: String,
+        converter: &UnitConverter,
+    ) -> InventoryItemResponse {
+        let today = Utc::now().date_naive();
+        let days_until_expiry = item.expiry_date.map(|d| (d - today).num_days());
+        let expiry_warning = days_until_expiry.map(|d| d <= 5).unwrap_or(false);
+        let quantity_grams = converter
+            .to_grams(item.ingredient_id, item.quantity, &item.unit)
+            .ok();
+
+        InventoryItemResponse {
+            id: item.id,
+            ingredient_id: item.ingredient_id,
+            ingredient_name,
+            custom_name: item.custom_name,
+            quantity: item.quantity,
+            unit: item.unit,
+            quantity_grams,
+            expiry_date: item.expiry_date,
+            storage_location: item.storage_location,
+            days_until_expiry,
+            expiry_warning,
+        }
+    }
+
     /// Return recipes that can be (partially) made with the user's current inventory.
     /// Scores each recipe by the fraction of its ingredients that are in the pantry.
     pub async fn recipe_suggestions(
@@ -370,9 +563,10 @@ impl InventoryService {
     ) -> Result<Vec<RecipeSuggestion>, AppError> {
         use std::collections::HashMap;
 
-        // 1. Get the user's inventory (set of ingredient_ids)
+        // 1. Get the user's (or household's) inventory (set of ingredient_ids)
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let inv_items = inventory_item::Entity::find()
-            .filter(inventory_item::Column::UserId.eq(user_id))
+            .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
             .all(&self.db)
             .await?;
 
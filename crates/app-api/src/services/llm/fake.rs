@@ -0,0 +1,130 @@
+//! Deterministic stand-in for a real model — no network, same reply every time.
+//!
+//! Replies come from an ordered list of [`FakeRule`]s matched against the
+//! last message (case-insensitive substring; first match wins). A rule that
+//! calls tools only fires on a user turn, so the tool loop always ends with
+//! a text reply. Unmatched requests echo the last message back, or `{}` in
+//! JSON mode.
+//!
+//! Rules can be loaded from a JSON file (`LLM_FAKE_SCRIPT`):
+//!
+//! ```json
+//! [
+//!   { "match": "what's in my pantry", "tool_calls": [{ "name": "get_pantry" }] },
+//!   { "match": "TOOL_SUCCESS", "reply": "Here's your pantry." },
+//!   { "match": "grocery item detector", "reply": "[{\"name\":\"Milk\",\"quantity\":1,\"unit\":\"l\"}]" }
+//! ]
+//! ```
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{LlmDelta, LlmMessage, LlmProvider, LlmRequest, LlmResponse, LlmStream, Role, ToolCall};
+use cookest_shared::errors::AppError;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FakeRule {
+    #[serde(rename = "match")]
+    pub pattern: String,
+    #[serde(default)]
+    pub reply: String,
+    #[serde(default)]
+    pub tool_calls: Vec<FakeToolCall>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FakeToolCall {
+    pub name: String,
+    #[serde(default = "empty_object")]
+    pub arguments: Value,
+}
+
+fn empty_object() -> Value {
+    Value::Object(Default::default())
+}
+
+pub struct FakeLlm {
+    rules: Vec<FakeRule>,
+    context_tokens: usize,
+}
+
+impl FakeLlm {
+    pub fn new(rules: Vec<FakeRule>) -> Self {
+        Self { rules, context_tokens: 8_192 }
+    }
+
+    /// Pretend to have a smaller (or larger) window, e.g. to exercise
+    /// context budgeting without long conversations
+    pub fn with_context_tokens(mut self, context_tokens: usize) -> Self {
+        self.context_tokens = context_tokens;
+        self
+    }
+
+    pub fn load_rules(path: &str) -> Result<Vec<FakeRule>, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        serde_json::from_str(&raw).map_err(|e| format!("invalid fake LLM script {}: {}", path, e))
+    }
+
+    fn respond(&self, req: &LlmRequest) -> LlmResponse {
+        let last = req.messages.last();
+        let content = last.map(|m| m.content.as_str()).unwrap_or_default();
+        let after_tool = last.is_some_and(|m| m.role == Role::Tool);
+        let lower = content.to_lowercase();
+
+        let rule = self.rules.iter().find(|r| {
+            lower.contains(&r.pattern.to_lowercase()) && (!after_tool || r.tool_calls.is_empty())
+        });
+
+        let message = match rule {
+            Some(rule) => LlmMessage {
+                tool_calls: rule
+                    .tool_calls
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| ToolCall {
+                        id: format!("fake_call_{}", i),
+                        name: c.name.clone(),
+                        arguments: c.arguments.clone(),
+                    })
+                    .collect(),
+                ..LlmMessage::assistant(rule.reply.clone())
+            },
+            None if req.json => LlmMessage::assistant("{}"),
+            None => LlmMessage::assistant(format!("[fake] {}", content)),
+        };
+
+        let tokens = message.content.split_whitespace().count() as i32;
+        LlmResponse { message, tokens_used: Some(tokens) }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for FakeLlm {
+    async fn chat(&self, req: LlmRequest) -> Result<LlmResponse, AppError> {
+        Ok(self.respond(&req))
+    }
+
+    /// Streams the reply a word at a time, tool calls and tokens last
+    async fn chat_stream(&self, req: LlmRequest) -> Result<LlmStream, AppError> {
+        let LlmResponse { message, tokens_used } = self.respond(&req);
+
+        let mut deltas: Vec<Result<LlmDelta, AppError>> = message
+            .content
+            .split_inclusive(' ')
+            .map(|word| Ok(LlmDelta { content: word.to_string(), ..Default::default() }))
+            .collect();
+        deltas.push(Ok(LlmDelta {
+            content: String::new(),
+            tool_calls: message.tool_calls,
+            tokens_used,
+        }));
+
+        Ok(futures::stream::iter(deltas).boxed())
+    }
+
+    fn context_tokens(&self) -> usize {
+        self.context_tokens
+    }
+}
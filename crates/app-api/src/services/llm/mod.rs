@@ -0,0 +1,347 @@
+//! LLM provider abstraction shared by chat, recipe generation and scanning.
+//!
+//! Services talk to [`LlmProvider`] in provider-neutral terms — messages,
+//! tool calls, images, JSON mode, streaming — and never see a wire format.
+//! The backend is chosen at startup from `LLM_PROVIDER`:
+//!
+//! - `ollama` (default) — [`OllamaProvider`], Ollama's native `/api/chat`
+//! - `openai` — [`OpenAiProvider`], any OpenAI-compatible `/chat/completions`
+//!   server (OpenAI, vLLM, LM Studio, llama.cpp, …)
+//! - `fake` — [`FakeLlm`], deterministic scripted replies for offline tests
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use serde_json::Value;
+
+use crate::config::{Config, LlmBackend};
+use cookest_shared::errors::AppError;
+
+pub mod fake;
+mod ollama;
+mod openai;
+
+pub use fake::FakeLlm;
+pub use ollama::OllamaProvider;
+pub use openai::OpenAiProvider;
+
+/// The provider every AI feature shares, built once in `main`
+pub type SharedLlm = Arc<dyn LlmProvider>;
+
+/// Incremental pieces of a streamed reply
+pub type LlmStream = BoxStream<'static, Result<LlmDelta, AppError>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    /// The result of a tool call, answering `tool_call_id`
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "system" => Some(Role::System),
+            "user" => Some(Role::User),
+            "assistant" => Some(Role::Assistant),
+            "tool" => Some(Role::Tool),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Provider-assigned id (synthesised for providers that don't send one)
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmMessage {
+    pub role: Role,
+    pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub tool_call_id: Option<String>,
+    /// Base64-encoded images (JPEG/PNG/WebP) for vision requests
+    pub images: Vec<String>,
+}
+
+impl LlmMessage {
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    pub fn tool(call_id: &str, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(call_id.to_string()),
+            ..Self::new(Role::Tool, content)
+        }
+    }
+
+    pub fn with_image(mut self, base64: String) -> Self {
+        self.images.push(base64);
+        self
+    }
+}
+
+/// One model call. Requests carrying images go to the provider's vision model.
+#[derive(Debug, Clone, Default)]
+pub struct LlmRequest {
+    pub messages: Vec<LlmMessage>,
+    /// Function definitions in the OpenAI `{"type":"function",…}` shape
+    pub tools: Vec<Value>,
+    /// Ask for a single JSON object as the reply
+    pub json: bool,
+}
+
+impl LlmRequest {
+    pub fn new(messages: Vec<LlmMessage>) -> Self {
+        Self { messages, ..Default::default() }
+    }
+
+    pub fn with_tools(mut self, tools: Vec<Value>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn json(mut self) -> Self {
+        self.json = true;
+        self
+    }
+
+    fn has_images(&self) -> bool {
+        self.messages.iter().any(|m| !m.images.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmResponse {
+    /// Always `Role::Assistant`
+    pub message: LlmMessage,
+    /// Completion tokens, when the provider reports them
+    pub tokens_used: Option<i32>,
+}
+
+/// A streamed fragment: text as it arrives, tool calls once complete, and
+/// the token count on the final delta.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmDelta {
+    pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub tokens_used: Option<i32>,
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Complete the conversation in one response
+    async fn chat(&self, req: LlmRequest) -> Result<LlmResponse, AppError>;
+
+    /// Complete the conversation as a stream of deltas. Request errors are
+    /// returned up front; errors after the stream starts arrive as items.
+    async fn chat_stream(&self, req: LlmRequest) -> Result<LlmStream, AppError>;
+
+    /// Context window of the chat model, in tokens — prompt and reply together
+    fn context_tokens(&self) -> usize;
+}
+
+/// Build the provider selected in the config
+pub fn from_config(config: &Config) -> Result<SharedLlm, String> {
+    let models = Models {
+        chat: config.llm_chat_model.clone(),
+        vision: config.llm_vision_model.clone(),
+    };
+    let timeouts = Timeouts {
+        call: Duration::from_secs(config.llm_timeout_secs),
+        stream: Duration::from_secs(config.llm_stream_timeout_secs),
+    };
+    let context_tokens = config
+        .llm_context_tokens
+        .unwrap_or_else(|| default_context_tokens(&config.llm_backend, &models.chat));
+
+    Ok(match &config.llm_backend {
+        LlmBackend::Ollama { url } => Arc::new(OllamaProvider::new(url.clone(), models, timeouts, context_tokens)),
+        LlmBackend::OpenAi { base_url, api_key } => Arc::new(OpenAiProvider::new(
+            base_url.clone(),
+            api_key.clone(),
+            models,
+            timeouts,
+            context_tokens,
+        )),
+        LlmBackend::Fake { script } => {
+            tracing::warn!("LLM_PROVIDER=fake — AI features return scripted replies");
+            let rules = match script {
+                Some(path) => FakeLlm::load_rules(path)?,
+                None => Vec::new(),
+            };
+            Arc::new(FakeLlm::new(rules).with_context_tokens(context_tokens))
+        }
+    })
+}
+
+/// Context window assumed when `LLM_CONTEXT_TOKENS` isn't set. Ollama gets a
+/// conservative window whatever the model supports, because the server
+/// allocates memory for all of it (it is sent as `num_ctx`).
+fn default_context_tokens(backend: &LlmBackend, model: &str) -> usize {
+    const LARGE: [&str; 6] = ["gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4"];
+    match backend {
+        LlmBackend::OpenAi { .. } if LARGE.iter().any(|p| model.starts_with(p)) => 128_000,
+        LlmBackend::OpenAi { .. } if model.starts_with("gpt-3.5") => 16_385,
+        _ => 8_192,
+    }
+}
+
+/// How long a provider waits on the model
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    /// A one-shot call; for a stream, the wait for its headers and for each
+    /// chunk after them
+    pub call: Duration,
+    /// A stream from start to end
+    pub stream: Duration,
+}
+
+/// Model names a provider picks between per request
+#[derive(Debug, Clone)]
+pub struct Models {
+    pub chat: String,
+    pub vision: String,
+}
+
+impl Models {
+    fn for_request(&self, req: &LlmRequest) -> &str {
+        if req.has_images() {
+            &self.vision
+        } else {
+            &self.chat
+        }
+    }
+}
+
+/// Log a failed HTTP call and turn it into the user-safe error
+fn unavailable(provider: &str, e: impl std::fmt::Display) -> AppError {
+    tracing::error!("{} request failed: {}", provider, e);
+    AppError::Internal("AI service unavailable".into())
+}
+
+/// Reject non-2xx responses, logging the body
+async fn check_status(provider: &str, resp: reqwest::Response) -> Result<reqwest::Response, AppError> {
+    if resp.status().is_success() {
+        return Ok(resp);
+    }
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    tracing::error!("{} error {}: {}", provider, status, body);
+    Err(AppError::Internal("AI service returned an error".into()))
+}
+
+fn bad_response(provider: &str, e: impl std::fmt::Display) -> AppError {
+    tracing::error!("Failed to parse {} response: {}", provider, e);
+    AppError::Internal("Failed to parse AI response".into())
+}
+
+/// Split a streamed response body into its non-blank lines (NDJSON and SSE
+/// are both line-based). The last line may lack a trailing newline. The
+/// stream ends in an error if the body stalls for `timeouts.call` or runs
+/// past `timeouts.stream`, so a hung provider can't hold a reply open forever.
+fn body_lines(resp: reqwest::Response, timeouts: Timeouts) -> BoxStream<'static, Result<String, AppError>> {
+    struct State {
+        body: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+        buffer: Vec<u8>,
+        ready: VecDeque<String>,
+        finished: bool,
+        idle: Duration,
+        deadline: tokio::time::Instant,
+    }
+
+    let state = State {
+        body: resp.bytes_stream().map(|chunk| chunk.map(|b| b.to_vec())).boxed(),
+        buffer: Vec::new(),
+        ready: VecDeque::new(),
+        finished: false,
+        idle: timeouts.call,
+        deadline: tokio::time::Instant::now() + timeouts.stream,
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(line) = state.ready.pop_front() {
+                return Some((Ok(line), state));
+            }
+            if state.finished {
+                return None;
+            }
+            let next = tokio::time::timeout(state.idle, state.body.next());
+            let failure = match tokio::time::timeout_at(state.deadline, next).await {
+                Ok(Ok(Some(Ok(bytes)))) => {
+                    state.buffer.extend_from_slice(&bytes);
+                    None
+                }
+                Ok(Ok(Some(Err(e)))) => Some(e.to_string()),
+                Ok(Ok(None)) => {
+                    state.finished = true;
+                    None
+                }
+                Ok(Err(_)) => Some(format!("no data for {:?}", state.idle)),
+                Err(_) => Some("deadline passed".to_string()),
+            };
+            if let Some(reason) = failure {
+                tracing::error!("AI stream interrupted: {}", reason);
+                state.finished = true;
+                return Some((Err(AppError::Internal("AI service stream interrupted".into())), state));
+            }
+            let flush = state.finished;
+            state.ready.extend(take_lines(&mut state.buffer, flush));
+        }
+    })
+    .boxed()
+}
+
+/// Split complete lines off the front of `buffer`; with `flush`, the
+/// remainder counts as a final line. Blank lines are skipped.
+fn take_lines(buffer: &mut Vec<u8>, flush: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=pos).collect();
+        lines.push(String::from_utf8_lossy(&line).trim().to_string());
+    }
+    if flush && !buffer.is_empty() {
+        lines.push(String::from_utf8_lossy(buffer).trim().to_string());
+        buffer.clear();
+    }
+    lines.retain(|l| !l.is_empty());
+    lines
+}
@@ -0,0 +1,297 @@
+//! Ollama's native `/api/chat` API (NDJSON when streaming)
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    bad_response, body_lines, check_status, unavailable, LlmDelta, LlmMessage, LlmProvider,
+    LlmRequest, LlmResponse, LlmStream, Models, Role, Timeouts, ToolCall,
+};
+use cookest_shared::errors::AppError;
+
+const PROVIDER: &str = "Ollama";
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest<'a> {
+    model: &'a str,
+    messages: Vec<OllamaMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    /// Ollama's context window defaults to a few thousand tokens and silently
+    /// truncates the prompt beyond it — always say how much we budgeted for
+    num_ctx: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct OllamaMessage {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<OllamaToolCall>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaToolCallFunction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCallFunction {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OllamaMessage,
+    #[serde(default)]
+    eval_count: Option<i32>,
+}
+
+/// One NDJSON line of a `stream: true` response
+#[derive(Debug, Deserialize)]
+struct OllamaStreamChunk {
+    #[serde(default)]
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    eval_count: Option<i32>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl From<&LlmMessage> for OllamaMessage {
+    fn from(m: &LlmMessage) -> Self {
+        let tool_calls = (!m.tool_calls.is_empty()).then(|| {
+            m.tool_calls
+                .iter()
+                .map(|c| OllamaToolCall {
+                    function: OllamaToolCallFunction {
+                        name: c.name.clone(),
+                        arguments: c.arguments.clone(),
+                    },
+                })
+                .collect()
+        });
+        OllamaMessage {
+            role: m.role.as_str().to_string(),
+            content: m.content.clone(),
+            images: m.images.clone(),
+            tool_calls,
+        }
+    }
+}
+
+/// Ollama doesn't number tool calls; ids only need to be unique per message
+fn tool_calls(calls: Option<Vec<OllamaToolCall>>, first: usize) -> Vec<ToolCall> {
+    calls
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| ToolCall {
+            id: format!("call_{}", first + i),
+            name: c.function.name,
+            arguments: c.function.arguments,
+        })
+        .collect()
+}
+
+pub struct OllamaProvider {
+    http: Client,
+    base_url: String,
+    models: Models,
+    timeouts: Timeouts,
+    context_tokens: usize,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: String, models: Models, timeouts: Timeouts, context_tokens: usize) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            models,
+            timeouts,
+            context_tokens,
+        }
+    }
+
+    async fn send(&self, req: &LlmRequest, stream: bool) -> Result<reqwest::Response, AppError> {
+        let body = OllamaRequest {
+            model: self.models.for_request(req),
+            messages: req.messages.iter().map(OllamaMessage::from).collect(),
+            stream,
+            tools: req.tools.clone(),
+            format: req.json.then_some("json"),
+            options: OllamaOptions { num_ctx: self.context_tokens },
+        };
+
+        let call = self.http.post(format!("{}/api/chat", self.base_url)).json(&body);
+        // A stream may legitimately outlive the call timeout: bound the wait
+        // for its headers here, and its body in `body_lines`
+        let resp = if stream {
+            tokio::time::timeout(self.timeouts.call, call.send())
+                .await
+                .map_err(|e| unavailable(PROVIDER, e))?
+        } else {
+            call.timeout(self.timeouts.call).send().await
+        };
+        check_status(PROVIDER, resp.map_err(|e| unavailable(PROVIDER, e))?).await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    async fn chat(&self, req: LlmRequest) -> Result<LlmResponse, AppError> {
+        let resp: OllamaResponse = self
+            .send(&req, false)
+            .await?
+            .json()
+            .await
+            .map_err(|e| bad_response(PROVIDER, e))?;
+
+        Ok(LlmResponse {
+            message: LlmMessage {
+                tool_calls: tool_calls(resp.message.tool_calls, 0),
+                ..LlmMessage::new(Role::Assistant, resp.message.content)
+            },
+            tokens_used: resp.eval_count,
+        })
+    }
+
+    async fn chat_stream(&self, req: LlmRequest) -> Result<LlmStream, AppError> {
+        let resp = self.send(&req, true).await?;
+        let mut calls_seen = 0;
+
+        Ok(body_lines(resp, self.timeouts)
+            .map(move |line| {
+                let chunk: OllamaStreamChunk =
+                    serde_json::from_str(&line?).map_err(|e| bad_response(PROVIDER, e))?;
+                if let Some(error) = chunk.error {
+                    tracing::error!("Ollama stream error: {}", error);
+                    return Err(AppError::Internal("AI service returned an error".into()));
+                }
+                let message = chunk.message.unwrap_or_default();
+                let calls = tool_calls(message.tool_calls, calls_seen);
+                calls_seen += calls.len();
+                Ok(LlmDelta {
+                    content: message.content,
+                    tool_calls: calls,
+                    tokens_used: chunk.eval_count,
+                })
+            })
+            .boxed())
+    }
+
+    fn context_tokens(&self) -> usize {
+        self.context_tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use std::time::Duration;
+
+    /// Mock Ollama: NDJSON chunks split mid-line, then a tool call, then `done`
+    async fn mock_ollama(body: web::Json<Value>) -> HttpResponse {
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["model"], "chat-model");
+        assert_eq!(body["options"]["num_ctx"], 4096);
+        let chunks: Vec<Result<web::Bytes, actix_web::Error>> = vec![
+            Ok(web::Bytes::from_static(b"{\"message\":{\"role\":\"assistant\",\"content\":\"Let me \"},\"done\":false}\n{\"message\":{\"role\":\"assi")),
+            Ok(web::Bytes::from_static(b"stant\",\"content\":\"check.\"},\"done\":false}\n\n")),
+            Ok(web::Bytes::from_static(b"{\"message\":{\"role\":\"assistant\",\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"get_pantry\",\"arguments\":{}}}]},\"done\":false}\n")),
+            Ok(web::Bytes::from_static(b"{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"eval_count\":42}")),
+        ];
+        HttpResponse::Ok()
+            .content_type("application/x-ndjson")
+            .streaming(futures::stream::iter(chunks))
+    }
+
+    #[actix_web::test]
+    async fn stream_reassembles_ndjson_lines() {
+        let server = HttpServer::new(|| App::new().route("/api/chat", web::post().to(mock_ollama)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        let models = Models { chat: "chat-model".into(), vision: "vision-model".into() };
+        let timeouts = Timeouts { call: Duration::from_secs(5), stream: Duration::from_secs(5) };
+        let provider = OllamaProvider::new(format!("http://{}/", addr), models, timeouts, 4096);
+        let deltas: Vec<LlmDelta> = provider
+            .chat_stream(LlmRequest::new(vec![LlmMessage::user("what's in my pantry?")]))
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let text: String = deltas.iter().map(|d| d.content.as_str()).collect();
+        assert_eq!(text, "Let me check.");
+        let calls: Vec<&ToolCall> = deltas.iter().flat_map(|d| &d.tool_calls).collect();
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].id.as_str(), calls[0].name.as_str()), ("call_0", "get_pantry"));
+        assert_eq!(deltas.last().unwrap().tokens_used, Some(42));
+    }
+
+    /// Mock Ollama that sends one chunk, then a chunk every 50ms forever
+    /// (`/slow`) or nothing at all (`/api/chat`)
+    async fn stalling_ollama(req: actix_web::HttpRequest) -> HttpResponse {
+        let first = futures::stream::once(async {
+            Ok::<_, actix_web::Error>(web::Bytes::from_static(b"{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":false}\n"))
+        });
+        let rest = if req.path() == "/slow/api/chat" {
+            futures::stream::unfold((), |_| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Some((Ok(web::Bytes::from_static(b"{\"message\":{\"role\":\"assistant\",\"content\":\".\"},\"done\":false}\n")), ()))
+            })
+            .boxed_local()
+        } else {
+            futures::stream::pending().boxed_local()
+        };
+        HttpResponse::Ok().content_type("application/x-ndjson").streaming(first.chain(rest))
+    }
+
+    #[actix_web::test]
+    async fn stalled_or_endless_streams_end_in_an_error() {
+        let server = HttpServer::new(|| App::new().default_service(web::post().to(stalling_ollama)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        let timeouts = Timeouts { call: Duration::from_millis(200), stream: Duration::from_millis(500) };
+        for base in [format!("http://{}", addr), format!("http://{}/slow", addr)] {
+            let models = Models { chat: "chat-model".into(), vision: "vision-model".into() };
+            let provider = OllamaProvider::new(base, models, timeouts, 4096);
+            let deltas: Vec<Result<LlmDelta, AppError>> = provider
+                .chat_stream(LlmRequest::new(vec![LlmMessage::user("hello")]))
+                .await
+                .unwrap()
+                .collect()
+                .await;
+
+            assert_eq!(deltas.first().unwrap().as_ref().unwrap().content, "Hi");
+            assert!(deltas.last().unwrap().is_err());
+        }
+    }
+}
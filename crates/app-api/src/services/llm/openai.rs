@@ -0,0 +1,384 @@
+//! OpenAI-compatible `/chat/completions` API (SSE when streaming).
+//!
+//! Works against OpenAI itself and the many local servers that mimic it
+//! (vLLM, LM Studio, llama.cpp, Ollama's `/v1`). The API key is optional
+//! because most local servers don't check one.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{
+    bad_response, body_lines, check_status, unavailable, LlmDelta, LlmMessage, LlmProvider,
+    LlmRequest, LlmResponse, LlmStream, Models, Role, Timeouts, ToolCall,
+};
+use cookest_shared::errors::AppError;
+
+const PROVIDER: &str = "OpenAI-compatible API";
+
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ResponseToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseToolCall {
+    id: String,
+    function: ResponseFunction,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseFunction {
+    name: String,
+    /// JSON-encoded object, as a string
+    arguments: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    completion_tokens: i32,
+}
+
+/// One `data:` event of a streamed completion
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct StreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<StreamToolCall>,
+}
+
+/// Tool calls arrive in fragments keyed by `index`: the id and name first,
+/// then the arguments string a few characters at a time.
+#[derive(Debug, Deserialize)]
+struct StreamToolCall {
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<StreamFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[derive(Default)]
+struct PartialCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Parse a function's arguments string; models occasionally emit invalid JSON
+fn parse_arguments(name: &str, raw: &str) -> Value {
+    if raw.trim().is_empty() {
+        return json!({});
+    }
+    serde_json::from_str(raw).unwrap_or_else(|e| {
+        tracing::warn!("Tool call {} has malformed arguments ({}): {}", name, e, raw);
+        json!({})
+    })
+}
+
+/// Turn one SSE line into a delta. Only `data:` lines carry payloads and
+/// `[DONE]` ends the stream; tool call fragments collect in `partial` until
+/// their choice finishes.
+fn parse_stream_line(
+    line: &str,
+    partial: &mut BTreeMap<usize, PartialCall>,
+) -> Result<Option<LlmDelta>, AppError> {
+    let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+        return Ok(None);
+    };
+    if data == "[DONE]" {
+        return Ok(None);
+    }
+    let event: StreamEvent = serde_json::from_str(data).map_err(|e| bad_response(PROVIDER, e))?;
+
+    let mut delta = LlmDelta {
+        tokens_used: event.usage.map(|u| u.completion_tokens),
+        ..Default::default()
+    };
+    for choice in event.choices {
+        delta.content.push_str(choice.delta.content.as_deref().unwrap_or_default());
+        for fragment in choice.delta.tool_calls {
+            let call = partial.entry(fragment.index).or_default();
+            if let Some(id) = fragment.id {
+                call.id = id;
+            }
+            if let Some(f) = fragment.function {
+                call.name.push_str(f.name.as_deref().unwrap_or_default());
+                call.arguments.push_str(f.arguments.as_deref().unwrap_or_default());
+            }
+        }
+        if choice.finish_reason.is_some() {
+            delta.tool_calls.extend(std::mem::take(partial).into_values().map(|c| ToolCall {
+                arguments: parse_arguments(&c.name, &c.arguments),
+                id: c.id,
+                name: c.name,
+            }));
+        }
+    }
+    Ok(Some(delta))
+}
+
+/// MIME type of a base64 image, from its magic bytes
+fn image_mime(base64: &str) -> &'static str {
+    if base64.starts_with("iVBOR") {
+        "image/png"
+    } else if base64.starts_with("UklGR") {
+        "image/webp"
+    } else if base64.starts_with("R0lGOD") {
+        "image/gif"
+    } else {
+        "image/jpeg"
+    }
+}
+
+fn wire_message(m: &LlmMessage) -> Value {
+    let mut out = json!({ "role": m.role.as_str() });
+
+    out["content"] = if m.images.is_empty() {
+        json!(m.content)
+    } else {
+        let mut parts = vec![json!({ "type": "text", "text": m.content })];
+        parts.extend(m.images.iter().map(|img| {
+            json!({
+                "type": "image_url",
+                "image_url": { "url": format!("data:{};base64,{}", image_mime(img), img) }
+            })
+        }));
+        Value::Array(parts)
+    };
+
+    if !m.tool_calls.is_empty() {
+        out["tool_calls"] = m
+            .tool_calls
+            .iter()
+            .map(|c| {
+                json!({
+                    "id": c.id,
+                    "type": "function",
+                    "function": { "name": c.name, "arguments": c.arguments.to_string() }
+                })
+            })
+            .collect();
+    }
+    if let Some(id) = &m.tool_call_id {
+        out["tool_call_id"] = json!(id);
+    }
+    out
+}
+
+pub struct OpenAiProvider {
+    http: Client,
+    base_url: String,
+    api_key: Option<SecretString>,
+    models: Models,
+    timeouts: Timeouts,
+    context_tokens: usize,
+}
+
+impl OpenAiProvider {
+    pub fn new(
+        base_url: String,
+        api_key: Option<SecretString>,
+        models: Models,
+        timeouts: Timeouts,
+        context_tokens: usize,
+    ) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            models,
+            timeouts,
+            context_tokens,
+        }
+    }
+
+    async fn send(&self, req: &LlmRequest, stream: bool) -> Result<reqwest::Response, AppError> {
+        let mut body = json!({
+            "model": self.models.for_request(req),
+            "messages": req.messages.iter().map(wire_message).collect::<Vec<_>>(),
+            "stream": stream,
+        });
+        if !req.tools.is_empty() {
+            body["tools"] = json!(req.tools);
+        }
+        if req.json {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+        if stream {
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+
+        let mut call = self.http.post(format!("{}/chat/completions", self.base_url)).json(&body);
+        if let Some(key) = &self.api_key {
+            call = call.bearer_auth(key.expose_secret());
+        }
+        // A stream may legitimately outlive the call timeout: bound the wait
+        // for its headers here, and its body in `body_lines`
+        let resp = if stream {
+            tokio::time::timeout(self.timeouts.call, call.send())
+                .await
+                .map_err(|e| unavailable(PROVIDER, e))?
+        } else {
+            call.timeout(self.timeouts.call).send().await
+        };
+        check_status(PROVIDER, resp.map_err(|e| unavailable(PROVIDER, e))?).await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    async fn chat(&self, req: LlmRequest) -> Result<LlmResponse, AppError> {
+        let resp: CompletionResponse = self
+            .send(&req, false)
+            .await?
+            .json()
+            .await
+            .map_err(|e| bad_response(PROVIDER, e))?;
+
+        let choice = resp
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| bad_response(PROVIDER, "no choices"))?;
+
+        Ok(LlmResponse {
+            message: LlmMessage {
+                tool_calls: choice
+                    .message
+                    .tool_calls
+                    .into_iter()
+                    .map(|c| ToolCall {
+                        arguments: parse_arguments(&c.function.name, &c.function.arguments),
+                        id: c.id,
+                        name: c.function.name,
+                    })
+                    .collect(),
+                ..LlmMessage::new(Role::Assistant, choice.message.content.unwrap_or_default())
+            },
+            tokens_used: resp.usage.map(|u| u.completion_tokens),
+        })
+    }
+
+    async fn chat_stream(&self, req: LlmRequest) -> Result<LlmStream, AppError> {
+        let resp = self.send(&req, true).await?;
+        let mut partial: BTreeMap<usize, PartialCall> = BTreeMap::new();
+
+        Ok(body_lines(resp, self.timeouts)
+            .filter_map(move |line| {
+                let delta = line.and_then(|line| parse_stream_line(&line, &mut partial));
+                futures::future::ready(delta.transpose())
+            })
+            .boxed())
+    }
+
+    fn context_tokens(&self) -> usize {
+        self.context_tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_use_openai_wire_shape() {
+        let mut assistant = LlmMessage::assistant("");
+        assistant.tool_calls.push(ToolCall {
+            id: "call_a".into(),
+            name: "get_pantry".into(),
+            arguments: json!({ "limit": 5 }),
+        });
+        assert_eq!(
+            wire_message(&assistant),
+            json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "id": "call_a", "type": "function", "function": { "name": "get_pantry", "arguments": "{\"limit\":5}" } }]
+            })
+        );
+
+        assert_eq!(
+            wire_message(&LlmMessage::tool("call_a", "TOOL_SUCCESS: []")),
+            json!({ "role": "tool", "content": "TOOL_SUCCESS: []", "tool_call_id": "call_a" })
+        );
+
+        let vision = LlmMessage::user("what is this?").with_image("iVBORw0KGgo=".into());
+        assert_eq!(
+            wire_message(&vision)["content"][1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[test]
+    fn stream_assembles_fragmented_tool_calls() {
+        let mut partial = BTreeMap::new();
+        let lines = [
+            r#"data: {"choices":[{"delta":{"content":"Checking"}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"get_pantry","arguments":"{\"li"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"mit\":3}"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+            r#"data: {"choices":[],"usage":{"prompt_tokens":50,"completion_tokens":12}}"#,
+            ": keep-alive",
+            "data: [DONE]",
+        ];
+        let deltas: Vec<LlmDelta> = lines
+            .iter()
+            .filter_map(|l| parse_stream_line(l, &mut partial).unwrap())
+            .collect();
+
+        assert_eq!(deltas[0].content, "Checking");
+        assert!(deltas[1].tool_calls.is_empty() && deltas[2].tool_calls.is_empty());
+        assert_eq!(
+            deltas[3].tool_calls,
+            vec![ToolCall { id: "call_1".into(), name: "get_pantry".into(), arguments: json!({ "limit": 3 }) }]
+        );
+        assert_eq!(deltas[4].tokens_used, Some(12));
+        assert_eq!(deltas.len(), 5);
+    }
+}
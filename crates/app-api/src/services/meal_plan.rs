@@ -1,42 +1,82 @@
 //! Meal Plan Service — AI-assisted weekly meal planning
 //!
-//! Scoring formula per recipe (weights sum to 100):
-//!   ingredient_coverage × 30  — prefer recipes user already has ingredients for
+//! Every candidate recipe gets a static preference score (weights sum to 100):
+//!   ingredient_coverage × 35  — prefer recipes user already has ingredients for
 //!   expiry_urgency      × 25  — prioritise ingredients close to expiry
-//!   ml_preference       × 25  — learned user taste via PreferenceService
-//!   nutrition_balance   × 12  — fill the week's nutritional gaps
-//!   variety_bonus       ×  8  — penalise recently cooked recipes
+//!   ml_preference       × 30  — learned user taste via PreferenceService
+//!   favourite           × 10  — recipes the user has saved
+//!
+//! The week is then built by `services::plan_optimizer`, which balances those
+//! scores against daily macros (the household's nutrition targets), variety,
+//! ingredient reuse, cooking time and skill, and the weekly budget (costs
+//! from `services::pricing`). The search is seeded; the seed is stored on the
+//! plan so the same inputs reproduce the same week.
+//!
+//! Some dinners are batch-cooked: the slot's `servings_override` is doubled
+//! and the next day's lunch is a leftover slot linked to it. Leftovers
+//! already stored (`services::inventory`) are planned before they expire.
+//! Leftover slots cost nothing and add nothing to the shopping list.
+//!
+//! Recipes cooked in the last two weeks are not candidates.
+//! Allergies and dietary restrictions are hard filters applied before scoring
+//! (see `services::dietary`); excluded recipes are reported back with reasons.
+//!
+//! Household members share one plan per week and one pantry; plans and
+//! inventory are looked up through [`DataScope`].
 
 use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
-    ActiveModelTrait, Set, PaginatorTrait,
+    sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    ActiveModelTrait, Set, PaginatorTrait, TransactionTrait,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 use crate::entity::{
     recipe, recipe_ingredient, recipe_nutrition, inventory_item, cooking_history,
-    user_favorite, meal_plan, meal_plan_slot,
+    user_favorite, meal_plan, meal_plan_slot, user, ingredient, ingredient_allergen, leftover,
 };
 use cookest_shared::errors::AppError;
-use crate::services::PreferenceService;
-
-/// Ideal daily nutrition targets (per person)
-const DAILY_CALORIES: f64 = 2000.0;
-const DAILY_PROTEIN_G: f64 = 50.0;
-const DAILY_CARBS_G: f64 = 275.0;
-const DAILY_FAT_G: f64 = 78.0;
-const DAILY_FIBER_G: f64 = 28.0;
+use crate::models::meal_plan::{ExcludedRecipe, PlanCost, RegenerateScope};
+use crate::services::{InventoryService, PreferenceService};
+use crate::services::dietary::{self, DietaryConstraints, RecipeAllergen};
+use crate::services::household::DataScope;
+use crate::services::nutrition;
+use crate::services::plan_optimizer::{
+    batch_days, slot_at, slot_day, slot_meal, Candidate, Macros, Optimiser, Preferences, Week, DAYS, DINNER,
+    LUNCH, MEAL_TYPES, SLOTS,
+};
+use crate::services::pricing::{self, RecipeCost};
+use crate::services::units::{QuantitySource, UnconvertedItem, UnitConverter};
 
-/// Score for each candidate recipe
+/// Result of plan generation: the saved plan, candidates removed by hard
+/// filters and the plan's estimated cost
 #[derive(Debug)]
-struct RecipeScore {
-    recipe_id: i64,
-    total_score: f64,
-    cuisine: Option<String>,
-    category: Option<String>,
-    total_time_min: Option<i32>,
+pub struct GeneratedPlan {
+    pub plan: meal_plan::Model,
+    pub excluded: Vec<ExcludedRecipe>,
+    pub cost: PlanCost,
+}
+
+/// Scored candidates and targets for one user's plan
+struct PlanningContext {
+    candidates: Vec<Candidate>,
+    /// Candidate index by recipe id; every recipe has one
+    index: HashMap<i64, usize>,
+    excluded: Vec<ExcludedRecipe>,
+    prefs: Preferences,
+    recipe_servings: HashMap<i64, i32>,
+    recipe_costs: HashMap<i64, RecipeCost>,
+    budget: Option<Decimal>,
+}
+
+/// Shopping list derived from the current plan, in grams
+#[derive(Debug, Default)]
+pub struct ShoppingListComputation {
+    pub items: Vec<serde_json::Value>,
+    /// Quantities left out because their units could not be converted to grams
+    pub unconverted: Vec<UnconvertedItem>,
 }
 
 pub struct MealPlanService {
@@ -53,18 +93,170 @@ impl MealPlanService {
         }
     }
 
+    /// Generate the plan for `week_start` unless the user (or their
+    /// household) already has one — used by the weekly scheduled job
+    pub async fn generate_if_missing(
+        &self,
+        user_id: Uuid,
+        household_size: i32,
+        week_start: NaiveDate,
+    ) -> Result<Option<GeneratedPlan>, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let existing = meal_plan::Entity::find()
+            .filter(plan_scope(&scope))
+            .filter(meal_plan::Column::WeekStart.eq(week_start))
+            .one(&self.db)
+            .await?;
+        if existing.is_some() {
+            return Ok(None);
+        }
+        self.generate_week_plan(user_id, household_size, week_start, None).await.map(Some)
+    }
+
     /// Generate a full week meal plan for a user and save it to the database.
     /// Generates 4 slots per day × 7 days: breakfast, lunch, dinner, snack.
+    /// Recipes that violate the user's allergies or dietary restrictions are
+    /// never scored; they are returned in `GeneratedPlan::excluded`.
+    /// Without a `seed` a random one is drawn; either way it is saved on the plan.
+    ///
+    /// If the week already has a plan it is kept: locked, completed and flex
+    /// slots stay and every other slot is re-optimised around them.
     pub async fn generate_week_plan(
         &self,
         user_id: Uuid,
         household_size: i32,
         week_start: NaiveDate,
-    ) -> Result<meal_plan::Model, AppError> {
+        seed: Option<u32>,
+    ) -> Result<GeneratedPlan, AppError> {
+        let user = user::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("User".into()))?;
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let context = self.planning_context(&user, &scope, household_size).await?;
+
+        let now = Utc::now().fixed_offset();
+        let seed = seed.unwrap_or_else(rand::random);
+
+        let existing = meal_plan::Entity::find()
+            .filter(plan_scope(&scope))
+            .filter(meal_plan::Column::WeekStart.eq(week_start))
+            .one(&self.db)
+            .await?;
+
+        let plan = match existing {
+            Some(plan) => {
+                let mut active: meal_plan::ActiveModel = plan.into();
+                active.is_ai_generated = Set(true);
+                active.generation_seed = Set(Some(seed as i64));
+                active.updated_at = Set(now);
+                active.update(&self.db).await?
+            }
+            None => {
+                meal_plan::ActiveModel {
+                    user_id: Set(user_id),
+                    household_id: Set(scope.household_id()),
+                    week_start: Set(week_start),
+                    is_ai_generated: Set(true),
+                    generation_seed: Set(Some(seed as i64)),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+
+        self.refill(user_id, plan, context, &RegenerateScope::Unlocked, seed, household_size)
+            .await
+    }
+
+    /// Re-optimise part of an existing plan: every slot, one day or one meal
+    /// type. Locked, completed and flex slots in scope are kept and the rest
+    /// of the week is left as it is; the new meals are chosen around them.
+    pub async fn regenerate(
+        &self,
+        user_id: Uuid,
+        plan_id: i64,
+        target: &RegenerateScope,
+        seed: Option<u32>,
+    ) -> Result<GeneratedPlan, AppError> {
+        if let RegenerateScope::MealType { meal_type } = target {
+            if !MEAL_TYPES.contains(&meal_type.as_str()) {
+                return Err(plan_error("meal_type", "meal_type must be breakfast, lunch, dinner or snack"));
+            }
+        }
+        if let RegenerateScope::Day { day_of_week } = target {
+            if !(0..DAYS as i16).contains(day_of_week) {
+                return Err(plan_error("day_of_week", "day_of_week must be between 0 (Monday) and 6"));
+            }
+        }
+
+        let plan = self.find_plan(user_id, plan_id).await?;
+        let user = user::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("User".into()))?;
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let context = self.planning_context(&user, &scope, user.household_size).await?;
+
+        let seed = seed.unwrap_or_else(rand::random);
+        let mut active: meal_plan::ActiveModel = plan.into();
+        active.generation_seed = Set(Some(seed as i64));
+        active.updated_at = Set(Utc::now().fixed_offset());
+        let plan = active.update(&self.db).await?;
+
+        self.refill(user_id, plan, context, target, seed, user.household_size)
+            .await
+    }
+
+    /// Lock or unlock a slot. Locked slots survive generation and regeneration.
+    pub async fn set_slot_locked(
+        &self,
+        user_id: Uuid,
+        plan_id: i64,
+        slot_id: i64,
+        locked: bool,
+    ) -> Result<serde_json::Value, AppError> {
+        self.find_plan(user_id, plan_id).await?;
+
+        let slot = meal_plan_slot::Entity::find_by_id(slot_id)
+            .one(&self.db)
+            .await?
+            .filter(|s| s.meal_plan_id == plan_id)
+            .ok_or(AppError::NotFound("Slot".into()))?;
+
+        let mut active: meal_plan_slot::ActiveModel = slot.into();
+        active.is_locked = Set(locked);
+        let updated = active.update(&self.db).await?;
+
+        Ok(serde_json::json!({
+            "id": updated.id,
+            "day_of_week": updated.day_of_week,
+            "meal_type": updated.meal_type,
+            "recipe_id": updated.recipe_id,
+            "is_locked": updated.is_locked,
+        }))
+    }
+
+    /// Score every recipe for the user and collect the optimiser's targets.
+    /// Every recipe becomes a candidate so kept slots can be judged, but
+    /// recipes excluded by dietary constraints or cooked in the last two
+    /// weeks fit no meal type and are never placed.
+    async fn planning_context(
+        &self,
+        user: &user::Model,
+        scope: &DataScope,
+        household_size: i32,
+    ) -> Result<PlanningContext, AppError> {
+        let user_id = user.id;
+        let constraints = DietaryConstraints::from_user(user);
+
         // ── 1. Load context data ──────────────────────────────────────────────
 
         let inventory = inventory_item::Entity::find()
-            .filter(inventory_item::Column::UserId.eq(user_id))
+            .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
             .all(&self.db)
             .await?;
 
@@ -123,16 +315,34 @@ impl MealPlanService {
             .map(|n| (n.recipe_id, n))
             .collect();
 
-        // ── 2. Score every recipe ─────────────────────────────────────────────
+        let recipe_costs = pricing::recipe_costs(&self.db, &all_recipe_ingredients).await?;
+        let budget = user.weekly_budget.filter(|b| *b > Decimal::ZERO);
 
-        let mut scored: Vec<RecipeScore> = Vec::new();
+        let allergens_by_recipe = if constraints.needs_allergen_data() {
+            self.load_recipe_allergens(&all_recipe_ingredients).await?
+        } else {
+            HashMap::new()
+        };
 
-        let weekly_calories = 0.0_f64;
-        let weekly_protein = 0.0_f64;
+        // ── 2. Score every recipe; hard filters make a recipe unplaceable ─────
+
+        let mut excluded: Vec<ExcludedRecipe> = Vec::new();
+        let mut candidates: Vec<Candidate> = Vec::new();
+        let mut index: HashMap<i64, usize> = HashMap::new();
 
         for recipe in &all_recipes {
-            if recent_recipes.contains(&recipe.id) {
-                continue;
+            let mut placeable = !recent_recipes.contains(&recipe.id);
+            if !constraints.is_empty() {
+                let allergens = allergens_by_recipe.get(&recipe.id).map(Vec::as_slice).unwrap_or(&[]);
+                let reasons = constraints.check(recipe, allergens);
+                if !reasons.is_empty() {
+                    excluded.push(ExcludedRecipe {
+                        recipe_id: recipe.id,
+                        recipe_name: recipe.name.clone(),
+                        reasons,
+                    });
+                    placeable = false;
+                }
             }
 
             let recipe_ing_ids = ingredients_by_recipe
@@ -153,136 +363,321 @@ impl MealPlanService {
                 .count();
             let expiry_urgency = (expiring_used as f64 / total_ings as f64).min(1.0);
 
-            let ml_preference = self
-                .preference_service
-                .score_recipe_for_user(user_id, recipe.id)
-                .await
-                .unwrap_or(0.5);
-
-            let variety_bonus = if recent_recipes.contains(&recipe.id) {
-                -0.3
-            } else if favourite_ids.contains(&recipe.id) {
-                0.1
-            } else {
-                0.0
-            };
-
-            let nutrition_balance = if let Some(n) = nutrition_by_recipe.get(&recipe.id) {
-                let scale = household_size as f64 / recipe.servings.max(1) as f64;
-                let cal = f64::try_from(n.calories.unwrap_or_default()).unwrap_or(0.0) * scale;
-                let pro = f64::try_from(n.protein_g.unwrap_or_default()).unwrap_or(0.0) * scale;
-
-                let cal_gap = (DAILY_CALORIES * 7.0 - weekly_calories).max(0.0);
-                let pro_gap = (DAILY_PROTEIN_G * 7.0 - weekly_protein).max(0.0);
-
-                let cal_score = (cal / cal_gap.max(1.0)).min(1.0);
-                let pro_score = (pro / pro_gap.max(1.0)).min(1.0);
-                (cal_score + pro_score) / 2.0
+            let ml_preference = if placeable {
+                self.preference_service
+                    .score_recipe_for_user(user_id, recipe.id)
+                    .await
+                    .unwrap_or(0.5)
             } else {
                 0.5
             };
 
-            let total_score = (ingredient_coverage * 0.30)
+            let favourite = if favourite_ids.contains(&recipe.id) { 1.0 } else { 0.0 };
+
+            let score = (ingredient_coverage * 0.35)
                 + (expiry_urgency * 0.25)
-                + (ml_preference * 0.25)
-                + (nutrition_balance * 0.12)
-                + (variety_bonus * 0.08);
+                + (ml_preference * 0.30)
+                + (favourite * 0.10);
+
+            let scale = household_size as f64 / recipe.servings.max(1) as f64;
+            let macros = nutrition_by_recipe.get(&recipe.id).map(|n| {
+                let f = |v: Option<Decimal>| f64::try_from(v.unwrap_or_default()).unwrap_or(0.0) * scale;
+                Macros {
+                    calories: f(n.calories),
+                    protein_g: f(n.protein_g),
+                    carbs_g: f(n.carbs_g),
+                    fat_g: f(n.fat_g),
+                }
+            });
+
+            let cost = recipe_costs
+                .get(&recipe.id)
+                .map(|c| c.for_servings(household_size, recipe.servings).total)
+                .unwrap_or_default();
 
-            scored.push(RecipeScore {
+            index.insert(recipe.id, candidates.len());
+            candidates.push(Candidate {
                 recipe_id: recipe.id,
-                total_score,
+                score,
+                meal_types: if placeable {
+                    MEAL_TYPES.map(|mt| Self::fits_meal_type_static(&recipe.category, mt))
+                } else {
+                    [false; 4]
+                },
+                macros,
                 cuisine: recipe.cuisine.clone(),
-                category: recipe.category.clone(),
+                ingredient_ids: recipe_ing_ids,
                 total_time_min: recipe.total_time_min,
+                difficulty: recipe.difficulty.clone(),
+                cost: f64::try_from(cost).unwrap_or(0.0),
             });
         }
 
-        // Use total_cmp (not partial_cmp) so NaN scores are sorted deterministically
-        // instead of panicking at runtime. NaN is treated as greater than any finite value,
-        // placing broken-nutrition recipes at the end of the sorted list.
-        scored.sort_by(|a, b| b.total_score.total_cmp(&a.total_score));
-
-        // ── 3. Greedy selection — 28 slots (4 per day × 7 days) ──────────────
-        // Meal types: breakfast, lunch, dinner, snack
-
-        let mut selected: Vec<(i64, u8, &str)> = Vec::new();
-        let mut used_recipe_ids: std::collections::HashSet<i64> = std::collections::HashSet::new();
-        let mut used_cuisines_today: HashMap<u8, String> = HashMap::new();
-
-        let meal_slots: &[(&str, fn(&Option<String>, &str) -> bool)] = &[
-            ("breakfast", |cat, mt| Self::fits_meal_type_static(cat, mt)),
-            ("lunch",     |cat, mt| Self::fits_meal_type_static(cat, mt)),
-            ("dinner",    |cat, mt| Self::fits_meal_type_static(cat, mt)),
-            ("snack",     |cat, mt| Self::fits_meal_type_static(cat, mt)),
-        ];
-
-        for day in 0u8..7 {
-            for (meal_type, fits) in meal_slots {
-                // Avoid same cuisine for lunch and dinner on same day
-                let avoid_cuisine = used_cuisines_today.get(&day).cloned();
-
-                if let Some(recipe) = scored.iter().find(|r| {
-                    !used_recipe_ids.contains(&r.recipe_id)
-                        && fits(&r.category, meal_type)
-                        && !(meal_type == &"dinner"
-                            && avoid_cuisine.as_ref() == r.cuisine.as_ref())
-                }) {
-                    selected.push((recipe.recipe_id, day, meal_type));
-                    used_recipe_ids.insert(recipe.recipe_id);
-                    if meal_type == &"lunch" {
-                        if let Some(cuisine) = &recipe.cuisine {
-                            used_cuisines_today.insert(day, cuisine.clone());
-                        }
+        let targets = nutrition::household_targets(&self.db, user_id).await?.daily;
+        let prefs = Preferences {
+            daily_macros: Macros {
+                calories: targets.calories,
+                protein_g: targets.protein_g,
+                carbs_g: targets.carbs_g,
+                fat_g: targets.fat_g,
+            },
+            max_time_min: user.preferred_time_per_meal_min,
+            skill_level: user.cooking_skill_level.clone(),
+            budget: budget.and_then(|b| f64::try_from(b).ok()),
+        };
+
+        Ok(PlanningContext {
+            candidates,
+            index,
+            excluded,
+            prefs,
+            recipe_servings: all_recipes.iter().map(|r| (r.id, r.servings)).collect(),
+            recipe_costs,
+            budget,
+        })
+    }
+
+    /// Replace the open slots of `plan` in `target` with newly optimised
+    /// meals. Locked, completed and flex slots, and everything outside
+    /// `target`, are kept and count towards the objective. Stored leftovers
+    /// are planned first, into open lunches and dinners before they expire;
+    /// then the optimiser fills the rest, batch-cooking some dinners for the
+    /// next day's lunch.
+    async fn refill(
+        &self,
+        user_id: Uuid,
+        plan: meal_plan::Model,
+        mut context: PlanningContext,
+        target: &RegenerateScope,
+        seed: u32,
+        household_size: i32,
+    ) -> Result<GeneratedPlan, AppError> {
+        let existing = meal_plan_slot::Entity::find()
+            .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
+            .all(&self.db)
+            .await?;
+
+        let mut fixed: Week = [None; SLOTS];
+        let mut open: [bool; SLOTS] = std::array::from_fn(|slot| in_scope(target, slot));
+        let mut replaced = Vec::new();
+        // Servings of stored leftovers already planned in kept slots
+        let mut planned_leftovers: HashMap<i64, i32> = HashMap::new();
+        for slot in &existing {
+            let Some(index) = slot_index(slot) else { continue };
+            if open[index] && !(slot.is_locked || slot.is_completed || slot.is_flex) {
+                replaced.push(slot);
+            } else {
+                open[index] = false;
+                fixed[index] = slot.recipe_id.and_then(|rid| context.index.get(&rid).copied());
+                if let (true, None, false, Some(rid)) =
+                    (slot.is_leftover, slot.leftover_of_slot_id, slot.is_completed, slot.recipe_id)
+                {
+                    *planned_leftovers.entry(rid).or_default() += slot.servings_override.unwrap_or(household_size);
+                }
+            }
+        }
+
+        // ── Stored leftovers go first, before they expire ─────────────────────
+
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        let today = Utc::now().date_naive();
+        let stored = leftover::Entity::find()
+            .filter(scope.condition(leftover::Column::UserId, leftover::Column::HouseholdId))
+            .filter(leftover::Column::ExpiryDate.gte(today.max(plan.week_start)))
+            .order_by_asc(leftover::Column::ExpiryDate)
+            .all(&self.db)
+            .await?;
+
+        let mut leftover_meals: HashMap<usize, (i64, i32)> = HashMap::new();
+        for item in stored {
+            let Some(&source) = context.index.get(&item.recipe_id) else { continue };
+            if context.excluded.iter().any(|e| e.recipe_id == item.recipe_id) {
+                continue;
+            }
+            let already = planned_leftovers.entry(item.recipe_id).or_default();
+            let mut servings = item.servings - *already;
+            *already = (*already - item.servings).max(0);
+
+            for day in 0..DAYS {
+                let date = plan.week_start + Duration::days(day as i64);
+                if servings <= 0 || date > item.expiry_date {
+                    break;
+                }
+                for slot in [slot_at(day, LUNCH), slot_at(day, DINNER)] {
+                    if servings <= 0 || date < today || !open[slot] {
+                        continue;
                     }
+                    let eaten = servings.min(household_size);
+                    // Already paid for, and eating it before it spoils is the point
+                    let mut candidate = context.candidates[source].clone();
+                    candidate.meal_types = [false; 4];
+                    candidate.cost = 0.0;
+                    candidate.score = 1.0;
+                    fixed[slot] = Some(context.candidates.len());
+                    context.candidates.push(candidate);
+                    open[slot] = false;
+                    leftover_meals.insert(slot, (item.recipe_id, eaten));
+                    servings -= eaten;
                 }
             }
         }
 
-        // ── 4. Save meal plan + slots to database ─────────────────────────────
+        // ── Optimise the open slots around the kept ones ──────────────────────
 
-        let now = Utc::now().fixed_offset();
+        let week = Optimiser::new(&context.candidates, &context.prefs).fill_around(seed as u64, &fixed, &open);
+        let batches = batch_days(&week, |day| open[slot_at(day, DINNER)] && open[slot_at(day + 1, LUNCH)]);
 
-        if let Some(existing) = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
-            .filter(meal_plan::Column::WeekStart.eq(week_start))
-            .one(&self.db)
-            .await?
-        {
+        // Swap the replaced slots for the new ones atomically, so a failed
+        // insert can't leave the week half-emptied
+        let txn = self.db.begin().await?;
+        for slot in &replaced {
+            Self::detach_leftovers(&txn, slot).await?;
+        }
+        if !replaced.is_empty() {
             meal_plan_slot::Entity::delete_many()
-                .filter(meal_plan_slot::Column::MealPlanId.eq(existing.id))
-                .exec(&self.db)
-                .await?;
-
-            meal_plan::Entity::delete_by_id(existing.id)
-                .exec(&self.db)
+                .filter(meal_plan_slot::Column::Id.is_in(replaced.iter().map(|s| s.id).collect::<Vec<_>>()))
+                .exec(&txn)
                 .await?;
         }
 
-        let plan = meal_plan::ActiveModel {
-            user_id: Set(user_id),
-            week_start: Set(week_start),
-            is_ai_generated: Set(true),
-            created_at: Set(now),
-            updated_at: Set(now),
-            ..Default::default()
-        };
-
-        let saved_plan = plan.insert(&self.db).await?;
-
-        for (recipe_id, day, meal_type) in selected {
-            let slot = meal_plan_slot::ActiveModel {
-                meal_plan_id: Set(saved_plan.id),
-                recipe_id: Set(Some(recipe_id)),
+        let mut batch_cooks: HashMap<usize, i64> = HashMap::new();
+        for (slot, chosen) in week.iter().enumerate() {
+            let (day, meal) = (slot_day(slot), slot_meal(slot));
+            let mut new_slot = meal_plan_slot::ActiveModel {
+                meal_plan_id: Set(plan.id),
                 day_of_week: Set(day as i16),
-                meal_type: Set(meal_type.to_string()),
+                meal_type: Set(MEAL_TYPES[meal].to_string()),
                 servings_override: Set(Some(household_size)),
                 is_completed: Set(false),
+                is_locked: Set(false),
                 ..Default::default()
             };
-            slot.insert(&self.db).await?;
+
+            if let Some((recipe_id, servings)) = leftover_meals.get(&slot) {
+                new_slot.recipe_id = Set(Some(*recipe_id));
+                new_slot.servings_override = Set(Some(*servings));
+                new_slot.is_leftover = Set(true);
+            } else if open[slot] {
+                let Some(i) = chosen else { continue };
+                new_slot.recipe_id = Set(Some(context.candidates[*i].recipe_id));
+                if meal == DINNER && batches.contains(&day) {
+                    new_slot.servings_override = Set(Some(household_size * 2));
+                } else if meal == LUNCH && day > 0 && batches.contains(&(day - 1)) {
+                    new_slot.is_leftover = Set(true);
+                    new_slot.leftover_of_slot_id = Set(batch_cooks.get(&(day - 1)).copied());
+                }
+            } else {
+                continue;
+            }
+
+            let saved = new_slot.insert(&txn).await?;
+            if meal == DINNER && batches.contains(&day) {
+                batch_cooks.insert(day, saved.id);
+            }
         }
 
-        Ok(saved_plan)
+        let saved_slots = meal_plan_slot::Entity::find()
+            .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
+            .all(&txn)
+            .await?;
+        txn.commit().await?;
+        let (_, cost) = cost_breakdown(&saved_slots, &context.recipe_servings, &context.recipe_costs, context.budget);
+        if cost.over_budget {
+            tracing::info!(
+                "Meal plan {} for user {}: estimated cost {} exceeds weekly budget {:?}",
+                plan.id, user_id, cost.total, context.budget
+            );
+        }
+
+        if !context.excluded.is_empty() {
+            tracing::info!(
+                "Meal plan {} for user {}: {} recipes excluded by dietary constraints",
+                plan.id, user_id, context.excluded.len()
+            );
+        }
+
+        Ok(GeneratedPlan { plan, excluded: context.excluded, cost })
+    }
+
+    /// Break a slot's leftover links before its recipe changes or it is
+    /// removed. Slots that ate its leftovers now cook for themselves and it
+    /// no longer cooks extra for them; if it ate another slot's leftovers,
+    /// that slot no longer cooks extra either. Completed slots keep their
+    /// servings — that cooking already happened.
+    async fn detach_leftovers<C: ConnectionTrait>(db: &C, slot: &meal_plan_slot::Model) -> Result<(), AppError> {
+        let eaters = meal_plan_slot::Entity::find()
+            .filter(meal_plan_slot::Column::LeftoverOfSlotId.eq(slot.id))
+            .all(db)
+            .await?;
+        let mut extra = 0;
+        for eater in eaters {
+            extra += eater.servings_override.unwrap_or(0);
+            let mut active: meal_plan_slot::ActiveModel = eater.into();
+            active.is_leftover = Set(false);
+            active.leftover_of_slot_id = Set(None);
+            active.update(db).await?;
+        }
+        if extra > 0 {
+            Self::cook_fewer(db, slot.clone(), extra).await?;
+        }
+
+        let Some(cook_id) = slot.leftover_of_slot_id else { return Ok(()) };
+        if let Some(cook) = meal_plan_slot::Entity::find_by_id(cook_id).one(db).await? {
+            Self::cook_fewer(db, cook, slot.servings_override.unwrap_or(0)).await?;
+        }
+        Ok(())
+    }
+
+    async fn cook_fewer<C: ConnectionTrait>(db: &C, slot: meal_plan_slot::Model, servings: i32) -> Result<(), AppError> {
+        if slot.is_completed {
+            return Ok(());
+        }
+        let remaining = (slot.servings_override.unwrap_or(1) - servings).max(1);
+        let mut active: meal_plan_slot::ActiveModel = slot.into();
+        active.servings_override = Set(Some(remaining));
+        active.update(db).await?;
+        Ok(())
+    }
+
+    /// Load ingredient-level allergens for every recipe in `recipe_ingredients`
+    async fn load_recipe_allergens(
+        &self,
+        recipe_ingredients: &[recipe_ingredient::Model],
+    ) -> Result<HashMap<i64, Vec<RecipeAllergen>>, AppError> {
+        let ingredient_ids: std::collections::HashSet<i64> =
+            recipe_ingredients.iter().map(|ri| ri.ingredient_id).collect();
+
+        let rows = ingredient_allergen::Entity::find()
+            .filter(ingredient_allergen::Column::IngredientId.is_in(ingredient_ids))
+            .all(&self.db)
+            .await?;
+
+        if rows.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let names: HashMap<i64, String> = ingredient::Entity::find()
+            .filter(ingredient::Column::Id.is_in(rows.iter().map(|a| a.ingredient_id).collect::<Vec<_>>()))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|i| (i.id, i.name))
+            .collect();
+
+        let mut by_ingredient: HashMap<i64, Vec<RecipeAllergen>> = HashMap::new();
+        for a in rows {
+            by_ingredient.entry(a.ingredient_id).or_default().push(RecipeAllergen {
+                ingredient_name: names.get(&a.ingredient_id).cloned().unwrap_or_default(),
+                allergen: a.allergen,
+                severity: a.severity,
+            });
+        }
+
+        let pairs: Vec<(i64, i64)> = recipe_ingredients
+            .iter()
+            .map(|ri| (ri.recipe_id, ri.ingredient_id))
+            .collect();
+        Ok(dietary::allergens_by_recipe(&pairs, &by_ingredient))
     }
 
     /// Heuristic: which meal types fit a recipe's category (static version for closures)
@@ -314,8 +709,9 @@ impl MealPlanService {
         let days_since_monday = today.weekday().num_days_from_monday() as i64;
         let week_start = today - chrono::Duration::days(days_since_monday);
 
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let plan = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(plan_scope(&scope))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await?;
@@ -324,7 +720,7 @@ impl MealPlanService {
             return Ok(None);
         };
 
-        Ok(Some(self.plan_to_json(&plan).await?))
+        Ok(Some(self.plan_to_json(user_id, &plan).await?))
     }
 
     /// List all meal plans for a user (newest first, paginated)
@@ -334,8 +730,9 @@ impl MealPlanService {
         page: u64,
         per_page: u64,
     ) -> Result<serde_json::Value, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let paginator = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(plan_scope(&scope))
             .order_by_desc(meal_plan::Column::WeekStart)
             .paginate(&self.db, per_page);
 
@@ -366,22 +763,14 @@ impl MealPlanService {
         user_id: Uuid,
         plan_id: i64,
     ) -> Result<serde_json::Value, AppError> {
-        let plan = meal_plan::Entity::find_by_id(plan_id)
-            .one(&self.db)
-            .await?
-            .filter(|p| p.user_id == user_id)
-            .ok_or(AppError::NotFound("Meal plan".into()))?;
+        let plan = self.find_plan(user_id, plan_id).await?;
 
-        self.plan_to_json(&plan).await
+        self.plan_to_json(user_id, &plan).await
     }
 
     /// Delete a meal plan and all its slots
     pub async fn delete_plan(&self, user_id: Uuid, plan_id: i64) -> Result<(), AppError> {
-        let plan = meal_plan::Entity::find_by_id(plan_id)
-            .one(&self.db)
-            .await?
-            .filter(|p| p.user_id == user_id)
-            .ok_or(AppError::NotFound("Meal plan".into()))?;
+        let plan = self.find_plan(user_id, plan_id).await?;
 
         meal_plan_slot::Entity::delete_many()
             .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
@@ -405,11 +794,7 @@ impl MealPlanService {
         servings: Option<i32>,
     ) -> Result<serde_json::Value, AppError> {
         // Ensure plan belongs to user
-        meal_plan::Entity::find_by_id(plan_id)
-            .one(&self.db)
-            .await?
-            .filter(|p| p.user_id == user_id)
-            .ok_or(AppError::NotFound("Meal plan".into()))?;
+        self.find_plan(user_id, plan_id).await?;
 
         // Check if a slot already exists for this day/meal_type
         let existing = meal_plan_slot::Entity::find()
@@ -421,9 +806,13 @@ impl MealPlanService {
 
         let slot = if let Some(slot) = existing {
             // Update existing
+            Self::detach_leftovers(&self.db, &slot).await?;
             let mut active: meal_plan_slot::ActiveModel = slot.into();
             active.recipe_id = Set(Some(recipe_id));
             active.is_flex = Set(false);
+            active.is_locked = Set(true);
+            active.is_leftover = Set(false);
+            active.leftover_of_slot_id = Set(None);
             active.update(&self.db).await?
         } else {
             // Create new
@@ -435,6 +824,7 @@ impl MealPlanService {
                 servings_override: Set(servings),
                 is_completed: Set(false),
                 is_flex: Set(false),
+                is_locked: Set(true),
                 ..Default::default()
             };
             slot.insert(&self.db).await?
@@ -457,7 +847,8 @@ impl MealPlanService {
         }))
     }
 
-    /// Swap the recipe in a slot (or mark it as a flex day by passing recipe_id=null)
+    /// Swap the recipe in a slot (or mark it as a flex day by passing recipe_id=null).
+    /// The slot is locked so regeneration keeps the user's choice.
     pub async fn swap_slot(
         &self,
         user_id: Uuid,
@@ -467,11 +858,7 @@ impl MealPlanService {
         flex_type: Option<String>,
         energy_level: Option<String>,
     ) -> Result<serde_json::Value, AppError> {
-        meal_plan::Entity::find_by_id(plan_id)
-            .one(&self.db)
-            .await?
-            .filter(|p| p.user_id == user_id)
-            .ok_or(AppError::NotFound("Meal plan".into()))?;
+        self.find_plan(user_id, plan_id).await?;
 
         let slot = meal_plan_slot::Entity::find_by_id(slot_id)
             .one(&self.db)
@@ -479,9 +866,18 @@ impl MealPlanService {
             .filter(|s| s.meal_plan_id == plan_id)
             .ok_or(AppError::NotFound("Slot".into()))?;
 
+        let recipe_changed = slot.recipe_id != recipe_id;
+        if recipe_changed {
+            Self::detach_leftovers(&self.db, &slot).await?;
+        }
         let mut active: meal_plan_slot::ActiveModel = slot.into();
+        if recipe_changed {
+            active.is_leftover = Set(false);
+            active.leftover_of_slot_id = Set(None);
+        }
         active.recipe_id = Set(recipe_id);
         active.is_flex = Set(recipe_id.is_none());
+        active.is_locked = Set(true);
         if let Some(ft) = flex_type {
             active.flex_type = Set(Some(ft));
         }
@@ -494,6 +890,7 @@ impl MealPlanService {
             "id": updated.id,
             "recipe_id": updated.recipe_id,
             "is_flex": updated.is_flex,
+            "is_locked": updated.is_locked,
             "flex_type": updated.flex_type,
             "energy_level": updated.energy_level,
             "meal_type": updated.meal_type,
@@ -515,17 +912,13 @@ impl MealPlanService {
         Ok(())
     }
 
-    /// Weekly nutrition summary: macro totals for all slots vs user goals
+    /// Weekly nutrition summary: macro totals for all slots vs the household's targets
     pub async fn get_nutrition_summary(
         &self,
         user_id: Uuid,
         plan_id: i64,
     ) -> Result<serde_json::Value, AppError> {
-        let plan = meal_plan::Entity::find_by_id(plan_id)
-            .one(&self.db)
-            .await?
-            .filter(|p| p.user_id == user_id)
-            .ok_or(AppError::NotFound("Meal plan".into()))?;
+        let plan = self.find_plan(user_id, plan_id).await?;
 
         let slots = meal_plan_slot::Entity::find()
             .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
@@ -552,10 +945,18 @@ impl MealPlanService {
 
         let mut totals = NutritionTotals::default();
 
+        // A batch cook's extra servings are eaten (and counted) in its leftover slots
+        let mut saved_for_later: HashMap<i64, i32> = HashMap::new();
+        for slot in &slots {
+            if let Some(cook) = slot.leftover_of_slot_id {
+                *saved_for_later.entry(cook).or_default() += slot.servings_override.unwrap_or(1);
+            }
+        }
+
         for slot in &slots {
             let Some(rid) = slot.recipe_id else { continue };
             let Some(n) = nutrition.get(&rid) else { continue };
-            let servings = slot.servings_override.unwrap_or(1);
+            let servings = slot.servings_override.unwrap_or(1) - saved_for_later.get(&slot.id).copied().unwrap_or(0);
             let recipe_servings = recipes.get(&rid).map(|r| r.servings).unwrap_or(1).max(1);
             let scale = servings as f64 / recipe_servings as f64;
 
@@ -567,6 +968,11 @@ impl MealPlanService {
         }
 
         let slot_count = slots.len().max(1) as f64;
+        let (_, cost) = self.plan_cost(user_id, &slots, &recipes).await?;
+        // Slots are served to the whole household, so compare against
+        // everyone's targets rather than one person's
+        let household = nutrition::household_targets(&self.db, user_id).await?;
+        let goals = household.daily.scale(7.0);
 
         Ok(serde_json::json!({
             "week_start": plan.week_start,
@@ -584,48 +990,50 @@ impl MealPlanService {
                 "fat_g":     totals.fat_g / 7.0,
                 "fiber_g":   totals.fiber_g / 7.0,
             },
-            "goals": {
-                "calories":  DAILY_CALORIES * 7.0,
-                "protein_g": DAILY_PROTEIN_G * 7.0,
-                "carbs_g":   DAILY_CARBS_G * 7.0,
-                "fat_g":     DAILY_FAT_G * 7.0,
-                "fiber_g":   DAILY_FIBER_G * 7.0,
-            },
+            "goals": goals,
             "percent_of_goal": {
-                "calories":  (totals.calories  / (DAILY_CALORIES  * 7.0) * 100.0).round(),
-                "protein_g": (totals.protein_g / (DAILY_PROTEIN_G * 7.0) * 100.0).round(),
-                "carbs_g":   (totals.carbs_g   / (DAILY_CARBS_G   * 7.0) * 100.0).round(),
-                "fat_g":     (totals.fat_g     / (DAILY_FAT_G     * 7.0) * 100.0).round(),
-                "fiber_g":   (totals.fiber_g   / (DAILY_FIBER_G   * 7.0) * 100.0).round(),
+                "calories":  percent(totals.calories,  goals.calories),
+                "protein_g": percent(totals.protein_g, goals.protein_g),
+                "carbs_g":   percent(totals.carbs_g,   goals.carbs_g),
+                "fat_g":     percent(totals.fat_g,     goals.fat_g),
+                "fiber_g":   percent(totals.fiber_g,   goals.fiber_g),
             },
+            "targets": household,
             "slots_with_data": slot_count as usize,
+            "cost": cost,
         }))
     }
 
-    /// Generate shopping list: ingredients needed minus what's in inventory
+    /// Generate shopping list: ingredients needed minus what's in inventory.
+    ///
+    /// Both sides are normalised to grams via `UnitConverter`; recipe lines or
+    /// pantry items whose units cannot be converted are reported separately
+    /// instead of being counted as grams.
     pub async fn get_shopping_list(
         &self,
         user_id: Uuid,
-    ) -> Result<Vec<serde_json::Value>, AppError> {
+    ) -> Result<ShoppingListComputation, AppError> {
         use crate::entity::{ingredient, recipe_ingredient};
 
         let today = Utc::now().date_naive();
         let days_since_monday = today.weekday().num_days_from_monday() as i64;
         let week_start = today - chrono::Duration::days(days_since_monday);
 
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let plan = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(plan_scope(&scope))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await?;
 
         let Some(plan) = plan else {
-            return Ok(vec![]);
+            return Ok(ShoppingListComputation::default());
         };
 
         let slots = meal_plan_slot::Entity::find()
             .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
             .filter(meal_plan_slot::Column::IsCompleted.eq(false))
+            .filter(meal_plan_slot::Column::IsLeftover.eq(false))
             .all(&self.db)
             .await?;
 
@@ -636,23 +1044,16 @@ impl MealPlanService {
             .all(&self.db)
             .await?;
 
-        let mut needed: HashMap<i64, rust_decimal::Decimal> = HashMap::new();
-        for ri in &required_ingredients {
-            if let Some(qty) = ri.quantity_grams {
-                *needed.entry(ri.ingredient_id).or_default() += qty;
-            }
-        }
+        let inventory_items = inventory_item::Entity::find()
+            .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
+            .all(&self.db)
+            .await?;
 
-        let inventory: HashMap<i64, rust_decimal::Decimal> =
-            inventory_item::Entity::find()
-                .filter(inventory_item::Column::UserId.eq(user_id))
-                .all(&self.db)
-                .await?
-                .into_iter()
-                .map(|i| (i.ingredient_id, i.quantity))
-                .collect();
+        let mut ingredient_ids: Vec<i64> = required_ingredients.iter().map(|ri| ri.ingredient_id).collect();
+        ingredient_ids.sort_unstable();
+        ingredient_ids.dedup();
 
-        let ingredient_ids: Vec<i64> = needed.keys().cloned().collect();
+        let converter = UnitConverter::load(&self.db, &ingredient_ids).await?;
         let names: HashMap<i64, String> = ingredient::Entity::find()
             .filter(ingredient::Column::Id.is_in(ingredient_ids))
             .all(&self.db)
@@ -660,6 +1061,40 @@ impl MealPlanService {
             .into_iter()
             .map(|i| (i.id, i.name))
             .collect();
+        let name_of = |id: &i64| names.get(id).cloned().unwrap_or_default();
+
+        let mut unconverted = Vec::new();
+
+        let mut needed: HashMap<i64, rust_decimal::Decimal> = HashMap::new();
+        for ri in &required_ingredients {
+            match converter.recipe_grams(ri) {
+                Ok(Some(grams)) => *needed.entry(ri.ingredient_id).or_default() += grams,
+                Ok(None) => {}
+                Err(error) => unconverted.push(UnconvertedItem {
+                    ingredient_id: ri.ingredient_id,
+                    name: name_of(&ri.ingredient_id),
+                    quantity: ri.quantity.unwrap_or_default(),
+                    unit: ri.unit.clone().unwrap_or_default(),
+                    source: QuantitySource::Recipe,
+                    error,
+                }),
+            }
+        }
+
+        let mut inventory: HashMap<i64, rust_decimal::Decimal> = HashMap::new();
+        for item in inventory_items.into_iter().filter(|i| needed.contains_key(&i.ingredient_id)) {
+            match converter.to_grams(item.ingredient_id, item.quantity, &item.unit) {
+                Ok(grams) => *inventory.entry(item.ingredient_id).or_default() += grams,
+                Err(error) => unconverted.push(UnconvertedItem {
+                    ingredient_id: item.ingredient_id,
+                    name: name_of(&item.ingredient_id),
+                    quantity: item.quantity,
+                    unit: item.unit,
+                    source: QuantitySource::Inventory,
+                    error,
+                }),
+            }
+        }
 
         let mut list = Vec::new();
         for (ingredient_id, needed_qty) in &needed {
@@ -668,7 +1103,7 @@ impl MealPlanService {
                 let to_buy = needed_qty - have;
                 list.push(serde_json::json!({
                     "ingredient_id": ingredient_id,
-                    "name": names.get(ingredient_id).cloned().unwrap_or_default(),
+                    "name": name_of(ingredient_id),
                     "needed_grams": needed_qty,
                     "have_grams": have,
                     "to_buy_grams": to_buy,
@@ -681,7 +1116,7 @@ impl MealPlanService {
             a["name"].as_str().unwrap_or("").cmp(b["name"].as_str().unwrap_or(""))
         });
 
-        Ok(list)
+        Ok(ShoppingListComputation { items: list, unconverted })
     }
 
     /// Mark a meal plan slot as completed
@@ -691,22 +1126,15 @@ impl MealPlanService {
         plan_id: i64,
         slot_id: i64,
     ) -> Result<(), AppError> {
-        meal_plan::Entity::find_by_id(plan_id)
-            .one(&self.db)
-            .await?
-            .filter(|p| p.user_id == user_id)
-            .ok_or(AppError::NotFound("Meal plan".into()))?;
+        self.find_plan(user_id, plan_id).await?;
 
         let slot = meal_plan_slot::Entity::find_by_id(slot_id)
             .one(&self.db)
             .await?
+            .filter(|s| s.meal_plan_id == plan_id)
             .ok_or(AppError::NotFound("Slot".into()))?;
 
-        let mut active: meal_plan_slot::ActiveModel = slot.into();
-        active.is_completed = Set(true);
-        active.update(&self.db).await?;
-
-        Ok(())
+        self.complete_slot(user_id, slot).await
     }
 
     // ── AI tool helpers ───────────────────────────────────────────────────────
@@ -720,8 +1148,9 @@ impl MealPlanService {
         let days_since_monday = today.weekday().num_days_from_monday() as i64;
         let week_start = today - Duration::days(days_since_monday);
 
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let plan = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(plan_scope(&scope))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await?;
@@ -777,7 +1206,7 @@ impl MealPlanService {
     }
 
     /// Replace the recipe in a specific meal slot. Returns the new recipe name.
-    /// Security: verifies the meal plan is owned by user_id.
+    /// Security: only the user's (or their household's) plan is considered.
     pub async fn update_slot_recipe(
         &self,
         user_id: Uuid,
@@ -789,8 +1218,9 @@ impl MealPlanService {
         let days_since_monday = today.weekday().num_days_from_monday() as i64;
         let week_start = today - Duration::days(days_since_monday);
 
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let plan = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(plan_scope(&scope))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await?
@@ -810,16 +1240,19 @@ impl MealPlanService {
             .await?
             .ok_or_else(|| AppError::NotFound("Slot".into()))?;
 
+        Self::detach_leftovers(&self.db, &slot).await?;
         let mut active: meal_plan_slot::ActiveModel = slot.into();
         active.recipe_id = Set(Some(recipe_id));
         active.is_completed = Set(false);
+        active.is_leftover = Set(false);
+        active.leftover_of_slot_id = Set(None);
         active.update(&self.db).await?;
 
         Ok(recipe_name)
     }
 
     /// Mark a meal slot as completed.
-    /// Security: verifies ownership via plan.user_id.
+    /// Security: only the user's (or their household's) plan is considered.
     pub async fn mark_slot_completed(
         &self,
         user_id: Uuid,
@@ -830,8 +1263,9 @@ impl MealPlanService {
         let days_since_monday = today.weekday().num_days_from_monday() as i64;
         let week_start = today - Duration::days(days_since_monday);
 
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let plan = meal_plan::Entity::find()
-            .filter(meal_plan::Column::UserId.eq(user_id))
+            .filter(plan_scope(&scope))
             .filter(meal_plan::Column::WeekStart.eq(week_start))
             .one(&self.db)
             .await?
@@ -845,16 +1279,85 @@ impl MealPlanService {
             .await?
             .ok_or_else(|| AppError::NotFound("Slot".into()))?;
 
-        let mut active: meal_plan_slot::ActiveModel = slot.into();
-        active.is_completed = Set(true);
-        active.update(&self.db).await?;
+        self.complete_slot(user_id, slot).await
+    }
+
+    // ── Internal helpers ──────────────────────────────────────────────────────
+
+    /// Load a plan the user may access — their own, or their household's
+    async fn find_plan(&self, user_id: Uuid, plan_id: i64) -> Result<meal_plan::Model, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
+        meal_plan::Entity::find_by_id(plan_id)
+            .one(&self.db)
+            .await?
+            .filter(|p| scope.contains(p.user_id, p.household_id))
+            .ok_or(AppError::NotFound("Meal plan".into()))
+    }
+
+    /// Mark a slot completed. A slot cooked for more than the household (a
+    /// batch-cook dinner) stores the extra servings as leftovers; a leftover
+    /// slot eats its servings from the stored leftovers, as far as there are any
+    async fn complete_slot(&self, user_id: Uuid, slot: meal_plan_slot::Model) -> Result<(), AppError> {
+        let txn = self.db.begin().await?;
+
+        // Claim the slot, so of two concurrent completions only one touches
+        // the leftovers
+        let claimed = meal_plan_slot::Entity::update_many()
+            .col_expr(meal_plan_slot::Column::IsCompleted, Expr::value(true))
+            .filter(meal_plan_slot::Column::Id.eq(slot.id))
+            .filter(meal_plan_slot::Column::IsCompleted.eq(false))
+            .exec(&txn)
+            .await?
+            .rows_affected
+            == 1;
+
+        if let (true, Some(rid)) = (claimed, slot.recipe_id) {
+            if slot.is_leftover {
+                InventoryService::eat_leftovers_of_recipe(&txn, user_id, rid, slot.servings_override.unwrap_or(1))
+                    .await?;
+            } else {
+                let household_size = user::Entity::find_by_id(user_id)
+                    .one(&txn)
+                    .await?
+                    .map_or(1, |u| u.household_size);
+                let extra = slot.servings_override.unwrap_or(household_size) - household_size;
+                if extra > 0 {
+                    if let Some(recipe) = recipe::Entity::find_by_id(rid).one(&txn).await? {
+                        InventoryService::store_leftovers(&txn, user_id, &recipe, extra).await?;
+                    }
+                }
+            }
+        }
 
+        txn.commit().await?;
         Ok(())
     }
 
-    // ── Internal helpers ──────────────────────────────────────────────────────
+    /// Estimated cost per slot (keyed by slot id) and for the whole plan,
+    /// against the requesting user's weekly budget
+    async fn plan_cost(
+        &self,
+        user_id: Uuid,
+        slots: &[meal_plan_slot::Model],
+        recipes: &HashMap<i64, recipe::Model>,
+    ) -> Result<(HashMap<i64, Decimal>, PlanCost), AppError> {
+        let budget = user::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .and_then(|u| u.weekly_budget)
+            .filter(|b| *b > Decimal::ZERO);
 
-    async fn plan_to_json(&self, plan: &meal_plan::Model) -> Result<serde_json::Value, AppError> {
+        let lines = recipe_ingredient::Entity::find()
+            .filter(recipe_ingredient::Column::RecipeId.is_in(recipes.keys().copied().collect::<Vec<_>>()))
+            .all(&self.db)
+            .await?;
+        let costs = pricing::recipe_costs(&self.db, &lines).await?;
+        let recipe_servings: HashMap<i64, i32> = recipes.values().map(|r| (r.id, r.servings)).collect();
+
+        Ok(cost_breakdown(slots, &recipe_servings, &costs, budget))
+    }
+
+    async fn plan_to_json(&self, user_id: Uuid, plan: &meal_plan::Model) -> Result<serde_json::Value, AppError> {
         let slots = meal_plan_slot::Entity::find()
             .filter(meal_plan_slot::Column::MealPlanId.eq(plan.id))
             .order_by_asc(meal_plan_slot::Column::DayOfWeek)
@@ -870,6 +1373,8 @@ impl MealPlanService {
             .map(|r| (r.id, r))
             .collect();
 
+        let (slot_costs, cost) = self.plan_cost(user_id, &slots, &recipes).await?;
+
         let slot_json: Vec<serde_json::Value> = slots
             .into_iter()
             .map(|s| {
@@ -880,9 +1385,13 @@ impl MealPlanService {
                     "meal_type": s.meal_type,
                     "is_completed": s.is_completed,
                     "is_flex": s.is_flex,
+                    "is_locked": s.is_locked,
+                    "is_leftover": s.is_leftover,
+                    "leftover_of_slot_id": s.leftover_of_slot_id,
                     "flex_type": s.flex_type,
                     "energy_level": s.energy_level,
                     "servings": s.servings_override,
+                    "estimated_cost": slot_costs.get(&s.id),
                     "recipe": r.map(|r| serde_json::json!({
                         "id": r.id,
                         "name": r.name,
@@ -900,7 +1409,9 @@ impl MealPlanService {
             "id": plan.id,
             "week_start": plan.week_start,
             "is_ai_generated": plan.is_ai_generated,
+            "generation_seed": plan.generation_seed,
             "slots": slot_json,
+            "cost": cost,
         }))
     }
 }
@@ -914,3 +1425,157 @@ struct NutritionTotals {
     fiber_g: f64,
 }
 
+
+/// Cost of each slot (keyed by slot id) and the plan total. Slots are priced
+/// for their servings, like the nutrition summary.
+fn cost_breakdown(
+    slots: &[meal_plan_slot::Model],
+    recipe_servings: &HashMap<i64, i32>,
+    costs: &HashMap<i64, RecipeCost>,
+    budget: Option<Decimal>,
+) -> (HashMap<i64, Decimal>, PlanCost) {
+    let mut slot_costs = HashMap::new();
+    let mut plan = PlanCost {
+        by_day: vec![Decimal::ZERO; 7],
+        weekly_budget: budget,
+        ..Default::default()
+    };
+    let mut by_meal_type: BTreeMap<String, Decimal> = BTreeMap::new();
+
+    for slot in slots {
+        // Leftovers were paid for when they were cooked
+        if slot.is_leftover {
+            continue;
+        }
+        let Some(rid) = slot.recipe_id else { continue };
+        let Some(recipe_cost) = costs.get(&rid) else { continue };
+        let recipe_servings = recipe_servings.get(&rid).copied().unwrap_or(1);
+        let cost = recipe_cost.for_servings(slot.servings_override.unwrap_or(1), recipe_servings);
+
+        plan.total += cost.total;
+        plan.from_promotions += cost.from_promotions;
+        if let Some(day) = plan.by_day.get_mut(slot.day_of_week as usize) {
+            *day += cost.total;
+        }
+        *by_meal_type.entry(slot.meal_type.clone()).or_default() += cost.total;
+        for id in cost.unpriced {
+            if !plan.unpriced_ingredient_ids.contains(&id) {
+                plan.unpriced_ingredient_ids.push(id);
+            }
+        }
+        slot_costs.insert(slot.id, cost.total);
+    }
+
+    plan.by_meal_type = by_meal_type;
+    plan.remaining = budget.map(|b| b - plan.total);
+    plan.over_budget = plan.remaining.is_some_and(|r| r < Decimal::ZERO);
+    (slot_costs, plan)
+}
+
+fn percent(value: f64, goal: f64) -> f64 {
+    if goal > 0.0 {
+        (value / goal * 100.0).round()
+    } else {
+        0.0
+    }
+}
+
+/// Optimiser slot for a saved slot; `None` for days or meal types outside the grid
+fn slot_index(slot: &meal_plan_slot::Model) -> Option<usize> {
+    let meal = MEAL_TYPES.iter().position(|mt| *mt == slot.meal_type)?;
+    let day = usize::try_from(slot.day_of_week).ok().filter(|d| *d < DAYS)?;
+    Some(day * MEAL_TYPES.len() + meal)
+}
+
+fn in_scope(target: &RegenerateScope, slot: usize) -> bool {
+    match target {
+        RegenerateScope::Unlocked => true,
+        RegenerateScope::Day { day_of_week } => slot_day(slot) as i16 == *day_of_week,
+        RegenerateScope::MealType { meal_type } => MEAL_TYPES[slot_meal(slot)] == meal_type,
+    }
+}
+
+fn plan_error(field: &'static str, message: &'static str) -> AppError {
+    let mut errors = validator::ValidationErrors::new();
+    let mut e = validator::ValidationError::new("meal_plan");
+    e.message = Some(message.into());
+    errors.add(field, e);
+    AppError::Validation(errors)
+}
+
+fn plan_scope(scope: &DataScope) -> sea_orm::Condition {
+    scope.condition(meal_plan::Column::UserId, meal_plan::Column::HouseholdId)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn completing_a_batch_cook_feeds_the_leftover_lunch() {
+        let db = test_db::connect().await;
+        let user = test_db::create_user(&db).await;
+        let household = user.household_size;
+        let recipe = test_db::create_recipe(&db, household).await;
+        let plan = test_db::create_plan(&db, user.id, Utc::now().date_naive()).await;
+
+        let dinner = test_db::add_slot(&db, &plan, 0, "dinner", Some(recipe.id)).await;
+        let mut active: meal_plan_slot::ActiveModel = dinner.clone().into();
+        active.servings_override = Set(Some(household * 2));
+        active.update(&db).await.unwrap();
+
+        let lunch = test_db::add_slot(&db, &plan, 1, "lunch", Some(recipe.id)).await;
+        let mut active: meal_plan_slot::ActiveModel = lunch.clone().into();
+        active.servings_override = Set(Some(household));
+        active.is_leftover = Set(true);
+        active.leftover_of_slot_id = Set(Some(dinner.id));
+        active.update(&db).await.unwrap();
+
+        let svc = MealPlanService::new(db.clone());
+        let stored = |db: DatabaseConnection| async move {
+            leftover::Entity::find()
+                .filter(leftover::Column::UserId.eq(user.id))
+                .filter(leftover::Column::RecipeId.eq(recipe.id))
+                .all(&db)
+                .await
+                .unwrap()
+                .iter()
+                .map(|l| l.servings)
+                .sum::<i32>()
+        };
+
+        // Completing it twice at once (say, app and chat) cooks one batch
+        let (a, b) = futures::join!(
+            svc.mark_slot_complete(user.id, plan.id, dinner.id),
+            svc.mark_slot_complete(user.id, plan.id, dinner.id),
+        );
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(stored(db.clone()).await, household);
+
+        // Last week's batch has gone off and isn't eaten
+        let now = Utc::now().fixed_offset();
+        leftover::ActiveModel {
+            user_id: Set(user.id),
+            recipe_id: Set(recipe.id),
+            servings: Set(5),
+            storage_location: Set("fridge".into()),
+            expiry_date: Set(now.date_naive() - Duration::days(1)),
+            cooked_at: Set(now - Duration::days(4)),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let (a, b) = futures::join!(
+            svc.mark_slot_complete(user.id, plan.id, lunch.id),
+            svc.mark_slot_complete(user.id, plan.id, lunch.id),
+        );
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(stored(db.clone()).await, 5);
+    }
+}
@@ -1,25 +1,42 @@
 //! Business-logic services; each module owns one domain area.
 pub mod auth;
+pub mod session;
 pub mod token;
+pub mod totp;
 pub mod recipe;
 pub mod ingredient;
 pub mod preference;
 pub mod meal_plan;
+pub mod plan_optimizer;
+pub mod dietary;
 pub mod inventory;
+pub mod units;
+pub mod nutrition;
+pub mod pricing;
 pub mod profile;
 pub mod interaction;
+pub mod llm;
 pub mod chat;
+pub mod chat_context;
 pub mod chat_tools;
 pub mod onboarding;
 pub mod shopping_list;
+pub mod household;
 pub mod subscription;
+pub mod stripe;
 pub mod store;
+pub mod promotion_match;
+pub mod pdf_worker;
 pub mod push_token;
+pub mod push;
+pub mod notification;
+pub mod scheduler;
 pub mod email;
 pub mod scan;
 pub mod recipe_gen;
 
 pub use auth::AuthService;
+pub use session::SessionService;
 pub use token::TokenService;
 pub use recipe::RecipeService;
 pub use ingredient::IngredientService;
@@ -31,9 +48,12 @@ pub use interaction::InteractionService;
 pub use chat::ChatService;
 pub use onboarding::OnboardingService;
 pub use shopping_list::ShoppingListService;
+pub use household::HouseholdService;
 pub use subscription::SubscriptionService;
 pub use store::StoreService;
 pub use push_token::PushTokenService;
+pub use notification::NotificationService;
+pub use scheduler::SchedulerService;
 pub use email::EmailService;
 pub use scan::ScanService;
 pub use recipe_gen::RecipeGenService;
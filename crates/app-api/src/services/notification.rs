@@ -0,0 +1,552 @@
+//! Notification service — queue push notifications and deliver them.
+//!
+//! Anything that wants to notify a user calls [`enqueue`], which only writes
+//! a row to `notification_outbox`, so it is cheap and can run inside the
+//! caller's transaction. A background dispatcher drains due rows:
+//!
+//! - rows are claimed with `FOR UPDATE SKIP LOCKED` and leased for a few
+//!   minutes, so several replicas never push the same notification and a
+//!   crash mid-send only delays it
+//! - the user's preferences are read at send time: disabled kinds are
+//!   skipped and quiet hours push the row back to the end of the window
+//! - every registered device gets the message; tokens the provider rejects
+//!   are deleted
+//! - if no device received it and some failure was transient, the row is
+//!   retried with exponential backoff up to [`MAX_ATTEMPTS`]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    QueryFilter, Set, Statement,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::entity::{notification_outbox, notification_preference, user_push_token};
+use crate::models::notification::{
+    NotificationPreferencesResponse, QuietHours, UpdateNotificationPreferencesRequest,
+};
+use crate::services::push::{PushMessage, PushOutcome, SharedPush};
+use cookest_shared::errors::AppError;
+
+/// Deliveries tried before a notification is marked failed
+pub const MAX_ATTEMPTS: i32 = 5;
+/// Notifications claimed per dispatcher pass
+const BATCH_SIZE: u64 = 50;
+/// How long a claimed row is hidden from other dispatchers
+const LEASE_SECS: i64 = 300;
+/// First retry delay; each later retry waits four times longer
+const BACKOFF_BASE_SECS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// Food or leftovers about to expire
+    Expiry,
+    /// A meal plan was generated or changed
+    MealPlan,
+    /// Someone joined or left the household
+    Household,
+    /// Sent on request from the settings screen
+    Test,
+}
+
+impl NotificationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::Expiry => "expiry",
+            NotificationKind::MealPlan => "meal_plan",
+            NotificationKind::Household => "household",
+            NotificationKind::Test => "test",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "expiry" => Some(NotificationKind::Expiry),
+            "meal_plan" => Some(NotificationKind::MealPlan),
+            "household" => Some(NotificationKind::Household),
+            "test" => Some(NotificationKind::Test),
+            _ => None,
+        }
+    }
+
+    fn allowed_by(self, prefs: &notification_preference::Model) -> bool {
+        prefs.enabled
+            && match self {
+                NotificationKind::Expiry => prefs.expiry_alerts,
+                NotificationKind::MealPlan => prefs.meal_plan_updates,
+                NotificationKind::Household => prefs.household_activity,
+                NotificationKind::Test => true,
+            }
+    }
+}
+
+/// A notification to queue for one user
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Passed to the app when the notification is opened
+    pub data: Value,
+    /// Queue at most one notification per user with this key
+    pub dedupe_key: Option<String>,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            body: body.into(),
+            data: Value::Object(Default::default()),
+            dedupe_key: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn dedupe(mut self, key: impl Into<String>) -> Self {
+        self.dedupe_key = Some(key.into());
+        self
+    }
+}
+
+/// Queue a notification for delivery. Returns false when one with the same
+/// dedupe key was already queued for this user.
+pub async fn enqueue<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+    notification: Notification,
+) -> Result<bool, AppError> {
+    let now = Utc::now().fixed_offset();
+    let row = notification_outbox::ActiveModel {
+        user_id: Set(user_id),
+        kind: Set(notification.kind.as_str().to_string()),
+        title: Set(notification.title),
+        body: Set(notification.body),
+        data: Set(notification.data),
+        dedupe_key: Set(notification.dedupe_key),
+        status: Set("pending".to_string()),
+        attempts: Set(0),
+        next_attempt_at: Set(now),
+        last_error: Set(None),
+        created_at: Set(now),
+        sent_at: Set(None),
+        ..Default::default()
+    };
+    let inserted = notification_outbox::Entity::insert(row)
+        .on_conflict(
+            OnConflict::columns([notification_outbox::Column::UserId, notification_outbox::Column::DedupeKey])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(inserted > 0)
+}
+
+/// Counts from one dispatcher pass
+#[derive(Debug, Default)]
+pub struct DispatchReport {
+    pub sent: usize,
+    pub retried: usize,
+    pub deferred: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub pruned_tokens: usize,
+}
+
+impl DispatchReport {
+    fn is_empty(&self) -> bool {
+        self.sent + self.retried + self.deferred + self.failed + self.skipped == 0
+    }
+}
+
+pub struct NotificationService {
+    db: DatabaseConnection,
+    push: SharedPush,
+}
+
+impl NotificationService {
+    pub fn new(db: DatabaseConnection, push: SharedPush) -> Self {
+        Self { db, push }
+    }
+
+    pub async fn get_preferences(&self, user_id: Uuid) -> Result<NotificationPreferencesResponse, AppError> {
+        Ok(preferences_response(self.preferences(user_id).await?))
+    }
+
+    pub async fn update_preferences(
+        &self,
+        user_id: Uuid,
+        req: UpdateNotificationPreferencesRequest,
+    ) -> Result<NotificationPreferencesResponse, AppError> {
+        let current = self.preferences(user_id).await?;
+        let (quiet_start, quiet_end) = match req.quiet_hours {
+            Some(q) if q.start == q.end => (None, None),
+            Some(q) => (Some(q.start), Some(q.end)),
+            None => (current.quiet_hours_start, current.quiet_hours_end),
+        };
+        let prefs = notification_preference::ActiveModel {
+            user_id: Set(user_id),
+            enabled: Set(req.enabled.unwrap_or(current.enabled)),
+            expiry_alerts: Set(req.expiry_alerts.unwrap_or(current.expiry_alerts)),
+            meal_plan_updates: Set(req.meal_plan_updates.unwrap_or(current.meal_plan_updates)),
+            household_activity: Set(req.household_activity.unwrap_or(current.household_activity)),
+            quiet_hours_start: Set(quiet_start),
+            quiet_hours_end: Set(quiet_end),
+            utc_offset_minutes: Set(req.utc_offset_minutes.unwrap_or(current.utc_offset_minutes)),
+            updated_at: Set(Utc::now().fixed_offset()),
+        };
+
+        // Upsert: two first saves at once must not both try to insert
+        let saved = notification_preference::Entity::insert(prefs)
+            .on_conflict(
+                OnConflict::column(notification_preference::Column::UserId)
+                    .update_columns([
+                        notification_preference::Column::Enabled,
+                        notification_preference::Column::ExpiryAlerts,
+                        notification_preference::Column::MealPlanUpdates,
+                        notification_preference::Column::HouseholdActivity,
+                        notification_preference::Column::QuietHoursStart,
+                        notification_preference::Column::QuietHoursEnd,
+                        notification_preference::Column::UtcOffsetMinutes,
+                        notification_preference::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec_with_returning(&self.db)
+            .await?;
+        Ok(preferences_response(saved))
+    }
+
+    /// Queue a test notification to every registered device
+    pub async fn send_test(&self, user_id: Uuid) -> Result<(), AppError> {
+        let devices = user_push_token::Entity::find()
+            .filter(user_push_token::Column::UserId.eq(user_id))
+            .all(&self.db)
+            .await?;
+        if devices.is_empty() {
+            return Err(AppError::NotFound("Push token".into()));
+        }
+        enqueue(
+            &self.db,
+            user_id,
+            Notification::new(NotificationKind::Test, "Cookest", "Notifications are working."),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Drain the outbox every `every`, forever
+    pub fn spawn_dispatcher(self: Arc<Self>, every: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                match self.dispatch_due().await {
+                    Ok(report) if !report.is_empty() => tracing::info!("Notification dispatch: {:?}", report),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Notification dispatch failed: {:?}", e),
+                }
+            }
+        });
+    }
+
+    /// Deliver one batch of due notifications
+    pub async fn dispatch_due(&self) -> Result<DispatchReport, AppError> {
+        let mut report = DispatchReport::default();
+        for row in self.claim_due().await? {
+            let id = row.id;
+            if let Err(e) = self.deliver(row, &mut report).await {
+                // The lease expires and the row is picked up again later
+                tracing::error!("Failed to deliver notification {}: {:?}", id, e);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Lease due rows so concurrent dispatchers pass over them
+    async fn claim_due(&self) -> Result<Vec<notification_outbox::Model>, AppError> {
+        let sql = r#"
+            UPDATE notification_outbox
+            SET attempts = attempts + 1,
+                next_attempt_at = NOW() + make_interval(secs => $1)
+            WHERE id IN (
+                SELECT id FROM notification_outbox
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        "#;
+        let rows = notification_outbox::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                [(LEASE_SECS as f64).into(), (BATCH_SIZE as i64).into()],
+            ))
+            .all(&self.db)
+            .await?;
+        Ok(rows)
+    }
+
+    async fn deliver(&self, row: notification_outbox::Model, report: &mut DispatchReport) -> Result<(), AppError> {
+        let prefs = self.preferences(row.user_id).await?;
+        let now = Utc::now();
+
+        let allowed = NotificationKind::parse(&row.kind).is_some_and(|k| k.allowed_by(&prefs));
+        if !allowed {
+            report.skipped += 1;
+            return finish(&self.db, row, "skipped", Some("Turned off in notification settings")).await;
+        }
+
+        // Test messages go out at once; holding others back isn't a delivery attempt
+        if row.kind != NotificationKind::Test.as_str() {
+            if let Some(until) = quiet_until(&prefs, now) {
+                report.deferred += 1;
+                let attempts = row.attempts - 1;
+                let mut active: notification_outbox::ActiveModel = row.into();
+                active.attempts = Set(attempts);
+                active.next_attempt_at = Set(until.fixed_offset());
+                active.update(&self.db).await?;
+                return Ok(());
+            }
+        }
+
+        let devices = user_push_token::Entity::find()
+            .filter(user_push_token::Column::UserId.eq(row.user_id))
+            .all(&self.db)
+            .await?;
+        if devices.is_empty() {
+            report.skipped += 1;
+            return finish(&self.db, row, "skipped", Some("No registered devices")).await;
+        }
+
+        let messages: Vec<PushMessage> = devices
+            .iter()
+            .map(|d| PushMessage {
+                token: d.token.clone(),
+                platform: d.platform.clone(),
+                title: row.title.clone(),
+                body: row.body.clone(),
+                data: row.data.clone(),
+            })
+            .collect();
+
+        let outcomes = match self.push.send(&messages).await {
+            Ok(outcomes) => outcomes,
+            Err(e) => vec![PushOutcome::Failed(error_text(e)); messages.len()],
+        };
+
+        let mut delivered = false;
+        let mut last_failure = None;
+        for (device, outcome) in devices.iter().zip(outcomes) {
+            match outcome {
+                PushOutcome::Delivered => delivered = true,
+                PushOutcome::InvalidToken => {
+                    user_push_token::Entity::delete_by_id(device.id).exec(&self.db).await?;
+                    tracing::info!("Pruned push token {} rejected by the provider", device.id);
+                    report.pruned_tokens += 1;
+                }
+                PushOutcome::Unsupported => {}
+                PushOutcome::Failed(e) => last_failure = Some(e),
+            }
+        }
+
+        if delivered {
+            report.sent += 1;
+            return finish(&self.db, row, "sent", None).await;
+        }
+        let Some(error) = last_failure else {
+            report.skipped += 1;
+            return finish(&self.db, row, "skipped", Some("No device the provider can reach")).await;
+        };
+        if row.attempts >= MAX_ATTEMPTS {
+            report.failed += 1;
+            return finish(&self.db, row, "failed", Some(&error)).await;
+        }
+
+        report.retried += 1;
+        let retry_at = now + chrono::Duration::seconds(backoff_secs(row.attempts));
+        let mut active: notification_outbox::ActiveModel = row.into();
+        active.next_attempt_at = Set(retry_at.fixed_offset());
+        active.last_error = Set(Some(error));
+        active.update(&self.db).await?;
+        Ok(())
+    }
+
+    /// Stored preferences, or the defaults for users who never changed them
+    async fn preferences(&self, user_id: Uuid) -> Result<notification_preference::Model, AppError> {
+        Ok(notification_preference::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .unwrap_or_else(|| notification_preference::Model {
+                user_id,
+                enabled: true,
+                expiry_alerts: true,
+                meal_plan_updates: true,
+                household_activity: true,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
+                utc_offset_minutes: 0,
+                updated_at: Utc::now().fixed_offset(),
+            }))
+    }
+}
+
+/// Close out a notification with a terminal status
+async fn finish(
+    db: &DatabaseConnection,
+    row: notification_outbox::Model,
+    status: &str,
+    error: Option<&str>,
+) -> Result<(), AppError> {
+    let sent = status == "sent";
+    let mut active: notification_outbox::ActiveModel = row.into();
+    active.status = Set(status.to_string());
+    active.last_error = Set(error.map(str::to_string));
+    if sent {
+        active.sent_at = Set(Some(Utc::now().fixed_offset()));
+    }
+    active.update(db).await?;
+    Ok(())
+}
+
+/// The provider's own message — `Internal` hides it behind a generic Display
+fn error_text(e: AppError) -> String {
+    match e {
+        AppError::Internal(msg) => msg,
+        other => other.to_string(),
+    }
+}
+
+/// Wait before retry number `attempts` + 1: 30s, 2m, 8m, 32m, …
+fn backoff_secs(attempts: i32) -> i64 {
+    BACKOFF_BASE_SECS * 4_i64.pow(attempts.clamp(1, 6) as u32 - 1)
+}
+
+/// End of the quiet window `now` falls in, if it falls in one
+fn quiet_until(prefs: &notification_preference::Model, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (start, end) = (prefs.quiet_hours_start?, prefs.quiet_hours_end?);
+    let offset = FixedOffset::east_opt(i32::from(prefs.utc_offset_minutes) * 60)?;
+    let local = now.with_timezone(&offset);
+    let time = local.time();
+
+    let quiet = if start <= end {
+        time >= start && time < end
+    } else {
+        time >= start || time < end
+    };
+    if !quiet {
+        return None;
+    }
+
+    // An overnight window entered this evening ends tomorrow
+    let mut date = local.date_naive();
+    if time >= end {
+        date = date.succ_opt()?;
+    }
+    offset
+        .from_local_datetime(&date.and_time(end))
+        .single()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn preferences_response(p: notification_preference::Model) -> NotificationPreferencesResponse {
+    NotificationPreferencesResponse {
+        enabled: p.enabled,
+        expiry_alerts: p.expiry_alerts,
+        meal_plan_updates: p.meal_plan_updates,
+        household_activity: p.household_activity,
+        quiet_hours: p
+            .quiet_hours_start
+            .zip(p.quiet_hours_end)
+            .map(|(start, end)| QuietHours { start, end }),
+        utc_offset_minutes: p.utc_offset_minutes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn prefs(start: &str, end: &str, offset_minutes: i16) -> notification_preference::Model {
+        notification_preference::Model {
+            user_id: Uuid::nil(),
+            enabled: true,
+            expiry_alerts: true,
+            meal_plan_updates: true,
+            household_activity: false,
+            quiet_hours_start: Some(start.parse().unwrap()),
+            quiet_hours_end: Some(end.parse().unwrap()),
+            utc_offset_minutes: offset_minutes,
+            updated_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn overnight_quiet_hours_end_next_morning() {
+        let p = prefs("22:00", "07:00", 60);
+        // 22:30 local (UTC+1) → held until 07:00 local the next day
+        assert_eq!(quiet_until(&p, at("2026-03-10T21:30:00Z")), Some(at("2026-03-11T06:00:00Z")));
+        // 06:00 local → same morning
+        assert_eq!(quiet_until(&p, at("2026-03-11T05:00:00Z")), Some(at("2026-03-11T06:00:00Z")));
+        // 12:00 local → not quiet
+        assert_eq!(quiet_until(&p, at("2026-03-11T11:00:00Z")), None);
+    }
+
+    #[test]
+    fn daytime_quiet_hours_and_kinds() {
+        let p = prefs("13:00", "14:30", 0);
+        assert_eq!(quiet_until(&p, at("2026-03-10T13:15:00Z")), Some(at("2026-03-10T14:30:00Z")));
+        assert_eq!(quiet_until(&p, at("2026-03-10T14:30:00Z")), None);
+        assert_eq!(p.quiet_hours_end, NaiveTime::from_hms_opt(14, 30, 0));
+
+        assert!(NotificationKind::Expiry.allowed_by(&p));
+        assert!(!NotificationKind::Household.allowed_by(&p));
+        assert!(!NotificationKind::Test.allowed_by(&notification_preference::Model { enabled: false, ..p }));
+    }
+
+    #[test]
+    fn retries_back_off() {
+        assert_eq!(backoff_secs(1), 30);
+        assert_eq!(backoff_secs(2), 120);
+        assert_eq!(backoff_secs(4), 1_920);
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn concurrent_first_saves_both_succeed() {
+        let db = crate::test_db::connect().await;
+        let user = crate::test_db::create_user(&db).await;
+        let svc = NotificationService::new(db, Arc::new(crate::services::push::MockPush));
+        let request = |body: serde_json::Value| -> UpdateNotificationPreferencesRequest {
+            serde_json::from_value(body).unwrap()
+        };
+
+        let (a, b) = futures::join!(
+            svc.update_preferences(user.id, request(serde_json::json!({ "expiry_alerts": false }))),
+            svc.update_preferences(user.id, request(serde_json::json!({ "utc_offset_minutes": 60 }))),
+        );
+        a.unwrap();
+        b.unwrap();
+
+        let saved = svc.update_preferences(user.id, request(serde_json::json!({ "enabled": false }))).await.unwrap();
+        assert!(!saved.enabled);
+    }
+}
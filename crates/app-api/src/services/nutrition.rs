@@ -0,0 +1,289 @@
+//! Daily nutrition targets per person and per household.
+//!
+//! Calories start from the Mifflin-St Jeor BMR (weight, height, age, sex)
+//! times an activity factor, then shift with `health_goals`. Protein follows
+//! body weight, fat and fibre follow calories, and carbs fill the rest. When
+//! metrics are missing, a reference adult (2000 kcal) is used instead, so a
+//! profile with only goals still gets sensible targets.
+//!
+//! Targets are stored on the user (`target_*`) whenever the profile changes,
+//! unless they were entered by hand. A household's target is the sum of its
+//! members' targets plus a reference adult for every extra person counted in
+//! `household_size` who has no account.
+
+use chrono::{Datelike, Utc};
+use sea_orm::{ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TryIntoModel};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::entity::{household_member, user};
+use crate::services::household::DataScope;
+use cookest_shared::errors::AppError;
+
+pub const SEXES: &[&str] = &["female", "male"];
+pub const ACTIVITY_LEVELS: &[&str] = &["sedentary", "light", "moderate", "active", "very_active"];
+
+/// Never plan below this many calories a day
+const MIN_CALORIES: f64 = 1200.0;
+/// Fibre per 1000 kcal
+const FIBER_PER_1000_KCAL: f64 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct NutritionTargets {
+    pub calories: f64,
+    pub protein_g: f64,
+    pub carbs_g: f64,
+    pub fat_g: f64,
+    pub fiber_g: f64,
+}
+
+impl NutritionTargets {
+    /// Reference adult — used for anyone we know nothing about
+    pub const REFERENCE: Self = Self {
+        calories: 2000.0,
+        protein_g: 50.0,
+        carbs_g: 275.0,
+        fat_g: 78.0,
+        fiber_g: 28.0,
+    };
+
+    /// Targets for a person with the given metrics and goals
+    pub fn compute(body: &BodyMetrics, goals: &[String]) -> Self {
+        let has_goal = |names: &[&str]| goals.iter().any(|g| names.contains(&g.to_lowercase().as_str()));
+
+        let maintenance = match (body.weight_kg, body.height_cm, body.age) {
+            (Some(w), Some(h), Some(age)) => {
+                let sex_offset = match body.sex.as_deref() {
+                    Some("male") => 5.0,
+                    Some("female") => -161.0,
+                    _ => -78.0,
+                };
+                (10.0 * w + 6.25 * h - 5.0 * age as f64 + sex_offset) * activity_factor(body.activity_level.as_deref())
+            }
+            _ => Self::REFERENCE.calories,
+        };
+
+        let mut calories = maintenance;
+        if has_goal(&["weight_loss", "lose_weight", "fat_loss"]) {
+            calories *= 0.8;
+        } else if has_goal(&["weight_gain", "gain_weight", "muscle_gain"]) {
+            calories *= 1.1;
+        }
+        let calories = calories.max(MIN_CALORIES).round();
+
+        // Grams per kg of body weight, or share of calories when weight is unknown
+        let (protein_per_kg, protein_share) = if has_goal(&["high_protein", "muscle_gain"]) {
+            (1.6, 0.25)
+        } else if has_goal(&["weight_loss", "lose_weight", "fat_loss"]) {
+            (1.2, 0.20)
+        } else {
+            (0.8, 0.10)
+        };
+        let protein_g = match body.weight_kg {
+            Some(w) => w * protein_per_kg,
+            None => calories * protein_share / 4.0,
+        }
+        .round();
+
+        let (carbs_g, fat_g) = if has_goal(&["low_carb", "keto"]) {
+            let carb_share = if has_goal(&["keto"]) { 0.05 } else { 0.25 };
+            let carbs = (calories * carb_share / 4.0).round();
+            (carbs, ((calories - protein_g * 4.0 - carbs * 4.0) / 9.0).max(0.0).round())
+        } else {
+            let fat = (calories * 0.35 / 9.0).round();
+            (((calories - protein_g * 4.0 - fat * 9.0) / 4.0).max(0.0).round(), fat)
+        };
+
+        Self {
+            calories,
+            protein_g,
+            carbs_g,
+            fat_g,
+            fiber_g: (calories / 1000.0 * FIBER_PER_1000_KCAL).round(),
+        }
+    }
+
+    /// Targets stored on the user, or computed from the profile when none are stored yet
+    pub fn for_user(user: &user::Model) -> Self {
+        match (
+            user.target_calories,
+            user.target_protein_g,
+            user.target_carbs_g,
+            user.target_fat_g,
+            user.target_fiber_g,
+        ) {
+            (Some(calories), Some(protein_g), Some(carbs_g), Some(fat_g), Some(fiber_g)) => Self {
+                calories: calories as f64,
+                protein_g: protein_g as f64,
+                carbs_g: carbs_g as f64,
+                fat_g: fat_g as f64,
+                fiber_g: fiber_g as f64,
+            },
+            _ => Self::compute(&BodyMetrics::from_user(user), user.health_goals.as_deref().unwrap_or(&[])),
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        Self {
+            calories: self.calories + other.calories,
+            protein_g: self.protein_g + other.protein_g,
+            carbs_g: self.carbs_g + other.carbs_g,
+            fat_g: self.fat_g + other.fat_g,
+            fiber_g: self.fiber_g + other.fiber_g,
+        }
+    }
+
+    pub fn scale(&self, factor: f64) -> Self {
+        Self {
+            calories: self.calories * factor,
+            protein_g: self.protein_g * factor,
+            carbs_g: self.carbs_g * factor,
+            fat_g: self.fat_g * factor,
+            fiber_g: self.fiber_g * factor,
+        }
+    }
+
+    /// Write these targets into the user's `target_*` columns
+    pub fn store(&self, active: &mut user::ActiveModel) {
+        active.target_calories = Set(Some(self.calories.round() as i32));
+        active.target_protein_g = Set(Some(self.protein_g.round() as i32));
+        active.target_carbs_g = Set(Some(self.carbs_g.round() as i32));
+        active.target_fat_g = Set(Some(self.fat_g.round() as i32));
+        active.target_fiber_g = Set(Some(self.fiber_g.round() as i32));
+    }
+}
+
+/// What the targets are computed from
+#[derive(Debug, Clone, Default)]
+pub struct BodyMetrics {
+    pub age: Option<i32>,
+    pub sex: Option<String>,
+    pub weight_kg: Option<f64>,
+    pub height_cm: Option<f64>,
+    pub activity_level: Option<String>,
+}
+
+impl BodyMetrics {
+    pub fn from_user(user: &user::Model) -> Self {
+        Self {
+            age: user.birth_year.map(|y| Utc::now().year() - y).filter(|a| *a > 0),
+            sex: user.sex.clone(),
+            weight_kg: user.weight_kg.and_then(|w| f64::try_from(w).ok()),
+            height_cm: user.height_cm.and_then(|h| f64::try_from(h).ok()),
+            activity_level: user.activity_level.clone(),
+        }
+    }
+}
+
+fn activity_factor(level: Option<&str>) -> f64 {
+    match level {
+        Some("sedentary") => 1.2,
+        Some("moderate") => 1.55,
+        Some("active") => 1.725,
+        Some("very_active") => 1.9,
+        _ => 1.375,
+    }
+}
+
+/// Recompute the user's stored targets from the profile as it will be
+/// saved, unless they were set by hand
+pub fn refresh_targets(active: &mut user::ActiveModel) -> Result<(), AppError> {
+    let user = active.clone().try_into_model()?;
+    if !user.nutrition_targets_manual {
+        NutritionTargets::compute(&BodyMetrics::from_user(&user), user.health_goals.as_deref().unwrap_or(&[]))
+            .store(active);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberTargets {
+    pub user_id: Uuid,
+    pub name: Option<String>,
+    pub targets: NutritionTargets,
+}
+
+/// Daily targets for everyone eating from a user's meal plan
+#[derive(Debug, Clone, Serialize)]
+pub struct HouseholdTargets {
+    pub daily: NutritionTargets,
+    pub members: Vec<MemberTargets>,
+    /// People counted in `household_size` without an account, at the reference targets
+    pub extra_people: i32,
+}
+
+pub async fn household_targets<C: ConnectionTrait>(db: &C, user_id: Uuid) -> Result<HouseholdTargets, AppError> {
+    let me = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound("User".into()))?;
+
+    let member_ids: Vec<Uuid> = match DataScope::for_user(db, user_id).await?.household_id() {
+        Some(household_id) => household_member::Entity::find()
+            .filter(household_member::Column::HouseholdId.eq(household_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|m| m.user_id)
+            .collect(),
+        None => vec![user_id],
+    };
+
+    let users = if member_ids == [user_id] {
+        vec![me.clone()]
+    } else {
+        user::Entity::find()
+            .filter(user::Column::Id.is_in(member_ids))
+            .all(db)
+            .await?
+    };
+
+    let members: Vec<MemberTargets> = users
+        .iter()
+        .map(|u| MemberTargets { user_id: u.id, name: u.name.clone(), targets: NutritionTargets::for_user(u) })
+        .collect();
+    let extra_people = (me.household_size - members.len() as i32).max(0);
+
+    let daily = members
+        .iter()
+        .fold(NutritionTargets::REFERENCE.scale(extra_people as f64), |sum, m| sum.add(&m.targets));
+
+    Ok(HouseholdTargets { daily, members, extra_people })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goals(list: &[&str]) -> Vec<String> {
+        list.iter().map(|g| g.to_string()).collect()
+    }
+
+    #[test]
+    fn unknown_person_gets_reference_targets() {
+        assert_eq!(NutritionTargets::compute(&BodyMetrics::default(), &[]), NutritionTargets::REFERENCE);
+    }
+
+    #[test]
+    fn targets_follow_metrics_and_goals() {
+        let body = BodyMetrics {
+            age: Some(30),
+            sex: Some("male".into()),
+            weight_kg: Some(80.0),
+            height_cm: Some(180.0),
+            activity_level: Some("moderate".into()),
+        };
+        // BMR 1780 × 1.55
+        let maintain = NutritionTargets::compute(&body, &[]);
+        assert_eq!(maintain.calories, 2759.0);
+        assert_eq!(maintain.protein_g, 64.0);
+
+        let cut = NutritionTargets::compute(&body, &goals(&["weight_loss", "high_protein"]));
+        assert_eq!(cut.calories, 2207.0);
+        assert_eq!(cut.protein_g, 128.0);
+
+        let keto = NutritionTargets::compute(&body, &goals(&["keto"]));
+        assert_eq!(keto.carbs_g, 34.0);
+        assert!(keto.fat_g > maintain.fat_g);
+    }
+}
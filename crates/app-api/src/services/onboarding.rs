@@ -8,6 +8,7 @@ use validator::Validate;
 
 use crate::entity::user::{ActiveModel as UserActiveModel, Entity as User, UserResponse};
 use cookest_shared::errors::AppError;
+use crate::services::nutrition;
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct OnboardingRequest {
@@ -73,6 +74,7 @@ impl OnboardingService {
             active.preferred_time_per_meal_min = Set(Some(time));
         }
 
+        nutrition::refresh_targets(&mut active)?;
         active.onboarding_completed = Set(true);
         active.updated_at = Set(Utc::now().fixed_offset());
 
@@ -0,0 +1,611 @@
+//! PDF worker — durable flyer processing driven by `pdf_processing_jobs`.
+//!
+//! Uploads only insert a `pending` job. Each replica runs one worker that
+//! claims due jobs with `FOR UPDATE SKIP LOCKED` and processes them a page
+//! at a time:
+//!
+//! - while a job runs its `heartbeat_at` is refreshed every
+//!   [`HEARTBEAT_SECS`]; the worker stops if the heartbeat finds the job
+//!   is no longer its own
+//! - every pass also reaps jobs whose heartbeat went stale (the replica
+//!   died or hung) and requeues them
+//! - each page's candidates are committed together with a `pdf_job_pages`
+//!   row, so a retry skips finished pages and never duplicates candidates
+//! - failed attempts — whole-job errors, failed pages, reaped jobs — are
+//!   retried with backoff up to [`MAX_RETRIES`]; after that the job ends
+//!   `failed`, or `done` with an error listing the pages it couldn't read
+//!
+//! Every state change after the claim is conditional on the job still being
+//! `processing`, held by the same worker and on the same attempt, so a
+//! reaped worker that wakes up can't overwrite its successor.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    DbBackend, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect, Set, Statement, TransactionTrait,
+};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::entity::{
+    pdf_job_page,
+    pdf_processing_job::{self, Entity as PdfJob},
+    store_promotion_candidate::ActiveModel as CandidateActiveModel,
+};
+use crate::services::llm::{LlmMessage, LlmRequest, SharedLlm};
+use crate::services::scheduler::instance_name;
+use crate::services::store::{end_of_day, start_of_day};
+use cookest_shared::errors::AppError;
+
+/// Failed attempts before a job is given up on
+pub const MAX_RETRIES: i32 = 3;
+/// How often a running job's heartbeat is refreshed
+const HEARTBEAT_SECS: u64 = 30;
+/// A processing job whose heartbeat is older than this is presumed dead
+const STALE_SECS: i64 = 300;
+/// Idle poll interval; uploads wake the worker immediately
+const POLL_SECS: u64 = 15;
+/// Delay before the first retry; each later retry waits four times longer
+const RETRY_BASE_SECS: i64 = 60;
+
+const EXTRACTION_PROMPT: &str = r#"
+You are a grocery flyer price extraction assistant.
+Analyze this supermarket flyer page and extract ALL products with their prices.
+Return a JSON array. Each item must have:
+  "product_name": string,
+  "brand": string or null,
+  "original_price": number or null (price before discount),
+  "discounted_price": number (current/sale price),
+  "discount_pct": number or null (e.g. 20 for 20%),
+  "unit": string or null (e.g. "kg", "500g", "piece"),
+  "valid_from": "YYYY-MM-DD" or null,
+  "valid_until": "YYYY-MM-DD" or null,
+  "confidence": number 0.0-1.0 (your confidence in this extraction)
+Return ONLY the JSON array, no other text.
+"#;
+
+/// How a processing attempt ends
+#[derive(Debug, Clone, PartialEq)]
+enum Settlement {
+    Done { error: Option<String> },
+    Retry { error: String },
+    Failed { error: String },
+}
+
+/// Pages that failed in one attempt, with the first error seen
+#[derive(Debug, Default)]
+struct PageFailures {
+    pages: Vec<i32>,
+    first_error: Option<String>,
+}
+
+pub struct PdfWorker {
+    db: DatabaseConnection,
+    llm: SharedLlm,
+    instance: String,
+    wake: Notify,
+}
+
+impl PdfWorker {
+    pub fn new(db: DatabaseConnection, llm: SharedLlm) -> Self {
+        Self { db, llm, instance: instance_name(), wake: Notify::new() }
+    }
+
+    /// Look for work now rather than at the next poll
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Process jobs until the server stops
+    pub fn spawn(self: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.reap_stale().await {
+                    tracing::error!("PDF job reaper failed: {:?}", e);
+                }
+                loop {
+                    match self.claim().await {
+                        Ok(Some(job)) => self.run(job).await,
+                        Ok(None) => break,
+                        Err(e) => {
+                            tracing::error!("Failed to claim a PDF job: {:?}", e);
+                            break;
+                        }
+                    }
+                }
+                tokio::select! {
+                    _ = self.wake.notified() => {}
+                    _ = tokio::time::sleep(Duration::from_secs(POLL_SECS)) => {}
+                }
+            }
+        });
+    }
+
+    /// Take the oldest due job
+    async fn claim(&self) -> Result<Option<pdf_processing_job::Model>, AppError> {
+        let sql = r#"
+            UPDATE pdf_processing_jobs
+            SET status = 'processing',
+                claimed_by = $1,
+                started_at = COALESCE(started_at, NOW()),
+                heartbeat_at = NOW()
+            WHERE id = (
+                SELECT id FROM pdf_processing_jobs
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        "#;
+        Ok(PdfJob::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                [self.instance.clone().into()],
+            ))
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Requeue (or give up on) jobs whose worker stopped sending heartbeats
+    async fn reap_stale(&self) -> Result<(), AppError> {
+        let cutoff = (Utc::now() - chrono::Duration::seconds(STALE_SECS)).fixed_offset();
+        let stale = PdfJob::find()
+            .filter(pdf_processing_job::Column::Status.eq("processing"))
+            .filter(
+                Condition::any()
+                    .add(pdf_processing_job::Column::HeartbeatAt.is_null())
+                    .add(pdf_processing_job::Column::HeartbeatAt.lt(cutoff)),
+            )
+            .all(&self.db)
+            .await?;
+
+        for job in stale {
+            let settlement = self.after_failure(&job, "Worker stopped responding".into()).await?;
+            if self.settle(&job, &settlement, Some(cutoff)).await? {
+                tracing::warn!("Reaped stale PDF job {} ({:?})", job.id, settlement);
+            }
+        }
+        Ok(())
+    }
+
+    async fn run(&self, job: pdf_processing_job::Model) {
+        tracing::info!("Processing PDF job {} (attempt {})", job.id, job.retry_count + 1);
+        let lost = Arc::new(AtomicBool::new(false));
+        let heartbeat = tokio::spawn(heartbeat(self.db.clone(), job.clone(), lost.clone()));
+        let result = self.process(&job, &lost).await;
+        heartbeat.abort();
+
+        if lost.load(Ordering::Relaxed) {
+            tracing::warn!("PDF job {} was taken over; dropping this attempt", job.id);
+            return;
+        }
+        let settled = async {
+            let settlement = match result {
+                Ok(failures) if failures.pages.is_empty() => Settlement::Done { error: None },
+                Ok(failures) => {
+                    let error = format!(
+                        "Pages {} failed: {}",
+                        join_pages(&failures.pages),
+                        failures.first_error.unwrap_or_default()
+                    );
+                    self.after_failure(&job, error).await?
+                }
+                Err(e) => self.after_failure(&job, format!("{:?}", e)).await?,
+            };
+            tracing::info!("PDF job {} settled: {:?}", job.id, settlement);
+            self.settle(&job, &settlement, None).await
+        };
+        if let Err(e) = settled.await {
+            // The reaper picks the job up once its heartbeat goes stale
+            tracing::error!("Failed to record the outcome of PDF job {}: {:?}", job.id, e);
+        }
+    }
+
+    /// Extract every page not yet done. Page errors are collected, not returned.
+    async fn process(&self, job: &pdf_processing_job::Model, lost: &AtomicBool) -> Result<PageFailures, AppError> {
+        let output_dir = Path::new(&job.file_path)
+            .parent()
+            .unwrap_or(Path::new("/tmp"))
+            .join(format!("pages_{}", job.id));
+        let pages = rasterise(&job.file_path, &output_dir).await?;
+
+        let mut active: pdf_processing_job::ActiveModel = job.clone().into();
+        active.page_count = Set(Some(pages.len() as i32));
+        active.update(&self.db).await?;
+
+        let finished: Vec<i32> = pdf_job_page::Entity::find()
+            .filter(pdf_job_page::Column::JobId.eq(job.id))
+            .filter(pdf_job_page::Column::Status.eq("done"))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|p| p.page_number)
+            .collect();
+
+        let mut failures = PageFailures::default();
+        for (index, png_path) in pages.iter().enumerate() {
+            let page = index as i32 + 1;
+            if finished.contains(&page) {
+                continue;
+            }
+            if lost.load(Ordering::Relaxed) {
+                return Err(AppError::Internal("Lost the job lease".into()));
+            }
+            match self.extract_page(png_path).await {
+                Ok(items) => {
+                    let Some(txn) = begin_if_owned(&self.db, job).await? else {
+                        return Err(AppError::Internal("Lost the job lease".into()));
+                    };
+                    let mut inserted = 0;
+                    for item in &items {
+                        if let Some(candidate) = parse_candidate(job, item) {
+                            candidate.insert(&txn).await?;
+                            inserted += 1;
+                        }
+                    }
+                    record_page(&txn, job.id, page, inserted, None).await?;
+                    txn.commit().await?;
+                }
+                Err(e) => {
+                    let error = format!("{:?}", e);
+                    tracing::warn!("PDF job {} page {} failed: {}", job.id, page, error);
+                    let Some(txn) = begin_if_owned(&self.db, job).await? else {
+                        return Err(AppError::Internal("Lost the job lease".into()));
+                    };
+                    record_page(&txn, job.id, page, 0, Some(&error)).await?;
+                    txn.commit().await?;
+                    failures.pages.push(page);
+                    failures.first_error.get_or_insert(error);
+                }
+            }
+        }
+
+        let _ = tokio::fs::remove_dir_all(&output_dir).await;
+        Ok(failures)
+    }
+
+    /// Send one rendered page to the vision model and return the items it found
+    async fn extract_page(&self, png_path: &Path) -> Result<Vec<serde_json::Value>, AppError> {
+        let png_bytes = tokio::fs::read(png_path)
+            .await
+            .map_err(|e| AppError::Internal(format!("read PNG failed: {}", e)))?;
+        let message = LlmMessage::user(EXTRACTION_PROMPT).with_image(BASE64.encode(&png_bytes));
+        let raw_text = self.llm.chat(LlmRequest::new(vec![message])).await?.message.content;
+
+        // The model may wrap the array in prose
+        let json = raw_text
+            .find('[')
+            .zip(raw_text.rfind(']'))
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| &raw_text[start..=end])
+            .ok_or_else(|| AppError::Internal("Model reply contained no JSON array".into()))?;
+        serde_json::from_str(json).map_err(|e| AppError::Internal(format!("Model reply was not valid JSON: {}", e)))
+    }
+
+    /// Decide what a failed attempt leads to. Once retries run out, a job
+    /// that got some pages through is still `done`, with the error kept.
+    async fn after_failure(&self, job: &pdf_processing_job::Model, error: String) -> Result<Settlement, AppError> {
+        if job.retry_count + 1 < MAX_RETRIES {
+            return Ok(Settlement::Retry { error });
+        }
+        let done_pages = pdf_job_page::Entity::find()
+            .filter(pdf_job_page::Column::JobId.eq(job.id))
+            .filter(pdf_job_page::Column::Status.eq("done"))
+            .count(&self.db)
+            .await?;
+        Ok(if done_pages > 0 {
+            Settlement::Done { error: Some(error) }
+        } else {
+            Settlement::Failed { error }
+        })
+    }
+
+    /// Move the job out of `processing` if it is still this attempt's.
+    /// With `stale_before`, only if its heartbeat is older than that.
+    async fn settle(
+        &self,
+        job: &pdf_processing_job::Model,
+        settlement: &Settlement,
+        stale_before: Option<DateTime<chrono::FixedOffset>>,
+    ) -> Result<bool, AppError> {
+        use pdf_processing_job::Column;
+
+        let now = Utc::now();
+        let mut update = PdfJob::update_many()
+            .col_expr(Column::ClaimedBy, Expr::value(sea_orm::Value::String(None)))
+            .filter(still_owned(job));
+        if let Some(cutoff) = stale_before {
+            update = update.filter(
+                Condition::any().add(Column::HeartbeatAt.is_null()).add(Column::HeartbeatAt.lt(cutoff)),
+            );
+        }
+        update = match settlement {
+            Settlement::Done { error } => update
+                .col_expr(Column::Status, Expr::value("done"))
+                .col_expr(Column::Error, Expr::value(error.clone()))
+                .col_expr(Column::ProcessedAt, Expr::value(now.fixed_offset())),
+            Settlement::Retry { error } => update
+                .col_expr(Column::Status, Expr::value("pending"))
+                .col_expr(Column::Error, Expr::value(error.clone()))
+                .col_expr(Column::RetryCount, Expr::value(job.retry_count + 1))
+                .col_expr(
+                    Column::NextAttemptAt,
+                    Expr::value((now + chrono::Duration::seconds(retry_delay_secs(job.retry_count + 1))).fixed_offset()),
+                ),
+            Settlement::Failed { error } => update
+                .col_expr(Column::Status, Expr::value("failed"))
+                .col_expr(Column::Error, Expr::value(error.clone()))
+                .col_expr(Column::RetryCount, Expr::value(job.retry_count + 1))
+                .col_expr(Column::ProcessedAt, Expr::value(now.fixed_offset())),
+        };
+        Ok(update.exec(&self.db).await?.rows_affected == 1)
+    }
+}
+
+/// The job is still `processing` under the same worker and attempt
+fn still_owned(job: &pdf_processing_job::Model) -> Condition {
+    use pdf_processing_job::Column;
+    let claimed = match &job.claimed_by {
+        Some(worker) => Column::ClaimedBy.eq(worker.clone()),
+        None => Column::ClaimedBy.is_null(),
+    };
+    Condition::all()
+        .add(Column::Id.eq(job.id))
+        .add(Column::Status.eq("processing"))
+        .add(Column::RetryCount.eq(job.retry_count))
+        .add(claimed)
+}
+
+/// Start a transaction holding the job's row lock, so a page's candidates
+/// are only written while the job is still this attempt's. `None` (and
+/// nothing held) once it isn't.
+async fn begin_if_owned(
+    db: &DatabaseConnection,
+    job: &pdf_processing_job::Model,
+) -> Result<Option<DatabaseTransaction>, AppError> {
+    let txn = db.begin().await?;
+    let owned = PdfJob::find().filter(still_owned(job)).lock_exclusive().one(&txn).await?;
+    if owned.is_none() {
+        txn.rollback().await?;
+        return Ok(None);
+    }
+    Ok(Some(txn))
+}
+
+/// Keep the job's heartbeat fresh; flags `lost` once the job is no longer ours
+async fn heartbeat(db: DatabaseConnection, job: pdf_processing_job::Model, lost: Arc<AtomicBool>) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(HEARTBEAT_SECS));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let result = PdfJob::update_many()
+            .col_expr(pdf_processing_job::Column::HeartbeatAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(still_owned(&job))
+            .exec(&db)
+            .await;
+        match result {
+            Ok(r) if r.rows_affected == 0 => {
+                lost.store(true, Ordering::Relaxed);
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("PDF job {} heartbeat failed: {:?}", job.id, e),
+        }
+    }
+}
+
+/// Render every page to PNG with pdftoppm, in page order
+async fn rasterise(pdf_path: &str, output_dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    // Start clean: a previous attempt may have left a partial render
+    let _ = tokio::fs::remove_dir_all(output_dir).await;
+    tokio::fs::create_dir_all(output_dir)
+        .await
+        .map_err(|e| AppError::Internal(format!("mkdir failed: {}", e)))?;
+
+    let prefix = output_dir.join("page").to_string_lossy().to_string();
+    let output = tokio::process::Command::new("pdftoppm")
+        .args(["-png", "-r", "150", pdf_path, &prefix])
+        .output()
+        .await
+        .map_err(|e| AppError::Internal(format!("pdftoppm spawn failed: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Internal(format!("pdftoppm failed: {}", stderr)));
+    }
+
+    let mut png_files: Vec<PathBuf> = vec![];
+    let mut dir = tokio::fs::read_dir(output_dir)
+        .await
+        .map_err(|e| AppError::Internal(format!("read_dir failed: {}", e)))?;
+    while let Some(entry) = dir
+        .next_entry()
+        .await
+        .map_err(|e| AppError::Internal(format!("dir entry failed: {}", e)))?
+    {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("png") {
+            png_files.push(path);
+        }
+    }
+    // pdftoppm zero-pads page numbers, so name order is page order
+    png_files.sort();
+    Ok(png_files)
+}
+
+/// Record a page outcome, counting the attempt
+async fn record_page<C: ConnectionTrait>(
+    db: &C,
+    job_id: Uuid,
+    page: i32,
+    candidates: i32,
+    error: Option<&str>,
+) -> Result<(), AppError> {
+    let row = pdf_job_page::ActiveModel {
+        job_id: Set(job_id),
+        page_number: Set(page),
+        status: Set(if error.is_some() { "failed" } else { "done" }.to_string()),
+        attempts: Set(1),
+        candidates: Set(candidates),
+        error: Set(error.map(str::to_string)),
+        updated_at: Set(Utc::now().fixed_offset()),
+    };
+    pdf_job_page::Entity::insert(row)
+        .on_conflict(
+            OnConflict::columns([pdf_job_page::Column::JobId, pdf_job_page::Column::PageNumber])
+                .update_columns([
+                    pdf_job_page::Column::Status,
+                    pdf_job_page::Column::Candidates,
+                    pdf_job_page::Column::Error,
+                    pdf_job_page::Column::UpdatedAt,
+                ])
+                .value(
+                    pdf_job_page::Column::Attempts,
+                    Expr::col((pdf_job_page::Entity, pdf_job_page::Column::Attempts)).add(1),
+                )
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// Build a staging row from one AI-extracted item; None if it lacks a name or price
+fn parse_candidate(job: &pdf_processing_job::Model, item: &serde_json::Value) -> Option<CandidateActiveModel> {
+    let product_name = item["product_name"].as_str().filter(|n| !n.is_empty())?.to_string();
+    let discounted_price = item["discounted_price"]
+        .as_f64()
+        .filter(|p| *p > 0.0)
+        .map(|p| Decimal::try_from(p).unwrap_or_default())?;
+
+    let original_price = item["original_price"].as_f64().and_then(|p| Decimal::try_from(p).ok());
+    let discount_pct = item["discount_pct"].as_f64().and_then(|p| Decimal::try_from(p).ok());
+    let confidence = item["confidence"].as_f64().and_then(|c| Decimal::try_from(c).ok());
+
+    let valid_from = item["valid_from"].as_str()
+        .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+        .map(start_of_day);
+
+    let valid_until = item["valid_until"].as_str()
+        .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+        .map(end_of_day);
+
+    Some(CandidateActiveModel {
+        id: Set(Uuid::new_v4()),
+        store_id: Set(job.store_id),
+        job_id: Set(job.id),
+        product_name: Set(product_name),
+        brand: Set(item["brand"].as_str().map(|s| s.to_string())),
+        original_price: Set(original_price),
+        discounted_price: Set(discounted_price),
+        discount_pct: Set(discount_pct),
+        unit: Set(item["unit"].as_str().map(|s| s.to_string())),
+        valid_from: Set(valid_from),
+        valid_until: Set(valid_until),
+        confidence: Set(confidence),
+        review_status: Set("pending".to_string()),
+        reviewed_by: Set(None),
+        reviewed_at: Set(None),
+        edited_by: Set(None),
+        edited_at: Set(None),
+        duplicate_of: Set(None),
+        created_at: Set(Utc::now().fixed_offset()),
+    })
+}
+
+/// Wait before retry `n`: 1, 4, 16 minutes, …
+fn retry_delay_secs(n: i32) -> i64 {
+    RETRY_BASE_SECS * 4_i64.pow(n.clamp(1, 6) as u32 - 1)
+}
+
+/// "3, 7, 12"
+fn join_pages(pages: &[i32]) -> String {
+    pages.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn job() -> pdf_processing_job::Model {
+        let now = Utc::now().fixed_offset();
+        pdf_processing_job::Model {
+            id: Uuid::nil(),
+            store_id: Uuid::nil(),
+            file_path: "/tmp/flyer.pdf".into(),
+            status: "processing".into(),
+            error: None,
+            retry_count: 0,
+            started_at: Some(now),
+            heartbeat_at: Some(now),
+            processed_at: None,
+            created_at: now,
+            next_attempt_at: now,
+            claimed_by: Some("worker-a".into()),
+            page_count: None,
+        }
+    }
+
+    #[test]
+    fn candidates_need_a_name_and_a_price() {
+        let job = job();
+        assert!(parse_candidate(&job, &json!({ "product_name": "Milk", "discounted_price": 0.89 })).is_some());
+        assert!(parse_candidate(&job, &json!({ "product_name": "", "discounted_price": 0.89 })).is_none());
+        assert!(parse_candidate(&job, &json!({ "product_name": "Milk", "discounted_price": 0 })).is_none());
+
+        let full = parse_candidate(
+            &job,
+            &json!({ "product_name": "Eggs", "discounted_price": 2.5, "valid_until": "2026-10-20", "confidence": 0.9 }),
+        )
+        .unwrap();
+        assert_eq!(full.valid_until.unwrap().unwrap().to_rfc3339(), "2026-10-20T23:59:59+00:00");
+    }
+
+    #[test]
+    fn retries_back_off() {
+        assert_eq!(retry_delay_secs(1), 60);
+        assert_eq!(retry_delay_secs(2), 240);
+        assert_eq!(join_pages(&[3, 7]), "3, 7");
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn pages_are_only_written_while_the_job_is_owned() {
+        let db = crate::test_db::connect().await;
+        let store = crate::test_db::create_store(&db).await;
+        let job = PdfJob::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO pdf_processing_jobs (store_id, file_path, status, claimed_by, started_at, heartbeat_at) \
+                 VALUES ($1, '/tmp/flyer.pdf', 'processing', 'worker-a', now(), now()) RETURNING *",
+                [store.id.into()],
+            ))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let txn = begin_if_owned(&db, &job).await.unwrap().expect("job is still ours");
+        record_page(&txn, job.id, 1, 0, None).await.unwrap();
+        txn.commit().await.unwrap();
+
+        // The reaper hands the job to a new attempt
+        let mut active: pdf_processing_job::ActiveModel = job.clone().into();
+        active.status = Set("pending".into());
+        active.retry_count = Set(1);
+        active.claimed_by = Set(None);
+        active.update(&db).await.unwrap();
+
+        assert!(begin_if_owned(&db, &job).await.unwrap().is_none());
+    }
+}
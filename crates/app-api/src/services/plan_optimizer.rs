@@ -0,0 +1,514 @@
+//! Weekly plan optimiser — fills the 28 slots of a week as a whole.
+//!
+//! A plan is judged by one objective (higher is better), each part scaled
+//! to 0–1 before weighting:
+//!   preference  × 25  — mean static score of the chosen recipes
+//!   macros      × 25  — each day's calories/protein/carbs/fat vs the targets
+//!   variety     × 15  — few repeated recipes, no repeated recipe or cuisine within a day
+//!   reuse       × 10  — ingredients shared between meals (less waste)
+//!   fit         × 10  — within the preferred cooking time and skill level
+//!   budget      × 10  — estimated cost within the weekly budget
+//!   leftovers   ×  5  — batch cooks, up to [`BATCH_TARGET`] a week
+//!
+//! A batch cook is a dinner cooked in double servings whose second half is
+//! the next day's lunch. In a [`Week`] it is simply the same candidate at
+//! dinner on day d and lunch on day d + 1; that repeat is not penalised as
+//! a repeat, and the leftover lunch needs no cooking.
+//!
+//! Search starts from a greedy week (best static score per slot) and then
+//! runs a fixed number of local-search moves — replace one slot's recipe,
+//! swap two days' recipes for the same meal, or turn a dinner into a batch
+//! cook — keeping any move that does not lower the objective. Randomness
+//! comes only from the seed, so the same candidates and seed always give
+//! the same week.
+//!
+//! [`Optimiser::fill_around`] re-optimises part of an existing week: slots
+//! that are not open keep their recipe and still count towards the
+//! objective, so the new meals balance around them.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+
+pub const MEAL_TYPES: [&str; 4] = ["breakfast", "lunch", "dinner", "snack"];
+pub const DAYS: usize = 7;
+pub const SLOTS: usize = DAYS * MEAL_TYPES.len();
+
+/// A recipe may appear at most this often in one week
+const MAX_USES: usize = 2;
+/// Local-search moves per plan
+const ITERATIONS: usize = 4000;
+
+/// Batch cooks a week that earn the full leftovers score
+pub const BATCH_TARGET: usize = 2;
+
+const W_PREFERENCE: f64 = 25.0;
+const W_MACROS: f64 = 25.0;
+const W_VARIETY: f64 = 15.0;
+const W_REUSE: f64 = 10.0;
+const W_FIT: f64 = 10.0;
+const W_BUDGET: f64 = 10.0;
+const W_LEFTOVERS: f64 = 5.0;
+
+/// Indexes into [`MEAL_TYPES`]
+pub const LUNCH: usize = 1;
+pub const DINNER: usize = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Macros {
+    pub calories: f64,
+    pub protein_g: f64,
+    pub carbs_g: f64,
+    pub fat_g: f64,
+}
+
+impl Macros {
+    fn add(&mut self, other: &Macros) {
+        self.calories += other.calories;
+        self.protein_g += other.protein_g;
+        self.carbs_g += other.carbs_g;
+        self.fat_g += other.fat_g;
+    }
+
+    /// 1 when `self` hits `target` exactly, falling to 0 at 100% off
+    fn closeness(&self, target: &Macros) -> f64 {
+        let pairs = [
+            (self.calories, target.calories),
+            (self.protein_g, target.protein_g),
+            (self.carbs_g, target.carbs_g),
+            (self.fat_g, target.fat_g),
+        ];
+        let (sum, n) = pairs
+            .iter()
+            .filter(|(_, t)| *t > 0.0)
+            .fold((0.0, 0), |(sum, n), (v, t)| (sum + (1.0 - ((v - t).abs() / t).min(1.0)), n + 1));
+        if n == 0 { 1.0 } else { sum / n as f64 }
+    }
+}
+
+/// A recipe the optimiser may place
+#[derive(Debug, Clone, Default)]
+pub struct Candidate {
+    pub recipe_id: i64,
+    /// Static preference score, 0–1
+    pub score: f64,
+    /// Which of [`MEAL_TYPES`] the recipe can fill
+    pub meal_types: [bool; 4],
+    /// Nutrition for the household's servings, when known
+    pub macros: Option<Macros>,
+    pub cuisine: Option<String>,
+    pub ingredient_ids: Vec<i64>,
+    pub total_time_min: Option<i32>,
+    /// "easy", "medium", "hard"
+    pub difficulty: Option<String>,
+    /// Estimated cost for the household's servings
+    pub cost: f64,
+}
+
+/// What the week is optimised towards
+#[derive(Debug, Clone, Default)]
+pub struct Preferences {
+    /// Household daily targets
+    pub daily_macros: Macros,
+    pub max_time_min: Option<i32>,
+    /// "beginner", "intermediate", "advanced"
+    pub skill_level: Option<String>,
+    pub budget: Option<f64>,
+}
+
+/// Chosen candidate index per slot; slot `day * 4 + meal` in [`MEAL_TYPES`] order
+pub type Week = [Option<usize>; SLOTS];
+
+pub fn slot_day(slot: usize) -> usize {
+    slot / MEAL_TYPES.len()
+}
+
+pub fn slot_meal(slot: usize) -> usize {
+    slot % MEAL_TYPES.len()
+}
+
+pub fn slot_at(day: usize, meal: usize) -> usize {
+    day * MEAL_TYPES.len() + meal
+}
+
+/// Days whose dinner is batch-cooked for the next day's lunch, counting
+/// only days for which `linkable` holds
+pub fn batch_days(week: &Week, linkable: impl Fn(usize) -> bool) -> Vec<usize> {
+    (0..DAYS - 1)
+        .filter(|&day| {
+            let dinner = week[slot_at(day, DINNER)];
+            dinner.is_some() && dinner == week[slot_at(day + 1, LUNCH)] && linkable(day)
+        })
+        .collect()
+}
+
+pub struct Optimiser<'a> {
+    candidates: &'a [Candidate],
+    prefs: &'a Preferences,
+    /// Candidate indexes that fit each meal type, in input order
+    by_meal: [Vec<usize>; 4],
+    /// Per-candidate time/skill fit, 0–1
+    fit: Vec<f64>,
+}
+
+impl<'a> Optimiser<'a> {
+    pub fn new(candidates: &'a [Candidate], prefs: &'a Preferences) -> Self {
+        let mut by_meal: [Vec<usize>; 4] = Default::default();
+        for (i, c) in candidates.iter().enumerate() {
+            for (meal, fits) in c.meal_types.iter().enumerate() {
+                if *fits {
+                    by_meal[meal].push(i);
+                }
+            }
+        }
+        let fit = candidates.iter().map(|c| recipe_fit(c, prefs)).collect();
+        Self { candidates, prefs, by_meal, fit }
+    }
+
+    /// Best week found for `seed` that keeps every slot of `fixed` not
+    /// marked in `open`; open slots are refilled from scratch
+    pub fn fill_around(&self, seed: u64, fixed: &Week, open: &[bool; SLOTS]) -> Week {
+        let open_slots: Vec<usize> = (0..SLOTS).filter(|s| open[*s]).collect();
+        let mut week = self.greedy_around(fixed, open);
+        if open_slots.is_empty() {
+            return week;
+        }
+
+        // A pair can only become a batch cook if both halves are replanned,
+        // or both are kept (and so were most likely planned together)
+        let linkable = |day: usize| open[slot_at(day, DINNER)] == open[slot_at(day + 1, LUNCH)];
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut best = self.objective(&week, &linkable);
+
+        for _ in 0..ITERATIONS {
+            let slot = open_slots[rng.gen_range(0..open_slots.len())];
+            let meal = slot_meal(slot);
+            let mut next = week;
+
+            let kind = rng.gen_range(0..3);
+            if kind == 0 {
+                let options = &self.by_meal[meal];
+                if options.is_empty() {
+                    continue;
+                }
+                let pick = options[rng.gen_range(0..options.len())];
+                if next[slot] == Some(pick) || self.uses(&next, pick) >= MAX_USES {
+                    continue;
+                }
+                next[slot] = Some(pick);
+            } else if kind == 1 {
+                let other = slot_at(rng.gen_range(0..DAYS), meal);
+                if other == slot || !open[other] || next[slot] == next[other] {
+                    continue;
+                }
+                next.swap(slot, other);
+            } else {
+                // Batch-cook a dinner: its leftovers become the next day's lunch
+                let day = rng.gen_range(0..DAYS - 1);
+                let (dinner, lunch) = (slot_at(day, DINNER), slot_at(day + 1, LUNCH));
+                let Some(cooked) = next[dinner] else { continue };
+                if !open[dinner] || !open[lunch] || next[lunch] == Some(cooked) {
+                    continue;
+                }
+                next[lunch] = None;
+                if self.uses(&next, cooked) >= MAX_USES {
+                    continue;
+                }
+                next[lunch] = Some(cooked);
+            }
+
+            let score = self.objective(&next, &linkable);
+            if score >= best {
+                week = next;
+                best = score;
+            }
+        }
+        week
+    }
+
+    /// `fixed` with every open slot given its highest static score, each
+    /// recipe once where possible
+    fn greedy_around(&self, fixed: &Week, open: &[bool; SLOTS]) -> Week {
+        let mut week: Week = *fixed;
+        for (slot, open) in open.iter().enumerate() {
+            if *open {
+                week[slot] = None;
+            }
+        }
+        for limit in 1..=MAX_USES {
+            for slot in 0..SLOTS {
+                if !open[slot] || week[slot].is_some() {
+                    continue;
+                }
+                // Ties go to the earlier candidate
+                let best = self.by_meal[slot_meal(slot)]
+                    .iter()
+                    .copied()
+                    .filter(|&i| self.uses(&week, i) < limit)
+                    .max_by(|&a, &b| {
+                        self.candidates[a].score.total_cmp(&self.candidates[b].score).then(b.cmp(&a))
+                    });
+                week[slot] = best;
+            }
+        }
+        week
+    }
+
+    /// Slots holding the candidate's recipe (through any candidate)
+    fn uses(&self, week: &Week, candidate: usize) -> usize {
+        let recipe_id = self.candidates[candidate].recipe_id;
+        week.iter().flatten().filter(|&&i| self.candidates[i].recipe_id == recipe_id).count()
+    }
+
+    /// Weighted objective for a whole week, counting only batch cooks on
+    /// `linkable` days; higher is better
+    pub fn objective(&self, week: &Week, linkable: &dyn Fn(usize) -> bool) -> f64 {
+        let chosen: Vec<(usize, &Candidate)> = week
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, c)| c.map(|i| (slot, &self.candidates[i])))
+            .collect();
+        if chosen.is_empty() {
+            return 0.0;
+        }
+        let filled = chosen.len() as f64;
+        let batches = batch_days(week, linkable);
+
+        let preference = chosen.iter().map(|(_, c)| c.score).sum::<f64>() / filled;
+        // Leftover lunches need no cooking, so they always fit
+        let fit = (week.iter().flatten().map(|&i| self.fit[i]).sum::<f64>()
+            + batches
+                .iter()
+                .map(|&day| 1.0 - week[slot_at(day + 1, LUNCH)].map_or(1.0, |i| self.fit[i]))
+                .sum::<f64>())
+            / filled;
+
+        // Daily macros, only over days that have nutrition data
+        let mut days = [Macros::default(); DAYS];
+        let mut has_data = [false; DAYS];
+        for (slot, c) in &chosen {
+            if let Some(m) = &c.macros {
+                days[slot_day(*slot)].add(m);
+                has_data[slot_day(*slot)] = true;
+            }
+        }
+        let macro_days: Vec<f64> = (0..DAYS)
+            .filter(|d| has_data[*d])
+            .map(|d| days[d].closeness(&self.prefs.daily_macros))
+            .collect();
+        let macros = if macro_days.is_empty() {
+            0.0
+        } else {
+            macro_days.iter().sum::<f64>() / macro_days.len() as f64
+        };
+
+        // Variety: repeated recipes, and a repeated recipe or cuisine within a day
+        let mut recipe_uses: HashMap<i64, usize> = HashMap::new();
+        for (_, c) in &chosen {
+            *recipe_uses.entry(c.recipe_id).or_default() += 1;
+        }
+        // A batch cook's leftover lunch is planned, not a repeat
+        let repeats = recipe_uses.values().map(|n| n - 1).sum::<usize>().saturating_sub(batches.len());
+        let mut clashes = 0usize;
+        for day in 0..DAYS {
+            let meals: Vec<&Candidate> = chosen
+                .iter()
+                .filter(|(slot, _)| slot_day(*slot) == day)
+                .map(|(_, c)| *c)
+                .collect();
+            for (i, a) in meals.iter().enumerate() {
+                clashes += meals[i + 1..]
+                    .iter()
+                    .filter(|b| {
+                        a.recipe_id == b.recipe_id
+                            || matches!((&a.cuisine, &b.cuisine), (Some(x), Some(y)) if x.eq_ignore_ascii_case(y))
+                    })
+                    .count();
+            }
+        }
+        let variety = (1.0 - (repeats as f64 + 0.5 * clashes as f64) / filled).max(0.0);
+
+        // Reuse: share of ingredient uses that repeat an ingredient bought for another meal
+        let mut ingredient_uses: HashMap<i64, usize> = HashMap::new();
+        let mut counted = HashSet::new();
+        for (_, c) in &chosen {
+            if counted.insert(c.recipe_id) {
+                for id in &c.ingredient_ids {
+                    *ingredient_uses.entry(*id).or_default() += 1;
+                }
+            }
+        }
+        let total_uses: usize = ingredient_uses.values().sum();
+        let reuse = if total_uses == 0 {
+            0.0
+        } else {
+            1.0 - ingredient_uses.len() as f64 / total_uses as f64
+        };
+
+        let budget = match self.prefs.budget {
+            Some(limit) if limit > 0.0 => {
+                let total: f64 = chosen.iter().map(|(_, c)| c.cost).sum();
+                if total <= limit { 1.0 } else { limit / total }
+            }
+            _ => 1.0,
+        };
+
+        preference * W_PREFERENCE
+            + macros * W_MACROS
+            + variety * W_VARIETY
+            + reuse * W_REUSE
+            + fit * W_FIT
+            + budget * W_BUDGET
+            + batches.len().min(BATCH_TARGET) as f64 / BATCH_TARGET as f64 * W_LEFTOVERS
+    }
+}
+
+
+/// 1 when the recipe is within the preferred time and skill level, lower the further over
+fn recipe_fit(c: &Candidate, prefs: &Preferences) -> f64 {
+    let time = match (c.total_time_min, prefs.max_time_min) {
+        (Some(t), Some(max)) if t > max && t > 0 => max.max(0) as f64 / t as f64,
+        _ => 1.0,
+    };
+
+    let skill = match (difficulty_rank(c.difficulty.as_deref()), skill_rank(prefs.skill_level.as_deref())) {
+        (Some(needed), Some(have)) if needed > have => (1.0 - 0.5 * (needed - have) as f64).max(0.0),
+        _ => 1.0,
+    };
+
+    (time + skill) / 2.0
+}
+
+fn difficulty_rank(difficulty: Option<&str>) -> Option<i32> {
+    match difficulty? {
+        "easy" => Some(0),
+        "medium" => Some(1),
+        "hard" => Some(2),
+        _ => None,
+    }
+}
+
+fn skill_rank(skill: Option<&str>) -> Option<i32> {
+    match skill? {
+        "beginner" => Some(0),
+        "intermediate" => Some(1),
+        "advanced" => Some(2),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(recipe_id: i64, meal: usize, score: f64) -> Candidate {
+        let mut meal_types = [false; 4];
+        meal_types[meal] = true;
+        Candidate {
+            recipe_id,
+            score,
+            meal_types,
+            macros: Some(Macros { calories: 500.0, protein_g: 20.0, carbs_g: 60.0, fat_g: 15.0 }),
+            ingredient_ids: vec![recipe_id * 10, recipe_id * 10 + 1],
+            ..Default::default()
+        }
+    }
+
+    fn pool() -> Vec<Candidate> {
+        (0..40).map(|i| candidate(i, (i % 4) as usize, (i % 7) as f64 / 7.0)).collect()
+    }
+
+    fn run(optimiser: &Optimiser, seed: u64) -> Week {
+        optimiser.fill_around(seed, &[None; SLOTS], &[true; SLOTS])
+    }
+
+    fn prefs() -> Preferences {
+        Preferences {
+            daily_macros: Macros { calories: 2000.0, protein_g: 80.0, carbs_g: 240.0, fat_g: 60.0 },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_week() {
+        let (pool, prefs) = (pool(), prefs());
+        let optimiser = Optimiser::new(&pool, &prefs);
+        assert_eq!(run(&optimiser, 7), run(&optimiser, 7));
+    }
+
+    #[test]
+    fn fills_every_slot_with_a_fitting_recipe_and_improves_on_greedy() {
+        let (pool, prefs) = (pool(), prefs());
+        let optimiser = Optimiser::new(&pool, &prefs);
+        let week = run(&optimiser, 42);
+        let leftover_lunches: Vec<usize> =
+            batch_days(&week, |_| true).iter().map(|day| slot_at(day + 1, LUNCH)).collect();
+
+        for (slot, chosen) in week.iter().enumerate() {
+            let i = chosen.expect("every meal type has candidates");
+            assert!(pool[i].meal_types[slot_meal(slot)] || leftover_lunches.contains(&slot));
+            assert!(optimiser.uses(&week, i) <= MAX_USES);
+        }
+        let greedy = optimiser.greedy_around(&[None; SLOTS], &[true; SLOTS]);
+        assert!(optimiser.objective(&week, &|_| true) >= optimiser.objective(&greedy, &|_| true));
+    }
+
+    #[test]
+    fn prefers_recipes_within_time_and_skill() {
+        let mut quick = candidate(1, 2, 0.5);
+        quick.total_time_min = Some(20);
+        let mut slow = candidate(2, 2, 0.5);
+        slow.total_time_min = Some(120);
+        slow.difficulty = Some("hard".into());
+
+        let prefs = Preferences {
+            max_time_min: Some(30),
+            skill_level: Some("beginner".into()),
+            ..prefs()
+        };
+        let pool = vec![quick, slow];
+        let optimiser = Optimiser::new(&pool, &prefs);
+
+        let mut with_quick: Week = [None; SLOTS];
+        with_quick[2] = Some(0);
+        let mut with_slow: Week = [None; SLOTS];
+        with_slow[2] = Some(1);
+        assert!(optimiser.objective(&with_quick, &|_| true) > optimiser.objective(&with_slow, &|_| true));
+    }
+
+    #[test]
+    fn plans_batch_cooks_for_next_day_lunch() {
+        let (pool, prefs) = (pool(), prefs());
+        let optimiser = Optimiser::new(&pool, &prefs);
+        let week = run(&optimiser, 9);
+
+        let batches = batch_days(&week, |_| true);
+        assert!(!batches.is_empty());
+        for day in batches {
+            assert_eq!(week[slot_at(day, DINNER)], week[slot_at(day + 1, LUNCH)]);
+        }
+    }
+
+    #[test]
+    fn fill_around_keeps_fixed_slots() {
+        let (pool, prefs) = (pool(), prefs());
+        let optimiser = Optimiser::new(&pool, &prefs);
+        let full = run(&optimiser, 3);
+
+        // Re-optimise only Wednesday; everything else must stay as it was
+        let open: [bool; SLOTS] = std::array::from_fn(|slot| slot_day(slot) == 2);
+        let mut fixed = full;
+        fixed[0] = Some(39);
+        let week = optimiser.fill_around(11, &fixed, &open);
+
+        for slot in 0..SLOTS {
+            if open[slot] {
+                let i = week[slot].expect("open slots are refilled");
+                assert!(pool[i].meal_types[slot_meal(slot)]);
+            } else {
+                assert_eq!(week[slot], fixed[slot]);
+            }
+        }
+    }
+}
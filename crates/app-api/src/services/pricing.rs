@@ -0,0 +1,301 @@
+//! Cost estimates for recipes and meal plans.
+//!
+//! Every ingredient gets a price per kilogram from the first source that
+//! has one:
+//!   1. the cheapest current store promotion matched to it
+//!      (`store_promotion_ingredients`) whose pack size converts to grams
+//!   2. `ingredients.reference_price_per_kg`
+//!   3. a default for the ingredient's category
+//!
+//! Recipe lines are priced by weight through [`UnitConverter`]. Lines whose
+//! quantity cannot be converted to grams are left out of the estimate and
+//! reported; lines with no quantity at all ("salt to taste") cost nothing.
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::entity::{ingredient, recipe_ingredient, store_promotion, store_promotion_ingredient};
+use crate::services::units::UnitConverter;
+use cookest_shared::errors::AppError;
+
+/// Default price per kg by `ingredients.category`, for ingredients with
+/// neither a promotion nor a reference price
+fn category_price_per_kg(category: Option<&str>) -> Decimal {
+    let cents: i64 = match category.unwrap_or("other") {
+        "protein" => 1000,
+        "dairy" => 600,
+        "vegetable" => 250,
+        "grain" => 200,
+        "fruit" => 300,
+        "fat" => 800,
+        "spice" => 2500,
+        _ => 500,
+    };
+    Decimal::new(cents, 2)
+}
+
+/// Where an ingredient's price came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    Promotion,
+    Reference,
+    Category,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IngredientPrice {
+    pub per_kg: Decimal,
+    pub source: PriceSource,
+}
+
+/// Estimated cost of a recipe (or a scaled share of one)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecipeCost {
+    pub total: Decimal,
+    /// Part of `total` priced from current store promotions
+    pub from_promotions: Decimal,
+    /// Ingredients left out because their quantity could not be converted to grams
+    pub unpriced: Vec<i64>,
+}
+
+impl RecipeCost {
+    /// Cost of `servings` portions of a recipe written for `recipe_servings`
+    pub fn for_servings(&self, servings: i32, recipe_servings: i32) -> RecipeCost {
+        let scale = Decimal::from(servings.max(0)) / Decimal::from(recipe_servings.max(1));
+        RecipeCost {
+            total: (self.total * scale).round_dp(2),
+            from_promotions: (self.from_promotions * scale).round_dp(2),
+            unpriced: self.unpriced.clone(),
+        }
+    }
+}
+
+/// Prices for a batch of ingredients. Load once per request with
+/// [`PriceBook::load`], then price recipes without further queries.
+#[derive(Debug, Default)]
+pub struct PriceBook {
+    prices: HashMap<i64, IngredientPrice>,
+}
+
+impl PriceBook {
+    /// Load promotion, reference and category prices for the given ingredients.
+    /// `converter` must cover the same ingredients (promotion pack sizes such
+    /// as "2 pcs" or "1 l" are converted through it).
+    pub async fn load<C: ConnectionTrait>(
+        db: &C,
+        ingredient_ids: &[i64],
+        converter: &UnitConverter,
+    ) -> Result<Self, AppError> {
+        let mut book = Self::default();
+        if ingredient_ids.is_empty() {
+            return Ok(book);
+        }
+
+        for ing in ingredient::Entity::find()
+            .filter(ingredient::Column::Id.is_in(ingredient_ids.to_vec()))
+            .all(db)
+            .await?
+        {
+            let price = match ing.reference_price_per_kg.filter(|p| *p > Decimal::ZERO) {
+                Some(per_kg) => IngredientPrice { per_kg, source: PriceSource::Reference },
+                None => IngredientPrice {
+                    per_kg: category_price_per_kg(ing.category.as_deref()),
+                    source: PriceSource::Category,
+                },
+            };
+            book.prices.insert(ing.id, price);
+        }
+
+        let links = store_promotion_ingredient::Entity::find()
+            .filter(store_promotion_ingredient::Column::IngredientId.is_in(ingredient_ids.to_vec()))
+            .all(db)
+            .await?;
+        if links.is_empty() {
+            return Ok(book);
+        }
+
+        let now = Utc::now().fixed_offset();
+        let promotions: HashMap<_, _> = store_promotion::Entity::find()
+            .filter(store_promotion::Column::Id.is_in(links.iter().map(|l| l.promotion_id).collect::<Vec<_>>()))
+            .filter(store_promotion::Column::IsActive.eq(true))
+            .filter(
+                Condition::any()
+                    .add(store_promotion::Column::ValidFrom.is_null())
+                    .add(store_promotion::Column::ValidFrom.lte(now)),
+            )
+            .filter(
+                Condition::any()
+                    .add(store_promotion::Column::ValidUntil.is_null())
+                    .add(store_promotion::Column::ValidUntil.gt(now)),
+            )
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        for link in links {
+            let Some(promo) = promotions.get(&link.promotion_id) else { continue };
+            let (amount, unit) = pack_size(promo.unit.as_deref());
+            let Ok(grams) = converter.to_grams(link.ingredient_id, amount, &unit) else { continue };
+            if grams <= Decimal::ZERO {
+                continue;
+            }
+            let per_kg = (promo.discounted_price * Decimal::from(1000) / grams).round_dp(2);
+            let cheaper = book.prices.get(&link.ingredient_id).is_none_or(|current| {
+                current.source != PriceSource::Promotion || per_kg < current.per_kg
+            });
+            if cheaper {
+                book.prices.insert(link.ingredient_id, IngredientPrice { per_kg, source: PriceSource::Promotion });
+            }
+        }
+
+        Ok(book)
+    }
+
+    pub fn price(&self, ingredient_id: i64) -> Option<IngredientPrice> {
+        self.prices.get(&ingredient_id).copied()
+    }
+
+    /// Estimated cost of one recipe's ingredient lines, as written
+    pub fn recipe_cost<'a>(
+        &self,
+        lines: impl IntoIterator<Item = &'a recipe_ingredient::Model>,
+        converter: &UnitConverter,
+    ) -> RecipeCost {
+        let mut cost = RecipeCost::default();
+        for line in lines {
+            let grams = match converter.recipe_grams(line) {
+                Ok(Some(grams)) => grams,
+                Ok(None) => continue,
+                Err(_) => {
+                    cost.unpriced.push(line.ingredient_id);
+                    continue;
+                }
+            };
+            let Some(price) = self.price(line.ingredient_id) else {
+                cost.unpriced.push(line.ingredient_id);
+                continue;
+            };
+            let line_cost = grams * price.per_kg / Decimal::from(1000);
+            cost.total += line_cost;
+            if price.source == PriceSource::Promotion {
+                cost.from_promotions += line_cost;
+            }
+        }
+        cost.total = cost.total.round_dp(2);
+        cost.from_promotions = cost.from_promotions.round_dp(2);
+        cost
+    }
+}
+
+/// Cost of every recipe in `lines` (all of a recipe's lines must be
+/// included), keyed by recipe id
+pub async fn recipe_costs<C: ConnectionTrait>(
+    db: &C,
+    lines: &[recipe_ingredient::Model],
+) -> Result<HashMap<i64, RecipeCost>, AppError> {
+    let mut ingredient_ids: Vec<i64> = lines.iter().map(|l| l.ingredient_id).collect();
+    ingredient_ids.sort_unstable();
+    ingredient_ids.dedup();
+
+    let converter = UnitConverter::load(db, &ingredient_ids).await?;
+    let book = PriceBook::load(db, &ingredient_ids, &converter).await?;
+
+    let mut by_recipe: HashMap<i64, Vec<&recipe_ingredient::Model>> = HashMap::new();
+    for line in lines {
+        by_recipe.entry(line.recipe_id).or_default().push(line);
+    }
+
+    Ok(by_recipe
+        .into_iter()
+        .map(|(recipe_id, lines)| (recipe_id, book.recipe_cost(lines, &converter)))
+        .collect())
+}
+
+/// Pack size from a promotion's unit string: "500g" → (500, "g"),
+/// "1,5 kg" → (1.5, "kg"), "2 x 250 g" → (500, "g"), "kg" → (1, "kg").
+/// A missing unit means one piece.
+fn pack_size(unit: Option<&str>) -> (Decimal, String) {
+    let text = unit.unwrap_or("").trim().to_lowercase();
+    let text = text.strip_prefix("per ").or_else(|| text.strip_prefix('/')).unwrap_or(&text).trim();
+
+    let (mut amount, mut rest) = leading_number(text);
+    if let Some(multiplied) = rest.trim_start().strip_prefix('x') {
+        let (inner, inner_rest) = leading_number(multiplied.trim_start());
+        if inner_rest.len() < multiplied.trim_start().len() {
+            amount *= inner;
+            rest = inner_rest;
+        }
+    }
+    (amount, rest.trim().to_string())
+}
+
+/// Split a leading decimal number off `text`; 1 when there is none
+fn leading_number(text: &str) -> (Decimal, &str) {
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(text.len());
+    let number = Decimal::from_str(&text[..end].replace(',', "."))
+        .ok()
+        .filter(|n| *n > Decimal::ZERO);
+    match number {
+        Some(n) => (n, &text[end..]),
+        None => (Decimal::ONE, text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn line(ingredient_id: i64, quantity: &str, unit: &str) -> recipe_ingredient::Model {
+        recipe_ingredient::Model {
+            id: ingredient_id,
+            recipe_id: 1,
+            ingredient_id,
+            quantity: Some(dec(quantity)),
+            unit: Some(unit.into()),
+            quantity_grams: None,
+            notes: None,
+            display_order: 0,
+        }
+    }
+
+    #[test]
+    fn parses_promotion_pack_sizes() {
+        assert_eq!(pack_size(Some("500g")), (dec("500"), "g".into()));
+        assert_eq!(pack_size(Some("1,5 Kg")), (dec("1.5"), "kg".into()));
+        assert_eq!(pack_size(Some("2 x 250 g")), (dec("500"), "g".into()));
+        assert_eq!(pack_size(Some("per kg")), (dec("1"), "kg".into()));
+        assert_eq!(pack_size(Some("piece")), (dec("1"), "piece".into()));
+        assert_eq!(pack_size(None), (dec("1"), "".into()));
+    }
+
+    #[test]
+    fn prices_recipe_lines_by_weight() {
+        let mut book = PriceBook::default();
+        book.prices.insert(1, IngredientPrice { per_kg: dec("8.00"), source: PriceSource::Promotion });
+        book.prices.insert(2, IngredientPrice { per_kg: dec("2.50"), source: PriceSource::Category });
+        let converter = UnitConverter::default();
+
+        let lines = [line(1, "250", "g"), line(2, "1", "kg"), line(3, "2", "cups")];
+        let cost = book.recipe_cost(&lines, &converter);
+        assert_eq!(cost.total, dec("4.50"));
+        assert_eq!(cost.from_promotions, dec("2.00"));
+        assert_eq!(cost.unpriced, vec![3]);
+
+        let shared = cost.for_servings(2, 4);
+        assert_eq!(shared.total, dec("2.25"));
+        assert_eq!(category_price_per_kg(None), dec("5.00"));
+    }
+}
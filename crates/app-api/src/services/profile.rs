@@ -1,6 +1,7 @@
 //! Profile Service — fetch and update user profile
 
 use chrono::Utc;
+use rust_decimal::Decimal;
 use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
 use serde_json::json;
 use uuid::Uuid;
@@ -8,6 +9,7 @@ use uuid::Uuid;
 use crate::entity::user;
 use cookest_shared::errors::AppError;
 use crate::models::profile::*;
+use crate::services::nutrition::{self, NutritionTargets, ACTIVITY_LEVELS, SEXES};
 
 pub struct ProfileService {
     db: DatabaseConnection,
@@ -25,26 +27,26 @@ impl ProfileService {
             .await?
             .ok_or(AppError::NotFound("User".into()))?;
 
-        Ok(ProfileResponse {
-            id: user.id.to_string(),
-            email: user.email,
-            name: user.name,
-            household_size: user.household_size,
-            dietary_restrictions: user.dietary_restrictions,
-            allergies: user.allergies,
-            avatar_url: user.avatar_url,
-            is_email_verified: user.is_email_verified,
-            two_factor_enabled: user.two_factor_enabled,
-            created_at: user.created_at.to_rfc3339(),
-        })
+        Ok(ProfileResponse::from(user))
     }
 
-    /// Update user profile fields
+    /// Update user profile fields. Nutrition targets are recomputed from the
+    /// updated metrics and goals unless the user has set them by hand.
     pub async fn update_profile(
         &self,
         user_id: Uuid,
         req: UpdateProfileRequest,
     ) -> Result<ProfileResponse, AppError> {
+        if req.sex.as_deref().is_some_and(|s| !SEXES.contains(&s)) {
+            return Err(profile_error("sex", "sex must be 'female' or 'male'"));
+        }
+        if req.activity_level.as_deref().is_some_and(|a| !ACTIVITY_LEVELS.contains(&a)) {
+            return Err(profile_error(
+                "activity_level",
+                "activity_level must be one of sedentary, light, moderate, active, very_active",
+            ));
+        }
+
         let user = user::Entity::find_by_id(user_id)
             .one(&self.db)
             .await?
@@ -68,21 +70,51 @@ impl ProfileService {
         if let Some(avatar) = req.avatar_url {
             active.avatar_url = Set(Some(avatar));
         }
+        if let Some(goals) = req.health_goals {
+            active.health_goals = Set(Some(goals));
+        }
+        if let Some(year) = req.birth_year {
+            active.birth_year = Set(Some(year));
+        }
+        if let Some(sex) = req.sex {
+            active.sex = Set(Some(sex));
+        }
+        if let Some(weight) = req.weight_kg {
+            active.weight_kg = Set(Some(Decimal::try_from(weight).unwrap_or_default().round_dp(1)));
+        }
+        if let Some(height) = req.height_cm {
+            active.height_cm = Set(Some(Decimal::try_from(height).unwrap_or_default().round_dp(1)));
+        }
+        if let Some(level) = req.activity_level {
+            active.activity_level = Set(Some(level));
+        }
+
+        if let Some(t) = req.nutrition_targets {
+            NutritionTargets {
+                calories: t.calories as f64,
+                protein_g: t.protein_g as f64,
+                carbs_g: t.carbs_g as f64,
+                fat_g: t.fat_g as f64,
+                fiber_g: t.fiber_g as f64,
+            }
+            .store(&mut active);
+            active.nutrition_targets_manual = Set(true);
+        } else if req.reset_nutrition_targets == Some(true) {
+            active.nutrition_targets_manual = Set(false);
+        }
+        nutrition::refresh_targets(&mut active)?;
         active.updated_at = Set(now);
 
         let saved = active.update(&self.db).await?;
 
-        Ok(ProfileResponse {
-            id: saved.id.to_string(),
-            email: saved.email,
-            name: saved.name,
-            household_size: saved.household_size,
-            dietary_restrictions: saved.dietary_restrictions,
-            allergies: saved.allergies,
-            avatar_url: saved.avatar_url,
-            is_email_verified: saved.is_email_verified,
-            two_factor_enabled: saved.two_factor_enabled,
-            created_at: saved.created_at.to_rfc3339(),
-        })
+        Ok(ProfileResponse::from(saved))
     }
 }
+
+fn profile_error(field: &'static str, message: &'static str) -> AppError {
+    let mut errors = validator::ValidationErrors::new();
+    let mut e = validator::ValidationError::new("profile");
+    e.message = Some(message.into());
+    errors.add(field, e);
+    AppError::Validation(errors)
+}
@@ -0,0 +1,202 @@
+//! Promotion matching — links published store promotions to ingredients.
+//!
+//! Flyer product names carry noise the ingredient list doesn't ("Mimosa
+//! Leite Meio-Gordo 1L", "Chicken Breast 2 x 250 g"), so names are
+//! normalised first: lowercased, the brand removed, pack sizes and unit
+//! words dropped. The result is scored with pg_trgm against every
+//! ingredient name and alias — the mean of `similarity()` and
+//! `word_similarity()`, so "beef" still scores well against "beef mince"
+//! while whole-name matches stay on top. The best match at or above
+//! [`AUTO_MATCH_THRESHOLD`] is linked in `store_promotion_ingredients`.
+//!
+//! `store_promotions.match_status` records where a promotion stands —
+//! matched automatically, unmatched, or confirmed by an admin — so admins
+//! can work through the automatic results and later runs never touch links
+//! an admin has set.
+
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait,
+    QueryFilter, QueryResult, Set, Statement,
+};
+use serde::Serialize;
+
+use crate::entity::{store_promotion, store_promotion_ingredient};
+use cookest_shared::errors::AppError;
+
+/// Lowest similarity linked without an admin
+pub const AUTO_MATCH_THRESHOLD: f64 = 0.5;
+
+/// Words that describe the pack rather than the product
+const NOISE_WORDS: &[&str] = &[
+    "g", "gr", "kg", "mg", "ml", "cl", "dl", "l", "lt", "ltr", "x", "un", "und", "unid", "uds",
+    "pc", "pcs", "piece", "pieces", "pack", "packs", "emb", "approx", "aprox", "per", "each",
+];
+
+/// An ingredient a promotion could be linked to
+#[derive(Debug, Clone, Serialize)]
+pub struct IngredientMatch {
+    pub ingredient_id: i64,
+    pub name: String,
+    /// pg_trgm score 0.0–1.0 against the closest name or alias
+    pub score: f64,
+}
+
+impl IngredientMatch {
+    /// Score as stored in `store_promotion_ingredients.similarity_score`
+    pub fn stored_score(&self) -> Option<Decimal> {
+        Decimal::try_from(self.score).ok().map(|s| s.round_dp(3))
+    }
+}
+
+/// "Mimosa Leite Meio-Gordo 1L" with brand "Mimosa" → "leite meio gordo".
+/// Falls back to the lowercased name when nothing would be left.
+pub fn normalise_product_name(name: &str, brand: Option<&str>) -> String {
+    let brand_words: Vec<String> = brand
+        .map(|b| b.to_lowercase().split(|c: char| !c.is_alphanumeric()).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let lower = name.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == ',' || c == '.'))
+        .map(|w| w.trim_matches(|c| c == ',' || c == '.'))
+        .filter(|w| !w.is_empty())
+        // "500g", "1,5", "2x250g", "30%" — quantities, not names
+        .filter(|w| !w.starts_with(|c: char| c.is_ascii_digit()))
+        .filter(|w| !NOISE_WORDS.contains(w))
+        .filter(|w| !brand_words.iter().any(|b| b == w))
+        .collect();
+
+    if words.is_empty() {
+        lower.trim().to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+/// Ingredients whose name or an alias resembles `query` (already
+/// normalised), best first
+pub async fn suggest<C: ConnectionTrait>(db: &C, query: &str, limit: u64) -> Result<Vec<IngredientMatch>, AppError> {
+    // `%` and `<%` use the trigram indexes with pg_trgm's default cut-offs
+    let sql = r#"
+        WITH names AS (
+            SELECT id AS ingredient_id, name AS term FROM ingredients
+            WHERE name % $1 OR name <% $1
+            UNION ALL
+            SELECT ingredient_id, alias FROM ingredient_aliases
+            WHERE alias % $1 OR alias <% $1
+        )
+        SELECT n.ingredient_id, i.name,
+               MAX((similarity(n.term, $1) + word_similarity(n.term, $1)) / 2)::float8 AS score
+        FROM names n
+        JOIN ingredients i ON i.id = n.ingredient_id
+        GROUP BY n.ingredient_id, i.name
+        ORDER BY score DESC, i.name
+        LIMIT $2
+    "#;
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [query.into(), (limit as i64).into()],
+        ))
+        .await?;
+    decode_matches(rows)
+}
+
+/// How closely each of `ingredient_ids` resembles `query`, for links an
+/// admin chose by hand. Unknown ids are left out.
+pub async fn score_ingredients<C: ConnectionTrait>(
+    db: &C,
+    query: &str,
+    ingredient_ids: &[i64],
+) -> Result<Vec<IngredientMatch>, AppError> {
+    let sql = r#"
+        SELECT i.id AS ingredient_id, i.name,
+               GREATEST(
+                   (similarity(i.name, $1) + word_similarity(i.name, $1)) / 2,
+                   COALESCE((SELECT MAX((similarity(a.alias, $1) + word_similarity(a.alias, $1)) / 2)
+                             FROM ingredient_aliases a
+                             WHERE a.ingredient_id = i.id), 0)
+               )::float8 AS score
+        FROM ingredients i
+        WHERE i.id = ANY($2)
+        ORDER BY score DESC, i.name
+    "#;
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [query.into(), ingredient_ids.to_vec().into()],
+        ))
+        .await?;
+    decode_matches(rows)
+}
+
+fn decode_matches(rows: Vec<QueryResult>) -> Result<Vec<IngredientMatch>, AppError> {
+    rows.into_iter()
+        .map(|row| {
+            Ok(IngredientMatch {
+                ingredient_id: row.try_get("", "ingredient_id").map_err(|e| AppError::Internal(e.to_string()))?,
+                name: row.try_get("", "name").map_err(|e| AppError::Internal(e.to_string()))?,
+                score: row.try_get("", "score").map_err(|e| AppError::Internal(e.to_string()))?,
+            })
+        })
+        .collect()
+}
+
+/// Link a promotion to its best-matching ingredient, replacing any earlier
+/// automatic link. Promotions an admin has confirmed are left alone.
+pub async fn auto_match<C: ConnectionTrait>(
+    db: &C,
+    promotion: &store_promotion::Model,
+) -> Result<Option<IngredientMatch>, AppError> {
+    if promotion.match_status == "confirmed" {
+        return Ok(None);
+    }
+
+    let query = normalise_product_name(&promotion.product_name, promotion.brand.as_deref());
+    let best = suggest(db, &query, 1)
+        .await?
+        .into_iter()
+        .next()
+        .filter(|m| m.score >= AUTO_MATCH_THRESHOLD);
+
+    store_promotion_ingredient::Entity::delete_many()
+        .filter(store_promotion_ingredient::Column::PromotionId.eq(promotion.id))
+        .exec(db)
+        .await?;
+    if let Some(m) = &best {
+        store_promotion_ingredient::ActiveModel {
+            promotion_id: Set(promotion.id),
+            ingredient_id: Set(m.ingredient_id),
+            similarity_score: Set(m.stored_score()),
+        }
+        .insert(db)
+        .await?;
+    }
+
+    let status = if best.is_some() { "auto" } else { "unmatched" };
+    store_promotion::Entity::update_many()
+        .col_expr(store_promotion::Column::MatchStatus, Expr::value(status))
+        .filter(store_promotion::Column::Id.eq(promotion.id))
+        .exec(db)
+        .await?;
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalising_drops_brand_and_pack_size() {
+        assert_eq!(normalise_product_name("Mimosa Leite Meio-Gordo 1L", Some("Mimosa")), "leite meio gordo");
+        assert_eq!(normalise_product_name("Chicken Breast 2 x 250 g", None), "chicken breast");
+        assert_eq!(normalise_product_name("Olive Oil 0,75 lt", Some("Gallo")), "olive oil");
+        assert_eq!(normalise_product_name("Bananas approx. 1kg", None), "bananas");
+        assert_eq!(normalise_product_name("Eggs pack 12 un", None), "eggs");
+        // Nothing but brand and size: keep what we were given
+        assert_eq!(normalise_product_name("Compal 1L", Some("Compal")), "compal 1l");
+    }
+}
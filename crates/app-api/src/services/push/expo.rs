@@ -0,0 +1,144 @@
+//! Expo push API (`POST /--/api/v2/push/send`).
+//!
+//! Expo accepts up to 100 messages per request and answers with one ticket
+//! per message. Only Expo push tokens (`ExponentPushToken[…]`) can be sent
+//! this way; anything else is reported as unsupported without a request.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{PushMessage, PushOutcome, PushProvider};
+use cookest_shared::errors::AppError;
+
+/// Expo's per-request message limit
+const MAX_BATCH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct SendResponse {
+    #[serde(default)]
+    data: Vec<Ticket>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ticket {
+    status: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    details: Option<TicketDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TicketDetails {
+    #[serde(default)]
+    error: Option<String>,
+}
+
+pub struct ExpoProvider {
+    client: Client,
+    url: String,
+    access_token: Option<SecretString>,
+}
+
+impl ExpoProvider {
+    pub fn new(url: String, access_token: Option<SecretString>) -> Self {
+        Self { client: Client::new(), url, access_token }
+    }
+
+    async fn send_batch(&self, batch: &[&PushMessage]) -> Result<Vec<PushOutcome>, AppError> {
+        let body: Vec<Value> = batch
+            .iter()
+            .map(|m| json!({ "to": m.token, "title": m.title, "body": m.body, "data": m.data, "sound": "default" }))
+            .collect();
+
+        let mut req = self.client.post(&self.url).json(&body);
+        if let Some(token) = &self.access_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|e| {
+            tracing::error!("Expo push request failed: {}", e);
+            AppError::Internal("Push provider unavailable".into())
+        })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            tracing::error!("Expo push error {}: {}", status, text);
+            return Err(AppError::Internal(format!("Push provider returned {}", status)));
+        }
+        let parsed: SendResponse = resp.json().await.map_err(|e| {
+            tracing::error!("Failed to parse Expo push response: {}", e);
+            AppError::Internal("Failed to parse push provider response".into())
+        })?;
+        Ok(outcomes(parsed, batch.len()))
+    }
+}
+
+fn is_expo_token(token: &str) -> bool {
+    (token.starts_with("ExponentPushToken[") || token.starts_with("ExpoPushToken[")) && token.ends_with(']')
+}
+
+/// One outcome per message; missing tickets count as failures
+fn outcomes(resp: SendResponse, expected: usize) -> Vec<PushOutcome> {
+    let mut tickets = resp.data.into_iter();
+    (0..expected)
+        .map(|_| match tickets.next() {
+            Some(t) if t.status == "ok" => PushOutcome::Delivered,
+            Some(t) => match t.details.and_then(|d| d.error).as_deref() {
+                Some("DeviceNotRegistered") => PushOutcome::InvalidToken,
+                code => PushOutcome::Failed(
+                    t.message.or(code.map(str::to_string)).unwrap_or_else(|| "Expo push error".into()),
+                ),
+            },
+            None => PushOutcome::Failed("Missing Expo push ticket".into()),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl PushProvider for ExpoProvider {
+    async fn send(&self, messages: &[PushMessage]) -> Result<Vec<PushOutcome>, AppError> {
+        let mut results = vec![PushOutcome::Unsupported; messages.len()];
+        let sendable: Vec<usize> = (0..messages.len()).filter(|&i| is_expo_token(&messages[i].token)).collect();
+
+        for chunk in sendable.chunks(MAX_BATCH) {
+            let batch: Vec<&PushMessage> = chunk.iter().map(|&i| &messages[i]).collect();
+            for (&i, outcome) in chunk.iter().zip(self.send_batch(&batch).await?) {
+                results[i] = outcome;
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tickets_map_to_outcomes() {
+        let resp: SendResponse = serde_json::from_value(json!({
+            "data": [
+                { "status": "ok", "id": "XXXX-1" },
+                { "status": "error", "message": "not registered", "details": { "error": "DeviceNotRegistered" } },
+                { "status": "error", "message": "slow down", "details": { "error": "MessageRateExceeded" } }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            outcomes(resp, 4),
+            vec![
+                PushOutcome::Delivered,
+                PushOutcome::InvalidToken,
+                PushOutcome::Failed("slow down".into()),
+                PushOutcome::Failed("Missing Expo push ticket".into()),
+            ]
+        );
+        assert!(is_expo_token("ExponentPushToken[xxxxxxxxxxxxxxxxxxxxxx]"));
+        assert!(!is_expo_token("fcm:APA91bH"));
+    }
+}
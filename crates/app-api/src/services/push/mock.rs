@@ -0,0 +1,31 @@
+//! Stand-in provider for local development — logs instead of sending.
+//!
+//! Tokens are treated by prefix so rejection and retry paths can be
+//! exercised without a real device: `invalid…` is rejected as unregistered,
+//! `unavailable…` fails transiently, anything else is delivered.
+
+use async_trait::async_trait;
+
+use super::{PushMessage, PushOutcome, PushProvider};
+use cookest_shared::errors::AppError;
+
+pub struct MockPush;
+
+#[async_trait]
+impl PushProvider for MockPush {
+    async fn send(&self, messages: &[PushMessage]) -> Result<Vec<PushOutcome>, AppError> {
+        Ok(messages
+            .iter()
+            .map(|m| {
+                if m.token.starts_with("invalid") {
+                    PushOutcome::InvalidToken
+                } else if m.token.starts_with("unavailable") {
+                    PushOutcome::Failed("mock provider unavailable".into())
+                } else {
+                    tracing::info!("[mock push] {} ({}): {} — {}", m.token, m.platform, m.title, m.body);
+                    PushOutcome::Delivered
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,68 @@
+//! Push provider abstraction used by the notification dispatcher.
+//!
+//! The dispatcher hands a provider one message per registered device and
+//! gets back one [`PushOutcome`] per message, so it can prune tokens the
+//! provider rejects and retry transient failures. The backend is chosen at
+//! startup from `PUSH_PROVIDER`:
+//!
+//! - `expo` (default) — [`ExpoProvider`], Expo's push API, which relays to
+//!   FCM (Android) and APNs (iOS)
+//! - `mock` — [`MockPush`], logs messages instead of sending them
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::config::{Config, PushBackend};
+use cookest_shared::errors::AppError;
+
+mod expo;
+pub mod mock;
+
+pub use expo::ExpoProvider;
+pub use mock::MockPush;
+
+/// The provider the dispatcher sends through, built once in `main`
+pub type SharedPush = Arc<dyn PushProvider>;
+
+/// One notification addressed to one device
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushMessage {
+    pub token: String,
+    /// "ios" | "android" | "web"
+    pub platform: String,
+    pub title: String,
+    pub body: String,
+    pub data: Value,
+}
+
+/// What happened to one message
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushOutcome {
+    Delivered,
+    /// The provider says the token will never work again — delete it
+    InvalidToken,
+    /// This provider can't reach the token (e.g. a web token sent through Expo)
+    Unsupported,
+    /// Worth retrying later
+    Failed(String),
+}
+
+#[async_trait]
+pub trait PushProvider: Send + Sync {
+    /// Send each message, returning one outcome per message in order.
+    /// `Err` means nothing could be sent (e.g. the provider is unreachable).
+    async fn send(&self, messages: &[PushMessage]) -> Result<Vec<PushOutcome>, AppError>;
+}
+
+/// Build the provider selected in the config
+pub fn from_config(config: &Config) -> SharedPush {
+    match &config.push_backend {
+        PushBackend::Expo { url, access_token } => Arc::new(ExpoProvider::new(url.clone(), access_token.clone())),
+        PushBackend::Mock => {
+            tracing::warn!("PUSH_PROVIDER=mock — push notifications are logged, not sent");
+            Arc::new(MockPush)
+        }
+    }
+}
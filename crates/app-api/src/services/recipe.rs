@@ -13,6 +13,7 @@ use crate::entity::{
 };
 use cookest_shared::errors::AppError;
 use crate::models::recipe::*;
+use crate::services::household::DataScope;
 
 pub struct RecipeService {
     db: DatabaseConnection,
@@ -258,10 +259,11 @@ impl RecipeService {
         user_id: Uuid,
         query: RecipeQuery,
     ) -> Result<PaginatedResponse<RecipeListItem>, AppError> {
-        // Load user inventory as a set of ingredient_ids
+        // Load user (or household) inventory as a set of ingredient_ids
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let user_ingredient_ids: std::collections::HashSet<i64> =
             inventory_item::Entity::find()
-                .filter(inventory_item::Column::UserId.eq(user_id))
+                .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
                 .all(&self.db)
                 .await?
                 .into_iter()
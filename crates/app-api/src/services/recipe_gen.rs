@@ -1,19 +1,19 @@
 //! AI-powered recipe generation service.
 //!
-//! Implements a **generate → score → refine** loop backed by a local Ollama
-//! LLM.  Each iteration asks Ollama to generate a recipe, a second pass
+//! Implements a **generate → score → refine** loop backed by the configured
+//! LLM provider.  Each iteration asks the model to generate a recipe, a second pass
 //! scores it for palatability and nutrition balance, and if the score is
 //! below [`SCORE_THRESHOLD`] the loop retries with the judge’s suggestions
 //! as a critique prompt.  The loop is capped at [`MAX_ITERATIONS`] to bound
 //! latency regardless of LLM quality.
 
-use reqwest::Client;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 use uuid::Uuid;
 
 use crate::entity::{ingredient, inventory_item, user};
+use crate::services::household::DataScope;
+use crate::services::llm::{LlmMessage, LlmRequest, SharedLlm};
 use cookest_shared::errors::AppError;
 
 /// Maximum number of generate → score → refine cycles before returning the
@@ -28,10 +28,10 @@ const MAX_ITERATIONS: usize = 3;
 /// with the judge’s suggestions as a critique.
 const SCORE_THRESHOLD: f32 = 7.0;
 
-// ── Internal Ollama types ─────────────────────────────────────────────────────
+// ── Internal model output types ───────────────────────────────────────────────
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-struct OllamaRecipeOutput {
+struct LlmRecipeOutput {
     name: String,
     description: String,
     cuisine: String,
@@ -46,7 +46,7 @@ struct OllamaRecipeOutput {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct OllamaScoreOutput {
+struct LlmScoreOutput {
     palatability_score: f32,
     palatability_reason: String,
     suggestions: Vec<String>,
@@ -122,39 +122,22 @@ pub struct GeneratedRecipeResponse {
 
 // ── Service ───────────────────────────────────────────────────────────────────
 
-/// Generates personalised recipes via Ollama using the user’s pantry,
-/// dietary restrictions, and cooking skill as context.
+/// Generates personalised recipes using the user’s pantry, dietary
+/// restrictions, and cooking skill as context.
 ///
 /// One instance is created per request (or shared as `Arc`) because it
 /// holds only immutable config alongside the connection pool.
 pub struct RecipeGenService {
     db: DatabaseConnection,
-    http: Client,
-    ollama_url: String,
-    model: String,
+    llm: SharedLlm,
 }
 
 impl RecipeGenService {
-    /// Construct a service instance.
-    ///
-    /// Reads `OLLAMA_URL` (default `http://localhost:11434`) and
-    /// `OLLAMA_MODEL` (default `llama3.1:8b`) from the environment so the
-    /// same binary can target different models per environment.
-    /// The HTTP client uses a 180-second timeout to accommodate slow
-    /// local hardware that may take minutes for a full generation pass.
-    pub fn new(db: DatabaseConnection) -> Self {
-        let http = Client::builder()
-            .timeout(Duration::from_secs(180))
-            .build()
-            .unwrap_or_default();
-        Self {
-            db,
-            http,
-            ollama_url: std::env::var("OLLAMA_URL")
-                .unwrap_or_else(|_| "http://localhost:11434".to_string()),
-            model: std::env::var("OLLAMA_MODEL")
-                .unwrap_or_else(|_| "llama3.1:8b".to_string()),
-        }
+    /// Construct a service instance on top of the shared LLM provider.
+    /// Both the generation and the judging pass use its chat model in JSON
+    /// mode; slow local hardware is covered by `LLM_TIMEOUT_SECS`.
+    pub fn new(db: DatabaseConnection, llm: SharedLlm) -> Self {
+        Self { db, llm }
     }
 
     /// Generate a personalised recipe for the given user.
@@ -165,7 +148,7 @@ impl RecipeGenService {
     /// score or after [`MAX_ITERATIONS`] attempts.
     ///
     /// # Errors
-    /// Returns `AppError` if the DB lookup, Ollama HTTP call, or JSON
+    /// Returns `AppError` if the DB lookup, model call, or JSON
     /// parsing fails at any point.
     pub async fn generate(
         &self,
@@ -249,66 +232,34 @@ impl RecipeGenService {
         }
     }
 
-    // ── Ollama calls ──────────────────────────────────────────────────────────
-
-    async fn call_generate(&self, prompt: &str) -> Result<OllamaRecipeOutput, AppError> {
-        let payload = serde_json::json!({
-            "model": self.model,
-            "prompt": prompt,
-            "stream": false,
-            "format": "json"
-        });
+    // ── Model calls ───────────────────────────────────────────────────────────
 
+    /// One JSON-mode completion of a single user prompt
+    async fn complete_json(&self, prompt: &str) -> Result<String, AppError> {
         let resp = self
-            .http
-            .post(format!("{}/api/generate", self.ollama_url))
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| AppError::Internal(format!("Ollama generate request failed: {e}")))?;
-
-        let body: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| AppError::Internal(format!("Ollama generate response parse failed: {e}")))?;
-
-        let raw = body["response"]
-            .as_str()
-            .ok_or_else(|| AppError::Internal("Ollama returned empty response field".into()))?;
-
-        serde_json::from_str(raw).map_err(|e| {
+            .llm
+            .chat(LlmRequest::new(vec![LlmMessage::user(prompt)]).json())
+            .await?;
+        Ok(resp.message.content)
+    }
+
+    async fn call_generate(&self, prompt: &str) -> Result<LlmRecipeOutput, AppError> {
+        let raw = self.complete_json(prompt).await?;
+        if raw.trim().is_empty() {
+            return Err(AppError::Internal("Model returned an empty recipe".into()));
+        }
+
+        serde_json::from_str(&raw).map_err(|e| {
             AppError::Internal(format!("Recipe JSON schema parse failed: {e}\nRaw: {raw}"))
         })
     }
 
-    async fn call_score(&self, prompt: &str) -> Result<OllamaScoreOutput, AppError> {
-        let payload = serde_json::json!({
-            "model": self.model,
-            "prompt": prompt,
-            "stream": false,
-            "format": "json"
-        });
+    async fn call_score(&self, prompt: &str) -> Result<LlmScoreOutput, AppError> {
+        let raw = self.complete_json(prompt).await?;
 
-        let resp = self
-            .http
-            .post(format!("{}/api/generate", self.ollama_url))
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| AppError::Internal(format!("Ollama score request failed: {e}")))?;
-
-        let body: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| AppError::Internal(format!("Ollama score response parse failed: {e}")))?;
-
-        let raw = body["response"]
-            .as_str()
-            .ok_or_else(|| AppError::Internal("Ollama score returned empty response".into()))?;
-
-        serde_json::from_str(raw).or_else(|e| {
+        serde_json::from_str(&raw).or_else(|e| {
             tracing::warn!("Score JSON parse failed ({e}), using neutral fallback");
-            Ok(OllamaScoreOutput {
+            Ok(LlmScoreOutput {
                 palatability_score: 7.0,
                 palatability_reason: "Score estimated — evaluation model parse error.".into(),
                 suggestions: vec![],
@@ -358,8 +309,9 @@ impl RecipeGenService {
         }
 
         let pantry_items = if use_pantry {
+            let scope = DataScope::for_user(&self.db, user_id).await?;
             let inventory = inventory_item::Entity::find()
-                .filter(inventory_item::Column::UserId.eq(user_id))
+                .filter(scope.condition(inventory_item::Column::UserId, inventory_item::Column::HouseholdId))
                 .all(&self.db)
                 .await?;
 
@@ -502,7 +454,7 @@ Rules:
         prompt
     }
 
-    fn build_score_prompt(&self, recipe: &OllamaRecipeOutput, user_ctx: &str) -> String {
+    fn build_score_prompt(&self, recipe: &LlmRecipeOutput, user_ctx: &str) -> String {
         let recipe_json = serde_json::to_string_pretty(recipe).unwrap_or_default();
         format!(
             r#"You are a food critic and nutritionist evaluating a recipe.
@@ -1,47 +1,16 @@
-//! Grocery Scan Service — AI-powered grocery detection via Ollama
+//! Grocery Scan Service — AI-powered grocery detection
 //!
-//! Sends a base64-encoded image to Ollama's multimodal model (qwen2.5vl by default)
-//! and parses the structured JSON response into a list of detected grocery items.
-//!
-//! Environment variables:
-//!   OLLAMA_URL               — Ollama server URL (default: http://localhost:11434)
-//!   OLLAMA_VISION_MODEL      — Vision model to use (default: qwen2.5vl:7b)
-//!   OLLAMA_VISION_TIMEOUT_SECS — HTTP timeout in seconds (default: 120)
+//! Sends a base64-encoded image to the LLM provider's vision model
+//! (qwen2.5vl on Ollama by default) and parses the structured JSON response
+//! into a list of detected grocery items.
 
 use base64::{Engine, engine::general_purpose::STANDARD as B64};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::services::llm::{LlmMessage, LlmRequest, SharedLlm};
 use cookest_shared::errors::AppError;
 
-// ── Ollama multimodal types ───────────────────────────────────────────────────
-
-#[derive(Debug, Serialize)]
-struct OllamaVisionRequest {
-    model: String,
-    messages: Vec<OllamaVisionMessage>,
-    stream: bool,
-}
-
-#[derive(Debug, Serialize)]
-struct OllamaVisionMessage {
-    role: String,
-    content: String,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    images: Vec<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct OllamaVisionResponse {
-    message: OllamaVisionMessageInner,
-}
-
-#[derive(Debug, Deserialize)]
-struct OllamaVisionMessageInner {
-    content: String,
-}
-
 // ── Public types ──────────────────────────────────────────────────────────────
 
 /// A single grocery item detected from an image scan
@@ -76,33 +45,12 @@ pub struct BulkAddItem {
 // ── Service ───────────────────────────────────────────────────────────────────
 
 pub struct ScanService {
-    http: Client,
-    ollama_url: String,
-    model: String,
+    llm: SharedLlm,
 }
 
 impl ScanService {
-    pub fn new() -> Self {
-        let ollama_url = std::env::var("OLLAMA_URL")
-            .unwrap_or_else(|_| "http://localhost:11434".to_string());
-        // Prefer a dedicated vision model env var, fall back to OLLAMA_MODEL, then qwen2.5vl
-        let model = std::env::var("OLLAMA_VISION_MODEL")
-            .or_else(|_| std::env::var("OLLAMA_MODEL"))
-            .unwrap_or_else(|_| "qwen2.5vl:7b".to_string());
-        let timeout_secs: u64 = std::env::var("OLLAMA_VISION_TIMEOUT_SECS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(120);
-
-        Self {
-            http: Client::builder()
-                .timeout(std::time::Duration::from_secs(timeout_secs))
-                // Panic at startup rather than silently losing the configured timeout
-                .build()
-                .expect("reqwest client with timeout"),
-            ollama_url,
-            model,
-        }
+    pub fn new(llm: SharedLlm) -> Self {
+        Self { llm }
     }
 
     /// Analyse an image and return detected grocery items.
@@ -125,41 +73,9 @@ impl ScanService {
             "Return only the JSON array, nothing else."
         );
 
-        let request = OllamaVisionRequest {
-            model: self.model.clone(),
-            messages: vec![OllamaVisionMessage {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-                images: vec![b64_image],
-            }],
-            stream: false,
-        };
-
-        let resp = self
-            .http
-            .post(format!("{}/api/chat", self.ollama_url))
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| {
-                tracing::error!("Ollama vision request failed: {}", e);
-                AppError::Internal("AI scan service unavailable".into())
-            })?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            tracing::error!("Ollama vision returned {}: {}", status, body);
-            return Err(AppError::Internal("AI scan service returned an error".into()));
-        }
-
-        let ollama_resp: OllamaVisionResponse = resp.json().await.map_err(|e| {
-            tracing::error!("Failed to parse Ollama vision response: {}", e);
-            AppError::Internal("Failed to parse AI scan response".into())
-        })?;
-
-        let raw = ollama_resp.message.content;
-        tracing::debug!("Ollama raw scan response: {}", raw);
+        let message = LlmMessage::user(prompt).with_image(b64_image);
+        let raw = self.llm.chat(LlmRequest::new(vec![message])).await?.message.content;
+        tracing::debug!("Raw scan response: {}", raw);
 
         let items = Self::parse_grocery_items(&raw);
         let count = items.len();
@@ -0,0 +1,306 @@
+//! The jobs the scheduler runs.
+//!
+//! - `expiry_alerts` (hourly) — one push a day per user listing pantry items
+//!   and leftovers that expire today or tomorrow
+//! - `weekly_plans` (every 6 hours) — from Friday on, generates next week's
+//!   meal plan for Pro and Family users who don't have one yet
+//! - `cleanup` (daily) — purges old Stripe idempotency keys, finished
+//!   notifications and job history
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate, Utc};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::ScheduledJob;
+use crate::entity::{
+    household_member, inventory_item, leftover, notification_outbox, scheduled_job_run,
+    stripe_processed_event, user,
+};
+use crate::services::notification::{self, Notification, NotificationKind};
+use crate::services::{InventoryService, MealPlanService};
+use cookest_shared::errors::AppError;
+
+/// Stripe retries a webhook for three days; keys older than this are dead
+const STRIPE_EVENT_RETENTION_DAYS: i64 = 30;
+/// Delivered, skipped and failed notifications kept for support
+const NOTIFICATION_RETENTION_DAYS: i64 = 30;
+const JOB_RUN_RETENTION_DAYS: i64 = 30;
+/// Next week's plan is generated this many days before it starts
+const WEEKLY_PLAN_LEAD_DAYS: i64 = 3;
+
+// ── Expiry alerts ─────────────────────────────────────────────────────────────
+
+pub struct ExpiryAlerts {
+    db: DatabaseConnection,
+    inventory: Arc<InventoryService>,
+}
+
+impl ExpiryAlerts {
+    pub fn new(db: DatabaseConnection, inventory: Arc<InventoryService>) -> Self {
+        Self { db, inventory }
+    }
+
+    /// Owners of food expiring by `until`, plus everyone in their households
+    async fn recipients(&self, today: NaiveDate, until: NaiveDate) -> Result<BTreeSet<Uuid>, AppError> {
+        let items: Vec<(Uuid, Option<Uuid>)> = inventory_item::Entity::find()
+            .select_only()
+            .column(inventory_item::Column::UserId)
+            .column(inventory_item::Column::HouseholdId)
+            .filter(inventory_item::Column::ExpiryDate.between(today, until))
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        let leftovers: Vec<(Uuid, Option<Uuid>)> = leftover::Entity::find()
+            .select_only()
+            .column(leftover::Column::UserId)
+            .column(leftover::Column::HouseholdId)
+            .filter(leftover::Column::ExpiryDate.between(today, until))
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let owners = items.into_iter().chain(leftovers);
+        let mut users = BTreeSet::new();
+        let mut households = BTreeSet::new();
+        for (user_id, household_id) in owners {
+            match household_id {
+                Some(h) => households.insert(h),
+                None => users.insert(user_id),
+            };
+        }
+        if !households.is_empty() {
+            let members = household_member::Entity::find()
+                .filter(household_member::Column::HouseholdId.is_in(households))
+                .all(&self.db)
+                .await?;
+            users.extend(members.into_iter().map(|m| m.user_id));
+        }
+        Ok(users)
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for ExpiryAlerts {
+    fn name(&self) -> &'static str {
+        "expiry_alerts"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(3_600)
+    }
+
+    async fn run(&self) -> Result<Value, AppError> {
+        let today = Utc::now().date_naive();
+        let tomorrow = today + chrono::Duration::days(1);
+        let mut notified = 0;
+
+        for user_id in self.recipients(today, tomorrow).await? {
+            let mut names: Vec<String> = self
+                .inventory
+                .expiring_soon(user_id, 1)
+                .await?
+                .into_iter()
+                .map(|i| i.custom_name.unwrap_or(i.ingredient_name))
+                .collect();
+            names.extend(
+                self.inventory
+                    .list_leftovers(user_id)
+                    .await?
+                    .into_iter()
+                    .filter(|l| (0..=1).contains(&l.days_until_expiry))
+                    .map(|l| format!("{} leftovers", l.recipe_name)),
+            );
+            let Some(body) = expiry_message(&names) else {
+                continue;
+            };
+
+            let queued = notification::enqueue(
+                &self.db,
+                user_id,
+                Notification::new(NotificationKind::Expiry, "Use it up", body)
+                    .with_data(json!({ "screen": "inventory" }))
+                    .dedupe(format!("expiry:{}", today)),
+            )
+            .await?;
+            if queued {
+                notified += 1;
+            }
+        }
+        Ok(json!({ "notified": notified }))
+    }
+}
+
+/// "Milk, Eggs and 2 more expire by tomorrow"
+fn expiry_message(names: &[String]) -> Option<String> {
+    const SHOWN: usize = 2;
+    match names.len() {
+        0 => None,
+        1 => Some(format!("{} expires by tomorrow", names[0])),
+        n if n <= SHOWN => Some(format!("{} expire by tomorrow", names.join(" and "))),
+        n => Some(format!("{} and {} more expire by tomorrow", names[..SHOWN].join(", "), n - SHOWN)),
+    }
+}
+
+// ── Weekly plans ──────────────────────────────────────────────────────────────
+
+pub struct WeeklyPlans {
+    db: DatabaseConnection,
+    meal_plans: Arc<MealPlanService>,
+}
+
+impl WeeklyPlans {
+    pub fn new(db: DatabaseConnection, meal_plans: Arc<MealPlanService>) -> Self {
+        Self { db, meal_plans }
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for WeeklyPlans {
+    fn name(&self) -> &'static str {
+        "weekly_plans"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(6 * 3_600)
+    }
+
+    async fn run(&self) -> Result<Value, AppError> {
+        let now = Utc::now();
+        let today = now.date_naive();
+        let week_start = next_monday(today);
+        if (week_start - today).num_days() > WEEKLY_PLAN_LEAD_DAYS {
+            return Ok(json!({ "week_start": week_start, "generated": 0, "skipped": "too early" }));
+        }
+
+        let subscribers = user::Entity::find()
+            .filter(user::Column::SubscriptionTier.is_in(["pro", "family"]))
+            .filter(
+                Condition::any()
+                    .add(user::Column::SubscriptionValidUntil.is_null())
+                    .add(user::Column::SubscriptionValidUntil.gt(now.fixed_offset())),
+            )
+            .all(&self.db)
+            .await?;
+
+        let (mut generated, mut failed) = (0, 0);
+        for subscriber in subscribers {
+            // One user's bad data mustn't stop everyone else's plan
+            let result = self
+                .meal_plans
+                .generate_if_missing(subscriber.id, subscriber.household_size, week_start)
+                .await;
+            match result {
+                Ok(Some(_)) => {
+                    generated += 1;
+                    notification::enqueue(
+                        &self.db,
+                        subscriber.id,
+                        Notification::new(
+                            NotificationKind::MealPlan,
+                            "Next week is planned",
+                            format!("Your meal plan for the week of {} is ready", week_start.format("%-d %B")),
+                        )
+                        .with_data(json!({ "screen": "meal_plan", "week_start": week_start }))
+                        .dedupe(format!("weekly_plan:{}", week_start)),
+                    )
+                    .await?;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::error!("Weekly plan for user {} failed: {:?}", subscriber.id, e);
+                    failed += 1;
+                }
+            }
+        }
+        Ok(json!({ "week_start": week_start, "generated": generated, "failed": failed }))
+    }
+}
+
+/// The Monday after `date` (a week later when `date` is a Monday)
+fn next_monday(date: NaiveDate) -> NaiveDate {
+    date + chrono::Duration::days(7 - i64::from(date.weekday().num_days_from_monday()))
+}
+
+// ── Cleanup ───────────────────────────────────────────────────────────────────
+
+pub struct Cleanup {
+    db: DatabaseConnection,
+}
+
+impl Cleanup {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for Cleanup {
+    fn name(&self) -> &'static str {
+        "cleanup"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(24 * 3_600)
+    }
+
+    async fn run(&self) -> Result<Value, AppError> {
+        let now = Utc::now();
+        let days_ago = |days: i64| (now - chrono::Duration::days(days)).fixed_offset();
+
+        let stripe_events = stripe_processed_event::Entity::delete_many()
+            .filter(stripe_processed_event::Column::ProcessedAt.lt(days_ago(STRIPE_EVENT_RETENTION_DAYS)))
+            .exec(&self.db)
+            .await?
+            .rows_affected;
+        let notifications = notification_outbox::Entity::delete_many()
+            .filter(notification_outbox::Column::Status.ne("pending"))
+            .filter(notification_outbox::Column::CreatedAt.lt(days_ago(NOTIFICATION_RETENTION_DAYS)))
+            .exec(&self.db)
+            .await?
+            .rows_affected;
+        let job_runs = scheduled_job_run::Entity::delete_many()
+            .filter(scheduled_job_run::Column::Status.ne("running"))
+            .filter(scheduled_job_run::Column::StartedAt.lt(days_ago(JOB_RUN_RETENTION_DAYS)))
+            .exec(&self.db)
+            .await?
+            .rows_affected;
+
+        Ok(json!({
+            "stripe_events": stripe_events,
+            "notifications": notifications,
+            "job_runs": job_runs,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_message_lists_the_first_two() {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(expiry_message(&[]), None);
+        assert_eq!(expiry_message(&names(&["Milk"])).unwrap(), "Milk expires by tomorrow");
+        assert_eq!(expiry_message(&names(&["Milk", "Eggs"])).unwrap(), "Milk and Eggs expire by tomorrow");
+        assert_eq!(
+            expiry_message(&names(&["Milk", "Eggs", "Chilli leftovers", "Kale"])).unwrap(),
+            "Milk, Eggs and 2 more expire by tomorrow"
+        );
+    }
+
+    #[test]
+    fn next_monday_is_always_ahead() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(next_monday(date("2026-10-16")), date("2026-10-19")); // Friday
+        assert_eq!(next_monday(date("2026-10-19")), date("2026-10-26")); // Monday
+    }
+}
@@ -0,0 +1,328 @@
+//! Scheduler — recurring background jobs persisted in Postgres.
+//!
+//! Each [`ScheduledJob`] registered at startup gets a row in
+//! `scheduled_jobs` holding its interval and next run time. Every replica
+//! polls for due jobs and leases one at a time:
+//!
+//! - the claim is `SELECT … FOR UPDATE SKIP LOCKED` plus a `locked_until`
+//!   lease, so a job runs on one replica at a time and a replica that dies
+//!   mid-run only blocks the job until the lease expires
+//! - each run is recorded in `scheduled_job_runs` with its summary or error
+//! - a failed run is retried sooner than its interval, backing off from one
+//!   minute, and never later than the interval itself
+//!
+//! Admins can list jobs and runs, pause jobs, change intervals and trigger a
+//! run (`/api/admin/jobs`).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbBackend, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, Statement,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use validator::Validate;
+
+use crate::entity::{scheduled_job, scheduled_job_run};
+use cookest_shared::errors::AppError;
+
+pub mod jobs;
+
+/// How long a claimed job is reserved for the replica running it; runs
+/// taking longer are cancelled
+const LEASE_SECS: i64 = 1_800;
+/// Retry delay after the first failure; doubles with each further failure
+const RETRY_BASE_SECS: i64 = 60;
+/// Runs returned by the admin history when no limit is given
+const DEFAULT_RUN_LIMIT: u64 = 50;
+
+/// A unit of recurring background work
+#[async_trait]
+pub trait ScheduledJob: Send + Sync {
+    /// Stable identifier, stored in `scheduled_jobs.name`
+    fn name(&self) -> &'static str;
+
+    /// Interval a newly registered job starts with (admins may change it)
+    fn default_interval(&self) -> Duration;
+
+    /// Do the work once, returning a summary for the run history. Jobs must
+    /// be safe to repeat: a run cut short by a crash is run again.
+    async fn run(&self) -> Result<Value, AppError>;
+}
+
+/// Admin changes to a job's schedule
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateJobRequest {
+    pub enabled: Option<bool>,
+
+    /// One minute to one week
+    #[validate(range(min = 60, max = 604_800))]
+    pub interval_secs: Option<i32>,
+}
+
+/// Filters for the admin run history
+#[derive(Debug, Deserialize)]
+pub struct RunsQuery {
+    pub job: Option<String>,
+    /// "running" | "succeeded" | "failed"
+    pub status: Option<String>,
+    pub limit: Option<u64>,
+}
+
+pub struct SchedulerService {
+    db: DatabaseConnection,
+    jobs: Vec<Arc<dyn ScheduledJob>>,
+    /// Identifies this replica in leases and run history
+    instance: String,
+}
+
+impl SchedulerService {
+    pub fn new(db: DatabaseConnection, jobs: Vec<Arc<dyn ScheduledJob>>) -> Self {
+        Self { db, jobs, instance: instance_name() }
+    }
+
+    /// Create rows for jobs seen for the first time; existing schedules are kept
+    pub async fn register(&self) -> Result<(), AppError> {
+        let now = Utc::now().fixed_offset();
+        for job in &self.jobs {
+            let row = scheduled_job::ActiveModel {
+                name: Set(job.name().to_string()),
+                enabled: Set(true),
+                interval_secs: Set(job.default_interval().as_secs() as i32),
+                next_run_at: Set(now),
+                locked_until: Set(None),
+                locked_by: Set(None),
+                last_started_at: Set(None),
+                last_finished_at: Set(None),
+                last_status: Set(None),
+                last_error: Set(None),
+                consecutive_failures: Set(0),
+                updated_at: Set(now),
+            };
+            scheduled_job::Entity::insert(row)
+                .on_conflict(OnConflict::column(scheduled_job::Column::Name).do_nothing().to_owned())
+                .exec_without_returning(&self.db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Poll for due jobs every `every`, forever
+    pub fn spawn(self: Arc<Self>, every: Duration) {
+        tokio::spawn(async move {
+            if let Err(e) = self.register().await {
+                tracing::error!("Failed to register scheduled jobs: {:?}", e);
+            }
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_due().await {
+                    tracing::error!("Scheduler pass failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// Run every job that is due and not leased by another replica
+    pub async fn run_due(&self) -> Result<(), AppError> {
+        // Each job is claimed at most once per pass
+        for _ in 0..self.jobs.len() {
+            let Some(row) = self.claim_due().await? else {
+                break;
+            };
+            self.execute(row).await?;
+        }
+        Ok(())
+    }
+
+    /// Lease the most overdue job this replica knows how to run
+    async fn claim_due(&self) -> Result<Option<scheduled_job::Model>, AppError> {
+        let names: Vec<String> = self.jobs.iter().map(|j| j.name().to_string()).collect();
+        let sql = r#"
+            UPDATE scheduled_jobs
+            SET locked_until = NOW() + make_interval(secs => $1),
+                locked_by = $2,
+                last_started_at = NOW(),
+                updated_at = NOW()
+            WHERE name = (
+                SELECT name FROM scheduled_jobs
+                WHERE enabled
+                  AND next_run_at <= NOW()
+                  AND (locked_until IS NULL OR locked_until < NOW())
+                  AND name = ANY($3)
+                ORDER BY next_run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        "#;
+        let row = scheduled_job::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                [(LEASE_SECS as f64).into(), self.instance.clone().into(), names.into()],
+            ))
+            .one(&self.db)
+            .await?;
+        Ok(row)
+    }
+
+    async fn execute(&self, row: scheduled_job::Model) -> Result<(), AppError> {
+        let Some(job) = self.jobs.iter().find(|j| j.name() == row.name) else {
+            return Ok(());
+        };
+
+        // A run still marked running lost its replica before it could finish
+        scheduled_job_run::Entity::update_many()
+            .col_expr(scheduled_job_run::Column::Status, Expr::value("failed"))
+            .col_expr(scheduled_job_run::Column::Error, Expr::value("Abandoned: lease expired"))
+            .col_expr(scheduled_job_run::Column::FinishedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(scheduled_job_run::Column::JobName.eq(&row.name))
+            .filter(scheduled_job_run::Column::Status.eq("running"))
+            .exec(&self.db)
+            .await?;
+
+        let run = scheduled_job_run::ActiveModel {
+            job_name: Set(row.name.clone()),
+            instance: Set(self.instance.clone()),
+            status: Set("running".to_string()),
+            started_at: Set(Utc::now().fixed_offset()),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        tracing::debug!("Running scheduled job {}", row.name);
+        let outcome = match tokio::time::timeout(Duration::from_secs(LEASE_SECS as u64), job.run()).await {
+            Ok(result) => result.map_err(|e| format!("{:?}", e)),
+            Err(_) => Err(format!("Timed out after {} seconds", LEASE_SECS)),
+        };
+
+        let now = Utc::now();
+        let mut run: scheduled_job_run::ActiveModel = run.into();
+        run.finished_at = Set(Some(now.fixed_offset()));
+        let failures = match &outcome {
+            Ok(_) => 0,
+            Err(_) => row.consecutive_failures + 1,
+        };
+        let delay = next_delay_secs(row.interval_secs, failures);
+        let mut active: scheduled_job::ActiveModel = row.clone().into();
+        match outcome {
+            Ok(summary) => {
+                tracing::info!("Scheduled job {} succeeded: {}", row.name, summary);
+                run.status = Set("succeeded".to_string());
+                run.summary = Set(Some(summary));
+                active.last_status = Set(Some("succeeded".to_string()));
+                active.last_error = Set(None);
+            }
+            Err(error) => {
+                tracing::error!("Scheduled job {} failed: {}", row.name, error);
+                run.status = Set("failed".to_string());
+                run.error = Set(Some(error.clone()));
+                active.last_status = Set(Some("failed".to_string()));
+                active.last_error = Set(Some(error));
+            }
+        }
+        run.update(&self.db).await?;
+
+        active.consecutive_failures = Set(failures);
+        active.next_run_at = Set((now + chrono::Duration::seconds(delay)).fixed_offset());
+        active.locked_until = Set(None);
+        active.locked_by = Set(None);
+        active.last_finished_at = Set(Some(now.fixed_offset()));
+        active.updated_at = Set(now.fixed_offset());
+        active.update(&self.db).await?;
+        Ok(())
+    }
+
+    // ── Admin ─────────────────────────────────────────────────────────────────
+
+    pub async fn list_jobs(&self) -> Result<Vec<scheduled_job::Model>, AppError> {
+        Ok(scheduled_job::Entity::find()
+            .order_by_asc(scheduled_job::Column::Name)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Most recent runs first
+    pub async fn list_runs(&self, query: RunsQuery) -> Result<Vec<scheduled_job_run::Model>, AppError> {
+        let mut select = scheduled_job_run::Entity::find();
+        if let Some(job) = query.job {
+            select = select.filter(scheduled_job_run::Column::JobName.eq(job));
+        }
+        if let Some(status) = query.status {
+            select = select.filter(scheduled_job_run::Column::Status.eq(status));
+        }
+        Ok(select
+            .order_by_desc(scheduled_job_run::Column::StartedAt)
+            .limit(query.limit.unwrap_or(DEFAULT_RUN_LIMIT).min(500))
+            .all(&self.db)
+            .await?)
+    }
+
+    pub async fn update_job(&self, name: &str, req: UpdateJobRequest) -> Result<scheduled_job::Model, AppError> {
+        let job = self.find_job(name).await?;
+        let mut active: scheduled_job::ActiveModel = job.into();
+        if let Some(enabled) = req.enabled {
+            active.enabled = Set(enabled);
+        }
+        if let Some(interval) = req.interval_secs {
+            active.interval_secs = Set(interval);
+        }
+        active.updated_at = Set(Utc::now().fixed_offset());
+        Ok(active.update(&self.db).await?)
+    }
+
+    /// Make a job due now; the next scheduler pass on any replica picks it up
+    pub async fn trigger(&self, name: &str) -> Result<scheduled_job::Model, AppError> {
+        let job = self.find_job(name).await?;
+        let mut active: scheduled_job::ActiveModel = job.into();
+        active.next_run_at = Set(Utc::now().fixed_offset());
+        active.updated_at = Set(Utc::now().fixed_offset());
+        Ok(active.update(&self.db).await?)
+    }
+
+    async fn find_job(&self, name: &str) -> Result<scheduled_job::Model, AppError> {
+        scheduled_job::Entity::find_by_id(name.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("Scheduled job".into()))
+    }
+}
+
+/// Name for this replica in leases: the host name plus a random suffix, so
+/// two processes on one host are told apart
+pub(crate) fn instance_name() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "app-api".to_string());
+    format!("{}-{}", host, &uuid::Uuid::new_v4().simple().to_string()[..8])
+}
+
+/// Seconds until the next run: the interval after a success, otherwise a
+/// backoff of 1, 2, 4, … minutes capped at the interval
+fn next_delay_secs(interval_secs: i32, consecutive_failures: i32) -> i64 {
+    let interval = i64::from(interval_secs);
+    if consecutive_failures == 0 {
+        return interval;
+    }
+    let backoff = RETRY_BASE_SECS << (consecutive_failures - 1).clamp(0, 16);
+    backoff.min(interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_runs_back_off_up_to_the_interval() {
+        assert_eq!(next_delay_secs(3_600, 0), 3_600);
+        assert_eq!(next_delay_secs(3_600, 1), 60);
+        assert_eq!(next_delay_secs(3_600, 3), 240);
+        assert_eq!(next_delay_secs(3_600, 10), 3_600);
+        assert_eq!(next_delay_secs(120, 40), 120);
+    }
+}
@@ -0,0 +1,235 @@
+//! Session service — one session per signed-in device
+//!
+//! Every login starts a session; its id travels in the JWTs as `sid`.
+//! Refreshing swaps the session's stored refresh hash atomically. Presenting a
+//! refresh token that is no longer current means it was copied (or raced), so
+//! the whole session — every token descended from that login — is revoked.
+//!
+//! Revoking a session stops further refreshes; access tokens already issued
+//! stay valid until they expire (15 min by default).
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::entity::user_session::{self, Entity as UserSession};
+use cookest_shared::errors::AppError;
+
+/// Where a login came from — recorded on the session for the device list
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// Session as shown in `GET /api/me/sessions`
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    /// True for the session making the request
+    pub current: bool,
+}
+
+pub struct SessionService {
+    db: DatabaseConnection,
+}
+
+impl SessionService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Record a new session. The id is chosen by the caller because it is
+    /// embedded in the refresh token whose hash is stored here.
+    pub async fn create(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+        refresh_token_hash: String,
+        device: DeviceInfo,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let now = Utc::now();
+
+        // Housekeeping: drop this user's sessions that can no longer be used
+        UserSession::delete_many()
+            .filter(user_session::Column::UserId.eq(user_id))
+            .filter(user_session::Column::ExpiresAt.lt(now.fixed_offset()))
+            .exec(&self.db)
+            .await?;
+
+        user_session::ActiveModel {
+            id: Set(session_id),
+            user_id: Set(user_id),
+            refresh_token_hash: Set(refresh_token_hash),
+            device_name: Set(device.device_name),
+            user_agent: Set(device.user_agent),
+            ip_address: Set(device.ip_address),
+            created_at: Set(now.fixed_offset()),
+            last_used_at: Set(now.fixed_offset()),
+            expires_at: Set(expires_at.fixed_offset()),
+            revoked_at: Set(None),
+        }
+        .insert(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Rotate a session's refresh token.
+    ///
+    /// The swap only succeeds while `presented_hash` is still the current
+    /// hash; otherwise the token was already rotated away and is being
+    /// reused, so the session is revoked.
+    pub async fn rotate(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+        presented_hash: &str,
+        new_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let now = Utc::now();
+
+        let rotated = UserSession::update_many()
+            .col_expr(user_session::Column::RefreshTokenHash, Expr::value(new_hash))
+            .col_expr(user_session::Column::LastUsedAt, Expr::value(now.fixed_offset()))
+            .col_expr(user_session::Column::ExpiresAt, Expr::value(expires_at.fixed_offset()))
+            .filter(user_session::Column::Id.eq(session_id))
+            .filter(user_session::Column::UserId.eq(user_id))
+            .filter(user_session::Column::RefreshTokenHash.eq(presented_hash))
+            .filter(user_session::Column::RevokedAt.is_null())
+            .filter(user_session::Column::ExpiresAt.gt(now.fixed_offset()))
+            .exec(&self.db)
+            .await?;
+
+        if rotated.rows_affected == 1 {
+            return Ok(());
+        }
+
+        let session = UserSession::find_by_id(session_id)
+            .filter(user_session::Column::UserId.eq(user_id))
+            .one(&self.db)
+            .await?;
+
+        if let Some(session) = session {
+            if session.revoked_at.is_none() && session.refresh_token_hash != presented_hash {
+                tracing::warn!(
+                    "Refresh token reuse detected — revoking session {} for user {}",
+                    session.id,
+                    user_id
+                );
+                self.revoke(user_id, session.id).await?;
+            }
+        }
+
+        Err(AppError::InvalidToken)
+    }
+
+    /// Active sessions, most recently used first
+    pub async fn list(&self, user_id: Uuid, current: Option<Uuid>) -> Result<Vec<SessionResponse>, AppError> {
+        let sessions = UserSession::find()
+            .filter(user_session::Column::UserId.eq(user_id))
+            .filter(user_session::Column::RevokedAt.is_null())
+            .filter(user_session::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+            .order_by_desc(user_session::Column::LastUsedAt)
+            .all(&self.db)
+            .await?;
+
+        Ok(sessions
+            .into_iter()
+            .map(|s| SessionResponse {
+                current: Some(s.id) == current,
+                id: s.id,
+                device_name: s.device_name,
+                user_agent: s.user_agent,
+                ip_address: s.ip_address,
+                created_at: s.created_at.with_timezone(&Utc),
+                last_used_at: s.last_used_at.with_timezone(&Utc),
+            })
+            .collect())
+    }
+
+    /// Revoke one session. Returns false if it doesn't exist or was already revoked.
+    pub async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> Result<bool, AppError> {
+        let revoked = UserSession::update_many()
+            .col_expr(user_session::Column::RevokedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(user_session::Column::Id.eq(session_id))
+            .filter(user_session::Column::UserId.eq(user_id))
+            .filter(user_session::Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        Ok(revoked.rows_affected > 0)
+    }
+
+    /// Revoke every session of a user, optionally keeping one (the caller's)
+    pub async fn revoke_all(&self, user_id: Uuid, except: Option<Uuid>) -> Result<u64, AppError> {
+        let mut query = UserSession::update_many()
+            .col_expr(user_session::Column::RevokedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(user_session::Column::UserId.eq(user_id))
+            .filter(user_session::Column::RevokedAt.is_null());
+        if let Some(keep) = except {
+            query = query.filter(user_session::Column::Id.ne(keep));
+        }
+
+        Ok(query.exec(&self.db).await?.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::auth::hash_token_sha256;
+    use crate::services::{AuthService, TokenService};
+    use crate::test_db;
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn reusing_a_refresh_token_revokes_only_that_device() {
+        let db = test_db::connect().await;
+        let tokens = TokenService::new(&test_db::config());
+        let auth = AuthService::new(db.clone(), TokenService::new(&test_db::config()), false, None);
+        let sessions = SessionService::new(db.clone());
+        let user = test_db::create_user(&db).await;
+        let expires = Utc::now() + chrono::Duration::days(7);
+
+        let mut refresh = Vec::new();
+        for device in ["phone", "tablet"] {
+            let session_id = Uuid::new_v4();
+            let token = tokens.generate_refresh_token(user.id, &user.email, session_id).unwrap();
+            let info = DeviceInfo { device_name: Some(device.into()), ..Default::default() };
+            sessions.create(session_id, user.id, hash_token_sha256(&token), info, expires).await.unwrap();
+            refresh.push((session_id, token));
+        }
+        let (phone, phone_token) = refresh[0].clone();
+        let (tablet, tablet_token) = refresh[1].clone();
+
+        let listed = sessions.list(user.id, Some(phone)).await.unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().any(|s| s.id == phone && s.current));
+
+        // Rotation hands out a new token; replaying the old one revokes the phone
+        let (_, rotated, _) = auth.refresh_token(&phone_token).await.unwrap();
+        assert!(matches!(auth.refresh_token(&phone_token).await, Err(AppError::InvalidToken)));
+        assert!(matches!(auth.refresh_token(&rotated).await, Err(AppError::InvalidToken)));
+
+        let listed = sessions.list(user.id, None).await.unwrap();
+        assert_eq!(listed.iter().map(|s| s.id).collect::<Vec<_>>(), vec![tablet]);
+        auth.refresh_token(&tablet_token).await.unwrap();
+
+        assert_eq!(sessions.revoke_all(user.id, Some(tablet)).await.unwrap(), 0);
+        assert!(sessions.revoke(user.id, tablet).await.unwrap());
+        assert!(!sessions.revoke(user.id, tablet).await.unwrap());
+    }
+}
@@ -1,4 +1,6 @@
 //! Shopping list service — persistent per-user shopping list with meal plan sync
+//!
+//! Household members share one list; every query is scoped by [`DataScope`].
 
 use chrono::Utc;
 use rust_decimal::Decimal;
@@ -9,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::entity::shopping_list_item::{self, ActiveModel, Entity as ShoppingListItem};
+use crate::services::household::DataScope;
 use cookest_shared::errors::AppError;
 
 #[derive(Debug, Serialize)]
@@ -66,10 +69,11 @@ impl ShoppingListService {
         Self { db }
     }
 
-    /// Get all shopping list items for a user
+    /// Get all shopping list items for a user (or their household)
     pub async fn get_list(&self, user_id: Uuid) -> Result<Vec<ShoppingListItemResponse>, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let items = ShoppingListItem::find()
-            .filter(shopping_list_item::Column::UserId.eq(user_id))
+            .filter(scope_condition(&scope))
             .all(&self.db)
             .await?;
         Ok(items.into_iter().map(ShoppingListItemResponse::from).collect())
@@ -81,10 +85,12 @@ impl ShoppingListService {
         user_id: Uuid,
         req: AddItemRequest,
     ) -> Result<ShoppingListItemResponse, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let now = Utc::now().fixed_offset();
         let item = ActiveModel {
             id: Set(Uuid::new_v4()),
             user_id: Set(user_id),
+            household_id: Set(scope.household_id()),
             ingredient_id: Set(req.ingredient_id),
             name: Set(req.name),
             quantity: Set(req.quantity),
@@ -105,8 +111,9 @@ impl ShoppingListService {
         user_id: Uuid,
         item_id: Uuid,
     ) -> Result<ShoppingListItemResponse, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let item = ShoppingListItem::find_by_id(item_id)
-            .filter(shopping_list_item::Column::UserId.eq(user_id))
+            .filter(scope_condition(&scope))
             .one(&self.db)
             .await?
             .ok_or_else(|| AppError::NotFound("Shopping list item".to_string()))?;
@@ -121,8 +128,9 @@ impl ShoppingListService {
 
     /// Remove an item
     pub async fn delete_item(&self, user_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let item = ShoppingListItem::find_by_id(item_id)
-            .filter(shopping_list_item::Column::UserId.eq(user_id))
+            .filter(scope_condition(&scope))
             .one(&self.db)
             .await?
             .ok_or_else(|| AppError::NotFound("Shopping list item".to_string()))?;
@@ -139,11 +147,12 @@ impl ShoppingListService {
         user_id: Uuid,
         items: Vec<SyncItem>,
     ) -> Result<Vec<ShoppingListItemResponse>, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let txn = self.db.begin().await?;
 
         // Delete all previously-synced (non-manual) items
         ShoppingListItem::delete_many()
-            .filter(shopping_list_item::Column::UserId.eq(user_id))
+            .filter(scope_condition(&scope))
             .filter(shopping_list_item::Column::IsManual.eq(false))
             .exec(&txn)
             .await?;
@@ -155,6 +164,7 @@ impl ShoppingListService {
             let model = ActiveModel {
                 id: Set(Uuid::new_v4()),
                 user_id: Set(user_id),
+                household_id: Set(scope.household_id()),
                 ingredient_id: Set(item.ingredient_id),
                 name: Set(item.name),
                 quantity: Set(item.quantity),
@@ -175,11 +185,16 @@ impl ShoppingListService {
 
     /// Clear all checked items
     pub async fn clear_checked(&self, user_id: Uuid) -> Result<u64, AppError> {
+        let scope = DataScope::for_user(&self.db, user_id).await?;
         let res = ShoppingListItem::delete_many()
-            .filter(shopping_list_item::Column::UserId.eq(user_id))
+            .filter(scope_condition(&scope))
             .filter(shopping_list_item::Column::IsChecked.eq(true))
             .exec(&self.db)
             .await?;
         Ok(res.rows_affected)
     }
 }
+
+fn scope_condition(scope: &DataScope) -> sea_orm::Condition {
+    scope.condition(shopping_list_item::Column::UserId, shopping_list_item::Column::HouseholdId)
+}
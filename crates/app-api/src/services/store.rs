@@ -2,31 +2,42 @@
 //!
 //! PDF processing pipeline:
 //! 1. Admin uploads PDF → saved to disk → job created (status=pending)
-//! 2. Background tokio task: pdftoppm converts each page to PNG
-//! 3. Each PNG is base64-encoded and sent to Ollama llava vision model
-//! 4. Structured JSON extracted → inserted into staging table (store_promotion_candidates)
+//! 2. The PDF worker (services/pdf_worker.rs) claims the job; pdftoppm converts each page to PNG
+//! 3. Each PNG is base64-encoded and sent to the LLM's vision model
+//! 4. Structured JSON extracted → inserted into staging table (store_promotion_candidates),
+//!    one transaction per page; failed pages and crashed workers are retried
 //! 5. Admin reviews candidates → approves to store_promotions
 //! 6. store_promotion_ingredients links promotions to known ingredients via pg_trgm
+//!    (services/promotion_match.rs); admins confirm or override the links
 
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
-    QueryFilter, Set,
+    sea_query::{Expr, OnConflict}, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait,
+    DatabaseConnection, DbBackend, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, Statement, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::entity::{
+    ingredient, ingredient_alias,
+    pdf_job_page,
     pdf_processing_job::{self, ActiveModel as JobActiveModel, Entity as PdfJob},
     store::{self, ActiveModel as StoreActiveModel, Entity as Store},
     store_promotion::{self, ActiveModel as PromotionActiveModel, Entity as StorePromotion},
     store_promotion_candidate::{self, ActiveModel as CandidateActiveModel, Entity as Candidate},
+    store_promotion_ingredient,
 };
+use crate::services::promotion_match::{self, IngredientMatch};
 use cookest_shared::errors::AppError;
 
+/// Candidates handled by one bulk review call
+pub const BULK_REVIEW_LIMIT: u64 = 500;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StoreResponse {
     pub id: Uuid,
@@ -74,7 +85,10 @@ pub struct JobStatusResponse {
     pub store_id: Uuid,
     pub status: String,
     pub retry_count: i32,
+    pub page_count: Option<i32>,
     pub error: Option<String>,
+    /// When a pending job becomes eligible to run (later than now after a failed attempt)
+    pub next_attempt_at: sea_orm::prelude::DateTimeWithTimeZone,
     pub created_at: sea_orm::prelude::DateTimeWithTimeZone,
     pub processed_at: Option<sea_orm::prelude::DateTimeWithTimeZone>,
 }
@@ -86,7 +100,9 @@ impl From<pdf_processing_job::Model> for JobStatusResponse {
             store_id: m.store_id,
             status: m.status,
             retry_count: m.retry_count,
+            page_count: m.page_count,
             error: m.error,
+            next_attempt_at: m.next_attempt_at,
             created_at: m.created_at,
             processed_at: m.processed_at,
         }
@@ -126,21 +142,188 @@ impl From<store_promotion::Model> for PromotionResponse {
     }
 }
 
+/// An approved promotion and the ingredient it was matched to, if any
+#[derive(Debug, Serialize)]
+pub struct ApprovalResponse {
+    #[serde(flatten)]
+    pub promotion: PromotionResponse,
+    pub ingredient_match: Option<IngredientMatch>,
+}
+
+/// A promotion's ingredient links, for admin review
+#[derive(Debug, Serialize)]
+pub struct PromotionMatchesResponse {
+    pub promotion: PromotionResponse,
+    /// "pending" | "auto" | "unmatched" | "confirmed"
+    pub match_status: String,
+    /// The product name as compared with ingredient names
+    pub normalised_name: String,
+    pub matches: Vec<IngredientMatch>,
+    /// Closest ingredients, on the single-promotion view only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<IngredientMatch>>,
+}
+
+/// Filters for the match review queue
+#[derive(Debug, Deserialize)]
+pub struct MatchQueueQuery {
+    /// "pending" | "auto" | "unmatched" | "confirmed"; default: auto and unmatched
+    pub status: Option<String>,
+    pub store_id: Option<Uuid>,
+    pub limit: Option<u64>,
+}
+
+/// Admin override of a promotion's ingredients
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetMatchesRequest {
+    /// Replaces the current links; empty means the promotion is no ingredient
+    #[validate(length(max = 10))]
+    pub ingredient_ids: Vec<i64>,
+    /// Also save the product name as an alias of each ingredient, so
+    /// similar products match automatically next time
+    #[serde(default)]
+    pub remember_alias: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAliasRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub alias: String,
+}
+
+/// Filters for the candidate review list
+#[derive(Debug, Deserialize)]
+pub struct CandidatesQuery {
+    pub job_id: Option<Uuid>,
+    /// "pending" (default) | "approved" | "rejected" | "duplicate"
+    pub status: Option<String>,
+}
+
+/// Candidate fields that can be cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateField {
+    Brand,
+    OriginalPrice,
+    DiscountPct,
+    Unit,
+    ValidFrom,
+    ValidUntil,
+}
+
+/// Corrections to an extracted candidate — omitted fields are left as they are
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCandidateRequest {
+    #[validate(length(min = 1, max = 200), custom(function = "not_blank"))]
+    pub product_name: Option<String>,
+    #[validate(length(min = 1, max = 100), custom(function = "not_blank"))]
+    pub brand: Option<String>,
+    pub original_price: Option<Decimal>,
+    pub discounted_price: Option<Decimal>,
+    pub discount_pct: Option<Decimal>,
+    #[validate(length(min = 1, max = 50), custom(function = "not_blank"))]
+    pub unit: Option<String>,
+    pub valid_from: Option<chrono::NaiveDate>,
+    pub valid_until: Option<chrono::NaiveDate>,
+    /// Fields to empty, e.g. a brand the model invented
+    #[serde(default)]
+    pub clear: Vec<CandidateField>,
+}
+
+/// What approving does when the candidate repeats a live promotion of the
+/// same store (same product, brand and pack size, overlapping dates)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Refuse, naming the existing promotion
+    #[default]
+    Fail,
+    /// Set the candidate aside as a duplicate
+    Skip,
+    /// Deactivate the existing promotion and publish the candidate
+    Replace,
+    /// Publish the candidate alongside the existing promotion
+    Keep,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApproveQuery {
+    #[serde(default)]
+    pub on_duplicate: DuplicatePolicy,
+}
+
+#[derive(Debug)]
+pub enum ApproveOutcome {
+    Approved(Box<ApprovalResponse>),
+    Duplicate { duplicate_of: Uuid },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewAction {
+    Approve,
+    Reject,
+}
+
+/// Approve or reject many pending candidates of a store at once
+#[derive(Debug, Deserialize, Validate)]
+pub struct BulkReviewRequest {
+    pub action: ReviewAction,
+    /// Only this job's candidates
+    pub job_id: Option<Uuid>,
+    /// Only candidates scored at least this confident
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub min_confidence: Option<f64>,
+    /// Only candidates scored below this confidence
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub max_confidence: Option<f64>,
+    /// For approvals; defaults to `skip`. `fail` is refused: one duplicate
+    /// would stop the batch halfway through.
+    #[validate(custom(function = "bulk_duplicate_policy"))]
+    pub on_duplicate: Option<DuplicatePolicy>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateCandidate {
+    pub candidate_id: Uuid,
+    pub duplicate_of: Uuid,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BulkReviewResponse {
+    pub approved: u64,
+    pub rejected: u64,
+    pub duplicates: Vec<DuplicateCandidate>,
+    /// Pending candidates still matching the filters (more than one call's worth)
+    pub remaining: u64,
+}
+
+/// Review outcomes of the candidates one PDF job produced
+#[derive(Debug, Serialize)]
+pub struct JobStatsResponse {
+    pub job_id: Uuid,
+    pub status: String,
+    pub page_count: Option<i32>,
+    pub total: i64,
+    pub pending: i64,
+    pub approved: i64,
+    pub rejected: i64,
+    pub duplicate: i64,
+    /// Candidates an admin corrected before reviewing
+    pub edited: i64,
+    pub average_confidence: Option<f64>,
+    /// Rejected share of approved + rejected; duplicates aren't extraction errors
+    pub rejection_rate: Option<f64>,
+}
+
 pub struct StoreService {
     db: DatabaseConnection,
     pdf_upload_dir: PathBuf,
-    ollama_url: String,
-    ollama_model: String,
 }
 
 impl StoreService {
-    pub fn new(
-        db: DatabaseConnection,
-        pdf_upload_dir: PathBuf,
-        ollama_url: String,
-        ollama_model: String,
-    ) -> Self {
-        Self { db, pdf_upload_dir, ollama_url, ollama_model }
+    pub fn new(db: DatabaseConnection, pdf_upload_dir: PathBuf) -> Self {
+        Self { db, pdf_upload_dir }
     }
 
     // ── Stores ──────────────────────────────────────────────────────────────
@@ -172,7 +355,7 @@ impl StoreService {
     // ── PDF upload & async processing ──────────────────────────────────────
 
     /// Save a PDF to disk and create a processing job.
-    /// Returns the job — actual processing happens in a spawned background task.
+    /// Returns the job — the PDF worker picks it up and processes it.
     pub async fn create_pdf_job(
         &self,
         store_id: Uuid,
@@ -214,33 +397,14 @@ impl StoreService {
             heartbeat_at: Set(None),
             processed_at: Set(None),
             created_at: Set(now),
+            next_attempt_at: Set(now),
+            claimed_by: Set(None),
+            page_count: Set(None),
         };
         let inserted = job.insert(&self.db).await?;
         Ok(JobStatusResponse::from(inserted))
     }
 
-    /// Spawn the background PDF processing task for a job.
-    /// Call this immediately after `create_pdf_job`.
-    pub fn spawn_pdf_processing(&self, job_id: Uuid) {
-        let db = self.db.clone();
-        let ollama_url = self.ollama_url.clone();
-        let ollama_model = self.ollama_model.clone();
-
-        tokio::spawn(async move {
-            if let Err(e) = process_pdf_job(&db, job_id, &ollama_url, &ollama_model).await {
-                tracing::error!("PDF job {} failed: {:?}", job_id, e);
-                // Mark job as failed
-                if let Ok(Some(job)) = PdfJob::find_by_id(job_id).one(&db).await {
-                    let mut active: JobActiveModel = job.into();
-                    active.status = Set("failed".to_string());
-                    active.error = Set(Some(format!("{:?}", e)));
-                    active.processed_at = Set(Some(Utc::now().fixed_offset()));
-                    let _ = active.update(&db).await;
-                }
-            }
-        });
-    }
-
     pub async fn list_jobs(&self, store_id: Uuid) -> Result<Vec<JobStatusResponse>, AppError> {
         let jobs = PdfJob::find()
             .filter(pdf_processing_job::Column::StoreId.eq(store_id))
@@ -249,34 +413,211 @@ impl StoreService {
         Ok(jobs.into_iter().map(JobStatusResponse::from).collect())
     }
 
-    pub async fn list_candidates(&self, store_id: Uuid) -> Result<Vec<store_promotion_candidate::Model>, AppError> {
-        let candidates = Candidate::find()
+    /// Per-page outcomes of a job, in page order
+    pub async fn list_job_pages(&self, store_id: Uuid, job_id: Uuid) -> Result<Vec<pdf_job_page::Model>, AppError> {
+        self.find_job(store_id, job_id).await?;
+        Ok(pdf_job_page::Entity::find()
+            .filter(pdf_job_page::Column::JobId.eq(job_id))
+            .order_by_asc(pdf_job_page::Column::PageNumber)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Queue a job again after it failed or finished with unreadable pages.
+    /// Pages already done are kept; the worker only redoes the rest.
+    pub async fn retry_job(&self, store_id: Uuid, job_id: Uuid) -> Result<JobStatusResponse, AppError> {
+        let job = self.find_job(store_id, job_id).await?;
+        let retryable = job.status == "failed" || (job.status == "done" && job.error.is_some());
+        if !retryable {
+            return Err(store_error("job", "Only failed jobs can be retried"));
+        }
+        let mut active: JobActiveModel = job.into();
+        active.status = Set("pending".to_string());
+        active.retry_count = Set(0);
+        active.next_attempt_at = Set(Utc::now().fixed_offset());
+        active.processed_at = Set(None);
+        Ok(JobStatusResponse::from(active.update(&self.db).await?))
+    }
+
+    async fn find_job(&self, store_id: Uuid, job_id: Uuid) -> Result<pdf_processing_job::Model, AppError> {
+        PdfJob::find_by_id(job_id)
+            .filter(pdf_processing_job::Column::StoreId.eq(store_id))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("PDF job".to_string()))
+    }
+
+    /// A store's candidates, oldest first; pending ones unless `status` says otherwise
+    pub async fn list_candidates(
+        &self,
+        store_id: Uuid,
+        query: CandidatesQuery,
+    ) -> Result<Vec<store_promotion_candidate::Model>, AppError> {
+        let mut select = Candidate::find()
             .filter(store_promotion_candidate::Column::StoreId.eq(store_id))
-            .filter(store_promotion_candidate::Column::ReviewStatus.eq("pending"))
+            .filter(store_promotion_candidate::Column::ReviewStatus.eq(query.status.as_deref().unwrap_or("pending")));
+        if let Some(job_id) = query.job_id {
+            select = select.filter(store_promotion_candidate::Column::JobId.eq(job_id));
+        }
+        let candidates = select
+            .order_by_asc(store_promotion_candidate::Column::CreatedAt)
             .all(&self.db)
             .await?;
         Ok(candidates)
     }
 
-    /// Approve a candidate — moves it to store_promotions
-    pub async fn approve_candidate(
+    /// Correct a pending candidate's extracted fields before it is approved
+    pub async fn update_candidate(
         &self,
         candidate_id: Uuid,
-        reviewer_id: Uuid,
-    ) -> Result<PromotionResponse, AppError> {
+        editor_id: Uuid,
+        req: UpdateCandidateRequest,
+    ) -> Result<store_promotion_candidate::Model, AppError> {
+        // Hold the row so a concurrent review can't land between the status
+        // check and the edit
+        let txn = self.db.begin().await?;
         let candidate = Candidate::find_by_id(candidate_id)
-            .one(&self.db)
+            .lock_exclusive()
+            .one(&txn)
             .await?
             .ok_or_else(|| AppError::NotFound("Candidate".to_string()))?;
+        if candidate.review_status != "pending" {
+            return Err(already_reviewed());
+        }
+        let mut active: CandidateActiveModel = candidate.into();
+
+        if let Some(name) = req.product_name {
+            active.product_name = Set(name.trim().to_string());
+        }
+        if let Some(brand) = req.brand {
+            active.brand = Set(Some(brand.trim().to_string()));
+        }
+        if let Some(price) = req.original_price {
+            active.original_price = Set(Some(price));
+        }
+        if let Some(price) = req.discounted_price {
+            active.discounted_price = Set(price);
+        }
+        if let Some(pct) = req.discount_pct {
+            active.discount_pct = Set(Some(pct));
+        }
+        if let Some(unit) = req.unit {
+            active.unit = Set(Some(unit.trim().to_string()));
+        }
+        if let Some(date) = req.valid_from {
+            active.valid_from = Set(Some(start_of_day(date)));
+        }
+        if let Some(date) = req.valid_until {
+            active.valid_until = Set(Some(end_of_day(date)));
+        }
+        for field in req.clear {
+            match field {
+                CandidateField::Brand => active.brand = Set(None),
+                CandidateField::OriginalPrice => active.original_price = Set(None),
+                CandidateField::DiscountPct => active.discount_pct = Set(None),
+                CandidateField::Unit => active.unit = Set(None),
+                CandidateField::ValidFrom => active.valid_from = Set(None),
+                CandidateField::ValidUntil => active.valid_until = Set(None),
+            }
+        }
+
+        // Check the result, not the request: a partial edit can still leave
+        // the candidate inconsistent
+        if *active.discounted_price.as_ref() <= Decimal::ZERO {
+            return Err(store_error("discounted_price", "Price must be positive"));
+        }
+        if active.original_price.as_ref().is_some_and(|p| p <= Decimal::ZERO) {
+            return Err(store_error("original_price", "Price must be positive"));
+        }
+        if active.discount_pct.as_ref().is_some_and(|p| p < Decimal::ZERO || p > Decimal::ONE_HUNDRED) {
+            return Err(store_error("discount_pct", "Discount must be between 0 and 100"));
+        }
+        if let (Some(from), Some(until)) = (active.valid_from.as_ref(), active.valid_until.as_ref()) {
+            if from > until {
+                return Err(store_error("valid_until", "Promotion must end after it starts"));
+            }
+        }
+
+        active.edited_by = Set(Some(editor_id));
+        active.edited_at = Set(Some(Utc::now().fixed_offset()));
+        let updated = active.update(&txn).await?;
+        txn.commit().await?;
+        Ok(updated)
+    }
+
+    /// Approve a candidate — moves it to store_promotions and matches it to an ingredient
+    pub async fn approve_candidate(
+        &self,
+        candidate_id: Uuid,
+        reviewer_id: Uuid,
+        on_duplicate: DuplicatePolicy,
+    ) -> Result<ApproveOutcome, AppError> {
+        let candidate = self.find_pending_candidate(candidate_id).await?;
+        let mut live = self.live_promotions(candidate.store_id).await?;
+        self.approve(candidate.id, reviewer_id, on_duplicate, &mut live)
+            .await?
+            .ok_or_else(already_reviewed)
+    }
 
+    /// Approve one candidate against `live`, the store's live promotions,
+    /// which is kept up to date for the next candidate of the same store.
+    /// `None` if the candidate was reviewed in the meantime.
+    async fn approve(
+        &self,
+        candidate_id: Uuid,
+        reviewer_id: Uuid,
+        on_duplicate: DuplicatePolicy,
+        live: &mut Vec<store_promotion::Model>,
+    ) -> Result<Option<ApproveOutcome>, AppError> {
         let now = Utc::now().fixed_offset();
+        let txn = self.db.begin().await?;
+
+        // A concurrent review of the same candidate waits on the lock, then
+        // finds it no longer pending
+        let Some(candidate) = Candidate::find_by_id(candidate_id)
+            .filter(store_promotion_candidate::Column::ReviewStatus.eq("pending"))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+        else {
+            txn.rollback().await?;
+            return Ok(None);
+        };
+
+        let duplicates: Vec<Uuid> = live.iter().filter(|p| is_duplicate(&candidate, p)).map(|p| p.id).collect();
+        if let Some(&existing) = duplicates.first() {
+            match on_duplicate {
+                DuplicatePolicy::Fail => {
+                    return Err(store_error("candidate", format!("Duplicates live promotion {}", existing)));
+                }
+                DuplicatePolicy::Skip => {
+                    let mut active: CandidateActiveModel = candidate.into();
+                    active.review_status = Set("duplicate".to_string());
+                    active.duplicate_of = Set(Some(existing));
+                    active.reviewed_by = Set(Some(reviewer_id));
+                    active.reviewed_at = Set(Some(now));
+                    active.update(&txn).await?;
+                    txn.commit().await?;
+                    return Ok(Some(ApproveOutcome::Duplicate { duplicate_of: existing }));
+                }
+                DuplicatePolicy::Replace => {
+                    StorePromotion::update_many()
+                        .col_expr(store_promotion::Column::IsActive, Expr::value(false))
+                        .col_expr(store_promotion::Column::UpdatedAt, Expr::value(now))
+                        .filter(store_promotion::Column::Id.is_in(duplicates.clone()))
+                        .exec(&txn)
+                        .await?;
+                }
+                DuplicatePolicy::Keep => {}
+            }
+        }
 
         // Mark candidate as approved
         let mut active_cand: CandidateActiveModel = candidate.clone().into();
         active_cand.review_status = Set("approved".to_string());
         active_cand.reviewed_by = Set(Some(reviewer_id));
         active_cand.reviewed_at = Set(Some(now));
-        active_cand.update(&self.db).await?;
+        active_cand.update(&txn).await?;
 
         // Create published promotion
         let promotion = PromotionActiveModel {
@@ -293,30 +634,321 @@ impl StoreService {
             is_active: Set(true),
             source_pdf_url: Set(None),
             confidence: Set(candidate.confidence),
+            match_status: Set("pending".to_string()),
             created_at: Set(now),
             updated_at: Set(now),
         };
-        let inserted = promotion.insert(&self.db).await?;
-        Ok(PromotionResponse::from(inserted))
+        let inserted = promotion.insert(&txn).await?;
+        let ingredient_match = promotion_match::auto_match(&txn, &inserted).await?;
+        txn.commit().await?;
+
+        if on_duplicate == DuplicatePolicy::Replace {
+            live.retain(|p| !duplicates.contains(&p.id));
+        }
+        live.push(inserted.clone());
+
+        Ok(Some(ApproveOutcome::Approved(Box::new(ApprovalResponse {
+            promotion: PromotionResponse::from(inserted),
+            ingredient_match,
+        }))))
+    }
+
+    /// A store's active promotions that haven't ended yet
+    async fn live_promotions(&self, store_id: Uuid) -> Result<Vec<store_promotion::Model>, AppError> {
+        let now = Utc::now().fixed_offset();
+        Ok(StorePromotion::find()
+            .filter(store_promotion::Column::StoreId.eq(store_id))
+            .filter(store_promotion::Column::IsActive.eq(true))
+            .filter(
+                Condition::any()
+                    .add(store_promotion::Column::ValidUntil.is_null())
+                    .add(store_promotion::Column::ValidUntil.gt(now)),
+            )
+            .all(&self.db)
+            .await?)
     }
 
     /// Reject a candidate
     pub async fn reject_candidate(&self, candidate_id: Uuid, reviewer_id: Uuid) -> Result<(), AppError> {
+        let candidate = self.find_pending_candidate(candidate_id).await?;
+
+        let rejected = Candidate::update_many()
+            .col_expr(store_promotion_candidate::Column::ReviewStatus, Expr::value("rejected"))
+            .col_expr(store_promotion_candidate::Column::ReviewedBy, Expr::value(reviewer_id))
+            .col_expr(store_promotion_candidate::Column::ReviewedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(store_promotion_candidate::Column::Id.eq(candidate.id))
+            .filter(store_promotion_candidate::Column::ReviewStatus.eq("pending"))
+            .exec(&self.db)
+            .await?;
+        if rejected.rows_affected == 0 {
+            return Err(already_reviewed());
+        }
+        Ok(())
+    }
+
+    /// Approve or reject a store's pending candidates in one go, optionally
+    /// only one job's or those on one side of a confidence threshold.
+    /// Handles up to [`BULK_REVIEW_LIMIT`] candidates per call.
+    pub async fn bulk_review(
+        &self,
+        store_id: Uuid,
+        reviewer_id: Uuid,
+        req: BulkReviewRequest,
+    ) -> Result<BulkReviewResponse, AppError> {
+        let filter = bulk_filter(store_id, &req);
+        let candidates = Candidate::find()
+            .filter(filter.clone())
+            .order_by_asc(store_promotion_candidate::Column::CreatedAt)
+            .limit(BULK_REVIEW_LIMIT)
+            .all(&self.db)
+            .await?;
+
+        let mut response = BulkReviewResponse::default();
+        match req.action {
+            ReviewAction::Approve => {
+                let on_duplicate = req.on_duplicate.unwrap_or(DuplicatePolicy::Skip);
+                let mut live = self.live_promotions(store_id).await?;
+                for candidate in candidates {
+                    match self.approve(candidate.id, reviewer_id, on_duplicate, &mut live).await? {
+                        Some(ApproveOutcome::Approved(_)) => response.approved += 1,
+                        Some(ApproveOutcome::Duplicate { duplicate_of }) => response
+                            .duplicates
+                            .push(DuplicateCandidate { candidate_id: candidate.id, duplicate_of }),
+                        // Reviewed by someone else meanwhile
+                        None => {}
+                    }
+                }
+            }
+            ReviewAction::Reject => {
+                let now = Utc::now().fixed_offset();
+                response.rejected = Candidate::update_many()
+                    .col_expr(store_promotion_candidate::Column::ReviewStatus, Expr::value("rejected"))
+                    .col_expr(store_promotion_candidate::Column::ReviewedBy, Expr::value(reviewer_id))
+                    .col_expr(store_promotion_candidate::Column::ReviewedAt, Expr::value(now))
+                    .filter(store_promotion_candidate::Column::Id.is_in(candidates.iter().map(|c| c.id)))
+                    .filter(store_promotion_candidate::Column::ReviewStatus.eq("pending"))
+                    .exec(&self.db)
+                    .await?
+                    .rows_affected;
+            }
+        }
+
+        response.remaining = Candidate::find().filter(filter).count(&self.db).await?;
+        Ok(response)
+    }
+
+    /// Extraction quality of one job: review outcomes and model confidence
+    pub async fn job_stats(&self, store_id: Uuid, job_id: Uuid) -> Result<JobStatsResponse, AppError> {
+        let job = self.find_job(store_id, job_id).await?;
+        let sql = r#"
+            SELECT COUNT(*) AS total,
+                   COUNT(*) FILTER (WHERE review_status = 'pending')   AS pending,
+                   COUNT(*) FILTER (WHERE review_status = 'approved')  AS approved,
+                   COUNT(*) FILTER (WHERE review_status = 'rejected')  AS rejected,
+                   COUNT(*) FILTER (WHERE review_status = 'duplicate') AS duplicate,
+                   COUNT(*) FILTER (WHERE edited_at IS NOT NULL)       AS edited,
+                   AVG(confidence)::float8                             AS average_confidence
+            FROM store_promotion_candidates
+            WHERE job_id = $1
+        "#;
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(DbBackend::Postgres, sql, [job.id.into()]))
+            .await?
+            .ok_or_else(|| AppError::Internal("Stats query returned no row".to_string()))?;
+        let count = |column: &str| -> Result<i64, AppError> {
+            row.try_get("", column).map_err(|e| AppError::Internal(e.to_string()))
+        };
+
+        let (approved, rejected) = (count("approved")?, count("rejected")?);
+        Ok(JobStatsResponse {
+            job_id: job.id,
+            status: job.status,
+            page_count: job.page_count,
+            total: count("total")?,
+            pending: count("pending")?,
+            approved,
+            rejected,
+            duplicate: count("duplicate")?,
+            edited: count("edited")?,
+            average_confidence: row
+                .try_get::<Option<f64>>("", "average_confidence")
+                .map_err(|e| AppError::Internal(e.to_string()))?
+                .map(|c| (c * 1000.0).round() / 1000.0),
+            rejection_rate: rejection_rate(approved, rejected),
+        })
+    }
+
+    async fn find_pending_candidate(&self, candidate_id: Uuid) -> Result<store_promotion_candidate::Model, AppError> {
         let candidate = Candidate::find_by_id(candidate_id)
             .one(&self.db)
             .await?
             .ok_or_else(|| AppError::NotFound("Candidate".to_string()))?;
+        if candidate.review_status != "pending" {
+            return Err(already_reviewed());
+        }
+        Ok(candidate)
+    }
 
-        let now = Utc::now().fixed_offset();
-        let mut active: CandidateActiveModel = candidate.into();
-        active.review_status = Set("rejected".to_string());
-        active.reviewed_by = Set(Some(reviewer_id));
-        active.reviewed_at = Set(Some(now));
-        active.update(&self.db).await?;
+    // ── Ingredient matching ───────────────────────────────────────────────
+
+    /// Promotions awaiting match review, newest first
+    pub async fn match_queue(&self, query: MatchQueueQuery) -> Result<Vec<PromotionMatchesResponse>, AppError> {
+        let mut select = StorePromotion::find();
+        select = match query.status {
+            Some(status) => select.filter(store_promotion::Column::MatchStatus.eq(status)),
+            None => select.filter(store_promotion::Column::MatchStatus.is_in(["auto", "unmatched"])),
+        };
+        if let Some(store_id) = query.store_id {
+            select = select.filter(store_promotion::Column::StoreId.eq(store_id));
+        }
+        let promotions = select
+            .order_by_desc(store_promotion::Column::CreatedAt)
+            .limit(query.limit.unwrap_or(50).min(200))
+            .all(&self.db)
+            .await?;
+
+        let links = self.links_for(promotions.iter().map(|p| p.id).collect()).await?;
+        Ok(promotions
+            .into_iter()
+            .map(|p| {
+                let matches = links.get(&p.id).cloned().unwrap_or_default();
+                matches_response(p, matches, None)
+            })
+            .collect())
+    }
+
+    /// A promotion's links plus the closest ingredients to choose from
+    pub async fn promotion_matches(&self, promotion_id: Uuid) -> Result<PromotionMatchesResponse, AppError> {
+        let promotion = self.find_promotion(promotion_id).await?;
+        let query = promotion_match::normalise_product_name(&promotion.product_name, promotion.brand.as_deref());
+        let suggestions = promotion_match::suggest(&self.db, &query, 5).await?;
+        let matches = self.links_for(vec![promotion.id]).await?.remove(&promotion.id).unwrap_or_default();
+        Ok(matches_response(promotion, matches, Some(suggestions)))
+    }
+
+    /// Replace a promotion's links with the admin's choice and mark it confirmed
+    pub async fn set_matches(
+        &self,
+        promotion_id: Uuid,
+        req: SetMatchesRequest,
+    ) -> Result<PromotionMatchesResponse, AppError> {
+        let promotion = self.find_promotion(promotion_id).await?;
+        let query = promotion_match::normalise_product_name(&promotion.product_name, promotion.brand.as_deref());
+        let matches = promotion_match::score_ingredients(&self.db, &query, &req.ingredient_ids).await?;
+        if matches.len() != dedup(&req.ingredient_ids).len() {
+            return Err(AppError::NotFound("Ingredient".to_string()));
+        }
+
+        let txn = self.db.begin().await?;
+        store_promotion_ingredient::Entity::delete_many()
+            .filter(store_promotion_ingredient::Column::PromotionId.eq(promotion.id))
+            .exec(&txn)
+            .await?;
+        for m in &matches {
+            store_promotion_ingredient::ActiveModel {
+                promotion_id: Set(promotion.id),
+                ingredient_id: Set(m.ingredient_id),
+                similarity_score: Set(m.stored_score()),
+            }
+            .insert(&txn)
+            .await?;
+            if req.remember_alias {
+                insert_alias(&txn, m.ingredient_id, &query).await?;
+            }
+        }
+        let promotion = confirm(&txn, promotion).await?;
+        txn.commit().await?;
+
+        Ok(matches_response(promotion, matches, None))
+    }
+
+    /// Accept a promotion's current links (or lack of them) as correct
+    pub async fn confirm_matches(&self, promotion_id: Uuid) -> Result<PromotionMatchesResponse, AppError> {
+        let promotion = self.find_promotion(promotion_id).await?;
+        let promotion = confirm(&self.db, promotion).await?;
+        let matches = self.links_for(vec![promotion.id]).await?.remove(&promotion.id).unwrap_or_default();
+        Ok(matches_response(promotion, matches, None))
+    }
+
+    /// Match promotions that have never been matched, e.g. those approved
+    /// before matching existed. Returns (matched, unmatched).
+    pub async fn match_pending(&self, limit: u64) -> Result<(usize, usize), AppError> {
+        let pending = StorePromotion::find()
+            .filter(store_promotion::Column::MatchStatus.eq("pending"))
+            .filter(store_promotion::Column::IsActive.eq(true))
+            .order_by_asc(store_promotion::Column::CreatedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        let (mut matched, mut unmatched) = (0, 0);
+        for promotion in pending {
+            match promotion_match::auto_match(&self.db, &promotion).await? {
+                Some(_) => matched += 1,
+                None => unmatched += 1,
+            }
+        }
+        Ok((matched, unmatched))
+    }
+
+    /// Add an alias to an ingredient; the alias is normalised like product names
+    pub async fn add_alias(&self, ingredient_id: i64, req: CreateAliasRequest) -> Result<ingredient_alias::Model, AppError> {
+        ingredient::Entity::find_by_id(ingredient_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Ingredient".to_string()))?;
+        let alias = promotion_match::normalise_product_name(&req.alias, None);
+        insert_alias(&self.db, ingredient_id, &alias).await?;
+        ingredient_alias::Entity::find()
+            .filter(ingredient_alias::Column::IngredientId.eq(ingredient_id))
+            .filter(ingredient_alias::Column::Alias.eq(alias))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::Internal("Alias vanished after insert".to_string()))
+    }
 
+    pub async fn delete_alias(&self, ingredient_id: i64, alias_id: i64) -> Result<(), AppError> {
+        let deleted = ingredient_alias::Entity::delete_many()
+            .filter(ingredient_alias::Column::Id.eq(alias_id))
+            .filter(ingredient_alias::Column::IngredientId.eq(ingredient_id))
+            .exec(&self.db)
+            .await?;
+        if deleted.rows_affected == 0 {
+            return Err(AppError::NotFound("Alias".to_string()));
+        }
         Ok(())
     }
 
+    async fn find_promotion(&self, promotion_id: Uuid) -> Result<store_promotion::Model, AppError> {
+        StorePromotion::find_by_id(promotion_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Promotion".to_string()))
+    }
+
+    /// Current links of each promotion, with ingredient names
+    async fn links_for(&self, promotion_ids: Vec<Uuid>) -> Result<HashMap<Uuid, Vec<IngredientMatch>>, AppError> {
+        let links = store_promotion_ingredient::Entity::find()
+            .filter(store_promotion_ingredient::Column::PromotionId.is_in(promotion_ids))
+            .find_also_related(ingredient::Entity)
+            .all(&self.db)
+            .await?;
+
+        let mut by_promotion: HashMap<Uuid, Vec<IngredientMatch>> = HashMap::new();
+        for (link, ing) in links {
+            by_promotion.entry(link.promotion_id).or_default().push(IngredientMatch {
+                ingredient_id: link.ingredient_id,
+                name: ing.map(|i| i.name).unwrap_or_default(),
+                score: link
+                    .similarity_score
+                    .and_then(|s| s.to_f64())
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(by_promotion)
+    }
+
     /// Get active promotions for an ingredient (Pro feature — price comparison)
     pub async fn get_promotions_for_ingredient(
         &self,
@@ -363,193 +995,384 @@ impl StoreService {
     }
 }
 
-/// Background task: process a PDF job using pdftoppm + Ollama llava
-async fn process_pdf_job(
-    db: &DatabaseConnection,
-    job_id: Uuid,
-    ollama_url: &str,
-    ollama_model: &str,
-) -> Result<(), AppError> {
-    let job = PdfJob::find_by_id(job_id)
-        .one(db)
-        .await?
-        .ok_or_else(|| AppError::NotFound("PDF job".to_string()))?;
-
-    // Mark as processing + set started_at
-    let now = Utc::now().fixed_offset();
-    let mut active: JobActiveModel = job.clone().into();
-    active.status = Set("processing".to_string());
-    active.started_at = Set(Some(now));
-    active.heartbeat_at = Set(Some(now));
-    active.update(db).await?;
-
-    // Convert PDF pages to PNGs via pdftoppm (non-blocking via tokio process)
-    let output_dir = std::path::Path::new(&job.file_path)
-        .parent()
-        .unwrap_or(std::path::Path::new("/tmp"))
-        .join(format!("pages_{}", job_id));
-    tokio::fs::create_dir_all(&output_dir).await
-        .map_err(|e| AppError::Internal(format!("mkdir failed: {}", e)))?;
-
-    let prefix = output_dir.join("page").to_string_lossy().to_string();
-
-    let output = tokio::process::Command::new("pdftoppm")
-        .args(["-png", "-r", "150", &job.file_path, &prefix])
-        .output()
-        .await
-        .map_err(|e| AppError::Internal(format!("pdftoppm spawn failed: {}", e)))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(AppError::Internal(format!("pdftoppm failed: {}", stderr)));
-    }
-
-    // Collect generated PNG files
-    let mut png_files: Vec<PathBuf> = vec![];
-    let mut dir = tokio::fs::read_dir(&output_dir).await
-        .map_err(|e| AppError::Internal(format!("read_dir failed: {}", e)))?;
-    while let Some(entry) = dir.next_entry().await
-        .map_err(|e| AppError::Internal(format!("dir entry failed: {}", e)))?
-    {
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) == Some("png") {
-            png_files.push(path);
-        }
-    }
-    png_files.sort();
-
-    let client = reqwest::Client::new();
-
-    for png_path in &png_files {
-        let png_bytes = tokio::fs::read(png_path).await
-            .map_err(|e| AppError::Internal(format!("read PNG failed: {}", e)))?;
-        let b64 = BASE64.encode(&png_bytes);
-
-        // Update heartbeat so recovery logic knows this job is still alive
-        if let Ok(Some(j)) = PdfJob::find_by_id(job_id).one(db).await {
-            let mut a: JobActiveModel = j.into();
-            a.heartbeat_at = Set(Some(Utc::now().fixed_offset()));
-            let _ = a.update(db).await;
-        }
-
-        let prompt = r#"
-You are a grocery flyer price extraction assistant.
-Analyze this supermarket flyer page and extract ALL products with their prices.
-Return a JSON array. Each item must have:
-  "product_name": string,
-  "brand": string or null,
-  "original_price": number or null (price before discount),
-  "discounted_price": number (current/sale price),
-  "discount_pct": number or null (e.g. 20 for 20%),
-  "unit": string or null (e.g. "kg", "500g", "piece"),
-  "valid_from": "YYYY-MM-DD" or null,
-  "valid_until": "YYYY-MM-DD" or null,
-  "confidence": number 0.0-1.0 (your confidence in this extraction)
-Return ONLY the JSON array, no other text.
-"#;
-
-        let req_body = serde_json::json!({
-            "model": ollama_model,
-            "prompt": prompt,
-            "images": [b64],
-            "stream": false
-        });
-
-        let resp = client
-            .post(format!("{}/api/generate", ollama_url))
-            .json(&req_body)
-            .send()
-            .await
-            .map_err(|e| AppError::Internal(format!("Ollama request failed: {}", e)))?;
+fn matches_response(
+    promotion: store_promotion::Model,
+    matches: Vec<IngredientMatch>,
+    suggestions: Option<Vec<IngredientMatch>>,
+) -> PromotionMatchesResponse {
+    PromotionMatchesResponse {
+        normalised_name: promotion_match::normalise_product_name(&promotion.product_name, promotion.brand.as_deref()),
+        match_status: promotion.match_status.clone(),
+        promotion: PromotionResponse::from(promotion),
+        matches,
+        suggestions,
+    }
+}
 
-        if !resp.status().is_success() {
-            tracing::warn!("Ollama returned {}", resp.status());
-            continue;
-        }
+async fn confirm<C: ConnectionTrait>(db: &C, promotion: store_promotion::Model) -> Result<store_promotion::Model, AppError> {
+    let mut active: PromotionActiveModel = promotion.into();
+    active.match_status = Set("confirmed".to_string());
+    active.updated_at = Set(Utc::now().fixed_offset());
+    Ok(active.update(db).await?)
+}
+
+async fn insert_alias<C: ConnectionTrait>(db: &C, ingredient_id: i64, alias: &str) -> Result<(), AppError> {
+    let row = ingredient_alias::ActiveModel {
+        ingredient_id: Set(ingredient_id),
+        alias: Set(alias.to_string()),
+        created_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    };
+    ingredient_alias::Entity::insert(row)
+        .on_conflict(
+            OnConflict::columns([ingredient_alias::Column::IngredientId, ingredient_alias::Column::Alias])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
 
-        let resp_json: serde_json::Value = resp.json().await
-            .map_err(|e| AppError::Internal(format!("Ollama JSON parse failed: {}", e)))?;
+fn dedup(ids: &[i64]) -> Vec<i64> {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
 
-        let raw_text = resp_json["response"].as_str().unwrap_or("");
-        // Extract JSON array from response (model may include surrounding text)
-        if let Some(start) = raw_text.find('[') {
-            if let Some(end) = raw_text.rfind(']') {
-                let json_str = &raw_text[start..=end];
-                if let Ok(items) = serde_json::from_str::<Vec<serde_json::Value>>(json_str) {
-                    for item in items {
-                        insert_candidate(db, &job, &item).await;
-                    }
-                }
-            }
-        }
+/// Pending candidates of a store narrowed by a bulk review's filters
+fn bulk_filter(store_id: Uuid, req: &BulkReviewRequest) -> Condition {
+    use store_promotion_candidate::Column;
+    let threshold = |c: f64| Decimal::try_from(c).unwrap_or_default();
+    let mut filter = Condition::all()
+        .add(Column::StoreId.eq(store_id))
+        .add(Column::ReviewStatus.eq("pending"));
+    if let Some(job_id) = req.job_id {
+        filter = filter.add(Column::JobId.eq(job_id));
+    }
+    if let Some(min) = req.min_confidence {
+        filter = filter.add(Column::Confidence.gte(threshold(min)));
     }
+    if let Some(max) = req.max_confidence {
+        filter = filter.add(Column::Confidence.lt(threshold(max)));
+    }
+    filter
+}
 
-    // Cleanup temporary PNG files
-    let _ = tokio::fs::remove_dir_all(&output_dir).await;
+/// Same product, brand and pack size on sale at overlapping dates
+fn is_duplicate(candidate: &store_promotion_candidate::Model, promotion: &store_promotion::Model) -> bool {
+    let same_text = |a: Option<&str>, b: Option<&str>| {
+        let clean = |s: Option<&str>| s.map(|s| s.to_lowercase().replace(' ', "").replace(',', "."));
+        clean(a).filter(|s| !s.is_empty()) == clean(b).filter(|s| !s.is_empty())
+    };
+    let overlaps = candidate.valid_from.zip(promotion.valid_until).is_none_or(|(from, until)| from <= until)
+        && promotion.valid_from.zip(candidate.valid_until).is_none_or(|(from, until)| from <= until);
+
+    promotion_match::normalise_product_name(&candidate.product_name, candidate.brand.as_deref())
+        == promotion_match::normalise_product_name(&promotion.product_name, promotion.brand.as_deref())
+        && same_text(candidate.brand.as_deref(), promotion.brand.as_deref())
+        && same_text(candidate.unit.as_deref(), promotion.unit.as_deref())
+        && overlaps
+}
+
+fn rejection_rate(approved: i64, rejected: i64) -> Option<f64> {
+    let reviewed = approved + rejected;
+    (reviewed > 0).then(|| (rejected as f64 / reviewed as f64 * 1000.0).round() / 1000.0)
+}
+
+/// A promotion date runs from midnight…
+pub(crate) fn start_of_day(date: chrono::NaiveDate) -> sea_orm::prelude::DateTimeWithTimeZone {
+    // 0,0,0 is always a valid time — expect() is safe here
+    date.and_hms_opt(0, 0, 0).expect("midnight (0,0,0) is always valid").and_utc().fixed_offset()
+}
+
+/// …to the last second of the day
+pub(crate) fn end_of_day(date: chrono::NaiveDate) -> sea_orm::prelude::DateTimeWithTimeZone {
+    // 23,59,59 is always a valid time — expect() is safe here
+    date.and_hms_opt(23, 59, 59).expect("end-of-day (23,59,59) is always valid").and_utc().fixed_offset()
+}
 
-    // Mark job as done
-    if let Ok(Some(j)) = PdfJob::find_by_id(job_id).one(db).await {
-        let mut a: JobActiveModel = j.into();
-        a.status = Set("done".to_string());
-        a.processed_at = Set(Some(Utc::now().fixed_offset()));
-        let _ = a.update(db).await;
+fn store_error(field: &'static str, message: impl Into<std::borrow::Cow<'static, str>>) -> AppError {
+    let mut errors = validator::ValidationErrors::new();
+    let mut e = validator::ValidationError::new("store");
+    e.message = Some(message.into());
+    errors.add(field, e);
+    AppError::Validation(errors)
+}
+
+fn already_reviewed() -> AppError {
+    store_error("candidate", "Candidate has already been reviewed")
+}
+
+/// Text fields are stored trimmed, so whitespace alone counts as empty
+fn not_blank(value: &str) -> Result<(), validator::ValidationError> {
+    if value.trim().is_empty() {
+        let mut err = validator::ValidationError::new("blank");
+        err.message = Some("Must not be blank".into());
+        return Err(err);
     }
+    Ok(())
+}
 
+fn bulk_duplicate_policy(policy: &DuplicatePolicy) -> Result<(), validator::ValidationError> {
+    if *policy == DuplicatePolicy::Fail {
+        let mut err = validator::ValidationError::new("on_duplicate");
+        err.message = Some("Bulk approval can't fail on duplicates; use skip, replace or keep".into());
+        return Err(err);
+    }
     Ok(())
 }
 
-/// Insert a single AI-extracted item into the staging table
-async fn insert_candidate(
-    db: &DatabaseConnection,
-    job: &pdf_processing_job::Model,
-    item: &serde_json::Value,
-) {
-    let product_name = match item["product_name"].as_str() {
-        Some(n) if !n.is_empty() => n.to_string(),
-        _ => return,
-    };
-    let discounted_price = match item["discounted_price"].as_f64() {
-        Some(p) if p > 0.0 => Decimal::try_from(p).unwrap_or_default(),
-        _ => return,
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let original_price = item["original_price"].as_f64()
-        .and_then(|p| Decimal::try_from(p).ok());
-    let discount_pct = item["discount_pct"].as_f64()
-        .and_then(|p| Decimal::try_from(p).ok());
-    let confidence = item["confidence"].as_f64()
-        .and_then(|c| Decimal::try_from(c).ok());
-
-    let valid_from = item["valid_from"].as_str()
-        .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
-        // 0,0,0 is always a valid time — expect() is safe here
-        .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight (0,0,0) is always valid").and_utc().fixed_offset());
-
-    let valid_until = item["valid_until"].as_str()
-        .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
-        // 23,59,59 is always a valid time — expect() is safe here
-        .map(|d| d.and_hms_opt(23, 59, 59).expect("end-of-day (23,59,59) is always valid").and_utc().fixed_offset());
-
-    let candidate = CandidateActiveModel {
-        id: Set(Uuid::new_v4()),
-        store_id: Set(job.store_id),
-        job_id: Set(job.id),
-        product_name: Set(product_name),
-        brand: Set(item["brand"].as_str().map(|s| s.to_string())),
-        original_price: Set(original_price),
-        discounted_price: Set(discounted_price),
-        discount_pct: Set(discount_pct),
-        unit: Set(item["unit"].as_str().map(|s| s.to_string())),
-        valid_from: Set(valid_from),
-        valid_until: Set(valid_until),
-        confidence: Set(confidence),
-        review_status: Set("pending".to_string()),
-        reviewed_by: Set(None),
-        reviewed_at: Set(None),
-        created_at: Set(Utc::now().fixed_offset()),
-    };
+    fn candidate(name: &str, brand: Option<&str>, unit: Option<&str>) -> store_promotion_candidate::Model {
+        let now = Utc::now().fixed_offset();
+        store_promotion_candidate::Model {
+            id: Uuid::new_v4(),
+            store_id: Uuid::nil(),
+            job_id: Uuid::nil(),
+            product_name: name.into(),
+            brand: brand.map(str::to_string),
+            original_price: None,
+            discounted_price: Decimal::ONE,
+            discount_pct: None,
+            unit: unit.map(str::to_string),
+            valid_from: None,
+            valid_until: None,
+            confidence: None,
+            review_status: "pending".into(),
+            reviewed_by: None,
+            reviewed_at: None,
+            edited_by: None,
+            edited_at: None,
+            duplicate_of: None,
+            created_at: now,
+        }
+    }
+
+    fn promotion(c: &store_promotion_candidate::Model) -> store_promotion::Model {
+        store_promotion::Model {
+            id: Uuid::new_v4(),
+            store_id: c.store_id,
+            product_name: c.product_name.clone(),
+            brand: c.brand.clone(),
+            original_price: None,
+            discounted_price: Decimal::TWO,
+            discount_pct: None,
+            unit: c.unit.clone(),
+            valid_from: c.valid_from,
+            valid_until: c.valid_until,
+            is_active: true,
+            source_pdf_url: None,
+            confidence: None,
+            match_status: "auto".into(),
+            created_at: c.created_at,
+            updated_at: c.created_at,
+        }
+    }
+
+    #[test]
+    fn duplicates_need_the_same_product_pack_and_dates() {
+        let date = |s: &str| start_of_day(chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap());
+        let live = promotion(&candidate("Mimosa Milk 1L", Some("Mimosa"), Some("1 L")));
+
+        // Different price and spelling of the same pack is still a repeat
+        assert!(is_duplicate(&candidate("MIMOSA milk", Some("mimosa"), Some("1l")), &live));
+        assert!(!is_duplicate(&candidate("Mimosa Milk", Some("Mimosa"), Some("6 x 1l")), &live));
+        assert!(!is_duplicate(&candidate("Milk", Some("Agros"), Some("1l")), &live));
+
+        let mut dated = promotion(&candidate("Eggs", None, None));
+        dated.valid_until = Some(date("2026-10-20"));
+        let mut next_week = candidate("Eggs", None, None);
+        next_week.valid_from = Some(date("2026-10-21"));
+        assert!(!is_duplicate(&next_week, &dated));
+        next_week.valid_from = Some(date("2026-10-19"));
+        assert!(is_duplicate(&next_week, &dated));
+    }
+
+    #[test]
+    fn rejection_rate_ignores_unreviewed() {
+        assert_eq!(rejection_rate(0, 0), None);
+        assert_eq!(rejection_rate(2, 1), Some(0.333));
+    }
+
+    async fn service_with_job() -> (StoreService, Uuid, Uuid, Uuid) {
+        let db = crate::test_db::connect().await;
+        let store = crate::test_db::create_store(&db).await;
+        let reviewer = crate::test_db::create_user(&db).await;
+        let job = PdfJob::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO pdf_processing_jobs (store_id, file_path, status, page_count) \
+                 VALUES ($1, '/tmp/flyer.pdf', 'done', 2) RETURNING *",
+                [store.id.into()],
+            ))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        (StoreService::new(db, PathBuf::from("/tmp")), store.id, job.id, reviewer.id)
+    }
+
+    async fn pending(
+        svc: &StoreService,
+        store_id: Uuid,
+        job_id: Uuid,
+        name: &str,
+        confidence: &str,
+    ) -> store_promotion_candidate::Model {
+        CandidateActiveModel {
+            id: Set(Uuid::new_v4()),
+            store_id: Set(store_id),
+            job_id: Set(job_id),
+            product_name: Set(name.into()),
+            unit: Set(Some("1 L".into())),
+            discounted_price: Set(Decimal::ONE),
+            confidence: Set(Some(confidence.parse().unwrap())),
+            review_status: Set("pending".into()),
+            created_at: Set(Utc::now().fixed_offset()),
+            ..Default::default()
+        }
+        .insert(&svc.db)
+        .await
+        .unwrap()
+    }
+
+    fn bulk(body: serde_json::Value) -> BulkReviewRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn bulk_approval_refuses_to_fail_on_duplicates() {
+        assert!(bulk(serde_json::json!({ "action": "approve", "on_duplicate": "fail" })).validate().is_err());
+        assert!(bulk(serde_json::json!({ "action": "approve", "on_duplicate": "skip" })).validate().is_ok());
+        assert!(bulk(serde_json::json!({ "action": "approve" })).validate().is_ok());
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn bulk_review_checks_duplicates_within_the_batch() {
+        let (svc, store_id, job_id, reviewer) = service_with_job().await;
+        let milk = pending(&svc, store_id, job_id, "Milk", "0.9").await;
+        let again = pending(&svc, store_id, job_id, "milk", "0.8").await;
+        let unsure = pending(&svc, store_id, job_id, "Butter", "0.2").await;
+
+        let approved = svc
+            .bulk_review(store_id, reviewer, bulk(serde_json::json!({ "action": "approve", "min_confidence": 0.5 })))
+            .await
+            .unwrap();
+        assert_eq!((approved.approved, approved.rejected, approved.remaining), (1, 0, 0));
+        assert_eq!(approved.duplicates.len(), 1);
+        assert_eq!(approved.duplicates[0].candidate_id, again.id);
+
+        let rejected = svc
+            .bulk_review(store_id, reviewer, bulk(serde_json::json!({ "action": "reject" })))
+            .await
+            .unwrap();
+        assert_eq!((rejected.rejected, rejected.remaining), (1, 0));
+
+        let db = &svc.db;
+        let status = |id| async move { Candidate::find_by_id(id).one(db).await.unwrap().unwrap().review_status };
+        assert_eq!(status(milk.id).await, "approved");
+        assert_eq!(status(again.id).await, "duplicate");
+        assert_eq!(status(unsure.id).await, "rejected");
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn duplicate_policies_when_approving_one_candidate() {
+        let (svc, store_id, job_id, reviewer) = service_with_job().await;
+        let first = pending(&svc, store_id, job_id, "Milk", "0.9").await;
+        let ApproveOutcome::Approved(live) =
+            svc.approve_candidate(first.id, reviewer, DuplicatePolicy::Fail).await.unwrap()
+        else {
+            panic!("first candidate is no duplicate");
+        };
+        assert!(svc.approve_candidate(first.id, reviewer, DuplicatePolicy::Keep).await.is_err());
+
+        let second = pending(&svc, store_id, job_id, "Milk", "0.9").await;
+        assert!(svc.approve_candidate(second.id, reviewer, DuplicatePolicy::Fail).await.is_err());
+        let skipped = svc.approve_candidate(second.id, reviewer, DuplicatePolicy::Skip).await.unwrap();
+        assert!(matches!(skipped, ApproveOutcome::Duplicate { duplicate_of } if duplicate_of == live.promotion.id));
+
+        let third = pending(&svc, store_id, job_id, "Milk", "0.9").await;
+        let replaced = svc.approve_candidate(third.id, reviewer, DuplicatePolicy::Replace).await.unwrap();
+        assert!(matches!(replaced, ApproveOutcome::Approved(_)));
+        let old = StorePromotion::find_by_id(live.promotion.id).one(&svc.db).await.unwrap().unwrap();
+        assert!(!old.is_active);
+        assert!(svc.reject_candidate(third.id, reviewer).await.is_err());
+    }
+
+    fn edit(body: serde_json::Value) -> UpdateCandidateRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn blank_edits_are_refused() {
+        assert!(edit(serde_json::json!({ "product_name": "   " })).validate().is_err());
+        assert!(edit(serde_json::json!({ "unit": " " })).validate().is_err());
+        assert!(edit(serde_json::json!({ "product_name": " Milk " })).validate().is_ok());
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn candidate_edits_are_trimmed_and_checked() {
+        let (svc, store_id, job_id, editor) = service_with_job().await;
+        let candidate = pending(&svc, store_id, job_id, "Mlik", "0.4").await;
+
+        let edited = svc
+            .update_candidate(candidate.id, editor, edit(serde_json::json!({ "product_name": "  Milk ", "clear": ["unit"] })))
+            .await
+            .unwrap();
+        assert_eq!(edited.product_name, "Milk");
+        assert_eq!(edited.unit, None);
+        assert_eq!(edited.edited_by, Some(editor));
+
+        // The result must stay consistent, not just the request
+        let bad = edit(serde_json::json!({ "valid_from": "2026-10-20", "valid_until": "2026-10-19" }));
+        assert!(svc.update_candidate(candidate.id, editor, bad).await.is_err());
+        assert!(svc.update_candidate(candidate.id, editor, edit(serde_json::json!({ "discounted_price": "0" }))).await.is_err());
+
+        svc.reject_candidate(candidate.id, editor).await.unwrap();
+        assert!(svc.update_candidate(candidate.id, editor, edit(serde_json::json!({ "brand": "Agros" }))).await.is_err());
+
+        // An edit racing an approval either lands first or is refused
+        let racing = pending(&svc, store_id, job_id, "Rice", "0.7").await;
+        let (edited, approved) = futures::join!(
+            svc.update_candidate(racing.id, editor, edit(serde_json::json!({ "brand": "Cigala" }))),
+            svc.approve_candidate(racing.id, editor, DuplicatePolicy::Keep),
+        );
+        let ApproveOutcome::Approved(published) = approved.unwrap() else { panic!("nothing to duplicate") };
+        assert_eq!(edited.is_ok(), published.promotion.brand.as_deref() == Some("Cigala"));
+    }
+
+    #[ignore = "needs TEST_DATABASE_URL"]
+    #[actix_web::test]
+    async fn job_stats_count_review_outcomes() {
+        let (svc, store_id, job_id, reviewer) = service_with_job().await;
+        let approve = pending(&svc, store_id, job_id, "Milk", "0.9").await;
+        let duplicate = pending(&svc, store_id, job_id, "Milk", "0.8").await;
+        let reject = pending(&svc, store_id, job_id, "Eggs", "0.4").await;
+        let edit = pending(&svc, store_id, job_id, "Buttr", "0.5").await;
+
+        svc.approve_candidate(approve.id, reviewer, DuplicatePolicy::Fail).await.unwrap();
+        svc.approve_candidate(duplicate.id, reviewer, DuplicatePolicy::Skip).await.unwrap();
+        svc.reject_candidate(reject.id, reviewer).await.unwrap();
+        let fix = serde_json::from_value(serde_json::json!({ "product_name": "Butter" })).unwrap();
+        svc.update_candidate(edit.id, reviewer, fix).await.unwrap();
+
+        let stats = svc.job_stats(store_id, job_id).await.unwrap();
+        assert_eq!(stats.page_count, Some(2));
+        assert_eq!(
+            (stats.total, stats.pending, stats.approved, stats.rejected, stats.duplicate, stats.edited),
+            (4, 1, 1, 1, 1, 1)
+        );
+        assert_eq!(stats.average_confidence, Some(0.65));
+        assert_eq!(stats.rejection_rate, Some(0.5));
 
-    if let Err(e) = candidate.insert(db).await {
-        tracing::warn!("Failed to insert candidate: {:?}", e);
+        assert!(svc.job_stats(Uuid::new_v4(), job_id).await.is_err());
     }
 }
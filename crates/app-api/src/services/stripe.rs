@@ -0,0 +1,231 @@
+//! Minimal Stripe REST client — customers, Checkout and Billing Portal.
+//!
+//! Only the handful of form-encoded endpoints the subscription flow needs are
+//! wrapped. The base URL is configurable (`STRIPE_API_BASE`) so tests and
+//! local development can point it at a mock HTTP server instead of
+//! `https://api.stripe.com`.
+
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use cookest_shared::errors::AppError;
+
+#[derive(Debug, Deserialize)]
+struct StripeObject {
+    id: String,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeErrorBody {
+    error: StripeErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeErrorDetail {
+    message: Option<String>,
+}
+
+/// A hosted Stripe page the client should redirect to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StripeRedirect {
+    pub id: String,
+    pub url: String,
+}
+
+pub struct StripeClient {
+    client: Arc<Client>,
+    api_base: String,
+    secret_key: Option<SecretString>,
+}
+
+impl StripeClient {
+    /// `secret_key` is `STRIPE_SECRET_KEY`; when absent every call fails with
+    /// an internal error so the rest of the API still runs without Stripe.
+    pub fn new(api_base: String, secret_key: Option<SecretString>) -> Self {
+        Self {
+            client: Arc::new(Client::new()),
+            api_base: api_base.trim_end_matches('/').to_string(),
+            secret_key,
+        }
+    }
+
+    /// Create a customer for a user. The idempotency key makes retries after a
+    /// timeout return the same customer instead of creating a duplicate.
+    pub async fn create_customer(&self, user_id: Uuid, email: &str) -> Result<String, AppError> {
+        let user_id = user_id.to_string();
+        let customer = self
+            .post(
+                "/v1/customers",
+                &[("email", email), ("metadata[user_id]", &user_id)],
+                Some(&format!("customer-{}", user_id)),
+            )
+            .await?;
+        Ok(customer.id)
+    }
+
+    /// Create a subscription-mode Checkout Session for one price
+    pub async fn create_checkout_session(
+        &self,
+        customer_id: &str,
+        price_id: &str,
+        user_id: Uuid,
+        success_url: &str,
+        cancel_url: &str,
+    ) -> Result<StripeRedirect, AppError> {
+        let user_id = user_id.to_string();
+        let session = self
+            .post(
+                "/v1/checkout/sessions",
+                &[
+                    ("mode", "subscription"),
+                    ("customer", customer_id),
+                    ("line_items[0][price]", price_id),
+                    ("line_items[0][quantity]", "1"),
+                    ("success_url", success_url),
+                    ("cancel_url", cancel_url),
+                    ("client_reference_id", &user_id),
+                    ("subscription_data[metadata][user_id]", &user_id),
+                ],
+                None,
+            )
+            .await?;
+        redirect(session)
+    }
+
+    /// Create a Billing Portal session where the customer can manage or cancel
+    pub async fn create_portal_session(
+        &self,
+        customer_id: &str,
+        return_url: &str,
+    ) -> Result<StripeRedirect, AppError> {
+        let session = self
+            .post(
+                "/v1/billing_portal/sessions",
+                &[("customer", customer_id), ("return_url", return_url)],
+                None,
+            )
+            .await?;
+        redirect(session)
+    }
+
+    async fn post(
+        &self,
+        path: &str,
+        form: &[(&str, &str)],
+        idempotency_key: Option<&str>,
+    ) -> Result<StripeObject, AppError> {
+        let key = self
+            .secret_key
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("STRIPE_SECRET_KEY not configured".to_string()))?;
+
+        let mut req = self
+            .client
+            .post(format!("{}{}", self.api_base, path))
+            .bearer_auth(key.expose_secret())
+            .form(form);
+        if let Some(idempotency_key) = idempotency_key {
+            req = req.header("Idempotency-Key", idempotency_key);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Stripe request failed: {}", e)))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let message = resp
+                .json::<StripeErrorBody>()
+                .await
+                .ok()
+                .and_then(|b| b.error.message)
+                .unwrap_or_default();
+            tracing::error!("Stripe {} returned {}: {}", path, status, message);
+            return Err(AppError::Internal(format!("Stripe error {}: {}", status, message)));
+        }
+
+        resp.json::<StripeObject>()
+            .await
+            .map_err(|e| AppError::Internal(format!("Invalid Stripe response: {}", e)))
+    }
+}
+
+fn redirect(obj: StripeObject) -> Result<StripeRedirect, AppError> {
+    let url = obj
+        .url
+        .ok_or_else(|| AppError::Internal("Stripe session has no URL".to_string()))?;
+    Ok(StripeRedirect { id: obj.id, url })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use std::collections::HashMap;
+
+    /// Echo-style mock: checks auth and returns canned objects per endpoint
+    async fn mock_stripe(req: HttpRequest, form: web::Form<HashMap<String, String>>) -> HttpResponse {
+        let auth = req.headers().get("authorization").and_then(|v| v.to_str().ok());
+        if auth != Some("Bearer sk_test_mock") {
+            return HttpResponse::Unauthorized()
+                .json(serde_json::json!({ "error": { "message": "Invalid API Key" } }));
+        }
+        match req.path() {
+            "/v1/customers" => {
+                assert!(req.headers().contains_key("idempotency-key"));
+                HttpResponse::Ok().json(serde_json::json!({ "id": format!("cus_{}", form["email"]) }))
+            }
+            "/v1/checkout/sessions" => {
+                assert_eq!(form["mode"], "subscription");
+                HttpResponse::Ok().json(serde_json::json!({
+                    "id": "cs_test_1",
+                    "url": format!("https://checkout.test/{}/{}", form["customer"], form["line_items[0][price]"]),
+                }))
+            }
+            _ => HttpResponse::NotFound().finish(),
+        }
+    }
+
+    async fn start_mock() -> String {
+        let server = HttpServer::new(|| App::new().default_service(web::post().to(mock_stripe)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{}", addr)
+    }
+
+    #[actix_web::test]
+    async fn creates_customer_and_checkout_against_mock() {
+        let base = start_mock().await;
+        let stripe = StripeClient::new(base, Some(SecretString::from("sk_test_mock")));
+        let user_id = Uuid::new_v4();
+
+        let customer = stripe.create_customer(user_id, "a@b.c").await.unwrap();
+        assert_eq!(customer, "cus_a@b.c");
+
+        let session = stripe
+            .create_checkout_session(&customer, "price_pro", user_id, "https://ok", "https://no")
+            .await
+            .unwrap();
+        assert_eq!(session.url, "https://checkout.test/cus_a@b.c/price_pro");
+    }
+
+    #[actix_web::test]
+    async fn surfaces_stripe_errors() {
+        let base = start_mock().await;
+        let stripe = StripeClient::new(base, Some(SecretString::from("sk_wrong")));
+        let err = stripe.create_customer(Uuid::new_v4(), "a@b.c").await.unwrap_err();
+        assert!(matches!(err, AppError::Internal(msg) if msg.contains("Invalid API Key")));
+
+        let unconfigured = StripeClient::new("https://api.stripe.com".to_string(), None);
+        assert!(unconfigured.create_portal_session("cus_1", "https://back").await.is_err());
+    }
+}
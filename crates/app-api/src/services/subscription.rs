@@ -1,4 +1,5 @@
-//! Subscription service — manages user tiers and Stripe webhook processing
+//! Subscription service — manages user tiers, Stripe Checkout / Billing Portal
+//! sessions and Stripe webhook processing
 
 use chrono::Utc;
 use hmac::{Hmac, Mac};
@@ -9,6 +10,7 @@ use uuid::Uuid;
 use crate::entity::stripe_processed_event;
 use crate::entity::user::{self, ActiveModel as UserActiveModel, Entity as User};
 use cookest_shared::errors::AppError;
+use crate::services::stripe::{StripeClient, StripeRedirect};
 use crate::services::token::SubscriptionTier;
 
 type HmacSha256 = Hmac<Sha256>;
@@ -18,27 +20,94 @@ pub const FEATURE_AI_MEAL_PLAN: &str = "ai_meal_plan";
 pub const FEATURE_PRICE_COMPARISON: &str = "price_comparison";
 pub const FEATURE_USER_RECIPES: &str = "user_recipes";
 pub const FEATURE_SHOPPING_OPTIMIZER: &str = "shopping_optimizer";
+pub const FEATURE_HOUSEHOLDS: &str = "households";
+
+/// Stripe price IDs for each paid tier (`STRIPE_PRICE_PRO`, `STRIPE_PRICE_FAMILY`).
+///
+/// The tier a subscription grants is always derived from these, never from
+/// client-editable metadata.
+#[derive(Debug, Clone, Default)]
+pub struct StripePrices {
+    pub pro: Option<String>,
+    pub family: Option<String>,
+}
+
+impl StripePrices {
+    pub fn price_for(&self, tier: &SubscriptionTier) -> Option<&str> {
+        match tier {
+            SubscriptionTier::Pro => self.pro.as_deref(),
+            SubscriptionTier::Family => self.family.as_deref(),
+            SubscriptionTier::Free => None,
+        }
+    }
+
+    pub fn tier_for(&self, price_id: &str) -> Option<SubscriptionTier> {
+        if self.family.as_deref() == Some(price_id) {
+            Some(SubscriptionTier::Family)
+        } else if self.pro.as_deref() == Some(price_id) {
+            Some(SubscriptionTier::Pro)
+        } else {
+            None
+        }
+    }
+
+    /// Highest tier granted by the items of a Stripe subscription object
+    pub fn tier_for_subscription(&self, subscription: &serde_json::Value) -> Option<SubscriptionTier> {
+        subscription["items"]["data"]
+            .as_array()?
+            .iter()
+            .filter_map(|item| item["price"]["id"].as_str())
+            .filter_map(|price_id| self.tier_for(price_id))
+            .max_by_key(|tier| match tier {
+                SubscriptionTier::Family => 2,
+                SubscriptionTier::Pro => 1,
+                SubscriptionTier::Free => 0,
+            })
+    }
+}
 
 pub struct SubscriptionService {
     db: DatabaseConnection,
     stripe_webhook_secret: Option<String>,
+    stripe: StripeClient,
+    prices: StripePrices,
+    /// Origins Stripe may redirect back to (`STRIPE_REDIRECT_ORIGINS`)
+    redirect_origins: Vec<String>,
 }
 
 impl SubscriptionService {
-    pub fn new(db: DatabaseConnection, stripe_webhook_secret: Option<String>) -> Self {
-        Self { db, stripe_webhook_secret }
+    pub fn new(
+        db: DatabaseConnection,
+        stripe_webhook_secret: Option<String>,
+        stripe: StripeClient,
+        prices: StripePrices,
+        redirect_origins: Vec<String>,
+    ) -> Self {
+        Self { db, stripe_webhook_secret, stripe, prices, redirect_origins }
+    }
+
+    /// Configured price → tier mapping, used by the webhook handler
+    pub fn prices(&self) -> &StripePrices {
+        &self.prices
     }
 
     /// Return the feature list for a given tier
     pub fn features_for_tier(tier: &SubscriptionTier) -> Vec<&'static str> {
         match tier {
             SubscriptionTier::Free => vec![],
-            SubscriptionTier::Pro | SubscriptionTier::Family => vec![
+            SubscriptionTier::Pro => vec![
                 FEATURE_AI_MEAL_PLAN,
                 FEATURE_PRICE_COMPARISON,
                 FEATURE_USER_RECIPES,
                 FEATURE_SHOPPING_OPTIMIZER,
             ],
+            SubscriptionTier::Family => vec![
+                FEATURE_AI_MEAL_PLAN,
+                FEATURE_PRICE_COMPARISON,
+                FEATURE_USER_RECIPES,
+                FEATURE_SHOPPING_OPTIMIZER,
+                FEATURE_HOUSEHOLDS,
+            ],
         }
     }
 
@@ -140,6 +209,73 @@ impl SubscriptionService {
         Ok(())
     }
 
+    /// Return the user's Stripe customer, creating and linking one on first use
+    async fn ensure_stripe_customer(&self, user_id: Uuid) -> Result<String, AppError> {
+        let user = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+
+        if let Some(customer_id) = user.stripe_customer_id {
+            return Ok(customer_id);
+        }
+
+        let customer_id = self.stripe.create_customer(user_id, &user.email).await?;
+        self.set_stripe_customer_id(user_id, &customer_id).await?;
+        tracing::info!("Linked Stripe customer {} to user {}", customer_id, user_id);
+        Ok(customer_id)
+    }
+
+    /// Create a Checkout Session for a paid tier → hosted page to redirect to
+    pub async fn create_checkout_session(
+        &self,
+        user_id: Uuid,
+        tier: &SubscriptionTier,
+        success_url: &str,
+        cancel_url: &str,
+    ) -> Result<StripeRedirect, AppError> {
+        self.check_redirect("success_url", success_url)?;
+        self.check_redirect("cancel_url", cancel_url)?;
+        let price_id = self.prices.price_for(tier).ok_or_else(|| {
+            AppError::Internal(format!("No Stripe price configured for tier '{}'", tier.as_str()))
+        })?;
+
+        let customer_id = self.ensure_stripe_customer(user_id).await?;
+        self.stripe
+            .create_checkout_session(&customer_id, price_id, user_id, success_url, cancel_url)
+            .await
+    }
+
+    /// Create a Billing Portal session so the user can manage or cancel
+    pub async fn create_portal_session(
+        &self,
+        user_id: Uuid,
+        return_url: &str,
+    ) -> Result<StripeRedirect, AppError> {
+        self.check_redirect("return_url", return_url)?;
+        let customer_id = User::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User".to_string()))?
+            .stripe_customer_id
+            .ok_or_else(|| AppError::NotFound("Billing account".to_string()))?;
+
+        self.stripe.create_portal_session(&customer_id, return_url).await
+    }
+
+    /// Stripe sends the user to these URLs after payment, so only our own
+    /// app may be named — anything else would make us an open redirect
+    fn check_redirect(&self, field: &'static str, url: &str) -> Result<(), AppError> {
+        if self.redirect_origins.iter().any(|origin| is_within_origin(url, origin)) {
+            return Ok(());
+        }
+        let mut errors = validator::ValidationErrors::new();
+        let mut e = validator::ValidationError::new("redirect");
+        e.message = Some("URL must point back to the app".into());
+        errors.add(field, e);
+        Err(AppError::Validation(errors))
+    }
+
     /// Require Pro or Family tier; returns HTTP 402 if the user is on Free tier.
     pub async fn require_pro(&self, claims: &crate::middleware::Claims) -> Result<(), AppError> {
         let tier = claims.tier.as_ref().unwrap_or(&SubscriptionTier::Free);
@@ -151,3 +287,45 @@ impl SubscriptionService {
         }
     }
 }
+
+/// `url` is `origin` itself or a path, query or fragment beneath it —
+/// "https://m.cookest.app.evil.com" and "https://m.cookest.app@evil.com" are not
+fn is_within_origin(url: &str, origin: &str) -> bool {
+    match url.strip_prefix(origin) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['/', '?', '#']),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tier_comes_from_configured_prices() {
+        let prices = StripePrices {
+            pro: Some("price_pro".into()),
+            family: Some("price_family".into()),
+        };
+        let sub = serde_json::json!({
+            "metadata": { "tier": "family" },
+            "items": { "data": [{ "price": { "id": "price_pro" } }] }
+        });
+        assert_eq!(prices.tier_for_subscription(&sub), Some(SubscriptionTier::Pro));
+
+        let unknown = serde_json::json!({ "items": { "data": [{ "price": { "id": "price_other" } }] } });
+        assert_eq!(prices.tier_for_subscription(&unknown), None);
+    }
+
+    #[test]
+    fn redirects_stay_on_the_app_origin() {
+        let origin = "https://m.cookest.app";
+        assert!(is_within_origin("https://m.cookest.app", origin));
+        assert!(is_within_origin("https://m.cookest.app/billing/done?session={CHECKOUT_SESSION_ID}", origin));
+        assert!(is_within_origin("https://m.cookest.app#settings", origin));
+        assert!(!is_within_origin("https://m.cookest.app.evil.com/billing", origin));
+        assert!(!is_within_origin("https://m.cookest.app@evil.com/", origin));
+        assert!(!is_within_origin("http://m.cookest.app/billing", origin));
+        assert!(!is_within_origin("https://evil.com/?https://m.cookest.app", origin));
+    }
+}
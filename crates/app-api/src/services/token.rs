@@ -2,10 +2,11 @@
 //! 
 //! Security features:
 //! - Short-lived access tokens (15 min default)
-//! - Refresh tokens stored as hashes in DB
+//! - Refresh tokens stored as hashes in DB, one session (`sid`) per device
 //! - Secure random token generation
 //! - Algorithm explicitly specified (prevents algorithm confusion attacks)
 //! - Subscription tier embedded ONLY in access tokens, never in refresh tokens
+//! - Short-lived "mfa pending" tokens bridge password and 2FA login steps
 
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
@@ -16,6 +17,9 @@ use uuid::Uuid;
 use crate::config::Config;
 use cookest_shared::errors::AppError;
 
+/// Time allowed between the password step and the 2FA code step
+const MFA_TOKEN_EXPIRY_SECONDS: i64 = 300;
+
 /// Subscription tier — determines feature access throughout the API
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -55,7 +59,7 @@ pub struct Claims {
     pub sub: String,
     /// User email
     pub email: String,
-    /// Token type: "access" or "refresh"
+    /// Token type: "access", "refresh" or "mfa"
     pub token_type: TokenType,
     /// Expiration time (Unix timestamp)
     pub exp: i64,
@@ -69,6 +73,9 @@ pub struct Claims {
     /// Whether the user is an admin — ONLY present in access tokens
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_admin: Option<bool>,
+    /// Session (device) this token belongs to — access and refresh tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -76,6 +83,8 @@ pub struct Claims {
 pub enum TokenType {
     Access,
     Refresh,
+    /// Password verified, 2FA code still outstanding — grants nothing else
+    Mfa,
 }
 
 /// Token pair returned after successful authentication
@@ -112,6 +121,7 @@ impl TokenService {
         email: &str,
         tier: SubscriptionTier,
         is_admin: bool,
+        session_id: Uuid,
     ) -> Result<String, AppError> {
         let now = Utc::now();
         let exp = now + Duration::seconds(self.access_expiry_seconds);
@@ -125,6 +135,7 @@ impl TokenService {
             jti: generate_jti(),
             tier: Some(tier),
             is_admin: Some(is_admin),
+            sid: Some(session_id.to_string()),
         };
 
         encode(&Header::default(), &claims, &self.encoding_key)
@@ -132,7 +143,7 @@ impl TokenService {
     }
 
     /// Generate refresh token (long-lived) — does NOT embed tier (tier read from DB on refresh)
-    pub fn generate_refresh_token(&self, user_id: Uuid, email: &str) -> Result<String, AppError> {
+    pub fn generate_refresh_token(&self, user_id: Uuid, email: &str, session_id: Uuid) -> Result<String, AppError> {
         let now = Utc::now();
         let exp = now + Duration::seconds(self.refresh_expiry_seconds);
 
@@ -145,6 +156,28 @@ impl TokenService {
             jti: generate_jti(),
             tier: None,
             is_admin: None,
+            sid: Some(session_id.to_string()),
+        };
+
+        encode(&Header::default(), &claims, &self.encoding_key)
+            .map_err(|e| AppError::Internal(format!("Token generation failed: {}", e)))
+    }
+
+    /// Generate the short-lived token returned when login needs a 2FA code
+    pub fn generate_mfa_token(&self, user_id: Uuid, email: &str) -> Result<String, AppError> {
+        let now = Utc::now();
+        let exp = now + Duration::seconds(MFA_TOKEN_EXPIRY_SECONDS);
+
+        let claims = Claims {
+            sub: user_id.to_string(),
+            email: email.to_string(),
+            token_type: TokenType::Mfa,
+            exp: exp.timestamp(),
+            iat: now.timestamp(),
+            jti: generate_jti(),
+            tier: None,
+            is_admin: None,
+            sid: None,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key)
@@ -187,6 +220,22 @@ impl TokenService {
         Ok(token_data.claims)
     }
 
+    /// Validate an "mfa pending" token specifically
+    pub fn validate_mfa_token(&self, token: &str) -> Result<Claims, AppError> {
+        let token_data = self.validate_token(token)?;
+
+        if token_data.claims.token_type != TokenType::Mfa {
+            return Err(AppError::InvalidToken);
+        }
+
+        Ok(token_data.claims)
+    }
+
+    /// Get "mfa pending" token expiry in seconds
+    pub fn mfa_expiry_seconds(&self) -> i64 {
+        MFA_TOKEN_EXPIRY_SECONDS
+    }
+
     /// Get refresh token expiry in seconds (for cookie max-age)
     pub fn refresh_expiry_seconds(&self) -> i64 {
         self.refresh_expiry_seconds
@@ -198,6 +247,13 @@ impl TokenService {
     }
 }
 
+impl Claims {
+    /// Session id carried by access/refresh tokens
+    pub fn session_id(&self) -> Option<Uuid> {
+        self.sid.as_deref().and_then(|sid| Uuid::parse_str(sid).ok())
+    }
+}
+
 /// Generate a unique JWT ID
 fn generate_jti() -> String {
     let mut rng = rand::thread_rng();
@@ -214,3 +270,25 @@ fn hex_encode(bytes: &[u8]) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    #[test]
+    fn tokens_carry_their_session() {
+        let tokens = TokenService::new(&test_db::config());
+        let (user_id, session_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let refresh = tokens.generate_refresh_token(user_id, "a@example.com", session_id).unwrap();
+        assert_eq!(tokens.validate_refresh_token(&refresh).unwrap().session_id(), Some(session_id));
+        assert!(tokens.validate_access_token(&refresh).is_err());
+
+        let access = tokens
+            .generate_access_token(user_id, "a@example.com", SubscriptionTier::Free, false, session_id)
+            .unwrap();
+        assert_eq!(tokens.validate_access_token(&access).unwrap().session_id(), Some(session_id));
+        assert!(tokens.validate_refresh_token(&access).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! TOTP (RFC 6238) primitives and at-rest encryption for 2FA secrets.
+//!
+//! - 160-bit secrets, SHA-1, 6 digits, 30 s period — what every
+//!   authenticator app supports by default
+//! - Codes are accepted one step either side of "now" to absorb clock drift;
+//!   the caller records the matched step so a code can't be replayed
+//! - Secrets are stored AES-256-GCM encrypted (`TOTP_ENCRYPTION_KEY`) as
+//!   base64(nonce || ciphertext)
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+use cookest_shared::errors::AppError;
+
+/// Seconds per TOTP step
+pub const PERIOD_SECS: i64 = 30;
+/// Digits in a generated code
+const DIGITS: u32 = 6;
+/// Steps either side of the current one that are still accepted
+const DRIFT_STEPS: i64 = 1;
+/// Issuer shown in authenticator apps
+const ISSUER: &str = "Cookest";
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const NONCE_LEN: usize = 12;
+
+/// Fresh random 160-bit shared secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 20];
+    rand::rngs::OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+/// RFC 4648 base32 without padding — the form authenticator apps expect
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// `otpauth://` URI for QR codes / manual entry in an authenticator app
+pub fn provisioning_uri(account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={PERIOD_SECS}",
+        issuer = ISSUER,
+        account = percent_encode(account),
+        secret = base32_encode(secret),
+    )
+}
+
+/// The code for a given time step (HOTP with counter = step)
+pub fn code_at(secret: &[u8], step: i64) -> u32 {
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(DIGITS)
+}
+
+/// Check `code` against the steps around `unix_time`.
+///
+/// Returns the matched step, or `None` when the code is wrong or its step is
+/// not newer than `last_used_step` (replay).
+pub fn verify(secret: &[u8], code: &str, unix_time: i64, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+
+    let current = unix_time.div_euclid(PERIOD_SECS);
+    (current - DRIFT_STEPS..=current + DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at(secret, *step) == code)
+}
+
+/// AES-256-GCM wrapper used to keep TOTP secrets encrypted in the database
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// Encrypt under a fresh random nonce → base64(nonce || ciphertext)
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String, AppError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| AppError::Internal("TOTP secret encryption failed".to_string()))?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(out))
+    }
+
+    pub fn decrypt(&self, encoded: &str) -> Result<Vec<u8>, AppError> {
+        let raw = BASE64
+            .decode(encoded)
+            .map_err(|_| AppError::Internal("Stored TOTP secret is not valid base64".to_string()))?;
+        if raw.len() <= NONCE_LEN {
+            return Err(AppError::Internal("Stored TOTP secret is truncated".to_string()));
+        }
+
+        let (nonce, ciphertext) = raw.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| AppError::Internal("TOTP secret decryption failed (wrong key?)".to_string()))
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~@".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B, SHA-1 vectors (last 6 digits of the 8-digit codes)
+    #[test]
+    fn matches_rfc6238_vectors() {
+        let secret = b"12345678901234567890";
+        assert_eq!(code_at(secret, 59 / PERIOD_SECS), 287082);
+        assert_eq!(code_at(secret, 1111111109 / PERIOD_SECS), 81804);
+        assert_eq!(code_at(secret, 1234567890 / PERIOD_SECS), 5924);
+        assert_eq!(code_at(secret, 2000000000 / PERIOD_SECS), 279037);
+    }
+
+    #[test]
+    fn verify_allows_drift_and_rejects_replay() {
+        let secret = b"12345678901234567890";
+        let now = 1111111109;
+        let step = now / PERIOD_SECS;
+
+        let previous = format!("{:06}", code_at(secret, step - 1));
+        assert_eq!(verify(secret, &previous, now, None), Some(step - 1));
+        assert_eq!(verify(secret, &previous, now, Some(step - 1)), None);
+        assert_eq!(verify(secret, "000000", now, None), None);
+        assert_eq!(verify(secret, "12345", now, None), None);
+
+        let stale = format!("{:06}", code_at(secret, step - 2));
+        assert_eq!(verify(secret, &stale, now, None), None);
+    }
+
+    #[test]
+    fn base32_and_uri() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        let uri = provisioning_uri("a b@x.io", b"12345678901234567890");
+        assert!(uri.starts_with("otpauth://totp/Cookest:a%20b@x.io?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&"));
+    }
+
+    #[test]
+    fn secret_round_trips_through_cipher() {
+        let cipher = SecretCipher::new(&[7u8; 32]);
+        let secret = generate_secret();
+        let stored = cipher.encrypt(&secret).unwrap();
+        assert_ne!(cipher.encrypt(&secret).unwrap(), stored, "nonce must be random");
+        assert_eq!(cipher.decrypt(&stored).unwrap(), secret);
+        assert!(SecretCipher::new(&[8u8; 32]).decrypt(&stored).is_err());
+    }
+}
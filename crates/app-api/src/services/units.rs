@@ -0,0 +1,349 @@
+//! Unit conversion — normalises pantry and recipe quantities to grams.
+//!
+//! Mass units convert directly. Volume units go through the ingredient's
+//! density (`ingredients.density_g_per_ml`, or derived from a volume-based
+//! `portion_sizes` row such as "1 cup" = 125 g). Count and container units
+//! ("pcs", "bottle", "clove") go through a matching `portion_sizes.weight_grams`.
+//!
+//! Anything that cannot be converted is reported as a [`ConversionError`]
+//! rather than silently treated as grams.
+
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::entity::{ingredient, portion_size, recipe_ingredient};
+use cookest_shared::errors::AppError;
+
+/// Canonical name for a single countable item ("pcs", "each", "x", …)
+const PIECE: &str = "piece";
+
+/// Portion units that describe one whole item, in order of preference
+const PIECE_PORTIONS: &[&str] = &[PIECE, "medium", "whole", "item", "large", "small"];
+
+/// What a unit measures, with its factor to the dimension's base unit
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitKind {
+    /// Grams per unit
+    Mass(f64),
+    /// Millilitres per unit
+    Volume(f64),
+    /// Canonical (singular, lowercase) count unit, e.g. "piece", "bottle"
+    Count(String),
+}
+
+/// Why a quantity could not be normalised to grams
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ConversionError {
+    /// Volume unit, but the ingredient has no known density
+    MissingDensity { unit: String },
+    /// Count/container unit with no matching portion weight for the ingredient
+    MissingPortionWeight { unit: String },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::MissingDensity { unit } => {
+                write!(f, "no density known to convert '{}' to grams", unit)
+            }
+            ConversionError::MissingPortionWeight { unit } => {
+                write!(f, "no portion weight known for '{}'", unit)
+            }
+        }
+    }
+}
+
+/// Where an unconvertible quantity came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuantitySource {
+    Recipe,
+    Inventory,
+}
+
+/// A quantity that was skipped because it could not be converted
+#[derive(Debug, Clone, Serialize)]
+pub struct UnconvertedItem {
+    pub ingredient_id: i64,
+    pub name: String,
+    pub quantity: Decimal,
+    pub unit: String,
+    pub source: QuantitySource,
+    #[serde(flatten)]
+    pub error: ConversionError,
+}
+
+#[derive(Debug)]
+struct Portion {
+    unit: Option<String>,
+    description: String,
+    /// Grams for one unit (description quantity already divided out)
+    grams_per_unit: f64,
+}
+
+#[derive(Debug, Default)]
+struct IngredientUnits {
+    density_g_per_ml: Option<f64>,
+    portions: Vec<Portion>,
+}
+
+/// Per-ingredient conversion data for a batch of ingredients.
+///
+/// Load once per request with [`UnitConverter::load`], then convert freely
+/// without further queries.
+#[derive(Debug, Default)]
+pub struct UnitConverter {
+    ingredients: HashMap<i64, IngredientUnits>,
+}
+
+impl UnitConverter {
+    /// Load densities and portion sizes for the given ingredients
+    pub async fn load<C: ConnectionTrait>(db: &C, ingredient_ids: &[i64]) -> Result<Self, AppError> {
+        let mut converter = Self::default();
+        if ingredient_ids.is_empty() {
+            return Ok(converter);
+        }
+
+        let ids = ingredient_ids.to_vec();
+        for ing in ingredient::Entity::find()
+            .filter(ingredient::Column::Id.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            converter.ingredients.entry(ing.id).or_default().density_g_per_ml =
+                ing.density_g_per_ml.and_then(|d| f64::try_from(d).ok()).filter(|d| *d > 0.0);
+        }
+
+        for p in portion_size::Entity::find()
+            .filter(portion_size::Column::IngredientId.is_in(ids))
+            .all(db)
+            .await?
+        {
+            converter.add_portion(p.ingredient_id, p.unit.as_deref(), &p.description, p.weight_grams);
+        }
+
+        Ok(converter)
+    }
+
+    fn add_portion(&mut self, ingredient_id: i64, unit: Option<&str>, description: &str, weight_grams: Decimal) {
+        let Ok(weight) = f64::try_from(weight_grams) else { return };
+        let amount = leading_amount(description).unwrap_or(1.0);
+        if weight <= 0.0 || amount <= 0.0 {
+            return;
+        }
+        let grams_per_unit = weight / amount;
+        let entry = self.ingredients.entry(ingredient_id).or_default();
+
+        // A volume portion ("1 cup" = 125 g) doubles as a density source
+        if let Some(UnitKind::Volume(ml)) = unit.map(classify) {
+            if entry.density_g_per_ml.is_none() {
+                entry.density_g_per_ml = Some(grams_per_unit / ml);
+            }
+        }
+
+        entry.portions.push(Portion {
+            unit: unit.map(|u| match classify(u) {
+                UnitKind::Count(c) => c,
+                _ => normalise(u),
+            }),
+            description: description.to_lowercase(),
+            grams_per_unit,
+        });
+    }
+
+    /// Grams in one `unit` of this ingredient
+    fn grams_per_unit(&self, ingredient_id: i64, unit: &str) -> Result<f64, ConversionError> {
+        let data = self.ingredients.get(&ingredient_id);
+        match classify(unit) {
+            UnitKind::Mass(g) => Ok(g),
+            UnitKind::Volume(ml) => data
+                .and_then(|d| d.density_g_per_ml)
+                .map(|density| ml * density)
+                .ok_or_else(|| ConversionError::MissingDensity { unit: unit.to_string() }),
+            UnitKind::Count(name) => data
+                .and_then(|d| d.portion_for(&name))
+                .ok_or_else(|| ConversionError::MissingPortionWeight { unit: unit.to_string() }),
+        }
+    }
+
+    /// Convert `quantity` of `unit` into grams
+    pub fn to_grams(&self, ingredient_id: i64, quantity: Decimal, unit: &str) -> Result<Decimal, ConversionError> {
+        let factor = self.grams_per_unit(ingredient_id, unit)?;
+        Ok(round(quantity * to_decimal(factor)))
+    }
+
+    /// Convert `grams` into the given `unit`
+    pub fn grams_to_unit(&self, ingredient_id: i64, grams: Decimal, unit: &str) -> Result<Decimal, ConversionError> {
+        let factor = to_decimal(self.grams_per_unit(ingredient_id, unit)?);
+        if factor.is_zero() {
+            return Err(ConversionError::MissingPortionWeight { unit: unit.to_string() });
+        }
+        Ok(round(grams / factor))
+    }
+
+    /// Grams required by a recipe line: `quantity_grams` when the importer
+    /// filled it in, otherwise `quantity` + `unit` converted here.
+    /// `Ok(None)` means the line has no quantity at all ("salt to taste").
+    pub fn recipe_grams(&self, ri: &recipe_ingredient::Model) -> Result<Option<Decimal>, ConversionError> {
+        if let Some(grams) = ri.quantity_grams {
+            return Ok(Some(grams));
+        }
+        match ri.quantity {
+            Some(q) => self.to_grams(ri.ingredient_id, q, ri.unit.as_deref().unwrap_or("")).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl IngredientUnits {
+    fn portion_for(&self, count_unit: &str) -> Option<f64> {
+        let exact = |u: &str| self.portions.iter().find(|p| p.unit.as_deref() == Some(u));
+
+        if count_unit == PIECE {
+            return PIECE_PORTIONS.iter().find_map(|u| exact(u)).map(|p| p.grams_per_unit);
+        }
+
+        exact(count_unit)
+            .or_else(|| {
+                self.portions.iter().find(|p| {
+                    p.description
+                        .split(|c: char| !c.is_alphanumeric())
+                        .any(|word| singular(word) == count_unit)
+                })
+            })
+            .map(|p| p.grams_per_unit)
+    }
+}
+
+/// Classify a unit string. Anything that is not a known mass or volume unit is
+/// treated as a count unit and needs a portion weight to convert.
+pub fn classify(unit: &str) -> UnitKind {
+    let u = normalise(unit);
+    let mass = match u.as_str() {
+        "g" | "gr" | "gram" | "grams" | "gramme" | "grammes" => Some(1.0),
+        "kg" | "kgs" | "kilo" | "kilos" | "kilogram" | "kilograms" => Some(1000.0),
+        "mg" | "milligram" | "milligrams" => Some(0.001),
+        "oz" | "ounce" | "ounces" => Some(28.349_523),
+        "lb" | "lbs" | "pound" | "pounds" => Some(453.592_37),
+        _ => None,
+    };
+    if let Some(g) = mass {
+        return UnitKind::Mass(g);
+    }
+    let volume = match u.as_str() {
+        "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => Some(1.0),
+        "cl" | "centiliter" | "centilitre" => Some(10.0),
+        "dl" | "deciliter" | "decilitre" => Some(100.0),
+        "l" | "lt" | "liter" | "liters" | "litre" | "litres" => Some(1000.0),
+        "tsp" | "teaspoon" | "teaspoons" => Some(4.928_92),
+        "tbsp" | "tbs" | "tablespoon" | "tablespoons" => Some(14.786_8),
+        "cup" | "cups" => Some(236.588),
+        "fl_oz" | "floz" | "fluid_ounce" | "fluid_ounces" => Some(29.573_5),
+        "pint" | "pints" | "pt" => Some(473.176),
+        "quart" | "quarts" | "qt" => Some(946.353),
+        "gallon" | "gallons" | "gal" => Some(3785.41),
+        _ => None,
+    };
+    if let Some(ml) = volume {
+        return UnitKind::Volume(ml);
+    }
+    match u.as_str() {
+        "" | "pc" | "pcs" | "piece" | "pieces" | "unit" | "units" | "each" | "ea" | "x" | "whole" => {
+            UnitKind::Count(PIECE.to_string())
+        }
+        other => UnitKind::Count(singular(other)),
+    }
+}
+
+/// Lowercase, trim and snake_case a unit ("Fl. Oz" → "fl_oz")
+fn normalise(unit: &str) -> String {
+    unit.trim()
+        .to_lowercase()
+        .replace('.', "")
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Crude English singular for container nouns ("boxes" → "box", "loaves" → "loaf")
+fn singular(word: &str) -> String {
+    let w = word.to_lowercase();
+    if w == "loaves" {
+        "loaf".to_string()
+    } else if let Some(stem) = w.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if ["ches", "shes", "xes", "sses"].iter().any(|s| w.ends_with(s)) {
+        w[..w.len() - 2].to_string()
+    } else if w.ends_with('s') && !w.ends_with("ss") && w.len() > 1 {
+        w[..w.len() - 1].to_string()
+    } else {
+        w
+    }
+}
+
+/// Amount at the start of a portion description: "2 slices" → 2, "1/2 cup" → 0.5
+fn leading_amount(description: &str) -> Option<f64> {
+    let token = description.split_whitespace().next()?;
+    if let Some((n, d)) = token.split_once('/') {
+        let (n, d) = (n.parse::<f64>().ok()?, d.parse::<f64>().ok()?);
+        return (d != 0.0).then(|| n / d);
+    }
+    token.parse().ok()
+}
+
+fn to_decimal(v: f64) -> Decimal {
+    Decimal::try_from(v).unwrap_or_default()
+}
+
+/// Match the NUMERIC(10,3) columns quantities are stored in
+fn round(v: Decimal) -> Decimal {
+    v.round_dp(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn converter() -> UnitConverter {
+        let mut c = UnitConverter::default();
+        c.add_portion(1, Some("cup"), "1 cup", dec("125"));
+        c.add_portion(2, Some("piece"), "1 medium egg", dec("50"));
+        c.add_portion(3, Some("bottle"), "2 bottles", dec("1000"));
+        c
+    }
+
+    #[test]
+    fn classifies_common_units() {
+        assert_eq!(classify("Kg"), UnitKind::Mass(1000.0));
+        assert_eq!(classify("l"), UnitKind::Volume(1000.0));
+        assert_eq!(classify("pcs"), UnitKind::Count(PIECE.into()));
+        assert_eq!(classify("Bottles"), UnitKind::Count("bottle".into()));
+    }
+
+    #[test]
+    fn converts_mass_volume_and_count() {
+        let c = converter();
+        assert_eq!(c.to_grams(9, dec("1.5"), "kg"), Ok(dec("1500")));
+        assert_eq!(c.to_grams(1, dec("2"), "cups"), Ok(dec("250")));
+        assert_eq!(c.to_grams(2, dec("3"), "pcs"), Ok(dec("150")));
+        assert_eq!(c.to_grams(3, dec("1"), "bottle"), Ok(dec("500")));
+        assert_eq!(c.grams_to_unit(2, dec("100"), "pcs"), Ok(dec("2")));
+    }
+
+    #[test]
+    fn reports_unconvertible_units() {
+        let c = converter();
+        assert!(matches!(c.to_grams(2, dec("1"), "l"), Err(ConversionError::MissingDensity { .. })));
+        assert!(matches!(c.to_grams(1, dec("1"), "jar"), Err(ConversionError::MissingPortionWeight { .. })));
+    }
+}
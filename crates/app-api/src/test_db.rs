@@ -0,0 +1,135 @@
+//! Postgres fixtures for service tests.
+//!
+//! Tests that need a database are marked
+//! `#[ignore = "needs TEST_DATABASE_URL"]` and start with
+//! `let db = test_db::connect().await;`, so a plain `cargo test` reports
+//! them as ignored rather than passed. Run them with
+//! `TEST_DATABASE_URL=… cargo test -- --include-ignored` against a scratch
+//! database; the schema is migrated on first use. Every test creates its own
+//! users, so tests share the database and run in parallel.
+
+use cookest_shared::migrate::Migrator;
+use chrono::{NaiveDate, Utc};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbBackend, EntityTrait, Set, Statement};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::entity::{meal_plan, meal_plan_slot, recipe, store, user};
+use crate::migrations::MIGRATIONS;
+
+static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+/// Connection to the migrated test database
+pub async fn connect() -> DatabaseConnection {
+    let url = std::env::var("TEST_DATABASE_URL")
+        .ok()
+        .filter(|u| !u.is_empty())
+        .expect("Database tests need TEST_DATABASE_URL");
+    let db = sea_orm::Database::connect(url.as_str())
+        .await
+        .expect("Failed to connect to TEST_DATABASE_URL");
+    MIGRATED
+        .get_or_init(|| async {
+            let migrator = Migrator::new(MIGRATIONS).expect("Invalid migration list");
+            migrator.up(&db, None).await.expect("Failed to migrate test database");
+        })
+        .await;
+    db
+}
+
+/// Configuration for services that need one (e.g. [`crate::services::TokenService`])
+pub fn config() -> Config {
+    if std::env::var("JWT_SECRET").is_err() {
+        std::env::set_var("JWT_SECRET", "test-secret-that-is-at-least-32-bytes-long");
+    }
+    if std::env::var("APP_DATABASE_URL").is_err() && std::env::var("DATABASE_URL").is_err() {
+        std::env::set_var("APP_DATABASE_URL", std::env::var("TEST_DATABASE_URL").unwrap_or_default());
+    }
+    Config::from_env().expect("Invalid test configuration")
+}
+
+/// A fresh user with every column at its default
+pub async fn create_user(db: &DatabaseConnection) -> user::Model {
+    let email = format!("test-{}@example.com", Uuid::new_v4().simple());
+    user::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "INSERT INTO users (email, password_hash) VALUES ($1, 'x') RETURNING *",
+            [email.into()],
+        ))
+        .one(db)
+        .await
+        .expect("Failed to create test user")
+        .expect("INSERT … RETURNING gave no row")
+}
+
+/// A public recipe with no ingredients, for `servings` people
+pub async fn create_recipe(db: &DatabaseConnection, servings: i32) -> recipe::Model {
+    let slug = format!("test-{}", Uuid::new_v4().simple());
+    recipe::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "INSERT INTO recipes (name, slug, servings) VALUES ($1, $1, $2) RETURNING *",
+            [slug.into(), servings.into()],
+        ))
+        .one(db)
+        .await
+        .expect("Failed to create test recipe")
+        .expect("INSERT … RETURNING gave no row")
+}
+
+/// A store with a unique slug
+pub async fn create_store(db: &DatabaseConnection) -> store::Model {
+    let slug = format!("test-{}", Uuid::new_v4().simple());
+    store::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "INSERT INTO stores (name, slug) VALUES ($1, $1) RETURNING *",
+            [slug.into()],
+        ))
+        .one(db)
+        .await
+        .expect("Failed to create test store")
+        .expect("INSERT … RETURNING gave no row")
+}
+
+/// A personal meal plan for the week starting `week_start`
+pub async fn create_plan(db: &DatabaseConnection, user_id: Uuid, week_start: NaiveDate) -> meal_plan::Model {
+    meal_plan::ActiveModel {
+        user_id: Set(user_id),
+        household_id: Set(None),
+        week_start: Set(week_start),
+        is_ai_generated: Set(false),
+        created_at: Set(Utc::now().fixed_offset()),
+        updated_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("Failed to create test meal plan")
+}
+
+/// A plain, unlocked slot in `plan`
+pub async fn add_slot(
+    db: &DatabaseConnection,
+    plan: &meal_plan::Model,
+    day_of_week: i16,
+    meal_type: &str,
+    recipe_id: Option<i64>,
+) -> meal_plan_slot::Model {
+    meal_plan_slot::ActiveModel {
+        meal_plan_id: Set(plan.id),
+        recipe_id: Set(recipe_id),
+        day_of_week: Set(day_of_week),
+        meal_type: Set(meal_type.to_string()),
+        is_completed: Set(false),
+        is_flex: Set(false),
+        is_locked: Set(false),
+        is_leftover: Set(false),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("Failed to create test slot")
+}
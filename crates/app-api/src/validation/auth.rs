@@ -35,6 +35,10 @@ pub struct LoginRequest {
 
     #[validate(length(max = 128, message = "Password too long"))]
     pub password: String,
+
+    /// Optional label shown in the session list, e.g. "Pixel 8"
+    #[validate(length(max = 100, message = "Device name too long"))]
+    pub device_name: Option<String>,
 }
 
 /// Refresh token request (token comes from HttpOnly cookie)
@@ -43,6 +47,63 @@ pub struct RefreshRequest {
     // Token extracted from cookie, not from body
 }
 
+/// Email-only request (forgot password, resend verification)
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailRequest {
+    #[validate(
+        email(message = "Invalid email format"),
+        length(max = 255, message = "Email too long")
+    )]
+    pub email: String,
+}
+
+/// Email verification — token from the link in the verification email
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(length(min = 1, max = 128, message = "Invalid token"))]
+    pub token: String,
+}
+
+/// Password reset — token from the reset email plus the new password
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 128, message = "Invalid token"))]
+    pub token: String,
+
+    #[validate(
+        length(min = 8, max = 128, message = "Password must be 8-128 characters"),
+        custom(function = "validate_password_strength")
+    )]
+    pub new_password: String,
+}
+
+/// Second login step — the "mfa pending" token plus a TOTP or recovery code
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorLoginRequest {
+    #[validate(length(min = 1, max = 2048, message = "Invalid token"))]
+    pub mfa_token: String,
+
+    #[validate(length(min = 6, max = 32, message = "Invalid code"))]
+    pub code: String,
+
+    #[validate(length(max = 100, message = "Device name too long"))]
+    pub device_name: Option<String>,
+}
+
+/// TOTP code confirming 2FA enrollment
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodeRequest {
+    #[validate(length(min = 6, max = 6, message = "Code must be 6 digits"))]
+    pub code: String,
+}
+
+/// Password confirmation for turning 2FA off
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordConfirmRequest {
+    #[validate(length(max = 128, message = "Password too long"))]
+    pub password: String,
+}
+
 /// Custom password strength validator
 /// Requires: uppercase, lowercase, digit, special character
 pub fn validate_password_strength(password: &str) -> Result<(), validator::ValidationError> {
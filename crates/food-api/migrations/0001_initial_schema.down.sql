@@ -0,0 +1,11 @@
+-- Extensions are left in place; see app-api 0001 for the rationale.
+DROP TABLE IF EXISTS api_keys;
+DROP TABLE IF EXISTS recipe_nutrition;
+DROP TABLE IF EXISTS recipe_images;
+DROP TABLE IF EXISTS recipe_steps;
+DROP TABLE IF EXISTS recipe_ingredients;
+DROP TABLE IF EXISTS recipes;
+DROP TABLE IF EXISTS portion_sizes;
+DROP TABLE IF EXISTS ingredient_allergens;
+DROP TABLE IF EXISTS ingredient_nutrients;
+DROP TABLE IF EXISTS ingredients;
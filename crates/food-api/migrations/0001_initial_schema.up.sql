@@ -0,0 +1,196 @@
+-- Food API schema: ingredient catalog, recipes and API keys
+
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Ingredients
+CREATE TABLE IF NOT EXISTS ingredients (
+    id          BIGSERIAL PRIMARY KEY,
+    name        TEXT UNIQUE NOT NULL,
+    category    TEXT,
+    fdc_id      INTEGER,
+    off_id      TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingredients_name_trgm
+    ON ingredients USING GIN (name gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_ingredients_category
+    ON ingredients(category);
+
+CREATE INDEX IF NOT EXISTS idx_ingredients_fdc_id
+    ON ingredients(fdc_id) WHERE fdc_id IS NOT NULL;
+
+-- Ingredient Nutrients
+CREATE TABLE IF NOT EXISTS ingredient_nutrients (
+    id                  BIGSERIAL PRIMARY KEY,
+    ingredient_id       BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
+    calories            NUMERIC(10,4),
+    protein_g           NUMERIC(10,4),
+    carbs_g             NUMERIC(10,4),
+    fat_g               NUMERIC(10,4),
+    fiber_g             NUMERIC(10,4),
+    sugar_g             NUMERIC(10,4),
+    sodium_mg           NUMERIC(10,4),
+    saturated_fat_g     NUMERIC(10,4),
+    cholesterol_mg      NUMERIC(10,4),
+    micronutrients      JSONB,
+    UNIQUE(ingredient_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingredient_nutrients_ingredient
+    ON ingredient_nutrients(ingredient_id);
+
+-- Ingredient Allergens
+CREATE TABLE IF NOT EXISTS ingredient_allergens (
+    id              BIGSERIAL PRIMARY KEY,
+    ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
+    allergen        TEXT NOT NULL,
+    severity        TEXT NOT NULL DEFAULT 'contains',
+    UNIQUE(ingredient_id, allergen)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingredient_allergens_ingredient
+    ON ingredient_allergens(ingredient_id);
+
+CREATE INDEX IF NOT EXISTS idx_ingredient_allergens_allergen
+    ON ingredient_allergens(allergen);
+
+-- Portion Sizes
+CREATE TABLE IF NOT EXISTS portion_sizes (
+    id              BIGSERIAL PRIMARY KEY,
+    ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
+    description     TEXT NOT NULL,
+    weight_grams    NUMERIC(10,3) NOT NULL,
+    unit            TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_portion_sizes_ingredient
+    ON portion_sizes(ingredient_id);
+
+-- Recipes
+CREATE TABLE IF NOT EXISTS recipes (
+    id              BIGSERIAL PRIMARY KEY,
+    name            TEXT NOT NULL,
+    slug            TEXT UNIQUE NOT NULL,
+    description     TEXT,
+    cuisine         TEXT,
+    category        TEXT,
+    difficulty      TEXT,
+    servings        INTEGER NOT NULL DEFAULT 2,
+    prep_time_min   INTEGER,
+    cook_time_min   INTEGER,
+    total_time_min  INTEGER,
+    is_vegetarian   BOOLEAN NOT NULL DEFAULT FALSE,
+    is_vegan        BOOLEAN NOT NULL DEFAULT FALSE,
+    is_gluten_free  BOOLEAN NOT NULL DEFAULT FALSE,
+    is_dairy_free   BOOLEAN NOT NULL DEFAULT FALSE,
+    is_nut_free     BOOLEAN NOT NULL DEFAULT FALSE,
+    source_url      TEXT,
+    average_rating  NUMERIC(3,2),
+    rating_count    INTEGER NOT NULL DEFAULT 0,
+    author_id       UUID,
+    is_public       BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipes_name_trgm
+    ON recipes USING GIN (name gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_recipes_cuisine    ON recipes(cuisine);
+CREATE INDEX IF NOT EXISTS idx_recipes_category   ON recipes(category);
+CREATE INDEX IF NOT EXISTS idx_recipes_difficulty ON recipes(difficulty);
+CREATE INDEX IF NOT EXISTS idx_recipes_dietary
+    ON recipes(is_vegetarian, is_vegan, is_gluten_free, is_dairy_free, is_nut_free);
+
+-- Recipe Ingredients
+CREATE TABLE IF NOT EXISTS recipe_ingredients (
+    id              BIGSERIAL PRIMARY KEY,
+    recipe_id       BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE RESTRICT,
+    quantity        NUMERIC(10,3),
+    unit            TEXT,
+    quantity_grams  NUMERIC(10,3),
+    notes           TEXT,
+    display_order   INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_recipe
+    ON recipe_ingredients(recipe_id);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_ingredient
+    ON recipe_ingredients(ingredient_id);
+
+-- Recipe Steps
+CREATE TABLE IF NOT EXISTS recipe_steps (
+    id              BIGSERIAL PRIMARY KEY,
+    recipe_id       BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    step_number     INTEGER NOT NULL,
+    instruction     TEXT NOT NULL,
+    duration_min    INTEGER,
+    image_url       TEXT,
+    tip             TEXT,
+    UNIQUE(recipe_id, step_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_steps_recipe
+    ON recipe_steps(recipe_id);
+
+-- Recipe Images
+CREATE TABLE IF NOT EXISTS recipe_images (
+    id          BIGSERIAL PRIMARY KEY,
+    recipe_id   BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    url         TEXT NOT NULL,
+    image_type  TEXT,
+    is_primary  BOOLEAN NOT NULL DEFAULT FALSE,
+    width       INTEGER,
+    height      INTEGER,
+    source      TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_images_recipe
+    ON recipe_images(recipe_id);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_images_primary
+    ON recipe_images(recipe_id, is_primary) WHERE is_primary = TRUE;
+
+-- Recipe Nutrition
+CREATE TABLE IF NOT EXISTS recipe_nutrition (
+    id                  BIGSERIAL PRIMARY KEY,
+    recipe_id           BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    per_serving         BOOLEAN NOT NULL DEFAULT TRUE,
+    calories            NUMERIC(10,4),
+    protein_g           NUMERIC(10,4),
+    carbs_g             NUMERIC(10,4),
+    fat_g               NUMERIC(10,4),
+    fiber_g             NUMERIC(10,4),
+    sugar_g             NUMERIC(10,4),
+    sodium_mg           NUMERIC(10,4),
+    saturated_fat_g     NUMERIC(10,4),
+    cholesterol_mg      NUMERIC(10,4),
+    micronutrients      JSONB,
+    calculated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(recipe_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_nutrition_recipe
+    ON recipe_nutrition(recipe_id);
+
+-- API Keys
+CREATE TABLE IF NOT EXISTS api_keys (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name            TEXT NOT NULL,
+    key_hash        TEXT NOT NULL,
+    tier            TEXT NOT NULL DEFAULT 'free',
+    rate_limit_rpm  INTEGER NOT NULL DEFAULT 60,
+    monthly_usage   BIGINT NOT NULL DEFAULT 0,
+    monthly_limit   BIGINT NOT NULL DEFAULT 10000,
+    is_active       BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at    TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_hash ON api_keys(key_hash);
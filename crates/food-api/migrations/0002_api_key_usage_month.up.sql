@@ -0,0 +1,4 @@
+-- Calendar month that api_keys.monthly_usage counts towards; the usage
+-- counter restarts on the first request of a new month.
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS usage_month DATE NOT NULL
+    DEFAULT date_trunc('month', NOW())::date;
@@ -27,6 +27,9 @@ pub struct Model {
     /// Monthly request count (reset on billing cycle)
     pub monthly_usage: i64,
 
+    /// First day of the calendar month `monthly_usage` counts towards
+    pub usage_month: Date,
+
     /// Monthly request limit
     pub monthly_limit: i64,
 
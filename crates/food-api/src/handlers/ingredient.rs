@@ -30,10 +30,10 @@ pub async fn get_ingredient(
     Ok(HttpResponse::Ok().json(ingredient))
 }
 
-/// Configure ingredient routes
+/// Configure ingredient routes (mounted under `/api/v1`)
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/api/v1/ingredients")
+        web::scope("/ingredients")
             .route("", web::get().to(search_ingredients))
             .route("/{id}", web::get().to(get_ingredient)),
     );
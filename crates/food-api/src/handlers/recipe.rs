@@ -73,10 +73,10 @@ pub async fn delete_recipe(
     Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "Recipe deleted" })))
 }
 
-/// Configure all recipe routes
+/// Configure all recipe routes (mounted under `/api/v1`)
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/api/v1/recipes")
+        web::scope("/recipes")
             .route("", web::get().to(list_recipes))
             .route("", web::post().to(create_recipe))
             .route("/slug/{slug}", web::get().to(get_recipe_by_slug))
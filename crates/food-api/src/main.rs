@@ -14,17 +14,35 @@ mod handlers;
 mod models;
 mod services;
 mod middleware;
+mod migrations;
 
 use actix_cors::Cors;
 use actix_web::{web, App, HttpServer, middleware::Logger};
+use cookest_shared::migrate::{MigrateCommand, Migrator};
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::config::Config;
 use crate::handlers::{configure_ingredients, configure_recipes};
 use crate::services::{IngredientService, RecipeService};
+use crate::middleware::api_key::ApiKeyAuth;
 use crate::middleware::security_headers::SecurityHeaders;
 
+/// Parse `<binary> migrate [ARGS]`; `None` means start the server
+fn migrate_command() -> Option<MigrateCommand> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("migrate") {
+        return None;
+    }
+    match MigrateCommand::parse(&args[1..]) {
+        Ok(command) => Some(command),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     tracing_subscriber::registry()
@@ -44,226 +62,29 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to connect to database");
 
-    // Run migrations
-    tracing::info!("Running food database migrations...");
-    use sea_orm::{ConnectionTrait, Statement};
-
-    let migrations: &[&str] = &[
-        r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp""#,
-        r#"CREATE EXTENSION IF NOT EXISTS pg_trgm"#,
-
-        // Ingredients
-        r#"
-        CREATE TABLE IF NOT EXISTS ingredients (
-            id          BIGSERIAL PRIMARY KEY,
-            name        TEXT UNIQUE NOT NULL,
-            category    TEXT,
-            fdc_id      INTEGER,
-            off_id      TEXT,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_ingredients_name_trgm
-            ON ingredients USING GIN (name gin_trgm_ops)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_ingredients_category
-            ON ingredients(category)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_ingredients_fdc_id
-            ON ingredients(fdc_id) WHERE fdc_id IS NOT NULL"#,
-
-        // Ingredient Nutrients
-        r#"
-        CREATE TABLE IF NOT EXISTS ingredient_nutrients (
-            id                  BIGSERIAL PRIMARY KEY,
-            ingredient_id       BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
-            calories            NUMERIC(10,4),
-            protein_g           NUMERIC(10,4),
-            carbs_g             NUMERIC(10,4),
-            fat_g               NUMERIC(10,4),
-            fiber_g             NUMERIC(10,4),
-            sugar_g             NUMERIC(10,4),
-            sodium_mg           NUMERIC(10,4),
-            saturated_fat_g     NUMERIC(10,4),
-            cholesterol_mg      NUMERIC(10,4),
-            micronutrients      JSONB,
-            UNIQUE(ingredient_id)
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_ingredient_nutrients_ingredient
-            ON ingredient_nutrients(ingredient_id)"#,
-
-        // Ingredient Allergens
-        r#"
-        CREATE TABLE IF NOT EXISTS ingredient_allergens (
-            id              BIGSERIAL PRIMARY KEY,
-            ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
-            allergen        TEXT NOT NULL,
-            severity        TEXT NOT NULL DEFAULT 'contains',
-            UNIQUE(ingredient_id, allergen)
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_ingredient_allergens_ingredient
-            ON ingredient_allergens(ingredient_id)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_ingredient_allergens_allergen
-            ON ingredient_allergens(allergen)"#,
-
-        // Portion Sizes
-        r#"
-        CREATE TABLE IF NOT EXISTS portion_sizes (
-            id              BIGSERIAL PRIMARY KEY,
-            ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE CASCADE,
-            description     TEXT NOT NULL,
-            weight_grams    NUMERIC(10,3) NOT NULL,
-            unit            TEXT
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_portion_sizes_ingredient
-            ON portion_sizes(ingredient_id)"#,
-
-        // Recipes
-        r#"
-        CREATE TABLE IF NOT EXISTS recipes (
-            id              BIGSERIAL PRIMARY KEY,
-            name            TEXT NOT NULL,
-            slug            TEXT UNIQUE NOT NULL,
-            description     TEXT,
-            cuisine         TEXT,
-            category        TEXT,
-            difficulty      TEXT,
-            servings        INTEGER NOT NULL DEFAULT 2,
-            prep_time_min   INTEGER,
-            cook_time_min   INTEGER,
-            total_time_min  INTEGER,
-            is_vegetarian   BOOLEAN NOT NULL DEFAULT FALSE,
-            is_vegan        BOOLEAN NOT NULL DEFAULT FALSE,
-            is_gluten_free  BOOLEAN NOT NULL DEFAULT FALSE,
-            is_dairy_free   BOOLEAN NOT NULL DEFAULT FALSE,
-            is_nut_free     BOOLEAN NOT NULL DEFAULT FALSE,
-            source_url      TEXT,
-            average_rating  NUMERIC(3,2),
-            rating_count    INTEGER NOT NULL DEFAULT 0,
-            author_id       UUID,
-            is_public       BOOLEAN NOT NULL DEFAULT TRUE,
-            created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipes_name_trgm
-            ON recipes USING GIN (name gin_trgm_ops)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipes_cuisine    ON recipes(cuisine)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipes_category   ON recipes(category)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipes_difficulty ON recipes(difficulty)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipes_dietary
-            ON recipes(is_vegetarian, is_vegan, is_gluten_free, is_dairy_free, is_nut_free)"#,
-
-        // Recipe Ingredients
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_ingredients (
-            id              BIGSERIAL PRIMARY KEY,
-            recipe_id       BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            ingredient_id   BIGINT NOT NULL REFERENCES ingredients(id) ON DELETE RESTRICT,
-            quantity        NUMERIC(10,3),
-            unit            TEXT,
-            quantity_grams  NUMERIC(10,3),
-            notes           TEXT,
-            display_order   INTEGER NOT NULL DEFAULT 0
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_recipe
-            ON recipe_ingredients(recipe_id)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_ingredient
-            ON recipe_ingredients(ingredient_id)"#,
-
-        // Recipe Steps
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_steps (
-            id              BIGSERIAL PRIMARY KEY,
-            recipe_id       BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            step_number     INTEGER NOT NULL,
-            instruction     TEXT NOT NULL,
-            duration_min    INTEGER,
-            image_url       TEXT,
-            tip             TEXT,
-            UNIQUE(recipe_id, step_number)
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipe_steps_recipe
-            ON recipe_steps(recipe_id)"#,
-
-        // Recipe Images
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_images (
-            id          BIGSERIAL PRIMARY KEY,
-            recipe_id   BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            url         TEXT NOT NULL,
-            image_type  TEXT,
-            is_primary  BOOLEAN NOT NULL DEFAULT FALSE,
-            width       INTEGER,
-            height      INTEGER,
-            source      TEXT,
-            created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipe_images_recipe
-            ON recipe_images(recipe_id)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipe_images_primary
-            ON recipe_images(recipe_id, is_primary) WHERE is_primary = TRUE"#,
-
-        // Recipe Nutrition
-        r#"
-        CREATE TABLE IF NOT EXISTS recipe_nutrition (
-            id                  BIGSERIAL PRIMARY KEY,
-            recipe_id           BIGINT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
-            per_serving         BOOLEAN NOT NULL DEFAULT TRUE,
-            calories            NUMERIC(10,4),
-            protein_g           NUMERIC(10,4),
-            carbs_g             NUMERIC(10,4),
-            fat_g               NUMERIC(10,4),
-            fiber_g             NUMERIC(10,4),
-            sugar_g             NUMERIC(10,4),
-            sodium_mg           NUMERIC(10,4),
-            saturated_fat_g     NUMERIC(10,4),
-            cholesterol_mg      NUMERIC(10,4),
-            micronutrients      JSONB,
-            calculated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            UNIQUE(recipe_id)
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_recipe_nutrition_recipe
-            ON recipe_nutrition(recipe_id)"#,
-
-        // API Keys
-        r#"
-        CREATE TABLE IF NOT EXISTS api_keys (
-            id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            name            TEXT NOT NULL,
-            key_hash        TEXT NOT NULL,
-            tier            TEXT NOT NULL DEFAULT 'free',
-            rate_limit_rpm  INTEGER NOT NULL DEFAULT 60,
-            monthly_usage   BIGINT NOT NULL DEFAULT 0,
-            monthly_limit   BIGINT NOT NULL DEFAULT 10000,
-            is_active       BOOLEAN NOT NULL DEFAULT TRUE,
-            created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            last_used_at    TIMESTAMPTZ
-        )
-        "#,
-        r#"CREATE INDEX IF NOT EXISTS idx_api_keys_hash ON api_keys(key_hash)"#,
-    ];
-
-    for sql in migrations {
-        db.execute(Statement::from_string(
-            sea_orm::DatabaseBackend::Postgres,
-            sql.to_string(),
-        ))
-        .await
-        .expect("Failed to run food migration");
+    // Schema changes are applied explicitly with `cookest-food-api migrate <up|down|status>`
+    let migrator = Migrator::new(migrations::MIGRATIONS).expect("Invalid migration list");
+    if let Some(command) = migrate_command() {
+        if let Err(e) = command.run(&migrator, &db).await {
+            tracing::error!("Migration failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
     }
 
-    tracing::info!("All food migrations complete");
+    // Never migrate implicitly on boot — refuse to serve against an outdated schema
+    migrator
+        .ensure_up_to_date(&db)
+        .await
+        .expect("Database schema is not up to date");
 
     // Initialize services
     let recipe_service = Arc::new(RecipeService::new(db.clone()));
     let ingredient_service = Arc::new(IngredientService::new(db.clone()));
 
+    // Shared across workers so per-key buckets are global to the process
+    let api_key_auth = ApiKeyAuth::new(db.clone());
+
     tracing::info!("Food API starting on {}", bind_address);
 
     HttpServer::new(move || {
@@ -309,9 +130,14 @@ async fn main() -> std::io::Result<()> {
                         }))
                     }))
             )
-            // API v1 routes
-            .configure(configure_ingredients)
-            .configure(configure_recipes)
+            // API v1 routes — X-API-Key auth, per-key rate limit and monthly quota.
+            // Only this scope is wrapped, so /health and unknown paths skip it.
+            .service(
+                web::scope("/api/v1")
+                    .wrap(api_key_auth.clone())
+                    .configure(configure_ingredients)
+                    .configure(configure_recipes),
+            )
     })
     .bind(&bind_address)?
     .run()
@@ -1,8 +1,34 @@
 //! API key authentication middleware
-//! Validates X-API-Key header against hashed keys in the database
+//! Validates X-API-Key header against hashed keys in the database, then
+//! enforces the key's per-minute token bucket and monthly quota.
+//!
+//! Every response under the wrapped scope carries:
+//! - `X-RateLimit-Limit` / `X-RateLimit-Remaining` — per-minute bucket
+//! - `X-RateLimit-Monthly-Limit` / `X-RateLimit-Monthly-Remaining` — quota
+//! - `X-RateLimit-Reset` + `Retry-After` (seconds) when the bucket is empty
 
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, FromRequest, HttpMessage, HttpRequest, ResponseError,
+};
+use futures::future::{ok, ready, LocalBoxFuture, Ready};
+use governor::{
+    clock::{Clock, DefaultClock},
+    middleware::StateInformationMiddleware,
+    state::{InMemoryState, NotKeyed},
+    Quota, RateLimiter,
+};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, QueryFilter,
+    Statement,
+};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 use crate::entity::api_key;
 use crate::errors::AppError;
@@ -13,6 +39,7 @@ pub struct ApiKeyInfo {
     pub id: uuid::Uuid,
     pub name: String,
     pub tier: String,
+    pub rate_limit_rpm: i32,
 }
 
 /// Validate an API key from the X-API-Key header
@@ -34,5 +61,288 @@ pub async fn validate_api_key(
         id: key.id,
         name: key.name,
         tier: key.tier,
+        rate_limit_rpm: key.rate_limit_rpm,
     })
 }
+
+/// Handlers behind `ApiKeyAuth` can take `ApiKeyInfo` as an argument
+impl FromRequest for ApiKeyInfo {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<ApiKeyInfo>()
+                .cloned()
+                .ok_or_else(|| AppError::ApiKeyInvalid.into()),
+        )
+    }
+}
+
+/// Monthly usage after counting this request
+#[derive(Debug, Clone, Copy)]
+struct MonthlyUsage {
+    used: i64,
+    limit: i64,
+}
+
+/// Count one request against the key's monthly quota.
+///
+/// A single conditional UPDATE both checks and increments, so concurrent
+/// requests cannot overshoot the limit. The counter restarts when the
+/// calendar month rolls over. Returns `None` when the quota is exhausted.
+async fn record_usage(
+    db: &DatabaseConnection,
+    key_id: uuid::Uuid,
+) -> Result<Option<MonthlyUsage>, AppError> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            UPDATE api_keys SET
+                monthly_usage = CASE
+                    WHEN usage_month < date_trunc('month', NOW())::date THEN 1
+                    ELSE monthly_usage + 1
+                END,
+                usage_month = date_trunc('month', NOW())::date,
+                last_used_at = NOW()
+            WHERE id = $1
+              AND (usage_month < date_trunc('month', NOW())::date OR monthly_usage < monthly_limit)
+            RETURNING monthly_usage, monthly_limit
+            "#,
+            [key_id.into()],
+        ))
+        .await?;
+
+    row.map(|r| {
+        Ok(MonthlyUsage {
+            used: r.try_get("", "monthly_usage")?,
+            limit: r.try_get("", "monthly_limit")?,
+        })
+    })
+    .transpose()
+    .map_err(AppError::Database)
+}
+
+type KeyLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock, StateInformationMiddleware>;
+
+/// Outcome of taking one token from a key's bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BucketDecision {
+    Allowed { remaining: u32 },
+    Limited { retry_after_secs: u64 },
+}
+
+/// One token bucket per API key, sized from its `rate_limit_rpm`.
+///
+/// Buckets are rebuilt when a key's limit changes (e.g. tier upgrade), so
+/// the new quota applies without a restart. Memory is bounded by the number
+/// of active keys.
+#[derive(Default)]
+struct KeyBuckets {
+    buckets: Mutex<HashMap<uuid::Uuid, (u32, Arc<KeyLimiter>)>>,
+}
+
+impl KeyBuckets {
+    fn check(&self, key_id: uuid::Uuid, rpm: u32) -> BucketDecision {
+        let limiter = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = buckets.entry(key_id).or_insert_with(|| (rpm, new_limiter(rpm)));
+            if entry.0 != rpm {
+                *entry = (rpm, new_limiter(rpm));
+            }
+            entry.1.clone()
+        };
+
+        match limiter.check() {
+            Ok(snapshot) => BucketDecision::Allowed {
+                remaining: snapshot.remaining_burst_capacity(),
+            },
+            Err(not_until) => {
+                let wait = not_until.wait_time_from(DefaultClock::default().now());
+                BucketDecision::Limited {
+                    retry_after_secs: wait.as_secs() + u64::from(wait.subsec_nanos() > 0),
+                }
+            }
+        }
+    }
+}
+
+fn new_limiter(rpm: u32) -> Arc<KeyLimiter> {
+    let quota = Quota::per_minute(NonZeroU32::new(rpm).unwrap_or(NonZeroU32::MIN));
+    Arc::new(RateLimiter::direct(quota).with_middleware::<StateInformationMiddleware>())
+}
+
+/// Middleware factory — wrap the `/api/v1` routes with `.wrap(api_key_auth.clone())`.
+///
+/// Build it once outside `HttpServer::new` so every worker shares the same
+/// buckets; a per-worker instance would multiply each key's limit.
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    db: DatabaseConnection,
+    buckets: Arc<KeyBuckets>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            buckets: Arc::new(KeyBuckets::default()),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ApiKeyAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ApiKeyAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ApiKeyAuthMiddleware {
+            service: Rc::new(service),
+            db: self.db.clone(),
+            buckets: self.buckets.clone(),
+        })
+    }
+}
+
+pub struct ApiKeyAuthMiddleware<S> {
+    service: Rc<S>,
+    db: DatabaseConnection,
+    buckets: Arc<KeyBuckets>,
+}
+
+impl<S, B> Service<ServiceRequest> for ApiKeyAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let db = self.db.clone();
+        let buckets = self.buckets.clone();
+
+        Box::pin(async move {
+            // ── Authenticate ─────────────────────────────────────────────────
+            let raw_key = req
+                .headers()
+                .get("X-API-Key")
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string);
+
+            let Some(raw_key) = raw_key else {
+                return Ok(reject(req, AppError::ApiKeyInvalid, &[]));
+            };
+
+            let info = match validate_api_key(&db, &raw_key).await {
+                Ok(info) => info,
+                Err(e) => return Ok(reject(req, e, &[])),
+            };
+
+            tracing::debug!("API key '{}' ({} tier) authenticated", info.name, info.tier);
+
+            // ── Per-minute token bucket ──────────────────────────────────────
+            let rpm = info.rate_limit_rpm.max(1) as u32;
+            let remaining = match buckets.check(info.id, rpm) {
+                BucketDecision::Allowed { remaining } => remaining,
+                BucketDecision::Limited { retry_after_secs } => {
+                    tracing::warn!("Rate limit exceeded for API key {}", info.id);
+                    let retry = retry_after_secs.to_string();
+                    return Ok(reject(
+                        req,
+                        AppError::RateLimitExceeded,
+                        &[
+                            ("x-ratelimit-limit", rpm.to_string()),
+                            ("x-ratelimit-remaining", "0".to_string()),
+                            ("x-ratelimit-reset", retry.clone()),
+                            ("retry-after", retry),
+                        ],
+                    ));
+                }
+            };
+
+            // ── Monthly quota ────────────────────────────────────────────────
+            let usage = match record_usage(&db, info.id).await {
+                Ok(Some(usage)) => usage,
+                Ok(None) => {
+                    tracing::warn!("Monthly quota exhausted for API key {}", info.id);
+                    return Ok(reject(
+                        req,
+                        AppError::RateLimitExceeded,
+                        &[("x-ratelimit-monthly-remaining", "0".to_string())],
+                    ));
+                }
+                Err(e) => return Ok(reject(req, e, &[])),
+            };
+
+            req.extensions_mut().insert(info);
+
+            let mut res = service.call(req).await?;
+            set_headers(
+                res.headers_mut(),
+                &[
+                    ("x-ratelimit-limit", rpm.to_string()),
+                    ("x-ratelimit-remaining", remaining.to_string()),
+                    ("x-ratelimit-monthly-limit", usage.limit.to_string()),
+                    ("x-ratelimit-monthly-remaining", (usage.limit - usage.used).max(0).to_string()),
+                ],
+            );
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+fn reject<B>(
+    req: ServiceRequest,
+    error: AppError,
+    headers: &[(&'static str, String)],
+) -> ServiceResponse<EitherBody<B>> {
+    let (http_req, _payload) = req.into_parts();
+    let mut response = error.error_response();
+    set_headers(response.headers_mut(), headers);
+    ServiceResponse::new(http_req, response).map_into_right_body()
+}
+
+fn set_headers(map: &mut actix_web::http::header::HeaderMap, headers: &[(&'static str, String)]) {
+    for (name, value) in headers {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            map.insert(HeaderName::from_static(name), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_is_sized_from_rpm_and_rebuilt_on_change() {
+        let buckets = KeyBuckets::default();
+        let key = uuid::Uuid::new_v4();
+
+        assert_eq!(buckets.check(key, 2), BucketDecision::Allowed { remaining: 1 });
+        assert_eq!(buckets.check(key, 2), BucketDecision::Allowed { remaining: 0 });
+        assert!(matches!(
+            buckets.check(key, 2),
+            BucketDecision::Limited { retry_after_secs } if (1..=30).contains(&retry_after_secs)
+        ));
+
+        // Tier upgrade → fresh, larger bucket
+        assert_eq!(buckets.check(key, 10), BucketDecision::Allowed { remaining: 9 });
+    }
+}
@@ -0,0 +1,12 @@
+//! Embedded schema migrations, applied with `cookest-food-api migrate up`.
+//!
+//! See `crates/app-api/src/migrations.rs` for the conventions.
+
+use cookest_shared::migrate::Migration;
+use cookest_shared::migration;
+
+/// Every schema migration for this service, in version order
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(1, "initial_schema", "0001_initial_schema"),
+    migration!(2, "api_key_usage_month", "0002_api_key_usage_month"),
+];
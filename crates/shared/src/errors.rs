@@ -31,6 +31,8 @@ pub enum AppError {
     Forbidden,
     /// API key invalid or missing
     ApiKeyInvalid,
+    /// Feature requires a verified email address
+    EmailNotVerified,
 }
 
 #[derive(Serialize)]
@@ -57,6 +59,7 @@ impl fmt::Display for AppError {
             }
             AppError::Forbidden => write!(f, "Forbidden"),
             AppError::ApiKeyInvalid => write!(f, "Invalid API key"),
+            AppError::EmailNotVerified => write!(f, "Email not verified"),
         }
     }
 }
@@ -169,6 +172,13 @@ impl ResponseError for AppError {
                     details: None,
                 },
             ),
+            AppError::EmailNotVerified => (
+                actix_web::http::StatusCode::FORBIDDEN,
+                ErrorResponse {
+                    error: "Please verify your email address to use this feature.".to_string(),
+                    details: None,
+                },
+            ),
         };
 
         HttpResponse::build(status).json(error_response)
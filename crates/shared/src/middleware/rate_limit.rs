@@ -1,42 +1,282 @@
 //! Rate Limiting Middleware
 //!
-//! Implements per-IP rate limiting using the Governor crate
-//! Different limits for different endpoint types
+//! Implements keyed rate limiting using the Governor crate. Each route group
+//! gets its own `RateLimit` with its own quota and key:
+//! - `RateLimitKey::Ip` — client IP, honouring `X-Forwarded-For` / `X-Real-IP`
+//!   only when the direct peer is a trusted proxy. IPv6 clients are grouped by
+//!   /64 since a single host usually controls the whole prefix.
+//! - `RateLimitKey::User` — authenticated user id, via an extractor supplied
+//!   by the service (falls back to the client IP when absent)
+//! - `RateLimitKey::ApiKey` — `X-API-Key` header, hashed before storage
+//!
+//! Rejections return 429 with `Retry-After`. Idle keys are evicted once the
+//! store reaches `max_keys`; if it is still full, the least recently used keys
+//! go, so a flood of fresh keys can't push new clients into a shared bucket.
+//!
+//! Build each `RateLimit` once outside `HttpServer::new` and `.wrap(limit.clone())`
+//! inside it, so every worker shares the same buckets.
 
 use actix_web::{
+    body::EitherBody,
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    Error,
+    http::header::{HeaderName, HeaderValue, RETRY_AFTER},
+    Error, HttpRequest, ResponseError,
 };
+use dashmap::DashMap;
 use futures::future::{ok, LocalBoxFuture, Ready};
 use governor::{
-    clock::DefaultClock,
-    state::{InMemoryState, NotKeyed},
+    clock::{Clock, DefaultClock},
+    nanos::Nanos,
+    state::{InMemoryState, NotKeyed, StateStore},
     Quota, RateLimiter,
 };
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
 use std::num::NonZeroU32;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::errors::AppError;
 
+/// A per-minute bucket left alone this long is full again, so forgetting it
+/// changes nothing
+const IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// What a rate limit bucket is keyed on
+#[derive(Clone, Copy)]
+pub enum RateLimitKey {
+    /// Client IP (see [`TrustedProxies`] for forwarded headers)
+    Ip,
+    /// Authenticated user id returned by the extractor; wrap the limiter
+    /// *inside* the auth middleware so the identity is already attached
+    User(fn(&ServiceRequest) -> Option<String>),
+    /// `X-API-Key` header value
+    ApiKey,
+}
+
+/// Proxies whose forwarding headers are believed, as IPs or CIDR ranges
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies {
+    networks: Vec<(IpAddr, u8)>,
+}
+
+impl TrustedProxies {
+    /// Parse a comma-separated list such as `"10.0.0.0/8, 127.0.0.1, ::1"`
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut networks = Vec::new();
+        for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (addr, prefix) = match entry.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(prefix)),
+                None => (entry, None),
+            };
+            let ip: IpAddr = addr.parse().map_err(|_| format!("invalid proxy address '{}'", entry))?;
+            let max = if ip.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix {
+                Some(p) => p
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|p| *p <= max)
+                    .ok_or_else(|| format!("invalid prefix length in '{}'", entry))?,
+                None => max,
+            };
+            networks.push((ip, prefix));
+        }
+        Ok(Self { networks })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|(net, prefix)| same_prefix(*net, ip, *prefix))
+    }
+}
+
+fn same_prefix(a: IpAddr, b: IpAddr, prefix: u8) -> bool {
+    match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(a) & mask == u32::from(b) & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(b)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(a) & mask == u128::from(b) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Resolve the originating client IP.
+///
+/// Forwarding headers are only consulted when the direct peer is trusted;
+/// `X-Forwarded-For` is walked right-to-left, skipping trusted hops, so a
+/// client cannot spoof its address by prepending entries.
+pub fn client_ip(req: &HttpRequest, trusted: &TrustedProxies) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted.contains(peer) {
+        return Some(peer);
+    }
+
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(forwarded) = header("x-forwarded-for") {
+        for hop in forwarded.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) if trusted.contains(ip) => continue,
+                Ok(ip) => return Some(ip),
+                Err(_) => break,
+            }
+        }
+    }
+
+    header("x-real-ip")
+        .and_then(|v| v.trim().parse().ok())
+        .or(Some(peer))
+}
+
+/// Bucket key for an IP; IPv6 is collapsed to its /64 prefix
+fn ip_key(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => format!("ip:{}", v4),
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            format!("ip6:{:x}:{:x}:{:x}:{:x}::/64", s[0], s[1], s[2], s[3])
+        }
+    }
+}
+
 /// Rate limiter configuration
 #[derive(Clone)]
 pub struct RateLimitConfig {
-    /// Requests per minute
+    /// Requests per minute, per key
     pub requests_per_minute: u32,
+    /// What each bucket is keyed on
+    pub key: RateLimitKey,
+    /// Proxies allowed to set forwarding headers (used for IP keys and fallbacks)
+    pub trusted_proxies: TrustedProxies,
+    /// Upper bound on tracked keys; idle, then least recently used, keys are evicted beyond it
+    pub max_keys: usize,
 }
 
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
             requests_per_minute: 60,
+            key: RateLimitKey::Ip,
+            trusted_proxies: TrustedProxies::default(),
+            max_keys: 100_000,
+        }
+    }
+}
+
+/// Bucket state per key, with when each key was last seen so the store can
+/// shed idle and least recently used keys
+#[derive(Clone)]
+struct KeyStore {
+    keys: Arc<DashMap<String, KeyState>>,
+    epoch: Instant,
+}
+
+#[derive(Default)]
+struct KeyState {
+    bucket: InMemoryState,
+    /// Nanoseconds since the store's epoch
+    last_seen: AtomicU64,
+}
+
+impl KeyStore {
+    fn new() -> Self {
+        Self { keys: Arc::new(DashMap::new()), epoch: Instant::now() }
+    }
+
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64
+    }
+
+    /// Shrink below `max_keys`: idle keys first, then the least recently
+    /// used ones. Evicts a batch at a time so a flood of new keys doesn't
+    /// scan the store on every request.
+    fn make_room(&self, max_keys: usize) {
+        let idle_before = self.now().saturating_sub(IDLE_AFTER.as_nanos() as u64);
+        self.keys.retain(|_, state| state.last_seen.load(Ordering::Relaxed) > idle_before);
+        if self.keys.len() < max_keys {
+            return;
+        }
+
+        let evict = self.keys.len() + 1 - max_keys + max_keys / 100;
+        let mut by_age: Vec<(u64, String)> = self
+            .keys
+            .iter()
+            .map(|entry| (entry.last_seen.load(Ordering::Relaxed), entry.key().clone()))
+            .collect();
+        if evict < by_age.len() {
+            by_age.select_nth_unstable(evict);
+            by_age.truncate(evict);
+        }
+        for (_, key) in by_age {
+            self.keys.remove(&key);
         }
+        tracing::warn!("Rate limit store full; evicted {} least recently used keys", evict);
+    }
+}
+
+impl StateStore for KeyStore {
+    type Key = String;
+
+    fn measure_and_replace<T, F, E>(&self, key: &String, f: F) -> Result<T, E>
+    where
+        F: Fn(Option<Nanos>) -> Result<(T, Nanos), E>,
+    {
+        let now = self.now();
+        let state = match self.keys.get(key) {
+            Some(state) => state,
+            None => self.keys.entry(key.clone()).or_default().downgrade(),
+        };
+        state.last_seen.store(now, Ordering::Relaxed);
+        state.bucket.measure_and_replace(&NotKeyed::NonKey, f)
+    }
+}
+
+struct Limiter {
+    limiter: RateLimiter<String, KeyStore, DefaultClock>,
+    store: KeyStore,
+    config: RateLimitConfig,
+}
+
+impl Limiter {
+    fn key_for(&self, req: &ServiceRequest) -> String {
+        let by_ip = || {
+            client_ip(req.request(), &self.config.trusted_proxies)
+                .map(ip_key)
+                .unwrap_or_else(|| "ip:unknown".to_string())
+        };
+        match self.config.key {
+            RateLimitKey::Ip => by_ip(),
+            RateLimitKey::User(extract) => extract(req).map(|id| format!("user:{}", id)).unwrap_or_else(by_ip),
+            RateLimitKey::ApiKey => req
+                .headers()
+                .get("X-API-Key")
+                .map(|k| format!("key:{:x}", Sha256::digest(k.as_bytes())))
+                .unwrap_or_else(by_ip),
+        }
+    }
+
+    /// `Err(seconds)` when the caller must wait before retrying
+    fn check(&self, key: String) -> Result<(), u64> {
+        if self.store.keys.len() >= self.config.max_keys && !self.store.keys.contains_key(&key) {
+            self.store.make_room(self.config.max_keys);
+        }
+
+        self.limiter.check_key(&key).map_err(|not_until| {
+            let wait = not_until.wait_time_from(DefaultClock::default().now());
+            (wait.as_secs() + u64::from(wait.subsec_nanos() > 0)).max(1)
+        })
     }
 }
 
 /// Rate limiting middleware factory
+#[derive(Clone)]
 pub struct RateLimit {
-    limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    inner: Arc<Limiter>,
 }
 
 impl RateLimit {
@@ -45,22 +285,29 @@ impl RateLimit {
             NonZeroU32::new(config.requests_per_minute).unwrap_or(NonZeroU32::new(60).unwrap()),
         );
 
+        let store = KeyStore::new();
         Self {
-            limiter: Arc::new(RateLimiter::direct(quota)),
+            inner: Arc::new(Limiter {
+                limiter: RateLimiter::new(quota, store.clone(), DefaultClock::default()),
+                store,
+                config,
+            }),
         }
     }
 
-    /// Create a strict rate limiter for auth endpoints
+    /// Create a strict per-IP rate limiter for auth endpoints
     pub fn strict() -> Self {
         Self::new(RateLimitConfig {
             requests_per_minute: 10,
+            ..Default::default()
         })
     }
 
-    /// Create a lenient rate limiter for general endpoints
+    /// Create a lenient per-IP rate limiter for general endpoints
     pub fn lenient() -> Self {
         Self::new(RateLimitConfig {
             requests_per_minute: 100,
+            ..Default::default()
         })
     }
 }
@@ -71,7 +318,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type InitError = ();
     type Transform = RateLimitMiddleware<S>;
@@ -80,14 +327,14 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(RateLimitMiddleware {
             service: Rc::new(service),
-            limiter: self.limiter.clone(),
+            inner: self.inner.clone(),
         })
     }
 }
 
 pub struct RateLimitMiddleware<S> {
     service: Rc<S>,
-    limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    inner: Arc<Limiter>,
 }
 
 impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
@@ -96,7 +343,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -104,19 +351,98 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
-        let limiter = self.limiter.clone();
+        let inner = self.inner.clone();
 
         Box::pin(async move {
             // Check rate limit
-            if limiter.check().is_err() {
-                tracing::warn!(
-                    "Rate limit exceeded for IP: {:?}",
-                    req.connection_info().peer_addr()
+            let key = inner.key_for(&req);
+            if let Err(retry_after) = inner.check(key.clone()) {
+                tracing::warn!("Rate limit exceeded for {}", key);
+
+                let (http_req, _payload) = req.into_parts();
+                let mut response = AppError::RateLimitExceeded.error_response();
+                let headers = response.headers_mut();
+                headers.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+                headers.insert(
+                    HeaderName::from_static("x-ratelimit-limit"),
+                    HeaderValue::from(inner.config.requests_per_minute),
                 );
-                return Err(AppError::RateLimitExceeded.into());
+                return Ok(ServiceResponse::new(http_req, response).map_into_right_body());
             }
 
-            service.call(req).await
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn request(peer: &str, forwarded: Option<&str>) -> ServiceRequest {
+        let mut req = TestRequest::default().peer_addr(format!("{}:4000", peer).parse().unwrap());
+        if let Some(f) = forwarded {
+            req = req.insert_header(("X-Forwarded-For", f));
+        }
+        req.to_srv_request()
+    }
+
+    #[test]
+    fn parses_cidrs_and_matches_members() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 127.0.0.1, fd00::/8").unwrap();
+        assert!(proxies.contains("10.20.30.40".parse().unwrap()));
+        assert!(proxies.contains("fd12::1".parse().unwrap()));
+        assert!(!proxies.contains("11.0.0.1".parse().unwrap()));
+        assert!(TrustedProxies::parse("10.0.0.0/33").is_err());
+    }
+
+    #[test]
+    fn forwarded_header_only_trusted_from_proxies() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8").unwrap();
+
+        // Spoofed header from an untrusted peer is ignored
+        let direct = request("203.0.113.9", Some("1.1.1.1"));
+        assert_eq!(client_ip(direct.request(), &proxies), Some("203.0.113.9".parse().unwrap()));
+
+        // Through the proxy: rightmost untrusted hop wins, even if the client prepends junk
+        let proxied = request("10.0.0.2", Some("1.1.1.1, 198.51.100.7, 10.0.0.5"));
+        assert_eq!(client_ip(proxied.request(), &proxies), Some("198.51.100.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn limits_per_key_with_retry_after() {
+        let limit = RateLimit::new(RateLimitConfig {
+            requests_per_minute: 1,
+            ..Default::default()
+        });
+        assert!(limit.inner.check("ip:1.1.1.1".into()).is_ok());
+        assert!(matches!(limit.inner.check("ip:1.1.1.1".into()), Err(secs) if secs >= 1));
+        // A different client is unaffected
+        assert!(limit.inner.check("ip:2.2.2.2".into()).is_ok());
+    }
+
+    #[test]
+    fn flooding_the_store_does_not_lock_out_new_clients() {
+        let limit = RateLimit::new(RateLimitConfig {
+            requests_per_minute: 1,
+            max_keys: 100,
+            ..Default::default()
+        });
+        assert!(limit.inner.check("ip:6.6.6.6".into()).is_ok());
+
+        // The attacker fills the store many times over while still hammering
+        // with its own key, which stays limited rather than being evicted
+        for i in 0..1_000 {
+            let _ = limit.inner.check(format!("key:{}", i));
+            assert!(limit.inner.check("ip:6.6.6.6".into()).is_err());
+        }
+        assert!(limit.inner.store.keys.len() <= 100);
+
+        // New clients still get a bucket of their own
+        assert!(limit.inner.check("ip:1.1.1.1".into()).is_ok());
+        assert!(limit.inner.check("ip:1.1.1.1".into()).is_err());
+        assert!(limit.inner.check("ip:2.2.2.2".into()).is_ok());
+    }
+}
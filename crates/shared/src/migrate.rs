@@ -0,0 +1,462 @@
+//! Versioned schema migrations shared by app-api and food-api.
+//!
+//! Each service keeps numbered `NNNN_name.up.sql` / `NNNN_name.down.sql` files
+//! under `migrations/` and embeds them with [`Migration::new`]. Applied
+//! versions are recorded in `schema_migrations` together with a SHA-256
+//! checksum of the up script, so an edited migration is detected instead of
+//! silently diverging.
+//!
+//! Every migration runs inside its own transaction, serialised across
+//! replicas by a transaction-scoped advisory lock. Files are sent to Postgres
+//! as a single simple-protocol query, so function bodies, `$$` quoting and
+//! string literals containing `;` need no client-side splitting.
+//!
+//! Servers never migrate on boot; deploys run `<binary> migrate up` first and
+//! the server refuses to start while migrations are pending
+//! (see [`Migrator::ensure_up_to_date`]).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+
+/// Advisory lock key taken by every migration transaction ("cookmigr")
+const MIGRATION_LOCK_KEY: i64 = 0x636f_6f6b_6d69_6772;
+
+const CREATE_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version         BIGINT PRIMARY KEY,
+    name            TEXT NOT NULL,
+    checksum        TEXT NOT NULL,
+    applied_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    execution_ms    BIGINT NOT NULL
+)
+"#;
+
+/// One embedded migration. `version` must be unique and strictly increasing.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: Option<&'static str>,
+}
+
+impl Migration {
+    pub const fn new(
+        version: i64,
+        name: &'static str,
+        up: &'static str,
+        down: Option<&'static str>,
+    ) -> Self {
+        Self { version, name, up, down }
+    }
+
+    /// Hex SHA-256 of the up script, stored in `schema_migrations.checksum`
+    pub fn checksum(&self) -> String {
+        format!("{:x}", Sha256::digest(self.up.as_bytes()))
+    }
+}
+
+/// Embed `migrations/<file>.up.sql` and `<file>.down.sql` from the calling
+/// crate's root as a [`Migration`].
+#[macro_export]
+macro_rules! migration {
+    ($version:expr, $name:expr, $file:literal) => {
+        $crate::migrate::Migration::new(
+            $version,
+            $name,
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/", $file, ".up.sql")),
+            Some(include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/", $file, ".down.sql"))),
+        )
+    };
+}
+
+/// A row of `schema_migrations`
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+}
+
+/// Per-migration state reported by `migrate status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded file no longer matches what was run
+    Modified,
+    /// Recorded in the database but unknown to this binary (newer deploy rolled back)
+    Unknown,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(DbErr),
+    /// Migration list is not strictly increasing by version
+    InvalidOrder { version: i64 },
+    /// An applied migration's file was edited after it ran
+    ChecksumMismatch { version: i64, name: String },
+    /// Rollback requested for a migration without a down script
+    Irreversible { version: i64, name: String },
+    /// Rollback requested for a version this binary does not embed
+    UnknownVersion { version: i64 },
+    /// Server started with migrations still pending
+    Pending { count: usize },
+    /// Bad `migrate` CLI arguments
+    Usage(String),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "Database error: {}", e),
+            MigrationError::InvalidOrder { version } => {
+                write!(f, "Migration {} is out of order or duplicated", version)
+            }
+            MigrationError::ChecksumMismatch { version, name } => write!(
+                f,
+                "Migration {} ({}) was modified after being applied",
+                version, name
+            ),
+            MigrationError::Irreversible { version, name } => {
+                write!(f, "Migration {} ({}) has no down script", version, name)
+            }
+            MigrationError::UnknownVersion { version } => {
+                write!(f, "Applied migration {} is not known to this binary", version)
+            }
+            MigrationError::Pending { count } => write!(
+                f,
+                "{} pending migration(s); run the `migrate up` subcommand first",
+                count
+            ),
+            MigrationError::Usage(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<DbErr> for MigrationError {
+    fn from(e: DbErr) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+/// Runs an ordered, embedded migration set against one database
+pub struct Migrator {
+    migrations: &'static [Migration],
+}
+
+impl Migrator {
+    pub fn new(migrations: &'static [Migration]) -> Result<Self, MigrationError> {
+        for pair in migrations.windows(2) {
+            if pair[1].version <= pair[0].version {
+                return Err(MigrationError::InvalidOrder { version: pair[1].version });
+            }
+        }
+        Ok(Self { migrations })
+    }
+
+    /// Apply pending migrations up to and including `target` (all when `None`).
+    /// Returns the versions applied by this call.
+    pub async fn up(
+        &self,
+        db: &DatabaseConnection,
+        target: Option<i64>,
+    ) -> Result<Vec<i64>, MigrationError> {
+        db.execute_unprepared(CREATE_TABLE_SQL).await?;
+        let applied = self.verified_applied(db).await?;
+        let mut ran = Vec::new();
+
+        for m in self.migrations {
+            if target.is_some_and(|t| m.version > t) {
+                break;
+            }
+            if applied.contains_key(&m.version) {
+                continue;
+            }
+
+            let txn = db.begin().await?;
+            lock(&txn).await?;
+            // Another replica may have applied it while we waited on the lock
+            if applied_versions(&txn).await?.contains_key(&m.version) {
+                txn.commit().await?;
+                continue;
+            }
+
+            tracing::info!("Applying migration {} ({})", m.version, m.name);
+            let started = Instant::now();
+            txn.execute_unprepared(m.up).await?;
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO schema_migrations (version, name, checksum, execution_ms) \
+                 VALUES ($1, $2, $3, $4)",
+                [
+                    m.version.into(),
+                    m.name.into(),
+                    m.checksum().into(),
+                    (started.elapsed().as_millis() as i64).into(),
+                ],
+            ))
+            .await?;
+            txn.commit().await?;
+            ran.push(m.version);
+        }
+
+        Ok(ran)
+    }
+
+    /// Roll back the `steps` most recently applied migrations, newest first.
+    /// Returns the versions rolled back by this call.
+    pub async fn down(
+        &self,
+        db: &DatabaseConnection,
+        steps: usize,
+    ) -> Result<Vec<i64>, MigrationError> {
+        let applied = self.verified_applied(db).await?;
+        let mut versions: Vec<i64> = applied.keys().copied().collect();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut reverted = Vec::new();
+        for version in versions.into_iter().take(steps) {
+            let m = self
+                .find(version)
+                .ok_or(MigrationError::UnknownVersion { version })?;
+            let down = m.down.ok_or_else(|| MigrationError::Irreversible {
+                version,
+                name: m.name.to_string(),
+            })?;
+
+            let txn = db.begin().await?;
+            lock(&txn).await?;
+            tracing::info!("Reverting migration {} ({})", m.version, m.name);
+            txn.execute_unprepared(down).await?;
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM schema_migrations WHERE version = $1",
+                [version.into()],
+            ))
+            .await?;
+            txn.commit().await?;
+            reverted.push(version);
+        }
+
+        Ok(reverted)
+    }
+
+    /// State of every known and recorded migration, ordered by version
+    pub async fn status(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<(i64, String, MigrationState)>, MigrationError> {
+        let mut applied = self.applied(db).await?;
+        let mut out = Vec::new();
+
+        for m in self.migrations {
+            let state = match applied.remove(&m.version) {
+                Some(row) if row.checksum != m.checksum() => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+                None => MigrationState::Pending,
+            };
+            out.push((m.version, m.name.to_string(), state));
+        }
+        for row in applied.into_values() {
+            out.push((row.version, row.name, MigrationState::Unknown));
+        }
+
+        out.sort_by_key(|(version, _, _)| *version);
+        Ok(out)
+    }
+
+    /// Fail unless every embedded migration has been applied unmodified.
+    /// Called by the servers at startup instead of migrating implicitly.
+    pub async fn ensure_up_to_date(&self, db: &DatabaseConnection) -> Result<(), MigrationError> {
+        let applied = self.verified_applied(db).await?;
+        let count = self
+            .migrations
+            .iter()
+            .filter(|m| !applied.contains_key(&m.version))
+            .count();
+        if count > 0 {
+            return Err(MigrationError::Pending { count });
+        }
+        Ok(())
+    }
+
+    fn find(&self, version: i64) -> Option<&Migration> {
+        self.migrations.iter().find(|m| m.version == version)
+    }
+
+    /// Applied rows; an absent `schema_migrations` table means nothing ran yet.
+    /// Read-only so `status` and the startup check never touch the schema.
+    async fn applied(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<HashMap<i64, AppliedMigration>, MigrationError> {
+        let exists = db
+            .query_one(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT to_regclass('schema_migrations') IS NOT NULL AS present",
+            ))
+            .await?
+            .map(|row| row.try_get::<bool>("", "present"))
+            .transpose()?
+            .unwrap_or(false);
+        if !exists {
+            return Ok(HashMap::new());
+        }
+        applied_versions(db).await
+    }
+
+    /// Applied rows, after checking none of them were edited since they ran
+    async fn verified_applied(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<HashMap<i64, AppliedMigration>, MigrationError> {
+        let applied = self.applied(db).await?;
+        for m in self.migrations {
+            if let Some(row) = applied.get(&m.version) {
+                if row.checksum != m.checksum() {
+                    return Err(MigrationError::ChecksumMismatch {
+                        version: m.version,
+                        name: m.name.to_string(),
+                    });
+                }
+            }
+        }
+        for version in applied.keys().filter(|v| self.find(**v).is_none()) {
+            tracing::warn!("Database has migration {} that this binary does not know", version);
+        }
+        Ok(applied)
+    }
+}
+
+async fn lock<C: ConnectionTrait>(conn: &C) -> Result<(), DbErr> {
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_advisory_xact_lock($1)",
+        [MIGRATION_LOCK_KEY.into()],
+    ))
+    .await
+    .map(|_| ())
+}
+
+async fn applied_versions<C: ConnectionTrait>(
+    conn: &C,
+) -> Result<HashMap<i64, AppliedMigration>, MigrationError> {
+    let rows = conn
+        .query_all(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT version, name, checksum FROM schema_migrations",
+        ))
+        .await?;
+
+    let mut out = HashMap::new();
+    for row in rows {
+        let applied = AppliedMigration {
+            version: row.try_get("", "version")?,
+            name: row.try_get("", "name")?,
+            checksum: row.try_get("", "checksum")?,
+        };
+        out.insert(applied.version, applied);
+    }
+    Ok(out)
+}
+
+/// A parsed `migrate` subcommand
+#[derive(Debug, PartialEq, Eq)]
+pub enum MigrateCommand {
+    /// `migrate up [VERSION]`
+    Up { target: Option<i64> },
+    /// `migrate down [STEPS]` (defaults to one step)
+    Down { steps: usize },
+    /// `migrate status`
+    Status,
+}
+
+impl MigrateCommand {
+    /// Parse the arguments following `migrate` (e.g. `["down", "2"]`)
+    pub fn parse(args: &[String]) -> Result<Self, MigrationError> {
+        let usage = || MigrationError::Usage("usage: migrate <up [VERSION] | down [STEPS] | status>".into());
+        let number = |arg: Option<&String>| -> Result<Option<i64>, MigrationError> {
+            arg.map(|s| s.parse::<i64>().map_err(|_| usage())).transpose()
+        };
+
+        match args.first().map(String::as_str) {
+            None | Some("up") if args.len() <= 2 => Ok(MigrateCommand::Up { target: number(args.get(1))? }),
+            Some("down") if args.len() <= 2 => {
+                let steps = number(args.get(1))?.unwrap_or(1);
+                if steps < 1 {
+                    return Err(usage());
+                }
+                Ok(MigrateCommand::Down { steps: steps as usize })
+            }
+            Some("status") if args.len() == 1 => Ok(MigrateCommand::Status),
+            _ => Err(usage()),
+        }
+    }
+
+    /// Execute against `db`, logging what happened
+    pub async fn run(&self, migrator: &Migrator, db: &DatabaseConnection) -> Result<(), MigrationError> {
+        match self {
+            MigrateCommand::Up { target } => {
+                let ran = migrator.up(db, *target).await?;
+                tracing::info!("Applied {} migration(s): {:?}", ran.len(), ran);
+            }
+            MigrateCommand::Down { steps } => {
+                let reverted = migrator.down(db, *steps).await?;
+                tracing::info!("Reverted {} migration(s): {:?}", reverted.len(), reverted);
+            }
+            MigrateCommand::Status => {
+                for (version, name, state) in migrator.status(db).await? {
+                    println!("{:>6}  {:<9}  {}", version, format!("{:?}", state).to_lowercase(), name);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_cli_arguments() {
+        assert_eq!(MigrateCommand::parse(&args(&[])).unwrap(), MigrateCommand::Up { target: None });
+        assert_eq!(MigrateCommand::parse(&args(&["up", "3"])).unwrap(), MigrateCommand::Up { target: Some(3) });
+        assert_eq!(MigrateCommand::parse(&args(&["down"])).unwrap(), MigrateCommand::Down { steps: 1 });
+        assert_eq!(MigrateCommand::parse(&args(&["status"])).unwrap(), MigrateCommand::Status);
+        assert!(MigrateCommand::parse(&args(&["down", "0"])).is_err());
+        assert!(MigrateCommand::parse(&args(&["sideways"])).is_err());
+    }
+
+    #[test]
+    fn rejects_unordered_migrations() {
+        static LIST: &[Migration] = &[
+            Migration::new(2, "b", "SELECT 1;", None),
+            Migration::new(1, "a", "SELECT 1;", None),
+        ];
+        assert!(matches!(Migrator::new(LIST), Err(MigrationError::InvalidOrder { version: 1 })));
+    }
+
+    #[test]
+    fn checksum_tracks_up_script_only() {
+        let a = Migration::new(1, "a", "CREATE TABLE t (id INT);", None);
+        let b = Migration::new(1, "a", "CREATE TABLE t (id INT);", Some("DROP TABLE t;"));
+        let c = Migration::new(1, "a", "CREATE TABLE t (id BIGINT);", None);
+        assert_eq!(a.checksum(), b.checksum());
+        assert_ne!(a.checksum(), c.checksum());
+        assert_eq!(a.checksum().len(), 64);
+    }
+}